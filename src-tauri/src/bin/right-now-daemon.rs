@@ -2,10 +2,11 @@
 //
 // Responsibilities:
 // - Own session registry and persist to sessions.json
-// - Spawn PTYs via portable-pty for shell sessions
+// - Spawn PTYs for shell sessions, via per-session holder processes when available
 // - Update TODO Markdown files atomically when sessions change state
 // - Expose Unix socket protocol for CLI/UI communication
 // - Broadcast session updates to subscribed clients
+// - Hand sessions over to a newer daemon started while this one runs
 
 use anyhow::{Context, Result};
 use rn_desktop_2_lib::{
@@ -16,79 +17,182 @@ use rn_desktop_2_lib::{
     },
     session::{
         attention,
-        config::Config,
+        config::{Config, Settings},
+        handoff::{self, HandoffEntry},
+        holder::{self, HolderArgs},
+        journal::{self, EventJournal, JournalLimits},
         markdown::{
             find_task_by_key, parse_body, update_task_session_in_content, TaskSessionStatus,
         },
         notify::{notify_attention, NotificationDebouncer},
+        output_log::{self, OutputLog},
+        peer,
         persistence::{atomic_write, SessionRegistry},
         protocol::{
-            deserialize_message, serialize_message, AttentionSummary, DaemonNotification,
-            DaemonRequest, DaemonResponse, Session, SessionId, SessionStatus,
+            deserialize_message, serialize_message, serialize_message_with, AttachMode,
+            AttachViewer, AttentionId, AttentionItem, AttentionSummary, ByteEncoding, Capability,
+            DaemonNotification, DaemonRequest, DaemonResponse, JournalEvent, NotificationEnvelope,
+            RecordingInfo, RequestFrame, RequestId, ResponseFrame, ScreenRepaint, ScreenText,
+            Session, SessionId, SessionStatus, StreamFrame, SubscriptionFilter,
         },
-        runtime::{PtyEvent, PtyRuntime},
+        recording,
+        runtime::{snapshot_env, PtyEvent, PtyRuntime, SpawnSpec},
     },
 };
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
 use tokio::signal;
-use tokio::sync::{broadcast, Mutex, RwLock};
+use tokio::sync::{broadcast, oneshot, Mutex, RwLock};
 use tokio::task::JoinHandle;
 
-const DEFAULT_TAIL_BYTES: usize = 4 * 1024;
+/// Finished pipelined responses queued per connection before handlers wait
+const PIPELINED_RESPONSE_BUFFER: usize = 64;
 
-/// Idle timeout threshold for context captures (10 minutes)
-const IDLE_CAPTURE_THRESHOLD_SECS: u64 = 10 * 60;
+/// Pipelined requests handled at once per connection; past this the
+/// connection isn't read until one finishes
+const MAX_PIPELINED_REQUESTS: usize = 32;
+
+/// Stream frames queued per connection before session output waits on it
+const STREAM_FRAME_BUFFER: usize = 256;
+
+/// Notifications queued per subscriber before a slow one is told to resync
+const NOTIFICATION_BUFFER: usize = 100;
+
+/// How long a refused connection gets to send its first request
+const REFUSAL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// How often an attention monitor rechecks the foreground command while
+/// output is arriving, to pick the profiles that apply
+const FOREGROUND_RECHECK: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Shown to clients detached because another attached exclusively
+const DETACHED_BY_EXCLUSIVE: &str = "Detached: another client attached exclusively";
 
 /// Daemon state shared across all client connections
 struct DaemonState {
     config: Config,
     registry: RwLock<SessionRegistry>,
     /// Broadcast channel for session updates
-    updates_tx: broadcast::Sender<DaemonNotification>,
+    updates_tx: broadcast::Sender<NotificationEnvelope>,
+    /// Journal of broadcast notifications, which also hands out their seqs;
+    /// held while sending so seq order is channel order
+    journal: std::sync::Mutex<EventJournal>,
     /// Active PTY sessions (session_id -> PTY handle)
     pty_handles: Mutex<HashMap<SessionId, PtyRuntime>>,
     /// Completed session tails retained after PTY exit
     completed_tails: Mutex<HashMap<SessionId, Vec<u8>>>,
+    /// Final rendered screens retained after PTY exit
+    completed_screens: Mutex<HashMap<SessionId, ScreenText>>,
     /// Active attach socket listeners
     attach_listeners: Mutex<HashMap<SessionId, AttachSocketHandle>>,
-    /// Per-session notification debouncers (5s cooldown)
+    /// Clients attached to each session, mirrored into `Session::viewers`
+    attach_viewers: std::sync::Mutex<HashMap<SessionId, Vec<ViewerSlot>>>,
+    next_viewer_id: AtomicU64,
+    /// Tunables from config.toml, replaced on reload
+    settings: std::sync::RwLock<Settings>,
+    /// Attention profiles from attention.toml, shared with session monitors
+    attention_profiles: attention::ProfileSet,
+    /// Per-session notification debouncers
     notification_debouncers: Mutex<HashMap<SessionId, NotificationDebouncer>>,
     /// Context Resurrection capture service (optional - graceful degradation if unavailable)
     capture_service: Mutex<Option<CaptureService>>,
     /// Context Resurrection snapshot store (separate from service for query ops)
     snapshot_store: SnapshotStore,
+    /// Holder binary that owns session PTYs; PTYs live in the daemon when missing
+    holder_bin: Option<PathBuf>,
 }
 
 struct AttachSocketHandle {
     path: PathBuf,
+    /// Listening socket owned by `task`, passed along on handoff
+    listener_fd: RawFd,
+    task: JoinHandle<()>,
+}
+
+/// What a client sees of a session when it attaches, before live output
+struct AttachView {
+    session: Session,
+    tail: Option<Vec<u8>>,
+    screen: Option<ScreenRepaint>,
+    offset: Option<u64>,
+}
+
+/// A client attached to a session, and how to detach it
+struct ViewerSlot {
+    viewer: AttachViewer,
+    /// Fired when another client attaches exclusively
+    kick: oneshot::Sender<()>,
+}
+
+/// Keeps a client listed among a session's viewers until dropped
+struct ViewerLease {
+    state: Arc<DaemonState>,
+    session_id: SessionId,
+    viewer_id: u64,
+}
+
+impl Drop for ViewerLease {
+    fn drop(&mut self) {
+        self.state.remove_viewer(self.session_id, self.viewer_id);
+    }
+}
+
+/// An attach stream open on a client connection
+struct AttachStream {
+    input_tx: tokio::sync::mpsc::Sender<Vec<u8>>,
+    /// Input and resizes from read-only clients are dropped
+    read_only: bool,
+    /// Forwards the session's output to the connection
     task: JoinHandle<()>,
+    _lease: ViewerLease,
+}
+
+impl Drop for AttachStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
 }
 
 impl DaemonState {
     fn new(config: Config) -> Result<Self> {
         // Load existing registry or create empty one
-        let registry = SessionRegistry::load(&config)?;
+        let mut registry = SessionRegistry::load(&config)?;
+        // Viewers were attached to whichever daemon ran before
+        for session in registry.sessions.values_mut() {
+            session.viewers.clear();
+        }
 
         // Create broadcast channel for updates
-        let (updates_tx, _) = broadcast::channel(100);
+        let (updates_tx, _) = broadcast::channel(NOTIFICATION_BUFFER);
+        let journal = EventJournal::open(&config.events_dir(), JournalLimits::default())?;
 
         // Initialize snapshot store for CR queries
         let snapshot_store = SnapshotStore::new(config.state_dir());
 
+        let (settings, profiles) = load_config(&config)?;
+
         Ok(Self {
             config,
             registry: RwLock::new(registry),
             updates_tx,
+            journal: std::sync::Mutex::new(journal),
             pty_handles: Mutex::new(HashMap::new()),
             completed_tails: Mutex::new(HashMap::new()),
+            completed_screens: Mutex::new(HashMap::new()),
             attach_listeners: Mutex::new(HashMap::new()),
+            attach_viewers: std::sync::Mutex::new(HashMap::new()),
+            next_viewer_id: AtomicU64::new(1),
+            settings: std::sync::RwLock::new(settings),
+            attention_profiles: attention::ProfileSet::new(profiles),
             notification_debouncers: Mutex::new(HashMap::new()),
             capture_service: Mutex::new(None), // Initialized after Arc::new in main()
             snapshot_store,
+            holder_bin: holder::find_holder_binary(),
         })
     }
 
@@ -99,6 +203,7 @@ impl DaemonState {
             let session_provider: Arc<dyn SessionProvider> =
                 Arc::clone(self) as Arc<dyn SessionProvider>;
             let capture_service = CaptureService::new(snapshot_store, Some(session_provider));
+            capture_service.set_retention(self.settings().snapshot_retention);
             *self.capture_service.lock().await = Some(capture_service);
             eprintln!("Context Resurrection capture service initialized");
         } else {
@@ -108,6 +213,36 @@ impl DaemonState {
         }
     }
 
+    /// The settings in effect
+    fn settings(&self) -> Settings {
+        *self.settings.read().unwrap()
+    }
+
+    /// Re-read config.toml and attention.toml and put them into effect,
+    /// returning the settings and the names of the attention profiles
+    ///
+    /// On error what is in effect stays. New idle timeouts and buffer sizes
+    /// apply to sessions started from now on; attention profiles apply to
+    /// running sessions' next output.
+    async fn reload_config(&self) -> Result<(Settings, Vec<String>)> {
+        let (settings, profiles) = load_config(&self.config)?;
+        let profile_names = profiles.iter().map(|p| p.name.clone()).collect();
+        self.attention_profiles.replace(profiles);
+        *self.settings.write().unwrap() = settings;
+        for debouncer in self.notification_debouncers.lock().await.values_mut() {
+            debouncer.set_cooldown(settings.notification_debounce());
+        }
+        if let Some(ref service) = *self.capture_service.lock().await {
+            service.set_retention(settings.snapshot_retention);
+        }
+        eprintln!(
+            "Reloaded {} and {}",
+            self.config.settings_file().display(),
+            self.config.attention_profiles_file().display()
+        );
+        Ok((settings, profile_names))
+    }
+
     /// Trigger a context capture if capture service is available
     ///
     /// Skips capture silently if:
@@ -163,16 +298,66 @@ impl DaemonState {
     /// Reconcile persisted sessions on daemon startup
     ///
     /// After a crash/restart, the registry may list Running/Waiting sessions
-    /// but no PTY handles exist. Mark them all as Stopped and update their
-    /// markdown badges.
-    async fn reconcile_stale_sessions(&self) {
+    /// but no PTY handles exist. Sessions whose holder process is still up are
+    /// adopted; the rest are marked Stopped and their markdown badges updated.
+    async fn reconcile_stale_sessions(self: &Arc<Self>) {
+        let live_sessions: Vec<SessionId> = {
+            let registry = self.registry.read().await;
+            registry
+                .sessions
+                .values()
+                .filter(|s| {
+                    s.status == SessionStatus::Running || s.status == SessionStatus::Waiting
+                })
+                .map(|s| s.id)
+                .collect()
+        };
+
+        // Exit codes reported by holders whose session ended while we were down
+        let mut exit_codes = HashMap::new();
+        for session_id in live_sessions {
+            let socket_path = holder::socket_path(self.config.runtime_dir(), session_id);
+            if !socket_path.exists() {
+                continue;
+            }
+            let tuning = self.settings().runtime_tuning();
+            let runtime = match tokio::task::spawn_blocking(move || {
+                PtyRuntime::connect_holder(session_id, &socket_path, tuning)
+            })
+            .await
+            {
+                Ok(Ok(runtime)) => runtime,
+                Ok(Err(e)) => {
+                    eprintln!("Failed to adopt session {}: {}", session_id, e);
+                    continue;
+                }
+                Err(_) => continue,
+            };
+
+            if runtime.is_alive() {
+                eprintln!("Adopting running session {} from its holder", session_id);
+                self.adopt_session(session_id, runtime).await;
+            } else {
+                exit_codes.insert(session_id, runtime.exit_code());
+                self.store_completed_output(
+                    session_id,
+                    runtime.get_recent_output_blocking(self.settings().tail_bytes),
+                    runtime.screen_text(),
+                )
+                .await;
+            }
+        }
+
         let sessions_to_stop: Vec<(SessionId, String, String)> = {
+            // Registry before handles, like everywhere else
             let mut registry = self.registry.write().await;
+            let adopted = self.pty_handles.lock().await;
             let mut to_stop = Vec::new();
 
             for session in registry.sessions.values_mut() {
-                if session.status == SessionStatus::Running
-                    || session.status == SessionStatus::Waiting
+                if (session.status == SessionStatus::Running
+                    || session.status == SessionStatus::Waiting)
+                    && !adopted.contains_key(&session.id)
                 {
                     eprintln!(
                         "Reconciling stale session {} '{}' (was {:?})",
@@ -184,7 +369,15 @@ impl DaemonState {
                         session.task_key.clone(),
                     ));
                     session.status = SessionStatus::Stopped;
+                    session.exit_code = exit_codes.get(&session.id).copied().flatten();
                     session.updated_at = chrono::Utc::now();
+                    // Journaled, so the exit shows up among the missed events
+                    self.broadcast(
+                        &session.project_path,
+                        DaemonNotification::SessionUpdated {
+                            session: session.clone(),
+                        },
+                    );
                 }
             }
 
@@ -228,16 +421,336 @@ impl DaemonState {
         }
     }
 
+    /// Delete the output log and recording left by an earlier session with this ID
+    ///
+    /// Session IDs start over if the registry is reset; a new session must
+    /// never append to an old session's files.
+    fn clear_session_files(&self, session_id: SessionId) {
+        if let Err(e) = output_log::remove_log(&self.config.session_log_dir(session_id)) {
+            eprintln!(
+                "Failed to clear old output log for session {}: {}",
+                session_id, e
+            );
+        }
+        match std::fs::remove_file(self.config.session_recording_path(session_id)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => eprintln!(
+                "Failed to clear old recording for session {}: {}",
+                session_id, e
+            ),
+            _ => {}
+        }
+    }
+
+    /// Spawn a PTY for a session, in a holder process when one is available
+    ///
+    /// Output is appended to the session's existing log and recording, if any.
+    async fn spawn_pty(
+        &self,
+        session_id: SessionId,
+        spec: SpawnSpec,
+        task_key: &str,
+        project_path: &str,
+        record: bool,
+    ) -> Result<PtyRuntime> {
+        let recording = record.then(|| self.config.session_recording_path(session_id));
+
+        let Some(holder_bin) = self.holder_bin.clone() else {
+            let log = self.open_output_log(session_id);
+            return PtyRuntime::spawn_with_outputs(
+                session_id,
+                &spec,
+                task_key,
+                project_path,
+                log,
+                recording.as_deref(),
+            );
+        };
+
+        let log_limits = self.settings().output_log_limits();
+        let args = HolderArgs {
+            socket_path: holder::socket_path(self.config.runtime_dir(), session_id),
+            session_id,
+            task_key: task_key.to_string(),
+            project_path: project_path.to_string(),
+            log_dir: log_limits.map(|_| self.config.session_log_dir(session_id)),
+            log_limits: log_limits.unwrap_or_default(),
+            recording,
+            cwd: spec.cwd,
+            env: spec.env,
+            shell: spec.shell,
+            tuning: spec.tuning,
+        };
+        tokio::task::spawn_blocking(move || holder::spawn_holder(&holder_bin, &args)).await?
+    }
+
+    /// Open a session's output log for appending, unless logging is disabled
+    ///
+    /// A log that can't be opened is reported and skipped; the session runs without it.
+    fn open_output_log(&self, session_id: SessionId) -> Option<OutputLog> {
+        let limits = self.settings().output_log_limits()?;
+        let dir = self.config.session_log_dir(session_id);
+        match OutputLog::open(&dir, limits) {
+            Ok(log) => Some(log),
+            Err(e) => {
+                eprintln!("Failed to open output log {}: {}", dir.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Read a range of a session's on-disk output log
+    async fn read_output_log(
+        &self,
+        session_id: SessionId,
+        offset: u64,
+        max_bytes: usize,
+    ) -> std::io::Result<output_log::LogRange> {
+        let dir = self.config.session_log_dir(session_id);
+        tokio::task::spawn_blocking(move || output_log::read_log(&dir, offset, max_bytes))
+            .await
+            .map_err(std::io::Error::other)?
+    }
+
+    /// Journaled notifications after `since_seq`
+    async fn read_events(
+        &self,
+        since_seq: u64,
+        limit: usize,
+        filter: SubscriptionFilter,
+    ) -> std::io::Result<Vec<JournalEvent>> {
+        let dir = self.config.events_dir();
+        tokio::task::spawn_blocking(move || journal::read_events(&dir, since_seq, limit, &filter))
+            .await
+            .map_err(std::io::Error::other)?
+    }
+
+    /// Recordings on disk, annotated with their sessions from the registry
+    async fn list_recordings(&self) -> std::io::Result<Vec<RecordingInfo>> {
+        let dir = self.config.recordings_dir();
+        let files = tokio::task::spawn_blocking(move || recording::list_recordings(&dir))
+            .await
+            .map_err(std::io::Error::other)??;
+
+        let registry = self.registry.read().await;
+        let handles = self.pty_handles.lock().await;
+        Ok(files
+            .into_iter()
+            .map(|file| {
+                let session = registry.get(file.session_id);
+                RecordingInfo {
+                    session_id: file.session_id,
+                    task_key: session.map(|s| s.task_key.clone()),
+                    path: file.path.to_string_lossy().to_string(),
+                    size_bytes: file.size_bytes,
+                    modified_at: file.modified.into(),
+                    active: session.is_some_and(|s| s.recording)
+                        && handles.contains_key(&file.session_id),
+                }
+            })
+            .collect())
+    }
+
+    /// Take over a session whose holder outlived a previous daemon
+    async fn adopt_session(self: &Arc<Self>, session_id: SessionId, runtime: PtyRuntime) {
+        let (project_path, task_key) = {
+            let registry = self.registry.read().await;
+            match registry.get(session_id) {
+                Some(session) => (session.project_path.clone(), session.task_key.clone()),
+                None => return,
+            }
+        };
+
+        self.pty_handles.lock().await.insert(session_id, runtime);
+
+        let state = Arc::clone(self);
+        tokio::spawn(async move {
+            watch_pty_output(state, session_id, project_path, task_key).await;
+        });
+        self.spawn_attention_monitor(session_id);
+    }
+
+    /// Take over sessions and attach sockets handed off by the daemon we replace
+    ///
+    /// Held sessions carry no PTY; reconciliation reconnects to their holders.
+    async fn adopt_handoff(self: &Arc<Self>, sessions: Vec<HandoffEntry<OwnedFd>>) {
+        for entry in sessions {
+            let session_id = entry.session.session_id;
+            if let Some(pty) = entry.pty {
+                let log = self.open_output_log(session_id);
+                let recording = {
+                    let registry = self.registry.read().await;
+                    registry
+                        .get(session_id)
+                        .filter(|session| session.recording)
+                        .map(|_| self.config.session_recording_path(session_id))
+                };
+                match PtyRuntime::adopt_handoff(
+                    &entry.session,
+                    pty,
+                    log,
+                    recording.as_deref(),
+                    self.settings().runtime_tuning(),
+                ) {
+                    Ok(runtime) => {
+                        eprintln!(
+                            "Adopting running session {} from previous daemon",
+                            session_id
+                        );
+                        self.adopt_session(session_id, runtime).await;
+                    }
+                    Err(e) => eprintln!("Failed to adopt session {}: {}", session_id, e),
+                }
+            }
+
+            if let Some(fd) = entry.attach_listener {
+                match listener_from_fd(fd) {
+                    Ok(listener) => {
+                        // Whoever asked the previous daemon for it asked in some mode we
+                        // don't know; treat its connection like any other
+                        let path = self.attach_socket_path(session_id);
+                        self.serve_attach_listener(
+                            session_id,
+                            path,
+                            listener,
+                            AttachMode::Shared,
+                            true,
+                        )
+                        .await;
+                    }
+                    Err(e) => eprintln!(
+                        "Failed to take over attach socket for session {}: {}",
+                        session_id, e
+                    ),
+                }
+            }
+        }
+    }
+
     /// Save the registry to disk
     async fn save_registry(&self) -> Result<()> {
         let registry = self.registry.read().await;
         registry.save(&self.config)
     }
 
-    /// Broadcast a notification to all subscribed clients
-    fn broadcast(&self, notification: DaemonNotification) {
+    /// Broadcast a notification about a session in `project_path` to subscribed clients
+    ///
+    /// The notification is journaled first, so it can be read back later with
+    /// `DaemonRequest::Events`.
+    fn broadcast(&self, project_path: &str, notification: DaemonNotification) {
+        let mut journal = self.journal.lock().unwrap();
+        let event = JournalEvent {
+            seq: journal.last_seq() + 1,
+            at: chrono::Utc::now(),
+            project_path: Some(project_path.to_string()),
+            notification,
+        };
+        if let Err(e) = journal.append(&event) {
+            eprintln!("Failed to journal event {}: {}", event.seq, e);
+        }
         // Ignore send errors (no subscribers)
-        let _ = self.updates_tx.send(notification);
+        let _ = self.updates_tx.send(NotificationEnvelope {
+            seq: event.seq,
+            project_path: event.project_path,
+            notification: event.notification,
+        });
+    }
+
+    /// Acknowledge the inbox items `select` picks, saving the registry and
+    /// telling each session's subscribers, and return their IDs
+    async fn ack_attention(&self, select: impl Fn(&AttentionItem) -> bool) -> Vec<AttentionId> {
+        let (acked, by_session) = {
+            let mut registry = self.registry.write().await;
+            let acked = registry.ack_attention(select);
+            let mut by_session: BTreeMap<(String, SessionId), Vec<AttentionId>> = BTreeMap::new();
+            for item in registry
+                .attention
+                .iter()
+                .filter(|item| acked.contains(&item.summary.id))
+            {
+                by_session
+                    .entry((item.project_path.clone(), item.session_id))
+                    .or_default()
+                    .push(item.summary.id);
+            }
+            (acked, by_session)
+        };
+        if acked.is_empty() {
+            return acked;
+        }
+        if let Err(e) = self.save_registry().await {
+            eprintln!("Failed to save acknowledged attention: {}", e);
+        }
+        for ((project_path, session_id), ids) in by_session {
+            self.broadcast(
+                &project_path,
+                DaemonNotification::AttentionAcked { session_id, ids },
+            );
+        }
+        acked
+    }
+
+    /// Seq of the latest notification broadcast (0 before the first)
+    fn current_seq(&self) -> u64 {
+        self.journal.lock().unwrap().last_seq()
+    }
+
+    /// Refresh the foreground command, working directory and attention
+    /// profiles of running sessions
+    ///
+    /// These are a few /proc reads, so they're refreshed whenever sessions are
+    /// listed or inspected rather than polled.
+    async fn refresh_foreground(&self) {
+        let live: Vec<_> = {
+            let handles = self.pty_handles.lock().await;
+            handles
+                .iter()
+                .map(|(&id, pty)| (id, pty.foreground_process()))
+                .collect()
+        };
+        let profiles = self.attention_profiles.current();
+        let mut registry = self.registry.write().await;
+        for (id, process) in live {
+            let Some(session) = registry.get_mut(id) else {
+                continue;
+            };
+            match process {
+                Some(process) => {
+                    session.foreground_command = Some(process.command);
+                    // Keep the last known directory if this one can't be read
+                    if process.cwd.is_some() {
+                        session.cwd = process.cwd;
+                    }
+                }
+                None => session.foreground_command = None,
+            }
+            session.active_attention_profiles = attention::ProfileSelection::for_session(
+                &session.attention_profiles,
+                session.foreground_command.as_deref(),
+            )
+            .select(&profiles)
+            .into_iter()
+            .map(|profile| profile.name)
+            .collect();
+        }
+    }
+
+    /// Which attention profiles check a session's output now: those it was
+    /// started with, or else those picked by its foreground command
+    async fn attention_selection(&self, session_id: SessionId) -> attention::ProfileSelection {
+        let bound = match self.registry.read().await.get(session_id) {
+            Some(session) => session.attention_profiles.clone(),
+            None => Vec::new(),
+        };
+        if !bound.is_empty() {
+            return attention::ProfileSelection::Named(bound);
+        }
+        let foreground = {
+            let handles = self.pty_handles.lock().await;
+            handles
+                .get(&session_id)
+                .and_then(|pty| pty.foreground_process())
+        };
+        attention::ProfileSelection::Foreground(foreground.map(|process| process.command))
     }
 
     /// Fetch a tail for a session, whether running or completed
@@ -262,15 +775,59 @@ impl DaemonState {
         })
     }
 
-    /// Store the last known tail for a completed session
-    async fn store_completed_tail(&self, session_id: SessionId, data: Vec<u8>) {
-        let mut tails = self.completed_tails.lock().await;
-        tails.insert(session_id, data);
+    /// Fetch the rendered screen for a session, whether running or completed
+    async fn session_screen(&self, session_id: SessionId) -> Option<ScreenText> {
+        {
+            let handles = self.pty_handles.lock().await;
+            if let Some(runtime) = handles.get(&session_id) {
+                return Some(runtime.screen_text());
+            }
+        }
+
+        let screens = self.completed_screens.lock().await;
+        screens.get(&session_id).cloned()
+    }
+
+    /// Store the last known tail and screen for a completed session
+    async fn store_completed_output(
+        &self,
+        session_id: SessionId,
+        data: Vec<u8>,
+        screen: ScreenText,
+    ) {
+        self.completed_tails.lock().await.insert(session_id, data);
+        self.completed_screens
+            .lock()
+            .await
+            .insert(session_id, screen);
+    }
+
+    /// Put back a stopped session whose restart failed, unless it was
+    /// stopped or removed since its reservation
+    async fn cancel_restart(&self, original: &Session) {
+        let mut registry = self.registry.write().await;
+        if let Some(session) = registry.get_mut(original.id) {
+            if session.status == SessionStatus::Running {
+                *session = original.clone();
+            }
+        }
+    }
+
+    /// Drop the reservation of a session whose start failed
+    async fn cancel_start(&self, session_id: SessionId) {
+        self.registry.write().await.remove(session_id);
+    }
+
+    /// Stop the PTY a failed start or restart spawned, if it is still around
+    async fn stop_spawned_pty(&self, session_id: SessionId) {
+        if let Some(mut pty) = self.pty_handles.lock().await.remove(&session_id) {
+            pty.stop();
+        }
     }
 
-    async fn clear_completed_tail(&self, session_id: SessionId) {
-        let mut tails = self.completed_tails.lock().await;
-        tails.remove(&session_id);
+    async fn clear_completed_output(&self, session_id: SessionId) {
+        self.completed_tails.lock().await.remove(&session_id);
+        self.completed_screens.lock().await.remove(&session_id);
     }
 
     fn attach_socket_path(&self, session_id: SessionId) -> PathBuf {
@@ -279,7 +836,13 @@ impl DaemonState {
             .join(format!("attach-{}.sock", session_id))
     }
 
-    async fn prepare_attach_socket(self: &Arc<Self>, session_id: SessionId) -> Result<PathBuf> {
+    /// Listen for the attach connection of a client that asked with `mode`
+    async fn prepare_attach_socket(
+        self: &Arc<Self>,
+        session_id: SessionId,
+        mode: AttachMode,
+        sees_attention: bool,
+    ) -> Result<PathBuf> {
         let path = self.attach_socket_path(session_id);
         if path.exists() {
             let _ = std::fs::remove_file(&path);
@@ -287,45 +850,82 @@ impl DaemonState {
 
         let listener = UnixListener::bind(&path)
             .with_context(|| format!("Failed to bind attach socket {}", path.display()))?;
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+                .with_context(|| format!("Failed to secure attach socket {}", path.display()))?;
+        }
 
         let mut listeners = self.attach_listeners.lock().await;
         if let Some(handle) = listeners.remove(&session_id) {
             handle.task.abort();
             let _ = std::fs::remove_file(handle.path);
         }
+        drop(listeners);
 
+        self.serve_attach_listener(session_id, path.clone(), listener, mode, sees_attention)
+            .await;
+        Ok(path)
+    }
+
+    /// Accept attach connections on `listener` until the session's socket is removed
+    async fn serve_attach_listener(
+        self: &Arc<Self>,
+        session_id: SessionId,
+        path: PathBuf,
+        listener: UnixListener,
+        mode: AttachMode,
+        sees_attention: bool,
+    ) {
+        let listener_fd = listener.as_raw_fd();
         let state = Arc::clone(self);
         let join_handle = tokio::spawn(async move {
-            if let Err(err) = DaemonState::run_attach_listener(state, session_id, listener).await {
+            if let Err(err) =
+                DaemonState::run_attach_listener(state, session_id, listener, mode, sees_attention)
+                    .await
+            {
                 eprintln!("Attach listener error for session {}: {}", session_id, err);
             }
         });
 
-        listeners.insert(
+        self.attach_listeners.lock().await.insert(
             session_id,
             AttachSocketHandle {
-                path: path.clone(),
+                path,
+                listener_fd,
                 task: join_handle,
             },
         );
-
-        Ok(path)
     }
 
     async fn run_attach_listener(
         state: Arc<DaemonState>,
         session_id: SessionId,
         listener: UnixListener,
+        mode: AttachMode,
+        sees_attention: bool,
     ) -> Result<()> {
         loop {
             match listener.accept().await {
-                Ok((stream, _addr)) => {
+                Ok((mut stream, _addr)) => {
+                    // Attach sockets carry raw terminal bytes, so refusals are plain text
+                    if let Some(message) = peer::peer_refusal(&stream) {
+                        eprintln!("Attach socket for session {}: {}", session_id, message);
+                        tokio::spawn(async move {
+                            let _ = stream
+                                .write_all(format!("[{}]\r\n", message).as_bytes())
+                                .await;
+                        });
+                        continue;
+                    }
                     let state_for_conn = Arc::clone(&state);
                     tokio::spawn(async move {
                         if let Err(err) = DaemonState::stream_attach_connection(
                             state_for_conn,
                             session_id,
                             stream,
+                            mode,
+                            sees_attention,
                         )
                         .await
                         {
@@ -344,7 +944,9 @@ impl DaemonState {
     async fn stream_attach_connection(
         state: Arc<DaemonState>,
         session_id: SessionId,
-        stream: UnixStream,
+        mut stream: UnixStream,
+        mode: AttachMode,
+        sees_attention: bool,
     ) -> Result<()> {
         let (input_tx, mut events) = {
             let handles = state.pty_handles.lock().await;
@@ -356,6 +958,18 @@ impl DaemonState {
             }
         };
 
+        let (_lease, mut kicked) = match state.add_viewer(session_id, mode, sees_attention).await {
+            Ok(viewer) => viewer,
+            Err(response) => {
+                if let DaemonResponse::Error { message, .. } = response {
+                    let _ = stream
+                        .write_all(format!("[{}]\r\n", message).as_bytes())
+                        .await;
+                }
+                return Ok(());
+            }
+        };
+
         let (mut socket_reader, mut socket_writer) = stream.into_split();
 
         let input_task = tokio::spawn({
@@ -365,6 +979,8 @@ impl DaemonState {
                 loop {
                     match socket_reader.read(&mut buf).await {
                         Ok(0) => break,
+                        // Read-only clients are read from only to notice them leave
+                        Ok(_) if mode == AttachMode::ReadOnly => continue,
                         Ok(n) => {
                             if input_tx.send(buf[..n].to_vec()).await.is_err() {
                                 break;
@@ -379,7 +995,16 @@ impl DaemonState {
 
         let output_task = tokio::spawn(async move {
             loop {
-                match events.recv().await {
+                let event = tokio::select! {
+                    event = events.recv() => event,
+                    Ok(()) = &mut kicked => {
+                        let msg = format!("\r\n[{}]\r\n", DETACHED_BY_EXCLUSIVE);
+                        let _ = socket_writer.write_all(msg.as_bytes()).await;
+                        let _ = socket_writer.flush().await;
+                        break;
+                    }
+                };
+                match event {
                     Ok(PtyEvent::Output(data)) => {
                         if socket_writer.write_all(&data).await.is_err() {
                             break;
@@ -417,75 +1042,350 @@ impl DaemonState {
         }
     }
 
-    fn spawn_attention_monitor(self: &Arc<Self>, session_id: SessionId) {
-        let state = Arc::clone(self);
-        tokio::spawn(async move {
-            let mut events = {
-                let handles = state.pty_handles.lock().await;
-                match handles.get(&session_id) {
-                    Some(runtime) => runtime.subscribe_events(),
-                    None => return,
-                }
-            };
-            let mut last_preview: Option<String> = None;
-            let mut accumulator = attention::AttentionAccumulator::default();
-            loop {
-                match events.recv().await {
-                    Ok(PtyEvent::Output(data)) => {
-                        for matched in accumulator.push_chunk(&data) {
-                            if last_preview
-                                .as_ref()
-                                .map(|prev| prev == &matched.preview)
-                                .unwrap_or(false)
-                            {
-                                continue;
-                            }
-                            last_preview = Some(matched.preview.clone());
-                            let summary = AttentionSummary {
-                                profile: matched.profile.to_string(),
-                                attention_type: matched.attention_type,
-                                preview: matched.preview,
-                                triggered_at: chrono::Utc::now(),
-                            };
-                            state.record_attention(session_id, summary).await;
-                        }
-                    }
-                    Ok(PtyEvent::Exited { .. }) => break,
-                    Ok(_) => continue,
-                    Err(broadcast::error::RecvError::Closed) => break,
-                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
-                }
-            }
+    /// Why a client can't attach to a session: another client holds it
+    /// exclusively and this one doesn't mean to take it over
+    fn attach_refusal(
+        viewers: &HashMap<SessionId, Vec<ViewerSlot>>,
+        session_id: SessionId,
+        mode: AttachMode,
+    ) -> Option<DaemonResponse> {
+        use rn_desktop_2_lib::session::protocol::DaemonErrorCode;
+
+        let held = viewers.get(&session_id).is_some_and(|slots| {
+            slots
+                .iter()
+                .any(|slot| slot.viewer.mode == AttachMode::Exclusive)
         });
+        (held && mode != AttachMode::Exclusive).then(|| DaemonResponse::Error {
+            code: DaemonErrorCode::InvalidRequest,
+            message: format!(
+                "Session {} is attached exclusively by another client",
+                session_id
+            ),
+        })
     }
 
-    async fn record_attention(&self, session_id: SessionId, summary: AttentionSummary) {
+    /// List a client as attached to a session, detaching the others first if
+    /// it attaches exclusively
+    ///
+    /// The receiver fires when another client later takes the session over.
+    /// The session's inbox items are acknowledged when the client
+    /// `sees_attention` (see `attach_sees_attention`).
+    async fn add_viewer(
+        self: &Arc<Self>,
+        session_id: SessionId,
+        mode: AttachMode,
+        sees_attention: bool,
+    ) -> Result<(ViewerLease, oneshot::Receiver<()>), DaemonResponse> {
+        let viewer_id = self.next_viewer_id.fetch_add(1, Ordering::Relaxed);
+        let (kick, kicked) = oneshot::channel();
         {
-            let mut registry = self.registry.write().await;
-            if let Some(session) = registry.get_mut(session_id) {
-                session.last_attention = Some(summary.clone());
-                session.updated_at = chrono::Utc::now();
-            } else {
-                return;
+            let mut viewers = self.attach_viewers.lock().unwrap();
+            if let Some(refusal) = Self::attach_refusal(&viewers, session_id, mode) {
+                return Err(refusal);
+            }
+            let slots = viewers.entry(session_id).or_default();
+            if mode == AttachMode::Exclusive {
+                for slot in slots.drain(..) {
+                    let _ = slot.kick.send(());
+                }
             }
+            slots.push(ViewerSlot {
+                viewer: AttachViewer {
+                    id: viewer_id,
+                    mode,
+                    attached_at: chrono::Utc::now(),
+                },
+                kick,
+            });
+        }
+        self.publish_viewers(session_id).await;
+        if sees_attention {
+            self.ack_attention(|item| item.session_id == session_id)
+                .await;
         }
 
-        let _ = self.save_registry().await;
-
-        self.broadcast(DaemonNotification::Attention {
+        let lease = ViewerLease {
+            state: Arc::clone(self),
             session_id,
-            profile: summary.profile.clone(),
-            attention_type: summary.attention_type,
-            preview: summary.preview.clone(),
-            triggered_at: summary.triggered_at,
+            viewer_id,
+        };
+        Ok((lease, kicked))
+    }
+
+    fn remove_viewer(self: &Arc<Self>, session_id: SessionId, viewer_id: u64) {
+        {
+            let mut viewers = self.attach_viewers.lock().unwrap();
+            let Some(slots) = viewers.get_mut(&session_id) else {
+                return;
+            };
+            slots.retain(|slot| slot.viewer.id != viewer_id);
+            if slots.is_empty() {
+                viewers.remove(&session_id);
+            }
+        }
+        // Nobody is left to tell once the runtime is shutting down
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let state = Arc::clone(self);
+            runtime.spawn(async move { state.publish_viewers(session_id).await });
+        }
+    }
+
+    /// Copy a session's viewers into the registry and tell subscribers
+    async fn publish_viewers(&self, session_id: SessionId) {
+        let mut registry = self.registry.write().await;
+        let viewers: Vec<AttachViewer> = self
+            .attach_viewers
+            .lock()
+            .unwrap()
+            .get(&session_id)
+            .map(|slots| slots.iter().map(|slot| slot.viewer.clone()).collect())
+            .unwrap_or_default();
+        let Some(session) = registry.get_mut(session_id) else {
+            return;
+        };
+        if session.viewers == viewers {
+            return;
+        }
+        session.viewers = viewers;
+        let session = session.clone();
+        drop(registry);
+
+        self.broadcast(
+            &session.project_path.clone(),
+            DaemonNotification::SessionUpdated { session },
+        );
+    }
+
+    /// Session, replayed output and screen for a client attaching to `session_id`
+    async fn attach_view(
+        &self,
+        session_id: SessionId,
+        tail_bytes: Option<usize>,
+        offset: Option<u64>,
+    ) -> Result<AttachView, DaemonResponse> {
+        use rn_desktop_2_lib::session::protocol::DaemonErrorCode;
+
+        let session = {
+            let registry = self.registry.read().await;
+            match registry.get(session_id) {
+                Some(session) => session.clone(),
+                None => {
+                    return Err(DaemonResponse::Error {
+                        code: DaemonErrorCode::NotFound,
+                        message: format!("Session {} not found", session_id),
+                    });
+                }
+            }
+        };
+
+        let screen = {
+            let handles = self.pty_handles.lock().await;
+            match handles.get(&session_id) {
+                Some(runtime) => runtime.screen_repaint(),
+                None => {
+                    return Err(DaemonResponse::Error {
+                        code: DaemonErrorCode::Internal,
+                        message: format!("Session {} is not running", session_id),
+                    });
+                }
+            }
+        };
+
+        // History from the log replaces the repaint, so clients replay it as-is
+        match offset {
+            Some(offset) => {
+                let max_bytes = tail_bytes.unwrap_or(output_log::MAX_LOG_READ_BYTES);
+                match self.read_output_log(session_id, offset, max_bytes).await {
+                    Ok(range) => Ok(AttachView {
+                        session,
+                        tail: Some(range.data),
+                        screen: None,
+                        offset: Some(range.offset),
+                    }),
+                    Err(e) => Err(DaemonResponse::Error {
+                        code: DaemonErrorCode::NotFound,
+                        message: format!("No output log for session {}: {}", session_id, e),
+                    }),
+                }
+            }
+            None => {
+                let tail = self
+                    .session_tail(session_id, tail_bytes.unwrap_or(self.settings().tail_bytes))
+                    .await;
+                Ok(AttachView {
+                    session,
+                    tail,
+                    screen: Some(screen),
+                    offset: None,
+                })
+            }
+        }
+    }
+
+    /// Start forwarding a session's output to a connection as stream frames
+    ///
+    /// Ends with a `Close` frame when the session exits or another client
+    /// takes it over.
+    async fn open_attach_stream(
+        self: &Arc<Self>,
+        session_id: SessionId,
+        mode: AttachMode,
+        sees_attention: bool,
+        frames_tx: tokio::sync::mpsc::Sender<StreamFrame>,
+    ) -> Result<AttachStream, DaemonResponse> {
+        use rn_desktop_2_lib::session::protocol::DaemonErrorCode;
+
+        let (input_tx, mut events) = {
+            let handles = self.pty_handles.lock().await;
+            match handles.get(&session_id) {
+                Some(runtime) => (runtime.input_sender(), runtime.subscribe_events()),
+                None => {
+                    return Err(DaemonResponse::Error {
+                        code: DaemonErrorCode::Internal,
+                        message: format!("Session {} has no active PTY to attach", session_id),
+                    });
+                }
+            }
+        };
+        let (lease, mut kicked) = self.add_viewer(session_id, mode, sees_attention).await?;
+
+        let task = tokio::spawn(async move {
+            let close = loop {
+                let event = tokio::select! {
+                    event = events.recv() => event,
+                    Ok(()) = &mut kicked => {
+                        break StreamFrame::Close {
+                            session_id,
+                            exited: false,
+                            exit_code: None,
+                            message: Some(DETACHED_BY_EXCLUSIVE.to_string()),
+                        };
+                    }
+                };
+                match event {
+                    Ok(PtyEvent::Output(data)) => {
+                        let frame = StreamFrame::Data { session_id, data };
+                        if frames_tx.send(frame).await.is_err() {
+                            return;
+                        }
+                    }
+                    Ok(PtyEvent::Exited { exit_code }) => {
+                        break StreamFrame::Close {
+                            session_id,
+                            exited: true,
+                            exit_code,
+                            message: None,
+                        };
+                    }
+                    Ok(PtyEvent::Idle) | Ok(PtyEvent::Active) => continue,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => {
+                        break StreamFrame::Close {
+                            session_id,
+                            exited: true,
+                            exit_code: None,
+                            message: None,
+                        };
+                    }
+                }
+            };
+            let _ = frames_tx.send(close).await;
+        });
+
+        Ok(AttachStream {
+            input_tx,
+            read_only: mode == AttachMode::ReadOnly,
+            task,
+            _lease: lease,
+        })
+    }
+
+    fn spawn_attention_monitor(self: &Arc<Self>, session_id: SessionId) {
+        let state = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut events = {
+                let handles = state.pty_handles.lock().await;
+                match handles.get(&session_id) {
+                    Some(runtime) => runtime.subscribe_events(),
+                    None => return,
+                }
+            };
+            let mut last_preview: Option<String> = None;
+            let mut accumulator = attention::AttentionAccumulator::default()
+                .with_profiles(state.attention_profiles.clone());
+            // The foreground command decides which profiles apply, so recheck
+            // it while output is arriving, at most every FOREGROUND_RECHECK
+            let mut selection_checked: Option<std::time::Instant> = None;
+            loop {
+                match events.recv().await {
+                    Ok(PtyEvent::Output(data)) => {
+                        if selection_checked.is_none_or(|at| at.elapsed() >= FOREGROUND_RECHECK) {
+                            accumulator.set_selection(state.attention_selection(session_id).await);
+                            selection_checked = Some(std::time::Instant::now());
+                        }
+                        for matched in accumulator.push_chunk(&data) {
+                            if last_preview
+                                .as_ref()
+                                .map(|prev| prev == &matched.preview)
+                                .unwrap_or(false)
+                            {
+                                continue;
+                            }
+                            last_preview = Some(matched.preview.clone());
+                            let summary = AttentionSummary {
+                                id: 0,
+                                profile: matched.profile.to_string(),
+                                attention_type: matched.attention_type,
+                                preview: matched.preview,
+                                triggered_at: chrono::Utc::now(),
+                                acked: false,
+                            };
+                            state.record_attention(session_id, summary).await;
+                        }
+                    }
+                    Ok(PtyEvent::Exited { .. }) => break,
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                }
+            }
         });
+    }
+
+    async fn record_attention(&self, session_id: SessionId, summary: AttentionSummary) {
+        let (summary, project_path) = {
+            let mut registry = self.registry.write().await;
+            let Some(summary) = registry.add_attention(session_id, summary) else {
+                return;
+            };
+            let Some(session) = registry.get_mut(session_id) else {
+                return;
+            };
+            session.updated_at = chrono::Utc::now();
+            (summary, session.project_path.clone())
+        };
+
+        let _ = self.save_registry().await;
+
+        self.broadcast(
+            &project_path,
+            DaemonNotification::Attention {
+                session_id,
+                attention_id: summary.id,
+                profile: summary.profile.clone(),
+                attention_type: summary.attention_type,
+                preview: summary.preview.clone(),
+                triggered_at: summary.triggered_at,
+            },
+        );
 
         // Send terminal notification with time-based debouncing
         {
             let mut debouncers = self.notification_debouncers.lock().await;
-            let debouncer = debouncers
-                .entry(session_id)
-                .or_insert_with(NotificationDebouncer::new);
+            let debouncer = debouncers.entry(session_id).or_insert_with(|| {
+                NotificationDebouncer::with_cooldown(self.settings().notification_debounce())
+            });
             if debouncer.should_notify() {
                 notify_attention(&summary.profile, summary.attention_type, &summary.preview);
             }
@@ -538,17 +1438,37 @@ async fn update_markdown_badge(
 }
 
 /// Handle a single client connection
+///
+/// A handoff request ends the conversation: the connection goes to the main
+/// loop, which replies with this daemon's sessions and exits.
 async fn handle_client(
     state: Arc<DaemonState>,
     mut stream: UnixStream,
     shutdown_tx: tokio::sync::mpsc::Sender<()>,
+    handoff_tx: tokio::sync::mpsc::Sender<UnixStream>,
 ) -> Result<()> {
+    let mut handoff_requested = false;
     let (reader, mut writer) = stream.split();
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
 
-    // Subscribe to updates for this client
-    let mut updates_rx = state.updates_tx.subscribe();
+    // Notifications flow only once the client subscribes
+    let mut subscription: Option<(
+        SubscriptionFilter,
+        broadcast::Receiver<NotificationEnvelope>,
+    )> = None;
+
+    // Responses to pipelined requests, finished in whatever order
+    let (responses_tx, mut responses_rx) =
+        tokio::sync::mpsc::channel::<ResponseFrame>(PIPELINED_RESPONSE_BUFFER);
+    let pipeline_slots = Arc::new(tokio::sync::Semaphore::new(MAX_PIPELINED_REQUESTS));
+
+    // Byte payloads go out as arrays until a handshake picks something else
+    let mut byte_encoding = ByteEncoding::Array;
+
+    // Attach streams open on this connection, and the frames they produce
+    let mut streams: HashMap<SessionId, AttachStream> = HashMap::new();
+    let (frames_tx, mut frames_rx) = tokio::sync::mpsc::channel::<StreamFrame>(STREAM_FRAME_BUFFER);
 
     loop {
         tokio::select! {
@@ -562,36 +1482,124 @@ async fn handle_client(
                     Ok(_) => {
                         use rn_desktop_2_lib::session::protocol::{DaemonErrorCode, MAX_REQUEST_FRAME_SIZE};
 
+                        // Frames on attach streams get no response
+                        if line.len() <= MAX_REQUEST_FRAME_SIZE {
+                            if let Ok(frame) = deserialize_message::<StreamFrame>(line.as_bytes()) {
+                                line.clear();
+                                if let Some(close) =
+                                    handle_stream_frame(&state, &mut streams, frame, &shutdown_tx)
+                                        .await
+                                {
+                                    let bytes = serialize_message_with(&close, byte_encoding)?;
+                                    writer.write_all(&bytes).await?;
+                                    writer.flush().await?;
+                                }
+                                continue;
+                            }
+                        }
+
                         // Enforce max request frame size (1MB)
-                        let response = if line.len() > MAX_REQUEST_FRAME_SIZE {
-                            DaemonResponse::Error {
+                        let frame = if line.len() > MAX_REQUEST_FRAME_SIZE {
+                            Err(DaemonResponse::Error {
                                 code: DaemonErrorCode::InvalidRequest,
                                 message: format!(
                                     "Request frame too large: {} bytes (max {})",
                                     line.len(),
                                     MAX_REQUEST_FRAME_SIZE
                                 ),
-                            }
+                            })
                         } else {
-                            match deserialize_message::<DaemonRequest>(line.as_bytes()) {
-                                Ok(request) => {
-                                    handle_request(&state, request, &shutdown_tx).await
-                                }
-                                Err(e) => {
-                                    DaemonResponse::Error {
-                                        code: DaemonErrorCode::InvalidRequest,
-                                        message: format!("Failed to parse request: {}", e),
-                                    }
+                            deserialize_message::<RequestFrame>(line.as_bytes()).map_err(|e| {
+                                DaemonResponse::Error {
+                                    code: DaemonErrorCode::InvalidRequest,
+                                    message: format!("Failed to parse request: {}", e),
                                 }
+                            })
+                        };
+                        let request_id = match frame {
+                            Ok(ref frame) => frame.request_id,
+                            // Echo the id of a request that didn't parse, if it has one
+                            Err(_) => raw_request_id(&line),
+                        };
+                        line.clear();
+
+                        let response = match frame.map(|frame| frame.request) {
+                            Ok(DaemonRequest::Handoff) => {
+                                handoff_requested = true;
+                                break;
+                            }
+                            Ok(DaemonRequest::Subscribe {
+                                projects,
+                                session_ids,
+                                kinds,
+                            }) => {
+                                let filter = SubscriptionFilter {
+                                    projects,
+                                    session_ids,
+                                    kinds,
+                                };
+                                // Keep the receiver so nothing is missed while refiltering
+                                let updates_rx = match subscription.take() {
+                                    Some((_, updates_rx)) => updates_rx,
+                                    None => state.updates_tx.subscribe(),
+                                };
+                                subscription = Some((filter.clone(), updates_rx));
+                                DaemonResponse::Subscribed { filter }
+                            }
+                            Ok(DaemonRequest::Unsubscribe) => {
+                                subscription = None;
+                                DaemonResponse::Unsubscribed
                             }
+                            Ok(DaemonRequest::OpenStream {
+                                session_id,
+                                tail_bytes,
+                                offset,
+                                mode,
+                            }) => {
+                                open_stream(
+                                    &state,
+                                    &mut streams,
+                                    &frames_tx,
+                                    session_id,
+                                    tail_bytes,
+                                    offset,
+                                    mode,
+                                )
+                                .await
+                            }
+                            Ok(request) if request_id.is_some() => {
+                                // Pipelined: answer whenever it's done
+                                let slot = Arc::clone(&pipeline_slots)
+                                    .acquire_owned()
+                                    .await
+                                    .expect("pipeline semaphore is never closed");
+                                let state = Arc::clone(&state);
+                                let shutdown_tx = shutdown_tx.clone();
+                                let responses_tx = responses_tx.clone();
+                                tokio::spawn(async move {
+                                    let response =
+                                        handle_request(&state, request, &shutdown_tx).await;
+                                    // Before the send, which waits on this loop
+                                    drop(slot);
+                                    let _ = responses_tx
+                                        .send(ResponseFrame { request_id, response })
+                                        .await;
+                                });
+                                continue;
+                            }
+                            Ok(request) => {
+                                handle_request(&state, request, &shutdown_tx).await
+                            }
+                            Err(response) => response,
                         };
 
                         // Send response
-                        let bytes = serialize_message(&response)?;
+                        let bytes = serialize_response_frame(
+                            &ResponseFrame { request_id, response },
+                            &mut byte_encoding,
+                        )?;
                         writer.write_all(&bytes).await?;
                         writer.flush().await?;
-
-                        line.clear();
                     }
                     Err(e) => {
                         eprintln!("Error reading from client: {}", e);
@@ -600,19 +1608,46 @@ async fn handle_client(
                 }
             }
 
-            // Forward broadcast updates to client
-            result = updates_rx.recv() => {
+            // Send responses to pipelined requests as they finish
+            Some(frame) = responses_rx.recv() => {
+                let bytes = serialize_response_frame(&frame, &mut byte_encoding)?;
+                writer.write_all(&bytes).await?;
+                writer.flush().await?;
+            }
+
+            // Forward output from this connection's attach streams
+            Some(frame) = frames_rx.recv() => {
+                if let StreamFrame::Close { session_id, .. } = frame {
+                    streams.remove(&session_id);
+                }
+                let bytes = serialize_message_with(&frame, byte_encoding)?;
+                if writer.write_all(&bytes).await.is_err() {
+                    break; // Client disconnected
+                }
+                let _ = writer.flush().await;
+            }
+
+            // Forward subscribed updates to client
+            result = next_update(&mut subscription) => {
                 match result {
-                    Ok(notification) => {
-                        let bytes = serialize_message(&notification)?;
+                    Ok(envelope) => {
+                        let bytes = serialize_message_with(&envelope, byte_encoding)?;
                         if writer.write_all(&bytes).await.is_err() {
                             break; // Client disconnected
                         }
                         let _ = writer.flush().await;
                     }
-                    Err(broadcast::error::RecvError::Lagged(_)) => {
-                        // Client is too slow, skip missed messages
-                        continue;
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        // Client is too slow; tell it to catch up from a snapshot
+                        let bytes = serialize_message(&NotificationEnvelope {
+                            seq: state.current_seq(),
+                            project_path: None,
+                            notification: DaemonNotification::Resync { missed },
+                        })?;
+                        if writer.write_all(&bytes).await.is_err() {
+                            break;
+                        }
+                        let _ = writer.flush().await;
                     }
                     Err(broadcast::error::RecvError::Closed) => {
                         break;
@@ -622,54 +1657,428 @@ async fn handle_client(
         }
     }
 
+    if handoff_requested {
+        let _ = handoff_tx.send(stream).await;
+    }
+
     Ok(())
 }
 
-/// Handle a single request from a client
-async fn handle_request(
+/// Answer the first request on a connection from another user with why it
+/// was refused, then hang up
+async fn refuse_connection(mut stream: UnixStream, message: String) {
+    use rn_desktop_2_lib::session::protocol::{DaemonErrorCode, MAX_REQUEST_FRAME_SIZE};
+
+    // Reading first means the client isn't cut off mid-write, and sees the error
+    let (reader, mut writer) = stream.split();
+    let mut reader = BufReader::new(reader.take(MAX_REQUEST_FRAME_SIZE as u64));
+    let mut line = String::new();
+    let _ = tokio::time::timeout(REFUSAL_TIMEOUT, reader.read_line(&mut line)).await;
+
+    let frame = ResponseFrame {
+        request_id: raw_request_id(&line),
+        response: DaemonResponse::Error {
+            code: DaemonErrorCode::PermissionDenied,
+            message,
+        },
+    };
+    if let Ok(bytes) = serialize_message(&frame) {
+        let _ = writer.write_all(&bytes).await;
+        let _ = writer.flush().await;
+    }
+}
+
+/// The `request_id` of a request frame that failed to parse, if it has one
+fn raw_request_id(line: &str) -> Option<RequestId> {
+    serde_json::from_str::<serde_json::Value>(line)
+        .ok()?
+        .get("request_id")?
+        .as_u64()
+}
+
+/// Open an attach stream on a connection, replacing any it had for the session
+async fn open_stream(
     state: &Arc<DaemonState>,
-    request: DaemonRequest,
-    shutdown_tx: &tokio::sync::mpsc::Sender<()>,
+    streams: &mut HashMap<SessionId, AttachStream>,
+    frames_tx: &tokio::sync::mpsc::Sender<StreamFrame>,
+    session_id: SessionId,
+    tail_bytes: Option<usize>,
+    offset: Option<u64>,
+    mode: AttachMode,
 ) -> DaemonResponse {
-    use rn_desktop_2_lib::session::protocol::{DaemonErrorCode, PROTOCOL_VERSION};
+    let view = match state.attach_view(session_id, tail_bytes, offset).await {
+        Ok(view) => view,
+        Err(response) => return response,
+    };
+    // Replacing a stream must not count it against an exclusive attach
+    streams.remove(&session_id);
+    let stream = match state
+        .open_attach_stream(
+            session_id,
+            mode,
+            attach_sees_attention(mode, offset),
+            frames_tx.clone(),
+        )
+        .await
+    {
+        Ok(stream) => stream,
+        Err(response) => return response,
+    };
+    streams.insert(session_id, stream);
 
-    match request {
-        DaemonRequest::Handshake { client_version } => {
-            if client_version != PROTOCOL_VERSION {
-                let message = if client_version < PROTOCOL_VERSION {
-                    "Daemon is newer than app—please update the app.".to_string()
-                } else {
-                    "Daemon is outdated—please restart daemon.".to_string()
-                };
-                return DaemonResponse::Error {
-                    code: DaemonErrorCode::VersionMismatch,
-                    message,
-                };
+    // The session as of now lists this client among its viewers
+    let mut session = view.session;
+    if let Some(current) = state.registry.read().await.get(session_id) {
+        session.viewers = current.viewers.clone();
+    }
+    DaemonResponse::StreamOpened {
+        session,
+        tail: view.tail,
+        screen: view.screen,
+        offset: view.offset,
+    }
+}
+
+/// Whether attaching counts as seeing the session's inbox items
+///
+/// A client typing into the live screen has seen them. Read-only attaches
+/// (dashboards, mirrors nobody may be watching) and replays of older output
+/// from the log haven't.
+fn attach_sees_attention(mode: AttachMode, offset: Option<u64>) -> bool {
+    mode != AttachMode::ReadOnly && offset.is_none()
+}
+
+/// Apply a frame a client sent on one of its attach streams
+///
+/// Returns a `Close` frame for the client when the stream isn't open (any
+/// more).
+async fn handle_stream_frame(
+    state: &Arc<DaemonState>,
+    streams: &mut HashMap<SessionId, AttachStream>,
+    frame: StreamFrame,
+    shutdown_tx: &tokio::sync::mpsc::Sender<()>,
+) -> Option<StreamFrame> {
+    let session_id = frame.session_id();
+    let closed = |message: String| StreamFrame::Close {
+        session_id,
+        exited: false,
+        exit_code: None,
+        message: Some(message),
+    };
+    match frame {
+        StreamFrame::Data { data, .. } => {
+            let Some(stream) = streams.get(&session_id) else {
+                return Some(closed(format!("No stream open for session {}", session_id)));
+            };
+            if stream.read_only {
+                return None;
             }
-            DaemonResponse::Handshake {
-                protocol_version: PROTOCOL_VERSION,
+            if stream.input_tx.send(data).await.is_err() {
+                streams.remove(&session_id);
+                return Some(closed(format!("Session {} is not running", session_id)));
+            }
+            None
+        }
+        StreamFrame::Resize { cols, rows, .. } => {
+            let Some(stream) = streams.get(&session_id) else {
+                return Some(closed(format!("No stream open for session {}", session_id)));
+            };
+            // Watchers don't get to reflow the terminal for everyone else
+            if stream.read_only {
+                return None;
+            }
+            // Best effort, like resize requests from legacy attach clients
+            let resize = DaemonRequest::Resize {
+                session_id,
+                cols,
+                rows,
+            };
+            if let DaemonResponse::Error { message, .. } =
+                handle_request(state, resize, shutdown_tx).await
+            {
+                eprintln!("Failed to resize session {}: {}", session_id, message);
             }
+            None
+        }
+        StreamFrame::Close { .. } => {
+            streams.remove(&session_id);
+            None
         }
+    }
+}
 
-        DaemonRequest::Ping => DaemonResponse::Pong,
+/// Serialize a response for a connection, switching its byte encoding once a
+/// handshake settles on one
+fn serialize_response_frame(
+    frame: &ResponseFrame,
+    byte_encoding: &mut ByteEncoding,
+) -> Result<Vec<u8>, serde_json::Error> {
+    let bytes = serialize_message_with(frame, *byte_encoding)?;
+    if let DaemonResponse::Handshake {
+        byte_encoding: Some(negotiated),
+        ..
+    } = frame.response
+    {
+        *byte_encoding = negotiated;
+    }
+    Ok(bytes)
+}
 
-        DaemonRequest::Shutdown => {
-            // Signal main loop to shut down
-            let _ = shutdown_tx.send(()).await;
-            DaemonResponse::ShuttingDown
+/// Wait for the next notification matching a connection's subscription
+///
+/// Never resolves for connections that haven't subscribed.
+async fn next_update(
+    subscription: &mut Option<(
+        SubscriptionFilter,
+        broadcast::Receiver<NotificationEnvelope>,
+    )>,
+) -> Result<NotificationEnvelope, broadcast::error::RecvError> {
+    let Some((filter, updates_rx)) = subscription else {
+        return std::future::pending().await;
+    };
+    loop {
+        let envelope = updates_rx.recv().await?;
+        if filter.matches(&envelope) {
+            return Ok(envelope);
         }
+    }
+}
 
-        DaemonRequest::Start {
-            task_key,
-            task_id,
-            project_path,
-            shell,
-        } => {
-            // Read and parse the markdown file
-            let project_file = PathBuf::from(&project_path);
-            let content = match tokio::fs::read_to_string(&project_file).await {
-                Ok(c) => c,
-                Err(e) => {
+/// Hand every session and socket to the daemon on the other end of `stream`
+///
+/// PTY readers are paused before their tails and screens are captured, so
+/// output produced during the handoff stays in the PTY for the new daemon.
+/// If sending fails the readers resume and this daemon carries on.
+async fn hand_off(
+    state: &Arc<DaemonState>,
+    listener: &UnixListener,
+    stream: UnixStream,
+) -> Result<()> {
+    // Registry before handles, the order restarts and reconciliation take them in
+    let registry = state.registry.read().await;
+    let handles = state.pty_handles.lock().await;
+    let attach_listeners = state.attach_listeners.lock().await;
+
+    let mut detached = Vec::new();
+    let mut entries = Vec::new();
+    let mut result = Ok(());
+    for (session_id, runtime) in handles.iter() {
+        if !runtime.is_alive() {
+            continue;
+        }
+        match runtime.detach_for_handoff() {
+            Ok((session, pty)) => {
+                detached.push(runtime);
+                entries.push(HandoffEntry {
+                    session,
+                    pty,
+                    attach_listener: attach_listeners
+                        .get(session_id)
+                        .map(|handle| handle.listener_fd),
+                });
+            }
+            Err(e) => {
+                result = Err(e.context(format!("Failed to pause session {}", session_id)));
+                break;
+            }
+        }
+    }
+
+    if result.is_ok() {
+        result = registry.save(&state.config);
+    }
+    if result.is_ok() {
+        result = stream
+            .into_std()
+            .and_then(|stream| stream.set_nonblocking(false).map(|_| stream))
+            .map_err(anyhow::Error::from)
+            .and_then(|stream| handoff::send_handoff(&stream, listener.as_raw_fd(), entries));
+    }
+
+    if result.is_err() {
+        for runtime in detached {
+            runtime.resume_after_handoff();
+        }
+    }
+    result
+}
+
+/// Adopt a listening socket received from another daemon
+fn listener_from_fd(fd: OwnedFd) -> Result<UnixListener> {
+    let listener = std::os::unix::net::UnixListener::from(fd);
+    listener.set_nonblocking(true)?;
+    Ok(UnixListener::from_std(listener)?)
+}
+
+/// Capabilities announced in the handshake
+fn daemon_capabilities(settings: &Settings) -> Vec<Capability> {
+    let output_log = settings.output_log_limits().is_some();
+    Capability::ALL
+        .iter()
+        .copied()
+        .filter(|&capability| capability != Capability::OutputLog || output_log)
+        .collect()
+}
+
+/// Read config.toml and attention.toml, naming the file in any error
+fn load_config(config: &Config) -> Result<(Settings, Vec<attention::AttentionProfile>)> {
+    let settings = config
+        .load_settings()
+        .with_context(|| format!("Invalid {}", config.settings_file().display()))?;
+    let profiles_file = config.attention_profiles_file();
+    let profiles = attention::load_profiles(&profiles_file)
+        .with_context(|| format!("Invalid {}", profiles_file.display()))?;
+    Ok((settings, profiles))
+}
+
+/// Handle a single request from a client
+async fn handle_request(
+    state: &Arc<DaemonState>,
+    request: DaemonRequest,
+    shutdown_tx: &tokio::sync::mpsc::Sender<()>,
+) -> DaemonResponse {
+    use rn_desktop_2_lib::session::protocol::{
+        negotiate_version, DaemonErrorCode, MIN_PROTOCOL_VERSION,
+    };
+
+    match request {
+        DaemonRequest::Handshake {
+            client_version,
+            min_version,
+            byte_encoding,
+        } => match negotiate_version(client_version, min_version) {
+            Ok(protocol_version) => DaemonResponse::Handshake {
+                protocol_version,
+                min_protocol_version: Some(MIN_PROTOCOL_VERSION),
+                capabilities: Some(daemon_capabilities(&state.settings())),
+                // Every encoding this build can name, it can write
+                byte_encoding,
+            },
+            Err(message) => DaemonResponse::Error {
+                code: DaemonErrorCode::VersionMismatch,
+                message,
+            },
+        },
+
+        DaemonRequest::Ping => DaemonResponse::Pong,
+
+        // Intercepted by handle_client, which owns the connection
+        DaemonRequest::Handoff => DaemonResponse::Error {
+            code: DaemonErrorCode::InvalidRequest,
+            message: "Handoff is not available on this connection".to_string(),
+        },
+        DaemonRequest::Subscribe { .. } | DaemonRequest::Unsubscribe => DaemonResponse::Error {
+            code: DaemonErrorCode::InvalidRequest,
+            message: "Subscriptions are not available on this connection".to_string(),
+        },
+        DaemonRequest::OpenStream { .. } => DaemonResponse::Error {
+            code: DaemonErrorCode::InvalidRequest,
+            message: "Streams are not available on this connection".to_string(),
+        },
+
+        DaemonRequest::ReloadConfig => match state.reload_config().await {
+            Ok((settings, attention_profiles)) => DaemonResponse::ConfigReloaded {
+                settings,
+                attention_profiles,
+            },
+            Err(e) => DaemonResponse::Error {
+                code: DaemonErrorCode::InvalidConfig,
+                message: format!("{:#} (keeping the current config)", e),
+            },
+        },
+
+        DaemonRequest::AttentionList {
+            project_path,
+            include_acked,
+        } => {
+            let registry = state.registry.read().await;
+            DaemonResponse::AttentionItems {
+                items: registry.attention_items(project_path.as_deref(), include_acked),
+            }
+        }
+
+        DaemonRequest::AttentionAck { ids } => {
+            {
+                let registry = state.registry.read().await;
+                if let Some(unknown) = ids.iter().find(|id| {
+                    !registry
+                        .attention
+                        .iter()
+                        .any(|item| item.summary.id == **id)
+                }) {
+                    return DaemonResponse::Error {
+                        code: DaemonErrorCode::NotFound,
+                        message: format!("Attention item {} not found", unknown),
+                    };
+                }
+            }
+            let acked = state
+                .ack_attention(|item| ids.contains(&item.summary.id))
+                .await;
+            DaemonResponse::AttentionAcked { ids: acked }
+        }
+
+        DaemonRequest::AttentionAckAll {
+            project_path,
+            session_id,
+        } => {
+            let acked = state
+                .ack_attention(|item| {
+                    project_path
+                        .as_ref()
+                        .is_none_or(|path| &item.project_path == path)
+                        && session_id.is_none_or(|id| item.session_id == id)
+                })
+                .await;
+            DaemonResponse::AttentionAcked { ids: acked }
+        }
+
+        DaemonRequest::Shutdown => {
+            // Signal main loop to shut down
+            let _ = shutdown_tx.send(()).await;
+            DaemonResponse::ShuttingDown
+        }
+
+        DaemonRequest::Start {
+            task_key,
+            task_id,
+            project_path,
+            shell,
+            cwd,
+            env,
+            record,
+            attention_profiles,
+        } => {
+            if let Some(ref dir) = cwd {
+                if !std::path::Path::new(dir).is_dir() {
+                    return DaemonResponse::Error {
+                        code: DaemonErrorCode::InvalidRequest,
+                        message: format!("Working directory '{}' does not exist", dir),
+                    };
+                }
+            }
+
+            let profiles = state.attention_profiles.current();
+            if let Some(unknown) = attention_profiles
+                .iter()
+                .find(|name| !profiles.iter().any(|profile| profile.name == **name))
+            {
+                let known: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+                return DaemonResponse::Error {
+                    code: DaemonErrorCode::InvalidRequest,
+                    message: format!(
+                        "Unknown attention profile '{}' (known: {})",
+                        unknown,
+                        known.join(", ")
+                    ),
+                };
+            }
+
+            // Read and parse the markdown file
+            let project_file = PathBuf::from(&project_path);
+            let content = match tokio::fs::read_to_string(&project_file).await {
+                Ok(c) => c,
+                Err(e) => {
                     return DaemonResponse::Error {
                         code: DaemonErrorCode::Internal,
                         message: format!("Failed to read project file '{}': {}", project_path, e),
@@ -696,34 +2105,58 @@ async fn handle_request(
             let full_task_name = task.name.clone();
             let resolved_task_id = task_id.or_else(|| task.task_id.clone());
 
-            let mut registry = state.registry.write().await;
+            // Reserve the session as Running, so a second start for the task
+            // is refused, without holding the registry across the spawn
+            let session = {
+                let mut registry = state.registry.write().await;
 
-            // Check if session already exists for this task
-            if let Some(existing) = registry.find_by_task_key(&full_task_name, &project_path) {
-                return DaemonResponse::Error {
-                    code: DaemonErrorCode::Internal,
-                    message: format!(
-                        "Session already exists for task '{}' (id: {})",
-                        full_task_name, existing.id
-                    ),
-                };
-            }
+                // Check if session already exists for this task
+                if let Some(existing) = registry.find_by_task_key(&full_task_name, &project_path) {
+                    return DaemonResponse::Error {
+                        code: DaemonErrorCode::Internal,
+                        message: format!(
+                            "Session already exists for task '{}' (id: {})",
+                            full_task_name, existing.id
+                        ),
+                    };
+                }
 
-            // Allocate new session
-            let id = registry.allocate_id();
-            let mut session = Session::new(
-                id,
-                full_task_name.clone(),
-                resolved_task_id,
-                project_path.clone(),
-            );
-            session.status = SessionStatus::Running;
-            session.exit_code = None;
+                // Allocate new session
+                let id = registry.allocate_id();
+                let mut session = Session::new(
+                    id,
+                    full_task_name.clone(),
+                    resolved_task_id,
+                    project_path.clone(),
+                );
+                session.status = SessionStatus::Running;
+                session.exit_code = None;
+                session.recording = record;
+                // Remember how the PTY was spawned so the session can be restarted
+                session.shell_command = shell;
+                session.spawn_cwd = cwd;
+                session.env = snapshot_env(env);
+                session.attention_profiles = attention_profiles;
+                registry.insert(session.clone());
+                session
+            };
+            let id = session.id;
+            let spec = SpawnSpec {
+                shell: session.shell_command.clone(),
+                cwd: session.spawn_cwd.as_ref().map(PathBuf::from),
+                env: session.env.clone(),
+                tuning: state.settings().runtime_tuning(),
+            };
 
             // Spawn the PTY with environment variables for shell integration
-            let pty = match PtyRuntime::spawn(id, shell, &full_task_name, &project_path) {
+            state.clear_session_files(id);
+            let pty = match state
+                .spawn_pty(id, spec, &full_task_name, &project_path, record)
+                .await
+            {
                 Ok(p) => p,
                 Err(e) => {
+                    state.cancel_start(id).await;
                     return DaemonResponse::Error {
                         code: DaemonErrorCode::Internal,
                         message: format!("Failed to spawn PTY: {}", e),
@@ -736,7 +2169,7 @@ async fn handle_request(
                 let mut handles = state.pty_handles.lock().await;
                 handles.insert(id, pty);
             }
-            state.clear_completed_tail(id).await;
+            state.clear_completed_output(id).await;
 
             // Update the markdown with session badge (reads fresh content to avoid clobbering)
             let session_status = TaskSessionStatus {
@@ -747,20 +2180,31 @@ async fn handle_request(
                 update_markdown_badge(&project_path, &full_task_name, Some(&session_status)).await
             {
                 // Clean up PTY on failure
-                let mut handles = state.pty_handles.lock().await;
-                if let Some(mut pty) = handles.remove(&id) {
-                    pty.stop();
-                }
+                state.stop_spawned_pty(id).await;
+                state.cancel_start(id).await;
                 return DaemonResponse::Error {
                     code: DaemonErrorCode::Internal,
                     message: format!("Failed to update markdown file: {}", e),
                 };
             }
 
-            registry.insert(session.clone());
+            // Commit, unless the session was stopped or removed meanwhile
+            let session = {
+                let registry = state.registry.read().await;
+                registry
+                    .get(id)
+                    .filter(|session| session.status == SessionStatus::Running)
+                    .cloned()
+            };
+            let Some(session) = session else {
+                state.stop_spawned_pty(id).await;
+                return DaemonResponse::Error {
+                    code: DaemonErrorCode::InvalidRequest,
+                    message: format!("Session {} was stopped while starting", id),
+                };
+            };
 
             // Save to disk
-            drop(registry);
             if let Err(e) = state.save_registry().await {
                 return DaemonResponse::Error {
                     code: DaemonErrorCode::Internal,
@@ -779,9 +2223,12 @@ async fn handle_request(
             state.spawn_attention_monitor(session_id);
 
             // Broadcast update
-            state.broadcast(DaemonNotification::SessionUpdated {
-                session: session.clone(),
-            });
+            state.broadcast(
+                &session.project_path,
+                DaemonNotification::SessionUpdated {
+                    session: session.clone(),
+                },
+            );
 
             DaemonResponse::SessionStarted { session }
         }
@@ -795,7 +2242,10 @@ async fn handle_request(
             match registry.get(session_id) {
                 Some(session) => {
                     let tail = state
-                        .session_tail(session_id, tail_bytes.unwrap_or(DEFAULT_TAIL_BYTES))
+                        .session_tail(
+                            session_id,
+                            tail_bytes.unwrap_or(state.settings().tail_bytes),
+                        )
                         .await;
                     DaemonResponse::SessionContinued {
                         session: session.clone(),
@@ -812,37 +2262,26 @@ async fn handle_request(
         DaemonRequest::Attach {
             session_id,
             tail_bytes,
+            offset,
+            mode,
         } => {
-            let session = {
-                let registry = state.registry.read().await;
-                match registry.get(session_id) {
-                    Some(session) => session.clone(),
-                    None => {
-                        return DaemonResponse::Error {
-                            code: DaemonErrorCode::NotFound,
-                            message: format!("Session {} not found", session_id),
-                        };
-                    }
-                }
+            let view = match state.attach_view(session_id, tail_bytes, offset).await {
+                Ok(view) => view,
+                Err(response) => return response,
             };
-
-            let is_running = {
-                let handles = state.pty_handles.lock().await;
-                handles.contains_key(&session_id)
+            // Refuse early; the client is listed as a viewer once it connects
+            let refused = {
+                let viewers = state.attach_viewers.lock().unwrap();
+                DaemonState::attach_refusal(&viewers, session_id, mode)
             };
-
-            if !is_running {
-                return DaemonResponse::Error {
-                    code: DaemonErrorCode::Internal,
-                    message: format!("Session {} is not running", session_id),
-                };
+            if let Some(response) = refused {
+                return response;
             }
 
-            let tail = state
-                .session_tail(session_id, tail_bytes.unwrap_or(DEFAULT_TAIL_BYTES))
-                .await;
-
-            let socket_path = match state.prepare_attach_socket(session_id).await {
+            let socket_path = match state
+                .prepare_attach_socket(session_id, mode, attach_sees_attention(mode, offset))
+                .await
+            {
                 Ok(path) => path,
                 Err(e) => {
                     return DaemonResponse::Error {
@@ -853,17 +2292,34 @@ async fn handle_request(
             };
 
             DaemonResponse::AttachReady {
-                session,
-                tail,
+                session: view.session,
+                tail: view.tail,
+                screen: view.screen,
+                offset: view.offset,
                 socket_path: socket_path.to_string_lossy().to_string(),
             }
         }
 
+        DaemonRequest::Screen { session_id } => match state.session_screen(session_id).await {
+            Some(screen) => DaemonResponse::SessionScreen { session_id, screen },
+            None => DaemonResponse::Error {
+                code: DaemonErrorCode::NotFound,
+                message: format!("No screen available for session {}", session_id),
+            },
+        },
+
         DaemonRequest::Resize {
             session_id,
             cols,
             rows,
         } => {
+            // Terminals without a size (pipes, bare ptys) report 0x0
+            if cols == 0 || rows == 0 {
+                return DaemonResponse::Error {
+                    code: DaemonErrorCode::InvalidRequest,
+                    message: format!("Invalid terminal size {}x{}", cols, rows),
+                };
+            }
             let handles = state.pty_handles.lock().await;
             match handles.get(&session_id) {
                 Some(runtime) => match runtime.resize(cols, rows) {
@@ -884,7 +2340,76 @@ async fn handle_request(
             }
         }
 
+        DaemonRequest::SendInput {
+            session_id,
+            data,
+            append_newline,
+        } => {
+            let input_tx = {
+                let handles = state.pty_handles.lock().await;
+                handles
+                    .get(&session_id)
+                    .map(|runtime| runtime.input_sender())
+            };
+
+            let input_tx = match input_tx {
+                Some(tx) => tx,
+                None => {
+                    return DaemonResponse::Error {
+                        code: DaemonErrorCode::Internal,
+                        message: format!("Session {} is not running", session_id),
+                    };
+                }
+            };
+
+            // Enter in a terminal is a carriage return; the PTY line discipline
+            // translates it for programs in canonical mode.
+            let mut bytes = data.into_bytes();
+            if append_newline {
+                bytes.push(b'\r');
+            }
+            let len = bytes.len();
+
+            match input_tx.send(bytes).await {
+                Ok(_) => DaemonResponse::InputSent {
+                    session_id,
+                    bytes: len,
+                },
+                Err(e) => DaemonResponse::Error {
+                    code: DaemonErrorCode::Internal,
+                    message: format!("Failed to send input to session {}: {}", session_id, e),
+                },
+            }
+        }
+
+        DaemonRequest::Snapshot => {
+            let registry = state.registry.read().await;
+            // Registry changes land before they're broadcast, so while the
+            // registry is read-locked every seq handed out is already reflected
+            let seq = state.current_seq();
+            let sessions = registry.all_sessions().into_iter().cloned().collect();
+            DaemonResponse::Snapshot { seq, sessions }
+        }
+
+        DaemonRequest::Events {
+            since_seq,
+            limit,
+            filter,
+        } => {
+            // Taken first: everything up to it is on disk before the read
+            let latest_seq = state.current_seq();
+            let limit = limit.unwrap_or(journal::DEFAULT_EVENTS_LIMIT);
+            match state.read_events(since_seq, limit, filter).await {
+                Ok(events) => DaemonResponse::Events { events, latest_seq },
+                Err(e) => DaemonResponse::Error {
+                    code: DaemonErrorCode::Internal,
+                    message: format!("Failed to read event journal: {}", e),
+                },
+            }
+        }
+
         DaemonRequest::List { project_path } => {
+            state.refresh_foreground().await;
             let registry = state.registry.read().await;
 
             let sessions = match project_path {
@@ -901,17 +2426,20 @@ async fn handle_request(
 
         DaemonRequest::Stop { session_id } => {
             // Stop the PTY first and capture its final output
-            let tail_snapshot = {
+            let final_output = {
                 let mut handles = state.pty_handles.lock().await;
                 if let Some(mut pty) = handles.remove(&session_id) {
                     pty.stop();
-                    Some(pty.get_recent_output_blocking(DEFAULT_TAIL_BYTES))
+                    Some((
+                        pty.get_recent_output_blocking(state.settings().tail_bytes),
+                        pty.screen_text(),
+                    ))
                 } else {
                     None
                 }
             };
-            if let Some(data) = tail_snapshot {
-                state.store_completed_tail(session_id, data).await;
+            if let Some((data, screen)) = final_output {
+                state.store_completed_output(session_id, data, screen).await;
             }
             state.remove_attach_socket(session_id).await;
             state.clear_notification_debouncer(session_id).await;
@@ -922,6 +2450,8 @@ async fn handle_request(
                 Some(session) => {
                     session.status = SessionStatus::Stopped;
                     session.exit_code = None;
+                    session.foreground_command = None;
+                    session.active_attention_profiles.clear();
                     session.updated_at = chrono::Utc::now();
                     let session = session.clone();
                     let project_path = session.project_path.clone();
@@ -945,9 +2475,12 @@ async fn handle_request(
                         .await;
 
                     // Broadcast update
-                    state.broadcast(DaemonNotification::SessionUpdated {
-                        session: session.clone(),
-                    });
+                    state.broadcast(
+                        &session.project_path,
+                        DaemonNotification::SessionUpdated {
+                            session: session.clone(),
+                        },
+                    );
 
                     DaemonResponse::SessionStopped { session }
                 }
@@ -958,69 +2491,266 @@ async fn handle_request(
             }
         }
 
-        DaemonRequest::Tail { session_id, bytes } => {
-            // Default to 4096 bytes if not specified
-            let max_bytes = bytes.unwrap_or(DEFAULT_TAIL_BYTES);
-            match state.session_tail(session_id, max_bytes).await {
-                Some(data) => DaemonResponse::SessionTail { session_id, data },
-                None => DaemonResponse::Error {
-                    code: DaemonErrorCode::Internal,
-                    message: format!(
-                        "Session {} is not running or has no PTY output available",
-                        session_id
-                    ),
-                },
-            }
-        }
-
-        DaemonRequest::Status { session_id } => {
-            let registry = state.registry.read().await;
-
-            match registry.get(session_id) {
-                Some(session) => DaemonResponse::SessionStatus {
-                    session: session.clone(),
-                },
-                None => DaemonResponse::Error {
-                    code: DaemonErrorCode::NotFound,
-                    message: format!("Session {} not found", session_id),
-                },
-            }
-        }
-
-        DaemonRequest::CrLatest {
-            project_path,
-            task_id,
-        } => {
-            use rn_desktop_2_lib::context_resurrection::query;
-            use rn_desktop_2_lib::session::protocol::DaemonErrorCode;
-
-            match query::cr_latest(&state.snapshot_store, &project_path, task_id.as_deref()) {
-                Ok(snapshot) => DaemonResponse::CrSnapshot { snapshot },
-                Err(e) => DaemonResponse::Error {
-                    code: DaemonErrorCode::Internal,
-                    message: e,
-                },
-            }
-        }
-
-        DaemonRequest::CrList {
-            project_path,
-            task_id,
-            limit,
-        } => {
-            use rn_desktop_2_lib::context_resurrection::query;
-            use rn_desktop_2_lib::session::protocol::DaemonErrorCode;
-
-            // Enforce limit semantics: None => 100, >500 => 500, <=0 => error
-            let enforced_limit = match limit {
-                None => Some(100),
-                Some(n) if n <= 0 => {
+        DaemonRequest::Restart { session_id } => {
+            // Reserve the session as Running so a second restart is refused,
+            // without holding the registry across the spawn
+            let original = {
+                let mut registry = state.registry.write().await;
+                let Some(session) = registry.get(session_id).cloned() else {
+                    return DaemonResponse::Error {
+                        code: DaemonErrorCode::NotFound,
+                        message: format!("Session {} not found", session_id),
+                    };
+                };
+                let running = state.pty_handles.lock().await.contains_key(&session_id);
+                if session.status != SessionStatus::Stopped || running {
                     return DaemonResponse::Error {
                         code: DaemonErrorCode::InvalidRequest,
-                        message: "limit must be greater than 0".to_string(),
+                        message: format!("Session {} is still running", session_id),
                     };
                 }
-                Some(n) if n > 500 => Some(500),
+                if let Some(ref dir) = session.spawn_cwd {
+                    if !std::path::Path::new(dir).is_dir() {
+                        return DaemonResponse::Error {
+                            code: DaemonErrorCode::InvalidRequest,
+                            message: format!("Working directory '{}' no longer exists", dir),
+                        };
+                    }
+                }
+                if let Some(reserved) = registry.get_mut(session_id) {
+                    reserved.status = SessionStatus::Running;
+                }
+                session
+            };
+
+            // Same command, directory and environment as the original spawn;
+            // output continues the session's existing log and recording
+            let spec = SpawnSpec {
+                shell: original.shell_command.clone(),
+                cwd: original.spawn_cwd.as_ref().map(PathBuf::from),
+                env: original.env.clone(),
+                tuning: state.settings().runtime_tuning(),
+            };
+            let pty = match state
+                .spawn_pty(
+                    session_id,
+                    spec,
+                    &original.task_key,
+                    &original.project_path,
+                    original.recording,
+                )
+                .await
+            {
+                Ok(p) => p,
+                Err(e) => {
+                    state.cancel_restart(&original).await;
+                    return DaemonResponse::Error {
+                        code: DaemonErrorCode::Internal,
+                        message: format!("Failed to spawn PTY: {}", e),
+                    };
+                }
+            };
+
+            {
+                let mut handles = state.pty_handles.lock().await;
+                handles.insert(session_id, pty);
+            }
+            state.clear_completed_output(session_id).await;
+
+            // Flip the markdown badge back to Running
+            let session_status = TaskSessionStatus {
+                status: SessionStatus::Running,
+                session_id,
+            };
+            if let Err(e) = update_markdown_badge(
+                &original.project_path,
+                &original.task_key,
+                Some(&session_status),
+            )
+            .await
+            {
+                state.stop_spawned_pty(session_id).await;
+                state.cancel_restart(&original).await;
+                return DaemonResponse::Error {
+                    code: DaemonErrorCode::Internal,
+                    message: format!("Failed to update markdown file: {}", e),
+                };
+            }
+
+            // Commit, unless the session was stopped or removed meanwhile
+            let session = {
+                let mut registry = state.registry.write().await;
+                match registry.get_mut(session_id) {
+                    Some(session) if session.status == SessionStatus::Running => {
+                        session.exit_code = None;
+                        session.last_attention = None;
+                        session.updated_at = chrono::Utc::now();
+                        Some(session.clone())
+                    }
+                    _ => None,
+                }
+            };
+            let Some(session) = session else {
+                state.stop_spawned_pty(session_id).await;
+                return DaemonResponse::Error {
+                    code: DaemonErrorCode::InvalidRequest,
+                    message: format!("Session {} was stopped while restarting", session_id),
+                };
+            };
+            if let Err(e) = state.save_registry().await {
+                return DaemonResponse::Error {
+                    code: DaemonErrorCode::Internal,
+                    message: format!("Failed to save session: {}", e),
+                };
+            }
+
+            let state_clone = Arc::clone(state);
+            let project_path = session.project_path.clone();
+            let task_key = session.task_key.clone();
+            tokio::spawn(async move {
+                watch_pty_output(state_clone, session_id, project_path, task_key).await;
+            });
+            state.spawn_attention_monitor(session_id);
+
+            state.broadcast(
+                &session.project_path,
+                DaemonNotification::SessionUpdated {
+                    session: session.clone(),
+                },
+            );
+
+            DaemonResponse::SessionRestarted { session }
+        }
+
+        DaemonRequest::Tail {
+            session_id,
+            bytes,
+            offset: Some(offset),
+        } => {
+            let max_bytes = bytes.unwrap_or(output_log::MAX_LOG_READ_BYTES);
+            match state.read_output_log(session_id, offset, max_bytes).await {
+                Ok(range) => DaemonResponse::SessionTail {
+                    session_id,
+                    data: range.data,
+                    offset: Some(range.offset),
+                    log_end: Some(range.end),
+                },
+                Err(e) => DaemonResponse::Error {
+                    code: DaemonErrorCode::NotFound,
+                    message: format!("No output log for session {}: {}", session_id, e),
+                },
+            }
+        }
+
+        DaemonRequest::Tail {
+            session_id,
+            bytes,
+            offset: None,
+        } => {
+            // Default to the configured tail size if not specified
+            let max_bytes = bytes.unwrap_or(state.settings().tail_bytes);
+            match state.session_tail(session_id, max_bytes).await {
+                Some(data) => DaemonResponse::SessionTail {
+                    session_id,
+                    data,
+                    offset: None,
+                    log_end: None,
+                },
+                None => DaemonResponse::Error {
+                    code: DaemonErrorCode::Internal,
+                    message: format!(
+                        "Session {} is not running or has no PTY output available",
+                        session_id
+                    ),
+                },
+            }
+        }
+
+        DaemonRequest::Status { session_id } => {
+            state.refresh_foreground().await;
+            let registry = state.registry.read().await;
+
+            match registry.get(session_id) {
+                Some(session) => DaemonResponse::SessionStatus {
+                    session: session.clone(),
+                },
+                None => DaemonResponse::Error {
+                    code: DaemonErrorCode::NotFound,
+                    message: format!("Session {} not found", session_id),
+                },
+            }
+        }
+
+        DaemonRequest::ListRecordings => match state.list_recordings().await {
+            Ok(recordings) => DaemonResponse::RecordingList { recordings },
+            Err(e) => DaemonResponse::Error {
+                code: DaemonErrorCode::Internal,
+                message: format!("Failed to list recordings: {}", e),
+            },
+        },
+
+        DaemonRequest::DeleteRecording { session_id } => {
+            let recording = {
+                let registry = state.registry.read().await;
+                registry.get(session_id).is_some_and(|s| s.recording)
+            };
+            if recording && state.pty_handles.lock().await.contains_key(&session_id) {
+                return DaemonResponse::Error {
+                    code: DaemonErrorCode::InvalidRequest,
+                    message: format!(
+                        "Session {} is still recording; stop it before deleting its recording",
+                        session_id
+                    ),
+                };
+            }
+
+            match tokio::fs::remove_file(state.config.session_recording_path(session_id)).await {
+                Ok(()) => DaemonResponse::RecordingDeleted { session_id },
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => DaemonResponse::Error {
+                    code: DaemonErrorCode::NotFound,
+                    message: format!("No recording for session {}", session_id),
+                },
+                Err(e) => DaemonResponse::Error {
+                    code: DaemonErrorCode::Internal,
+                    message: format!("Failed to delete recording: {}", e),
+                },
+            }
+        }
+
+        DaemonRequest::CrLatest {
+            project_path,
+            task_id,
+        } => {
+            use rn_desktop_2_lib::context_resurrection::query;
+            use rn_desktop_2_lib::session::protocol::DaemonErrorCode;
+
+            match query::cr_latest(&state.snapshot_store, &project_path, task_id.as_deref()) {
+                Ok(snapshot) => DaemonResponse::CrSnapshot { snapshot },
+                Err(e) => DaemonResponse::Error {
+                    code: DaemonErrorCode::Internal,
+                    message: e,
+                },
+            }
+        }
+
+        DaemonRequest::CrList {
+            project_path,
+            task_id,
+            limit,
+        } => {
+            use rn_desktop_2_lib::context_resurrection::query;
+            use rn_desktop_2_lib::session::protocol::DaemonErrorCode;
+
+            // Enforce limit semantics: None => 100, >500 => 500, <=0 => error
+            let enforced_limit = match limit {
+                None => Some(100),
+                Some(n) if n <= 0 => {
+                    return DaemonResponse::Error {
+                        code: DaemonErrorCode::InvalidRequest,
+                        message: "limit must be greater than 0".to_string(),
+                    };
+                }
+                Some(n) if n > 500 => Some(500),
                 Some(n) => Some(n),
             };
 
@@ -1161,125 +2891,168 @@ async fn handle_request(
     }
 }
 
-/// Watch PTY output and update session status based on activity
+/// Drive session status from PTY idle/active/exit events as they happen
 async fn watch_pty_output(
     state: Arc<DaemonState>,
     session_id: SessionId,
     project_path: String,
     task_key: String,
 ) {
-    let mut last_status = SessionStatus::Running;
-    let mut idle_start: Option<std::time::Instant> = None;
-    let mut last_idle_capture: Option<std::time::Instant> = None;
+    // Subscribe before reading the current state so no transition falls in between
+    let mut events = {
+        let handles = state.pty_handles.lock().await;
+        match handles.get(&session_id) {
+            Some(pty) => pty.subscribe_events(),
+            None => return,
+        }
+    };
+
+    let mut is_idle = false;
+    // Fires the idle timeout capture once per idle period
+    let mut idle_capture_at: Option<tokio::time::Instant> = None;
+    // The PTY may have gone idle or exited before we subscribed
+    let mut pending = current_pty_event(&state, session_id).await;
 
     loop {
-        // Poll every 5 seconds
-        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        let event = match pending.take() {
+            Some(event) => event,
+            None => {
+                tokio::select! {
+                    received = events.recv() => match received {
+                        Ok(event) => event,
+                        Err(broadcast::error::RecvError::Lagged(_)) => {
+                            // Missed transitions; fall back to the runtime's current state
+                            match current_pty_event(&state, session_id).await {
+                                Some(event) => event,
+                                None => break,
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    _ = sleep_until_deadline(idle_capture_at) => {
+                        idle_capture_at = None;
+                        let task_id = {
+                            let registry = state.registry.read().await;
+                            registry.get(session_id).and_then(|s| s.task_id.clone())
+                        };
 
-        // Check PTY status
-        let (is_alive, is_idle) = {
-            let handles = state.pty_handles.lock().await;
-            match handles.get(&session_id) {
-                Some(pty) => {
-                    let alive = pty.is_alive();
-                    let idle = pty.is_idle();
-                    (alive, idle)
+                        state.trigger_capture(
+                            project_path.clone(),
+                            task_id,
+                            task_key.clone(),
+                            session_id,
+                            CaptureReason::IdleTimeout,
+                        );
+                        continue;
+                    }
                 }
-                None => break, // PTY was removed
             }
         };
 
-        if !is_alive {
-            // PTY exited
-            eprintln!("Session {} PTY exited", session_id);
-            let (exit_code, tail_snapshot) = {
-                let handles = state.pty_handles.lock().await;
-                match handles.get(&session_id) {
-                    Some(pty) => (
-                        pty.exit_code(),
-                        Some(pty.get_recent_output_blocking(DEFAULT_TAIL_BYTES)),
-                    ),
-                    None => (None, None),
+        match event {
+            PtyEvent::Output(_) => {}
+            PtyEvent::Idle => {
+                if !is_idle {
+                    is_idle = true;
+                    idle_capture_at =
+                        Some(tokio::time::Instant::now() + state.settings().idle_capture());
                 }
-            };
-
-            update_session_status(
-                &state,
-                session_id,
-                &project_path,
-                &task_key,
-                SessionStatus::Stopped,
-                exit_code,
-            )
-            .await;
-
-            if let Some(data) = tail_snapshot {
-                state.store_completed_tail(session_id, data).await;
+                update_session_status(
+                    &state,
+                    session_id,
+                    &project_path,
+                    &task_key,
+                    SessionStatus::Waiting,
+                    None,
+                )
+                .await;
             }
-            state.remove_attach_socket(session_id).await;
-            state.clear_notification_debouncer(session_id).await;
-
-            // Remove PTY handle
-            let mut handles = state.pty_handles.lock().await;
-            handles.remove(&session_id);
-            break;
-        }
-
-        // Determine new status based on idle state
-        let new_status = if is_idle {
-            SessionStatus::Waiting
-        } else {
-            SessionStatus::Running
-        };
-
-        // Track idle duration for timeout captures
-        if is_idle {
-            if idle_start.is_none() {
-                idle_start = Some(std::time::Instant::now());
+            PtyEvent::Active => {
+                is_idle = false;
+                idle_capture_at = None;
+                update_session_status(
+                    &state,
+                    session_id,
+                    &project_path,
+                    &task_key,
+                    SessionStatus::Running,
+                    None,
+                )
+                .await;
+            }
+            PtyEvent::Exited { exit_code } => {
+                handle_pty_exit(&state, session_id, &project_path, &task_key, exit_code).await;
+                break;
             }
-        } else {
-            idle_start = None;
-            last_idle_capture = None; // Reset capture tracking when activity resumes
         }
+    }
+}
 
-        // Trigger idle timeout capture if threshold exceeded
-        if let Some(start) = idle_start {
-            let idle_duration = start.elapsed();
-            let threshold = std::time::Duration::from_secs(IDLE_CAPTURE_THRESHOLD_SECS);
-
-            // Capture once per idle period (avoid repeated captures)
-            if idle_duration >= threshold && last_idle_capture.is_none() {
-                let task_id = {
-                    let registry = state.registry.read().await;
-                    registry.get(session_id).and_then(|s| s.task_id.clone())
-                };
+/// Synthesize the event matching a PTY's current state, for catching up after
+/// subscribing late or lagging. Returns None once the PTY handle is gone.
+async fn current_pty_event(state: &Arc<DaemonState>, session_id: SessionId) -> Option<PtyEvent> {
+    let handles = state.pty_handles.lock().await;
+    let pty = handles.get(&session_id)?;
+    Some(if !pty.is_alive() {
+        PtyEvent::Exited {
+            exit_code: pty.exit_code(),
+        }
+    } else if pty.is_idle() {
+        PtyEvent::Idle
+    } else {
+        PtyEvent::Active
+    })
+}
 
-                state.trigger_capture(
-                    project_path.clone(),
-                    task_id,
-                    task_key.clone(),
-                    session_id,
-                    CaptureReason::IdleTimeout,
-                );
+/// Sleep until `deadline`, or forever when there is none
+async fn sleep_until_deadline(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
 
-                last_idle_capture = Some(std::time::Instant::now());
-            }
+/// Mark a session Stopped after its PTY exits and release its runtime resources
+async fn handle_pty_exit(
+    state: &Arc<DaemonState>,
+    session_id: SessionId,
+    project_path: &str,
+    task_key: &str,
+    exit_code: Option<i32>,
+) {
+    let (exit_code, tail_snapshot, final_screen) = {
+        let handles = state.pty_handles.lock().await;
+        match handles.get(&session_id) {
+            Some(pty) => (
+                exit_code.or_else(|| pty.exit_code()),
+                pty.get_recent_output_blocking(state.settings().tail_bytes),
+                pty.screen_text(),
+            ),
+            // Already stopped and cleaned up via a Stop request
+            None => return,
         }
+    };
+    eprintln!("Session {} PTY exited", session_id);
 
-        // Only update if status changed
-        if new_status != last_status {
-            update_session_status(
-                &state,
-                session_id,
-                &project_path,
-                &task_key,
-                new_status,
-                None,
-            )
-            .await;
-            last_status = new_status;
-        }
-    }
+    update_session_status(
+        state,
+        session_id,
+        project_path,
+        task_key,
+        SessionStatus::Stopped,
+        exit_code,
+    )
+    .await;
+
+    state
+        .store_completed_output(session_id, tail_snapshot, final_screen)
+        .await;
+    state.remove_attach_socket(session_id).await;
+    state.clear_notification_debouncer(session_id).await;
+
+    // Remove PTY handle
+    let mut handles = state.pty_handles.lock().await;
+    handles.remove(&session_id);
 }
 
 /// Helper to update session status in registry and markdown
@@ -1303,6 +3076,10 @@ async fn update_session_status(
             if let Some(code) = exit_code {
                 session.exit_code = Some(code);
             }
+            if new_status == SessionStatus::Stopped {
+                session.foreground_command = None;
+                session.active_attention_profiles.clear();
+            }
             (old, session.task_id.clone())
         } else {
             return;
@@ -1344,9 +3121,12 @@ async fn update_session_status(
     // Broadcast update
     let registry = state.registry.read().await;
     if let Some(session) = registry.get(session_id) {
-        state.broadcast(DaemonNotification::SessionUpdated {
-            session: session.clone(),
-        });
+        state.broadcast(
+            &session.project_path,
+            DaemonNotification::SessionUpdated {
+                session: session.clone(),
+            },
+        );
     }
 }
 
@@ -1379,6 +3159,7 @@ impl SessionProvider for DaemonState {
 
         // Get session metadata from registry
         let session = run_async(async {
+            self.refresh_foreground().await;
             let registry = self.registry.read().await;
             registry.get(session_id).cloned()
         })?;
@@ -1390,6 +3171,16 @@ impl SessionProvider for DaemonState {
         // Decode tail as UTF-8 (lossy is fine for CR - we just need context)
         let tail = String::from_utf8_lossy(&tail_bytes).to_string();
 
+        // The rendered screen shows what the user actually saw, after redraws
+        let screen = run_async(async { self.session_screen(session_id).await }).map(|screen| {
+            rn_desktop_2_lib::context_resurrection::models::TerminalScreen {
+                lines: screen.lines,
+                cursor_row: screen.cursor_row,
+                cursor_col: screen.cursor_col,
+                title: (!screen.title.is_empty()).then_some(screen.title),
+            }
+        });
+
         // Map protocol SessionStatus to CR SessionStatus
         let status = match session.status {
             SessionStatus::Running => {
@@ -1430,6 +3221,9 @@ impl SessionProvider for DaemonState {
             exit_code: session.exit_code,
             last_attention,
             tail,
+            screen,
+            foreground_command: session.foreground_command,
+            cwd: session.cwd,
         })
     }
 }
@@ -1443,17 +3237,43 @@ async fn main() -> Result<()> {
     config
         .ensure_dirs()
         .context("Failed to create data directory")?;
+    // Its sockets must not be reachable by other users
+    config
+        .check_runtime_dir()
+        .context("Refusing to start with an unsafe runtime directory")?;
+    // Checked before taking over from a running daemon, which keeps going if this fails
+    load_config(&config).context("Refusing to start with an invalid config")?;
 
-    // Clean up stale socket if exists
+    // Take over from a running daemon, or clean up a stale socket
+    let mut handoff = None;
     if config.socket_exists() {
         if config.is_daemon_running() {
-            eprintln!("Daemon already running (PID: {:?})", config.read_pid());
-            std::process::exit(1);
+            let forced = std::env::var_os(handoff::FORCE_HANDOFF_ENV).is_some();
+            let takes_over = handoff::probe_daemon(&config.socket_path).is_ok_and(|running| {
+                running.supports(Capability::Handoff)
+                    && (forced || running.is_older_than_this_build())
+            });
+            if !takes_over {
+                eprintln!("Daemon already running (PID: {:?})", config.read_pid());
+                std::process::exit(1);
+            }
+            println!(
+                "Daemon already running (PID: {:?}), taking over its sessions",
+                config.read_pid()
+            );
+            match handoff::request_handoff(&config.socket_path) {
+                Ok(received) => handoff = Some(received),
+                Err(e) => {
+                    eprintln!("Failed to take over from running daemon: {:#}", e);
+                    std::process::exit(1);
+                }
+            }
+        } else {
+            // Stale socket, remove it
+            config
+                .remove_socket()
+                .context("Failed to remove stale socket")?;
         }
-        // Stale socket, remove it
-        config
-            .remove_socket()
-            .context("Failed to remove stale socket")?;
     }
 
     // Write PID file
@@ -1461,35 +3281,64 @@ async fn main() -> Result<()> {
 
     // Initialize daemon state
     let state = Arc::new(DaemonState::new(config.clone())?);
+    match state.holder_bin {
+        Some(ref bin) => println!("Session PTYs are held by {}", bin.display()),
+        None => eprintln!(
+            "Warning: holder binary not found; sessions will not survive a daemon restart"
+        ),
+    }
 
     // Initialize Context Resurrection capture service
     state.init_capture_service().await;
 
+    // Sessions handed over by the previous daemon keep running under this one
+    let inherited_listener = match handoff {
+        Some(handoff) => {
+            state.adopt_handoff(handoff.sessions).await;
+            Some(handoff.control_listener)
+        }
+        None => None,
+    };
+
     // Reconcile any stale sessions from previous runs
     state.reconcile_stale_sessions().await;
 
-    // Create Unix socket listener
-    let listener = UnixListener::bind(&config.socket_path)
-        .with_context(|| format!("Failed to bind socket: {}", config.socket_path.display()))?;
-
-    // Secure socket permissions (Unix only - owner-only access)
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        std::fs::set_permissions(&config.socket_path, std::fs::Permissions::from_mode(0o600))
-            .with_context(|| {
-                format!(
-                    "Failed to set socket permissions: {}",
-                    config.socket_path.display()
-                )
+    let listener = match inherited_listener {
+        // The previous daemon's listener, already bound to our socket path
+        Some(fd) => listener_from_fd(fd).context("Failed to take over control socket")?,
+        None => {
+            // Create Unix socket listener
+            let listener = UnixListener::bind(&config.socket_path).with_context(|| {
+                format!("Failed to bind socket: {}", config.socket_path.display())
             })?;
-    }
-
-    println!("Daemon listening on {}", config.socket_path.display());
+
+            // Secure socket permissions (Unix only - owner-only access)
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(
+                    &config.socket_path,
+                    std::fs::Permissions::from_mode(0o600),
+                )
+                .with_context(|| {
+                    format!(
+                        "Failed to set socket permissions: {}",
+                        config.socket_path.display()
+                    )
+                })?;
+            }
+            listener
+        }
+    };
+
+    println!("Daemon listening on {}", config.socket_path.display());
 
     // Shutdown signal channel
     let (shutdown_tx, mut shutdown_rx) = tokio::sync::mpsc::channel::<()>(1);
 
+    // Connections from newer daemons asking to take over
+    let (handoff_tx, mut handoff_rx) = tokio::sync::mpsc::channel::<UnixStream>(1);
+
     // Handle SIGTERM/SIGINT for graceful shutdown
     let shutdown_tx_clone = shutdown_tx.clone();
     tokio::spawn(async move {
@@ -1497,16 +3346,39 @@ async fn main() -> Result<()> {
         let _ = shutdown_tx_clone.send(()).await;
     });
 
+    // Reload config.toml and attention.toml on SIGHUP
+    let mut hangups = signal::unix::signal(signal::unix::SignalKind::hangup())
+        .context("Failed to listen for SIGHUP")?;
+    let reload_state = Arc::clone(&state);
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            if let Err(e) = reload_state.reload_config().await {
+                eprintln!(
+                    "Failed to reload config (keeping the current config): {:#}",
+                    e
+                );
+            }
+        }
+    });
+
     // Accept connections until shutdown
     loop {
         tokio::select! {
             result = listener.accept() => {
                 match result {
                     Ok((stream, _addr)) => {
+                        if let Some(message) = peer::peer_refusal(&stream) {
+                            eprintln!("{}", message);
+                            tokio::spawn(refuse_connection(stream, message));
+                            continue;
+                        }
                         let state = Arc::clone(&state);
                         let shutdown_tx = shutdown_tx.clone();
+                        let handoff_tx = handoff_tx.clone();
                         tokio::spawn(async move {
-                            if let Err(e) = handle_client(state, stream, shutdown_tx).await {
+                            if let Err(e) =
+                                handle_client(state, stream, shutdown_tx, handoff_tx).await
+                            {
                                 eprintln!("Client error: {}", e);
                             }
                         });
@@ -1517,6 +3389,18 @@ async fn main() -> Result<()> {
                 }
             }
 
+            Some(stream) = handoff_rx.recv() => {
+                match hand_off(&state, &listener, stream).await {
+                    Ok(()) => {
+                        // The new daemon owns the socket, PID file and sessions now;
+                        // exit without running any cleanup that would disturb them
+                        println!("Handed off to new daemon");
+                        std::process::exit(0);
+                    }
+                    Err(e) => eprintln!("Handoff failed: {:#}", e),
+                }
+            }
+
             _ = shutdown_rx.recv() => {
                 println!("Shutting down daemon...");
                 break;
@@ -1572,6 +3456,10 @@ mod tests {
             task_id: None,
             project_path: markdown_path.to_string_lossy().to_string(),
             shell: Some(vec!["echo".to_string(), "hello".to_string()]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
         };
 
         let response = handle_request(&state, request, &shutdown_tx).await;
@@ -1672,6 +3560,10 @@ mod tests {
             task_id: None,
             project_path: markdown_path.to_string_lossy().to_string(),
             shell: None,
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
         };
 
         let response = handle_request(&state, request, &shutdown_tx).await;
@@ -1710,6 +3602,10 @@ mod tests {
             task_id: None,
             project_path: markdown_path.to_string_lossy().to_string(),
             shell: Some(vec!["echo".to_string(), "hello".to_string()]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
         };
         let _ = handle_request(&state, request, &shutdown_tx).await;
 
@@ -1719,6 +3615,10 @@ mod tests {
             task_id: None,
             project_path: markdown_path.to_string_lossy().to_string(),
             shell: Some(vec!["echo".to_string(), "hello".to_string()]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
         };
         let response = handle_request(&state, request, &shutdown_tx).await;
 
@@ -1735,6 +3635,45 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_racing_starts_spawn_once() {
+        let (config, temp_dir) = test_config();
+        let markdown_path = temp_dir.path().join("TODO.md");
+        tokio::fs::write(&markdown_path, "# Tasks\n- [ ] Race task\n")
+            .await
+            .unwrap();
+
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+
+        let start = || DaemonRequest::Start {
+            task_key: "Race".to_string(),
+            task_id: None,
+            project_path: markdown_path.to_string_lossy().to_string(),
+            shell: Some(vec!["sleep".to_string(), "30".to_string()]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
+        };
+        let (first, second) = tokio::join!(
+            handle_request(&state, start(), &shutdown_tx),
+            handle_request(&state, start(), &shutdown_tx),
+        );
+        let started: Vec<SessionId> = [&first, &second]
+            .iter()
+            .filter_map(|response| match response {
+                DaemonResponse::SessionStarted { session } => Some(session.id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(started.len(), 1, "{:?} / {:?}", first, second);
+        assert_eq!(state.registry.read().await.sessions.len(), 1);
+
+        let session_id = started[0];
+        let _ = handle_request(&state, DaemonRequest::Stop { session_id }, &shutdown_tx).await;
+    }
+
     #[tokio::test]
     async fn test_list_sessions() {
         let (config, temp_dir) = test_config();
@@ -1756,6 +3695,10 @@ mod tests {
             task_id: None,
             project_path: markdown_path.to_string_lossy().to_string(),
             shell: Some(vec!["echo".to_string(), "1".to_string()]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
         };
         let _ = handle_request(&state, request, &shutdown_tx).await;
 
@@ -1764,6 +3707,10 @@ mod tests {
             task_id: None,
             project_path: markdown_path.to_string_lossy().to_string(),
             shell: Some(vec!["echo".to_string(), "2".to_string()]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
         };
         let _ = handle_request(&state, request, &shutdown_tx).await;
 
@@ -1811,6 +3758,10 @@ mod tests {
             task_id: None,
             project_path: markdown_path.to_string_lossy().to_string(),
             shell: Some(vec!["echo".to_string(), "tail-output".to_string()]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
         };
 
         let response = handle_request(&state, request, &shutdown_tx).await;
@@ -1831,6 +3782,7 @@ mod tests {
                     let tail_request = DaemonRequest::Tail {
                         session_id,
                         bytes: Some(1024),
+                        offset: None,
                     };
                     match handle_request(&state, tail_request, &shutdown_tx).await {
                         DaemonResponse::SessionTail { data, .. } => {
@@ -1845,6 +3797,186 @@ mod tests {
         .await;
     }
 
+    #[tokio::test]
+    async fn test_tail_bytes_use_negotiated_encoding() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let (config, temp_dir) = test_config();
+        let markdown_path = temp_dir.path().join("TODO.md");
+        tokio::fs::write(&markdown_path, "# Tasks\n- [ ] Encoding test\n")
+            .await
+            .unwrap();
+
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+        let (handoff_tx, _handoff_rx) = tokio::sync::mpsc::channel::<UnixStream>(1);
+
+        let start = DaemonRequest::Start {
+            task_key: "Encoding".to_string(),
+            task_id: None,
+            project_path: markdown_path.to_string_lossy().to_string(),
+            shell: Some(vec!["echo".to_string(), "encoded-output".to_string()]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
+        };
+        let session_id = match handle_request(&state, start, &shutdown_tx).await {
+            DaemonResponse::SessionStarted { session } => session.id,
+            other => panic!("Start failed: {:?}", other),
+        };
+        let tail = DaemonRequest::Tail {
+            session_id,
+            bytes: Some(1024),
+            offset: None,
+        };
+        assert_eventually_bool(
+            "tail to contain 'encoded-output'",
+            Duration::from_secs(3),
+            Duration::from_millis(50),
+            || {
+                let state = Arc::clone(&state);
+                let shutdown_tx = shutdown_tx.clone();
+                let tail = tail.clone();
+                async move {
+                    matches!(
+                        handle_request(&state, tail, &shutdown_tx).await,
+                        DaemonResponse::SessionTail { data, .. }
+                            if String::from_utf8_lossy(&data).contains("encoded-output")
+                    )
+                }
+            },
+        )
+        .await;
+
+        let (client, server) = UnixStream::pair().unwrap();
+        tokio::spawn(handle_client(
+            Arc::clone(&state),
+            server,
+            shutdown_tx,
+            handoff_tx,
+        ));
+        let (reader, mut writer) = client.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        let frame = |request: &DaemonRequest| serialize_message(request).unwrap();
+
+        // Arrays until the client asks for something else
+        writer.write_all(&frame(&tail)).await.unwrap();
+        let line = lines.next_line().await.unwrap().unwrap();
+        assert!(line.contains("\"data\":["), "{}", line);
+
+        let handshake = DaemonRequest::Handshake {
+            client_version: rn_desktop_2_lib::session::protocol::PROTOCOL_VERSION,
+            min_version: None,
+            byte_encoding: Some(ByteEncoding::Base64),
+        };
+        writer.write_all(&frame(&handshake)).await.unwrap();
+        let line = lines.next_line().await.unwrap().unwrap();
+        assert!(matches!(
+            deserialize_message::<DaemonResponse>(line.as_bytes()).unwrap(),
+            DaemonResponse::Handshake {
+                byte_encoding: Some(ByteEncoding::Base64),
+                ..
+            }
+        ));
+
+        writer.write_all(&frame(&tail)).await.unwrap();
+        let line = lines.next_line().await.unwrap().unwrap();
+        assert!(line.contains("\"data\":\""), "{}", line);
+        match deserialize_message::<DaemonResponse>(line.as_bytes()).unwrap() {
+            DaemonResponse::SessionTail { data, .. } => {
+                assert!(String::from_utf8_lossy(&data).contains("encoded-output"))
+            }
+            other => panic!("Expected SessionTail, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tail_offset_reads_from_output_log() {
+        let (config, temp_dir) = test_config();
+        std::fs::write(
+            config.settings_file(),
+            "output_log_retained_bytes = 16777216\n",
+        )
+        .unwrap();
+        let markdown_path = temp_dir.path().join("TODO.md");
+        tokio::fs::write(&markdown_path, "# Tasks\n- [ ] Long log\n")
+            .await
+            .unwrap();
+
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+
+        let script = "echo first-line; seq 1 2000; echo last-line; sleep 5";
+        let start = DaemonRequest::Start {
+            task_key: "Long log".to_string(),
+            task_id: None,
+            project_path: markdown_path.to_string_lossy().to_string(),
+            shell: Some(vec!["sh".to_string(), "-c".to_string(), script.to_string()]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
+        };
+        let session_id = match handle_request(&state, start, &shutdown_tx).await {
+            DaemonResponse::SessionStarted { session } => session.id,
+            other => panic!("Start failed: {:?}", other),
+        };
+
+        assert_eventually_bool(
+            "output to finish",
+            Duration::from_secs(5),
+            Duration::from_millis(50),
+            || {
+                let state = Arc::clone(&state);
+                async move {
+                    state
+                        .session_tail(session_id, 64)
+                        .await
+                        .map(|data| String::from_utf8_lossy(&data).contains("last-line"))
+                        .unwrap_or(false)
+                }
+            },
+        )
+        .await;
+
+        let from_start = DaemonRequest::Tail {
+            session_id,
+            bytes: Some(32),
+            offset: Some(0),
+        };
+        let log_end = match handle_request(&state, from_start, &shutdown_tx).await {
+            DaemonResponse::SessionTail {
+                data,
+                offset,
+                log_end,
+                ..
+            } => {
+                assert_eq!(offset, Some(0));
+                assert!(String::from_utf8_lossy(&data).starts_with("first-line"));
+                log_end.expect("log end")
+            }
+            other => panic!("Expected SessionTail, got {:?}", other),
+        };
+        assert!(log_end > 8_000);
+
+        // Paging to the end of the log reaches the newest output
+        let last_page = DaemonRequest::Tail {
+            session_id,
+            bytes: None,
+            offset: Some(log_end - 16),
+        };
+        match handle_request(&state, last_page, &shutdown_tx).await {
+            DaemonResponse::SessionTail { data, offset, .. } => {
+                assert_eq!(offset, Some(log_end - 16));
+                assert!(String::from_utf8_lossy(&data).contains("last-line"));
+            }
+            other => panic!("Expected SessionTail, got {:?}", other),
+        }
+
+        let _ = handle_request(&state, DaemonRequest::Stop { session_id }, &shutdown_tx).await;
+    }
+
     #[tokio::test]
     async fn test_attach_request_creates_socket() {
         let (config, temp_dir) = test_config();
@@ -1862,6 +3994,10 @@ mod tests {
             task_id: None,
             project_path: markdown_path.to_string_lossy().to_string(),
             shell: Some(vec!["sleep".to_string(), "1".to_string()]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
         };
         let response = handle_request(&state, start, &shutdown_tx).await;
         let session_id = match response {
@@ -1872,6 +4008,8 @@ mod tests {
         let attach_req = DaemonRequest::Attach {
             session_id,
             tail_bytes: Some(512),
+            offset: None,
+            mode: AttachMode::Shared,
         };
         let response = handle_request(&state, attach_req, &shutdown_tx).await;
         match response {
@@ -1906,6 +4044,10 @@ mod tests {
             task_id: None,
             project_path: markdown_path.to_string_lossy().to_string(),
             shell: Some(vec!["cat".to_string()]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
         };
         let response = handle_request(&state, start, &shutdown_tx).await;
         let session_id = match response {
@@ -1916,6 +4058,8 @@ mod tests {
         let attach_req = DaemonRequest::Attach {
             session_id,
             tail_bytes: Some(128),
+            offset: None,
+            mode: AttachMode::Shared,
         };
         let response = handle_request(&state, attach_req, &shutdown_tx).await;
         let socket_path = match response {
@@ -1928,88 +4072,1397 @@ mod tests {
             .expect("failed to connect to attach socket");
         let (mut reader, mut writer) = stream.into_split();
 
-        writer
-            .write_all(b"attach-stream-test\n")
+        writer
+            .write_all(b"attach-stream-test\n")
+            .await
+            .expect("failed to send input");
+        writer.flush().await.expect("failed to flush writer");
+
+        let mut collected = Vec::new();
+        let mut buf = vec![0u8; 1024];
+        let mut attempts = 0;
+
+        loop {
+            attempts += 1;
+            if attempts > 20 {
+                panic!("timed out waiting for attach output");
+            }
+
+            match tokio::time::timeout(Duration::from_millis(200), reader.read(&mut buf)).await {
+                Ok(Ok(0)) => break,
+                Ok(Ok(n)) => {
+                    collected.extend_from_slice(&buf[..n]);
+                    if collected.contains(&b'\n') {
+                        break;
+                    }
+                }
+                Ok(Err(e)) => panic!("attach read error: {}", e),
+                Err(_) => continue,
+            }
+        }
+
+        let text = String::from_utf8_lossy(&collected);
+        assert!(
+            text.contains("attach-stream-test"),
+            "attach stream should echo input, got '{}'",
+            text
+        );
+
+        // Stop session to clean up
+        let _ = handle_request(&state, DaemonRequest::Stop { session_id }, &shutdown_tx).await;
+    }
+
+    #[tokio::test]
+    async fn test_attach_ready_includes_screen_repaint() {
+        let (config, temp_dir) = test_config();
+        let markdown_path = temp_dir.path().join("TODO.md");
+        tokio::fs::write(&markdown_path, "# Tasks\n- [ ] Repaint task\n")
+            .await
+            .unwrap();
+
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+
+        // Enter the alternate screen and draw at a fixed position, like a TUI would
+        let script = r"printf '\033[?1049h\033[H\033[2J\033[3;5Hfull-screen-ui'; sleep 5";
+        let start = DaemonRequest::Start {
+            task_key: "Repaint".to_string(),
+            task_id: None,
+            project_path: markdown_path.to_string_lossy().to_string(),
+            shell: Some(vec![
+                "/bin/sh".to_string(),
+                "-c".to_string(),
+                script.to_string(),
+            ]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
+        };
+        let session_id = match handle_request(&state, start, &shutdown_tx).await {
+            DaemonResponse::SessionStarted { session } => session.id,
+            other => panic!("Expected SessionStarted, got {:?}", other),
+        };
+
+        assert_eventually_bool(
+            "TUI output to be drawn",
+            Duration::from_secs(3),
+            Duration::from_millis(50),
+            || {
+                let state = Arc::clone(&state);
+                async move {
+                    state
+                        .session_tail(session_id, 1024)
+                        .await
+                        .map(|data| String::from_utf8_lossy(&data).contains("full-screen-ui"))
+                        .unwrap_or(false)
+                }
+            },
+        )
+        .await;
+
+        let attach_req = DaemonRequest::Attach {
+            session_id,
+            tail_bytes: Some(128),
+            offset: None,
+            mode: AttachMode::Shared,
+        };
+        let screen = match handle_request(&state, attach_req, &shutdown_tx).await {
+            DaemonResponse::AttachReady { screen, .. } => screen.expect("screen repaint"),
+            other => panic!("Expected AttachReady response, got {:?}", other),
+        };
+
+        assert!(screen.alternate_screen);
+        assert_eq!((screen.rows, screen.cols), (24, 80));
+        assert_eq!((screen.cursor_row, screen.cursor_col), (2, 18));
+        let painted = String::from_utf8_lossy(&screen.screen);
+        assert!(
+            painted.contains("full-screen-ui"),
+            "Repaint should contain drawn text. Got: {:?}",
+            painted
+        );
+
+        let _ = handle_request(&state, DaemonRequest::Stop { session_id }, &shutdown_tx).await;
+    }
+
+    #[tokio::test]
+    async fn test_screen_request_returns_rendered_text() {
+        let (config, temp_dir) = test_config();
+        let markdown_path = temp_dir.path().join("TODO.md");
+        tokio::fs::write(&markdown_path, "# Tasks\n- [ ] Screen task\n")
+            .await
+            .unwrap();
+
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+
+        // Redraw a progress line in place; only the final text should be on screen
+        let script = r"printf 'step 1/3\rstep 3/3\r\n\033]2;builder\007ready'; sleep 5";
+        let start = DaemonRequest::Start {
+            task_key: "Screen".to_string(),
+            task_id: None,
+            project_path: markdown_path.to_string_lossy().to_string(),
+            shell: Some(vec![
+                "/bin/sh".to_string(),
+                "-c".to_string(),
+                script.to_string(),
+            ]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
+        };
+        let session_id = match handle_request(&state, start, &shutdown_tx).await {
+            DaemonResponse::SessionStarted { session } => session.id,
+            other => panic!("Expected SessionStarted, got {:?}", other),
+        };
+
+        assert_eventually_bool(
+            "screen to be drawn",
+            Duration::from_secs(3),
+            Duration::from_millis(50),
+            || {
+                let state = Arc::clone(&state);
+                async move {
+                    state
+                        .session_screen(session_id)
+                        .await
+                        .map(|screen| screen.lines.get(1).map(String::as_str) == Some("ready"))
+                        .unwrap_or(false)
+                }
+            },
+        )
+        .await;
+
+        let screen = match handle_request(
+            &state,
+            DaemonRequest::Screen { session_id },
+            &shutdown_tx,
+        )
+        .await
+        {
+            DaemonResponse::SessionScreen { screen, .. } => screen,
+            other => panic!("Expected SessionScreen response, got {:?}", other),
+        };
+        assert_eq!((screen.rows, screen.cols), (24, 80));
+        assert_eq!(screen.lines.len(), 24);
+        assert_eq!(screen.lines[0], "step 3/3");
+        assert_eq!((screen.cursor_row, screen.cursor_col), (1, 5));
+        assert_eq!(screen.title, "builder");
+
+        // The final screen stays available after the session is stopped
+        let _ = handle_request(&state, DaemonRequest::Stop { session_id }, &shutdown_tx).await;
+        match handle_request(&state, DaemonRequest::Screen { session_id }, &shutdown_tx).await {
+            DaemonResponse::SessionScreen { screen, .. } => {
+                assert_eq!(screen.lines[1], "ready");
+            }
+            other => panic!("Expected SessionScreen after stop, got {:?}", other),
+        }
+
+        // Unknown sessions have no screen
+        match handle_request(
+            &state,
+            DaemonRequest::Screen { session_id: 999 },
+            &shutdown_tx,
+        )
+        .await
+        {
+            DaemonResponse::Error { message, .. } => {
+                assert!(
+                    message.contains("No screen"),
+                    "Unexpected error: {}",
+                    message
+                )
+            }
+            other => panic!("Expected Error response, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resize_request_succeeds() {
+        let (config, temp_dir) = test_config();
+        let markdown_path = temp_dir.path().join("TODO.md");
+        tokio::fs::write(&markdown_path, "# Tasks\n- [ ] Resize task\n")
+            .await
+            .unwrap();
+
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+
+        let start = DaemonRequest::Start {
+            task_key: "Resize".to_string(),
+            task_id: None,
+            project_path: markdown_path.to_string_lossy().to_string(),
+            shell: Some(vec!["sleep".to_string(), "2".to_string()]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
+        };
+        let response = handle_request(&state, start, &shutdown_tx).await;
+        let session_id = match response {
+            DaemonResponse::SessionStarted { session } => session.id,
+            other => panic!("Expected SessionStarted, got {:?}", other),
+        };
+
+        let resize_req = DaemonRequest::Resize {
+            session_id,
+            cols: 132,
+            rows: 40,
+        };
+        match handle_request(&state, resize_req, &shutdown_tx).await {
+            DaemonResponse::SessionResized {
+                session_id: sid,
+                cols,
+                rows,
+            } => {
+                assert_eq!(sid, session_id);
+                assert_eq!(cols, 132);
+                assert_eq!(rows, 40);
+            }
+            other => panic!("Expected SessionResized response, got {:?}", other),
+        }
+
+        let _ = handle_request(&state, DaemonRequest::Stop { session_id }, &shutdown_tx).await;
+    }
+
+    #[tokio::test]
+    async fn test_exit_is_reported_to_subscribers_promptly() {
+        let (config, temp_dir) = test_config();
+        let markdown_path = temp_dir.path().join("TODO.md");
+        tokio::fs::write(&markdown_path, "# Tasks\n- [ ] Quick exit\n")
+            .await
+            .unwrap();
+
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+        let mut updates = state.updates_tx.subscribe();
+
+        let start = DaemonRequest::Start {
+            task_key: "Quick exit".to_string(),
+            task_id: None,
+            project_path: markdown_path.to_string_lossy().to_string(),
+            shell: Some(vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "exit 7".to_string(),
+            ]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
+        };
+        let session_id = match handle_request(&state, start, &shutdown_tx).await {
+            DaemonResponse::SessionStarted { session } => session.id,
+            other => panic!("Expected SessionStarted, got {:?}", other),
+        };
+
+        // Well under the old 5s polling interval
+        let stopped = tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                match updates.recv().await.map(|envelope| envelope.notification) {
+                    Ok(DaemonNotification::SessionUpdated { session })
+                        if session.id == session_id && session.status == SessionStatus::Stopped =>
+                    {
+                        break session;
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(e) => panic!("Update channel closed: {}", e),
+                }
+            }
+        })
+        .await
+        .expect("Stopped update should arrive promptly");
+
+        assert_eq!(stopped.exit_code, Some(7));
+        let content = tokio::fs::read_to_string(&markdown_path).await.unwrap();
+        assert!(
+            content.contains("Stopped"),
+            "Markdown badge should show Stopped. Got: {}",
+            content
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_input_reaches_pty() {
+        let (config, temp_dir) = test_config();
+        let markdown_path = temp_dir.path().join("TODO.md");
+        tokio::fs::write(&markdown_path, "# Tasks\n- [ ] Send input task\n")
+            .await
+            .unwrap();
+
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+
+        let start = DaemonRequest::Start {
+            task_key: "Send input".to_string(),
+            task_id: None,
+            project_path: markdown_path.to_string_lossy().to_string(),
+            shell: Some(vec!["cat".to_string()]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
+        };
+        let session_id = match handle_request(&state, start, &shutdown_tx).await {
+            DaemonResponse::SessionStarted { session } => session.id,
+            other => panic!("Expected SessionStarted, got {:?}", other),
+        };
+
+        let send = DaemonRequest::SendInput {
+            session_id,
+            data: "send-input-test".to_string(),
+            append_newline: true,
+        };
+        match handle_request(&state, send, &shutdown_tx).await {
+            DaemonResponse::InputSent {
+                session_id: sid,
+                bytes,
+            } => {
+                assert_eq!(sid, session_id);
+                assert_eq!(bytes, "send-input-test".len() + 1);
+            }
+            other => panic!("Expected InputSent response, got {:?}", other),
+        }
+
+        assert_eventually_bool(
+            "tail to contain sent input",
+            Duration::from_secs(3),
+            Duration::from_millis(50),
+            || {
+                let state = Arc::clone(&state);
+                async move {
+                    state
+                        .session_tail(session_id, 1024)
+                        .await
+                        .map(|data| String::from_utf8_lossy(&data).contains("send-input-test"))
+                        .unwrap_or(false)
+                }
+            },
+        )
+        .await;
+
+        let _ = handle_request(&state, DaemonRequest::Stop { session_id }, &shutdown_tx).await;
+    }
+
+    #[tokio::test]
+    async fn test_recorded_session_is_listed_and_deletable() {
+        use rn_desktop_2_lib::session::protocol::DaemonErrorCode;
+        use rn_desktop_2_lib::session::recording::{read_recording, CastEvent};
+
+        let (config, temp_dir) = test_config();
+        let markdown_path = temp_dir.path().join("TODO.md");
+        tokio::fs::write(&markdown_path, "# Tasks\n- [ ] Recorded task\n")
+            .await
+            .unwrap();
+
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+
+        let start = DaemonRequest::Start {
+            task_key: "Recorded".to_string(),
+            task_id: None,
+            project_path: markdown_path.to_string_lossy().to_string(),
+            shell: Some(vec!["cat".to_string()]),
+            cwd: None,
+            env: Default::default(),
+            record: true,
+            attention_profiles: Vec::new(),
+        };
+        let session_id = match handle_request(&state, start, &shutdown_tx).await {
+            DaemonResponse::SessionStarted { session } => {
+                assert!(session.recording);
+                session.id
+            }
+            other => panic!("Expected SessionStarted, got {:?}", other),
+        };
+
+        let send = DaemonRequest::SendInput {
+            session_id,
+            data: "recorded-input".to_string(),
+            append_newline: true,
+        };
+        let _ = handle_request(&state, send, &shutdown_tx).await;
+        assert_eventually_bool(
+            "tail to contain sent input",
+            Duration::from_secs(3),
+            Duration::from_millis(50),
+            || {
+                let state = Arc::clone(&state);
+                async move {
+                    state
+                        .session_tail(session_id, 1024)
+                        .await
+                        .map(|data| String::from_utf8_lossy(&data).contains("recorded-input"))
+                        .unwrap_or(false)
+                }
+            },
+        )
+        .await;
+        let resize = DaemonRequest::Resize {
+            session_id,
+            cols: 100,
+            rows: 30,
+        };
+        let _ = handle_request(&state, resize, &shutdown_tx).await;
+
+        let path = match handle_request(&state, DaemonRequest::ListRecordings, &shutdown_tx).await {
+            DaemonResponse::RecordingList { recordings } => {
+                assert_eq!(recordings.len(), 1);
+                assert_eq!(recordings[0].session_id, session_id);
+                assert_eq!(recordings[0].task_key.as_deref(), Some("Recorded task"));
+                assert!(recordings[0].active);
+                PathBuf::from(&recordings[0].path)
+            }
+            other => panic!("Expected RecordingList, got {:?}", other),
+        };
+
+        // A recording can't be deleted out from under a running session
+        let delete = DaemonRequest::DeleteRecording { session_id };
+        match handle_request(&state, delete.clone(), &shutdown_tx).await {
+            DaemonResponse::Error { code, .. } => assert_eq!(code, DaemonErrorCode::InvalidRequest),
+            other => panic!("Expected Error, got {:?}", other),
+        }
+
+        let _ = handle_request(&state, DaemonRequest::Stop { session_id }, &shutdown_tx).await;
+
+        let (_, entries) = read_recording(&path).unwrap();
+        let recorded: String = entries
+            .iter()
+            .filter_map(|entry| match &entry.event {
+                CastEvent::Output(text) => Some(text.as_str()),
+                CastEvent::Resize { .. } => None,
+            })
+            .collect();
+        assert!(recorded.contains("recorded-input"));
+        assert!(entries.iter().any(|entry| entry.event
+            == CastEvent::Resize {
+                cols: 100,
+                rows: 30
+            }));
+
+        match handle_request(&state, delete.clone(), &shutdown_tx).await {
+            DaemonResponse::RecordingDeleted { session_id: sid } => assert_eq!(sid, session_id),
+            other => panic!("Expected RecordingDeleted, got {:?}", other),
+        }
+        assert!(!path.exists());
+        match handle_request(&state, delete, &shutdown_tx).await {
+            DaemonResponse::Error { code, .. } => assert_eq!(code, DaemonErrorCode::NotFound),
+            other => panic!("Expected Error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_restart_reuses_command_cwd_and_env() {
+        use rn_desktop_2_lib::session::protocol::DaemonErrorCode;
+
+        let (config, temp_dir) = test_config();
+        let markdown_path = temp_dir.path().join("TODO.md");
+        tokio::fs::write(&markdown_path, "# Tasks\n- [ ] Restartable task\n")
+            .await
+            .unwrap();
+        let work_dir = temp_dir.path().join("work");
+        std::fs::create_dir(&work_dir).unwrap();
+        let work_dir = work_dir.canonicalize().unwrap();
+
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+
+        let start = DaemonRequest::Start {
+            task_key: "Restartable".to_string(),
+            task_id: None,
+            project_path: markdown_path.to_string_lossy().to_string(),
+            shell: Some(vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "echo \"flavor=$RN_FLAVOR dir=$(pwd)\"; exec cat".to_string(),
+            ]),
+            cwd: Some(work_dir.to_string_lossy().to_string()),
+            env: [("RN_FLAVOR".to_string(), "mint".to_string())].into(),
+            record: false,
+            attention_profiles: Vec::new(),
+        };
+        let session_id = match handle_request(&state, start, &shutdown_tx).await {
+            DaemonResponse::SessionStarted { session } => {
+                assert_eq!(session.spawn_cwd.as_deref(), work_dir.to_str());
+                session.id
+            }
+            other => panic!("Expected SessionStarted, got {:?}", other),
+        };
+
+        // Only stopped sessions can be restarted
+        match handle_request(&state, DaemonRequest::Restart { session_id }, &shutdown_tx).await {
+            DaemonResponse::Error { code, .. } => assert_eq!(code, DaemonErrorCode::InvalidRequest),
+            other => panic!("Expected Error, got {:?}", other),
+        }
+        let _ = handle_request(&state, DaemonRequest::Stop { session_id }, &shutdown_tx).await;
+
+        match handle_request(&state, DaemonRequest::Restart { session_id }, &shutdown_tx).await {
+            DaemonResponse::SessionRestarted { session } => {
+                assert_eq!(session.id, session_id);
+                assert_eq!(session.status, SessionStatus::Running);
+            }
+            other => panic!("Expected SessionRestarted, got {:?}", other),
+        }
+        let expected = format!("flavor=mint dir={}", work_dir.display());
+        assert_eventually_bool(
+            "restarted session to print its cwd and env",
+            Duration::from_secs(3),
+            Duration::from_millis(50),
+            || {
+                let state = Arc::clone(&state);
+                let expected = expected.clone();
+                async move {
+                    state
+                        .session_tail(session_id, 1024)
+                        .await
+                        .map(|data| String::from_utf8_lossy(&data).contains(&expected))
+                        .unwrap_or(false)
+                }
+            },
+        )
+        .await;
+
+        let content = tokio::fs::read_to_string(&markdown_path).await.unwrap();
+        assert!(
+            content.contains(&format!("[Running](todos://session/{})", session_id)),
+            "Badge should be back to Running: {}",
+            content
+        );
+
+        // Of two restarts racing each other, exactly one spawns
+        let _ = handle_request(&state, DaemonRequest::Stop { session_id }, &shutdown_tx).await;
+        let (first, second) = tokio::join!(
+            handle_request(&state, DaemonRequest::Restart { session_id }, &shutdown_tx),
+            handle_request(&state, DaemonRequest::Restart { session_id }, &shutdown_tx),
+        );
+        let restarted = [&first, &second]
+            .iter()
+            .filter(|response| matches!(response, DaemonResponse::SessionRestarted { .. }))
+            .count();
+        assert_eq!(restarted, 1, "{:?} / {:?}", first, second);
+
+        let _ = handle_request(&state, DaemonRequest::Stop { session_id }, &shutdown_tx).await;
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_status_and_list_report_foreground_process() {
+        let (config, temp_dir) = test_config();
+        let markdown_path = temp_dir.path().join("TODO.md");
+        tokio::fs::write(&markdown_path, "# Tasks\n- [ ] Watch task\n")
+            .await
+            .unwrap();
+        let work_dir = temp_dir.path().join("crate");
+        std::fs::create_dir(&work_dir).unwrap();
+        let work_dir = work_dir.canonicalize().unwrap();
+
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+
+        let start = DaemonRequest::Start {
+            task_key: "Watch".to_string(),
+            task_id: None,
+            project_path: markdown_path.to_string_lossy().to_string(),
+            shell: Some(vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "cd crate && exec sleep 30".to_string(),
+            ]),
+            cwd: Some(temp_dir.path().to_string_lossy().to_string()),
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
+        };
+        let session_id = match handle_request(&state, start, &shutdown_tx).await {
+            DaemonResponse::SessionStarted { session } => session.id,
+            other => panic!("Expected SessionStarted, got {:?}", other),
+        };
+
+        assert_eventually_bool(
+            "status to report sleep in the foreground",
+            Duration::from_secs(3),
+            Duration::from_millis(50),
+            || {
+                let state = Arc::clone(&state);
+                let shutdown_tx = shutdown_tx.clone();
+                async move {
+                    let status = DaemonRequest::Status { session_id };
+                    matches!(
+                        handle_request(&state, status, &shutdown_tx).await,
+                        DaemonResponse::SessionStatus { session }
+                            if session.foreground_command.as_deref() == Some("sleep 30")
+                    )
+                }
+            },
+        )
+        .await;
+
+        let list = DaemonRequest::List { project_path: None };
+        match handle_request(&state, list, &shutdown_tx).await {
+            DaemonResponse::SessionList { sessions } => {
+                assert_eq!(sessions.len(), 1);
+                assert_eq!(sessions[0].foreground_command.as_deref(), Some("sleep 30"));
+                assert_eq!(sessions[0].cwd.as_deref(), work_dir.to_str());
+            }
+            other => panic!("Expected SessionList, got {:?}", other),
+        }
+
+        // The last directory outlives the process; the command does not
+        match handle_request(&state, DaemonRequest::Stop { session_id }, &shutdown_tx).await {
+            DaemonResponse::SessionStopped { session } => {
+                assert!(session.foreground_command.is_none());
+                assert_eq!(session.cwd.as_deref(), work_dir.to_str());
+            }
+            other => panic!("Expected SessionStopped, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notifications_only_reach_subscribed_connections() {
+        use rn_desktop_2_lib::session::protocol::NotificationKind;
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let (config, _temp_dir) = test_config();
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+        let (handoff_tx, _handoff_rx) = tokio::sync::mpsc::channel::<UnixStream>(1);
+
+        let (client, server) = UnixStream::pair().unwrap();
+        tokio::spawn(handle_client(
+            Arc::clone(&state),
+            server,
+            shutdown_tx,
+            handoff_tx,
+        ));
+        let (reader, mut writer) = client.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        let frame = |request: DaemonRequest| serialize_message(&request).unwrap();
+        let removed = |session_id| DaemonNotification::SessionRemoved { session_id };
+
+        // Nothing arrives before subscribing
+        state.broadcast("/a/TODO.md", removed(1));
+        writer.write_all(&frame(DaemonRequest::Ping)).await.unwrap();
+        let line = lines.next_line().await.unwrap().unwrap();
+        assert!(matches!(
+            deserialize_message::<DaemonResponse>(line.as_bytes()),
+            Ok(DaemonResponse::Pong)
+        ));
+
+        let subscribe = DaemonRequest::Subscribe {
+            projects: vec!["/a/TODO.md".to_string()],
+            session_ids: Vec::new(),
+            kinds: vec![NotificationKind::SessionRemoved],
+        };
+        writer.write_all(&frame(subscribe)).await.unwrap();
+        let line = lines.next_line().await.unwrap().unwrap();
+        assert!(matches!(
+            deserialize_message::<DaemonResponse>(line.as_bytes()),
+            Ok(DaemonResponse::Subscribed { .. })
+        ));
+
+        // Only the matching notification is forwarded, in an envelope
+        state.broadcast("/b/TODO.md", removed(2));
+        state.broadcast("/a/TODO.md", removed(3));
+        let line = lines.next_line().await.unwrap().unwrap();
+        assert!(deserialize_message::<DaemonResponse>(line.as_bytes()).is_err());
+        let envelope: NotificationEnvelope = deserialize_message(line.as_bytes()).unwrap();
+        assert_eq!(envelope.project_path.as_deref(), Some("/a/TODO.md"));
+        assert_eq!(envelope.notification.session_id(), Some(3));
+        assert_eq!(envelope.seq, 3);
+
+        writer
+            .write_all(&frame(DaemonRequest::Unsubscribe))
+            .await
+            .unwrap();
+        let line = lines.next_line().await.unwrap().unwrap();
+        assert!(matches!(
+            deserialize_message::<DaemonResponse>(line.as_bytes()),
+            Ok(DaemonResponse::Unsubscribed)
+        ));
+        state.broadcast("/a/TODO.md", removed(4));
+        writer.write_all(&frame(DaemonRequest::Ping)).await.unwrap();
+        let line = lines.next_line().await.unwrap().unwrap();
+        assert!(matches!(
+            deserialize_message::<DaemonResponse>(line.as_bytes()),
+            Ok(DaemonResponse::Pong)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_lagging_subscriber_is_told_to_resync_from_snapshot() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let (config, _temp_dir) = test_config();
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+        let (handoff_tx, _handoff_rx) = tokio::sync::mpsc::channel::<UnixStream>(1);
+
+        {
+            let mut registry = state.registry.write().await;
+            registry.insert(Session::new(
+                0,
+                "Build feature".to_string(),
+                None,
+                "/a/TODO.md".to_string(),
+            ));
+            registry.next_id = 1;
+        }
+
+        let (client, server) = UnixStream::pair().unwrap();
+        tokio::spawn(handle_client(
+            Arc::clone(&state),
+            server,
+            shutdown_tx.clone(),
+            handoff_tx,
+        ));
+        let (reader, mut writer) = client.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        let frame = |request: DaemonRequest| serialize_message(&request).unwrap();
+
+        let subscribe = DaemonRequest::Subscribe {
+            projects: Vec::new(),
+            session_ids: Vec::new(),
+            kinds: Vec::new(),
+        };
+        writer.write_all(&frame(subscribe)).await.unwrap();
+        lines.next_line().await.unwrap().unwrap();
+
+        // Overflow the channel before the connection gets a chance to drain it
+        // (its capacity is rounded up to a power of two)
+        let sent = 2 * NOTIFICATION_BUFFER as u64;
+        for _ in 0..sent {
+            state.broadcast(
+                "/a/TODO.md",
+                DaemonNotification::SessionRemoved { session_id: 9 },
+            );
+        }
+
+        let line = lines.next_line().await.unwrap().unwrap();
+        let envelope: NotificationEnvelope = deserialize_message(line.as_bytes()).unwrap();
+        let DaemonNotification::Resync { missed } = envelope.notification else {
+            panic!("Expected Resync, got {:?}", envelope);
+        };
+        assert!(missed > 0);
+        assert_eq!(envelope.seq, sent);
+        assert_eq!(envelope.project_path, None);
+
+        // What's still buffered follows, in seq order
+        for expected in missed + 1..=sent {
+            let line = lines.next_line().await.unwrap().unwrap();
+            let envelope: NotificationEnvelope = deserialize_message(line.as_bytes()).unwrap();
+            assert_eq!(envelope.seq, expected);
+        }
+
+        match handle_request(&state, DaemonRequest::Snapshot, &shutdown_tx).await {
+            DaemonResponse::Snapshot { seq, sessions } => {
+                assert_eq!(seq, sent);
+                assert_eq!(sessions.len(), 1);
+                assert_eq!(sessions[0].task_key, "Build feature");
+            }
+            other => panic!("Expected Snapshot, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_events_are_journaled_across_restarts() {
+        let (config, _temp_dir) = test_config();
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+        let removed = |session_id| DaemonNotification::SessionRemoved { session_id };
+
+        {
+            let state = DaemonState::new(config.clone()).unwrap();
+            state.broadcast("/a/TODO.md", removed(1));
+            state.broadcast("/b/TODO.md", removed(2));
+        }
+
+        // A new daemon picks up the seqs where the old one stopped
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        state.broadcast("/a/TODO.md", removed(3));
+
+        let request = DaemonRequest::Events {
+            since_seq: 0,
+            limit: None,
+            filter: SubscriptionFilter {
+                projects: vec!["/a/TODO.md".to_string()],
+                ..Default::default()
+            },
+        };
+        match handle_request(&state, request, &shutdown_tx).await {
+            DaemonResponse::Events { events, latest_seq } => {
+                assert_eq!(latest_seq, 3);
+                let seqs: Vec<_> = events.iter().map(|event| event.seq).collect();
+                assert_eq!(seqs, [1, 3]);
+                assert!(matches!(
+                    events[1].notification,
+                    DaemonNotification::SessionRemoved { session_id: 3 }
+                ));
+            }
+            other => panic!("Expected Events, got {:?}", other),
+        }
+
+        let request = DaemonRequest::Events {
+            since_seq: 1,
+            limit: Some(1),
+            filter: SubscriptionFilter::default(),
+        };
+        match handle_request(&state, request, &shutdown_tx).await {
+            DaemonResponse::Events { events, .. } => {
+                assert_eq!(events.len(), 1);
+                assert_eq!(events[0].seq, 2);
+            }
+            other => panic!("Expected Events, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pipelined_requests_are_matched_by_request_id() {
+        use rn_desktop_2_lib::session::daemon_client::DaemonClient;
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let (config, temp_dir) = test_config();
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+        let (handoff_tx, _handoff_rx) = tokio::sync::mpsc::channel::<UnixStream>(1);
+
+        let socket_path = temp_dir.path().join("pipelined.sock");
+        let listener = UnixListener::bind(&socket_path).unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(handle_client(
+                    Arc::clone(&state),
+                    stream,
+                    shutdown_tx.clone(),
+                    handoff_tx.clone(),
+                ));
+            }
+        });
+
+        // Many requests in flight on one connection, more than are handled at
+        // once, each get their own answer
+        let client = Arc::new(DaemonClient::connect(&socket_path).await.unwrap());
+        let mut statuses = tokio::task::JoinSet::new();
+        for session_id in 1..=2 * MAX_PIPELINED_REQUESTS as SessionId {
+            let client = Arc::clone(&client);
+            statuses.spawn(async move {
+                let status = DaemonRequest::Status { session_id };
+                (session_id, client.request(status).await.unwrap())
+            });
+        }
+        while let Some(result) = statuses.join_next().await {
+            let (session_id, response) = result.unwrap();
+            match response {
+                DaemonResponse::Error { message, .. } => {
+                    assert!(message.contains(&format!("Session {} ", session_id)))
+                }
+                other => panic!("Expected Error, got {:?}", other),
+            }
+        }
+
+        // Bare requests get bare responses; unparseable ones still echo their id
+        let stream = UnixStream::connect(&socket_path).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        writer.write_all(b"{\"type\":\"ping\"}\n").await.unwrap();
+        let line = lines.next_line().await.unwrap().unwrap();
+        let frame: ResponseFrame = deserialize_message(line.as_bytes()).unwrap();
+        assert_eq!(frame.request_id, None);
+        assert!(matches!(frame.response, DaemonResponse::Pong));
+
+        writer
+            .write_all(b"{\"type\":\"no_such_request\",\"request_id\":42}\n")
+            .await
+            .unwrap();
+        let line = lines.next_line().await.unwrap().unwrap();
+        let frame: ResponseFrame = deserialize_message(line.as_bytes()).unwrap();
+        assert_eq!(frame.request_id, Some(42));
+        assert!(matches!(frame.response, DaemonResponse::Error { .. }));
+    }
+
+    /// Collect a stream's output until it contains `needle`
+    async fn read_stream_until(
+        frames: &mut tokio::sync::mpsc::Receiver<StreamFrame>,
+        needle: &str,
+    ) -> String {
+        let mut output = Vec::new();
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(3);
+        while !String::from_utf8_lossy(&output).contains(needle) {
+            match tokio::time::timeout_at(deadline, frames.recv()).await {
+                Ok(Some(StreamFrame::Data { data, .. })) => output.extend(data),
+                Ok(other) => panic!("Expected stream data, got {:?}", other),
+                Err(_) => panic!(
+                    "timed out waiting for {:?}, got {:?}",
+                    needle,
+                    String::from_utf8_lossy(&output)
+                ),
+            }
+        }
+        String::from_utf8_lossy(&output).into_owned()
+    }
+
+    #[tokio::test]
+    async fn test_streams_for_several_sessions_share_one_connection() {
+        use rn_desktop_2_lib::session::daemon_client::DaemonClient;
+
+        let (config, temp_dir) = test_config();
+        let markdown_path = temp_dir.path().join("TODO.md");
+        tokio::fs::write(
+            &markdown_path,
+            "# Tasks\n- [ ] Stream one\n- [ ] Stream two\n",
+        )
+        .await
+        .unwrap();
+
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+        let (handoff_tx, _handoff_rx) = tokio::sync::mpsc::channel::<UnixStream>(1);
+
+        let mut session_ids = Vec::new();
+        for task_key in ["Stream one", "Stream two"] {
+            let start = DaemonRequest::Start {
+                task_key: task_key.to_string(),
+                task_id: None,
+                project_path: markdown_path.to_string_lossy().to_string(),
+                shell: Some(vec!["cat".to_string()]),
+                cwd: None,
+                env: Default::default(),
+                record: false,
+                attention_profiles: Vec::new(),
+            };
+            match handle_request(&state, start, &shutdown_tx).await {
+                DaemonResponse::SessionStarted { session } => session_ids.push(session.id),
+                other => panic!("Expected SessionStarted, got {:?}", other),
+            }
+        }
+        let (one, two) = (session_ids[0], session_ids[1]);
+
+        let socket_path = temp_dir.path().join("streams.sock");
+        let listener = UnixListener::bind(&socket_path).unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(handle_client(
+                    Arc::clone(&state),
+                    stream,
+                    shutdown_tx.clone(),
+                    handoff_tx.clone(),
+                ));
+            }
+        });
+        let client = DaemonClient::connect(&socket_path).await.unwrap();
+
+        let (response, mut one_frames) = client
+            .open_stream(one, None, None, AttachMode::Shared)
+            .await
+            .unwrap();
+        assert!(matches!(response, DaemonResponse::StreamOpened { .. }));
+        let (response, mut two_frames) = client
+            .open_stream(two, None, None, AttachMode::Shared)
+            .await
+            .unwrap();
+        assert!(matches!(response, DaemonResponse::StreamOpened { .. }));
+
+        // Input and output stay with their own session
+        for (session_id, text) in [(one, "first-stream\n"), (two, "second-stream\n")] {
+            let input = StreamFrame::Data {
+                session_id,
+                data: text.as_bytes().to_vec(),
+            };
+            client.send_stream_frame(&input).await.unwrap();
+        }
+        let output = read_stream_until(&mut one_frames, "first-stream").await;
+        assert!(!output.contains("second-stream"), "{}", output);
+        let output = read_stream_until(&mut two_frames, "second-stream").await;
+        assert!(!output.contains("first-stream"), "{}", output);
+
+        // Detaching one stream leaves the other flowing
+        client
+            .send_stream_frame(&StreamFrame::detach(one))
+            .await
+            .unwrap();
+        // Output already on its way (e.g. the program echoing) may come first
+        let mut closing = one_frames.recv().await;
+        while matches!(closing, Some(StreamFrame::Data { .. })) {
+            closing = one_frames.recv().await;
+        }
+        assert_eq!(closing, Some(StreamFrame::detach(one)));
+        assert_eq!(one_frames.recv().await, None);
+
+        let resize = StreamFrame::Resize {
+            session_id: two,
+            cols: 100,
+            rows: 30,
+        };
+        client.send_stream_frame(&resize).await.unwrap();
+        let screen = client
+            .request(DaemonRequest::Screen { session_id: two })
+            .await
+            .unwrap();
+        assert!(matches!(
+            screen,
+            DaemonResponse::SessionScreen { screen, .. } if screen.cols == 100 && screen.rows == 30
+        ));
+
+        // The daemon closes a stream when its session exits
+        client
+            .request(DaemonRequest::Stop { session_id: two })
+            .await
+            .unwrap();
+        loop {
+            match tokio::time::timeout(Duration::from_secs(3), two_frames.recv()).await {
+                Ok(Some(StreamFrame::Data { .. })) => continue,
+                Ok(Some(StreamFrame::Close { exited, .. })) => {
+                    assert!(exited);
+                    break;
+                }
+                other => panic!("Expected stream close, got {:?}", other),
+            }
+        }
+
+        // Streams can't be used without opening them
+        let (response, mut frames) = client
+            .open_stream(99, None, None, AttachMode::Shared)
+            .await
+            .unwrap();
+        assert!(matches!(
+            response,
+            DaemonResponse::Error {
+                code: rn_desktop_2_lib::session::protocol::DaemonErrorCode::NotFound,
+                ..
+            }
+        ));
+        assert_eq!(frames.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_attach_modes_limit_input_and_other_viewers() {
+        use rn_desktop_2_lib::session::daemon_client::DaemonClient;
+        use rn_desktop_2_lib::session::protocol::DaemonErrorCode;
+
+        let (config, temp_dir) = test_config();
+        let markdown_path = temp_dir.path().join("TODO.md");
+        tokio::fs::write(&markdown_path, "# Tasks\n- [ ] Watched\n")
+            .await
+            .unwrap();
+
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+        let (handoff_tx, _handoff_rx) = tokio::sync::mpsc::channel::<UnixStream>(1);
+
+        let start = DaemonRequest::Start {
+            task_key: "Watched".to_string(),
+            task_id: None,
+            project_path: markdown_path.to_string_lossy().to_string(),
+            shell: Some(vec!["cat".to_string()]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
+        };
+        let session_id = match handle_request(&state, start, &shutdown_tx).await {
+            DaemonResponse::SessionStarted { session } => session.id,
+            other => panic!("Expected SessionStarted, got {:?}", other),
+        };
+
+        let socket_path = temp_dir.path().join("modes.sock");
+        let listener = UnixListener::bind(&socket_path).unwrap();
+        tokio::spawn({
+            let state = Arc::clone(&state);
+            let shutdown_tx = shutdown_tx.clone();
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    tokio::spawn(handle_client(
+                        Arc::clone(&state),
+                        stream,
+                        shutdown_tx.clone(),
+                        handoff_tx.clone(),
+                    ));
+                }
+            }
+        });
+        let watcher = DaemonClient::connect(&socket_path).await.unwrap();
+        let typist = DaemonClient::connect(&socket_path).await.unwrap();
+        let owner = DaemonClient::connect(&socket_path).await.unwrap();
+        let modes = |session: &Session| -> Vec<AttachMode> {
+            session.viewers.iter().map(|viewer| viewer.mode).collect()
+        };
+
+        let (_, mut watcher_frames) = watcher
+            .open_stream(session_id, None, None, AttachMode::ReadOnly)
+            .await
+            .unwrap();
+        let (response, mut typist_frames) = typist
+            .open_stream(session_id, None, None, AttachMode::Shared)
+            .await
+            .unwrap();
+        match response {
+            DaemonResponse::StreamOpened { session, .. } => {
+                assert_eq!(
+                    modes(&session),
+                    vec![AttachMode::ReadOnly, AttachMode::Shared]
+                );
+            }
+            other => panic!("Expected StreamOpened, got {:?}", other),
+        }
+
+        // Read-only input is dropped; the request after it shows it was handled
+        let ignored = StreamFrame::Data {
+            session_id,
+            data: b"from-watcher\n".to_vec(),
+        };
+        watcher.send_stream_frame(&ignored).await.unwrap();
+        watcher
+            .request(DaemonRequest::List { project_path: None })
             .await
-            .expect("failed to send input");
-        writer.flush().await.expect("failed to flush writer");
-
-        let mut collected = Vec::new();
-        let mut buf = vec![0u8; 1024];
-        let mut attempts = 0;
+            .unwrap();
+        let typed = StreamFrame::Data {
+            session_id,
+            data: b"from-typist\n".to_vec(),
+        };
+        typist.send_stream_frame(&typed).await.unwrap();
+        let output = read_stream_until(&mut watcher_frames, "from-typist").await;
+        assert!(!output.contains("from-watcher"), "{}", output);
 
-        loop {
-            attempts += 1;
-            if attempts > 20 {
-                panic!("timed out waiting for attach output");
+        // An exclusive client detaches everyone else...
+        let (response, mut owner_frames) = owner
+            .open_stream(session_id, None, None, AttachMode::Exclusive)
+            .await
+            .unwrap();
+        match response {
+            DaemonResponse::StreamOpened { session, .. } => {
+                assert_eq!(modes(&session), vec![AttachMode::Exclusive]);
             }
-
-            match tokio::time::timeout(Duration::from_millis(200), reader.read(&mut buf)).await {
-                Ok(Ok(0)) => break,
-                Ok(Ok(n)) => {
-                    collected.extend_from_slice(&buf[..n]);
-                    if collected.contains(&b'\n') {
+            other => panic!("Expected StreamOpened, got {:?}", other),
+        }
+        for frames in [&mut watcher_frames, &mut typist_frames] {
+            loop {
+                match tokio::time::timeout(Duration::from_secs(3), frames.recv()).await {
+                    Ok(Some(StreamFrame::Data { .. })) => continue,
+                    Ok(Some(StreamFrame::Close {
+                        exited, message, ..
+                    })) => {
+                        assert!(!exited);
+                        assert_eq!(message.as_deref(), Some(DETACHED_BY_EXCLUSIVE));
                         break;
                     }
+                    other => panic!("Expected stream close, got {:?}", other),
                 }
-                Ok(Err(e)) => panic!("attach read error: {}", e),
-                Err(_) => continue,
             }
         }
 
-        let text = String::from_utf8_lossy(&collected);
-        assert!(
-            text.contains("attach-stream-test"),
-            "attach stream should echo input, got '{}'",
-            text
-        );
+        // ...and keeps them out, whichever way they attach
+        let (response, _) = typist
+            .open_stream(session_id, None, None, AttachMode::Shared)
+            .await
+            .unwrap();
+        assert!(matches!(
+            response,
+            DaemonResponse::Error {
+                code: DaemonErrorCode::InvalidRequest,
+                ..
+            }
+        ));
+        let attach = DaemonRequest::Attach {
+            session_id,
+            tail_bytes: None,
+            offset: None,
+            mode: AttachMode::ReadOnly,
+        };
+        assert!(matches!(
+            handle_request(&state, attach, &shutdown_tx).await,
+            DaemonResponse::Error { .. }
+        ));
 
-        // Stop session to clean up
-        let _ = handle_request(&state, DaemonRequest::Stop { session_id }, &shutdown_tx).await;
+        // Once it detaches the session is open to all again
+        owner
+            .send_stream_frame(&StreamFrame::detach(session_id))
+            .await
+            .unwrap();
+        assert_eq!(
+            owner_frames.recv().await,
+            Some(StreamFrame::detach(session_id))
+        );
+        assert_eventually_bool(
+            "viewers to be cleared",
+            Duration::from_secs(3),
+            Duration::from_millis(20),
+            || async {
+                let registry = state.registry.read().await;
+                registry
+                    .get(session_id)
+                    .is_some_and(|s| s.viewers.is_empty())
+            },
+        )
+        .await;
+        let (response, _typist_frames) = typist
+            .open_stream(session_id, None, None, AttachMode::Shared)
+            .await
+            .unwrap();
+        assert!(matches!(response, DaemonResponse::StreamOpened { .. }));
     }
 
     #[tokio::test]
-    async fn test_resize_request_succeeds() {
-        let (config, temp_dir) = test_config();
-        let markdown_path = temp_dir.path().join("TODO.md");
-        tokio::fs::write(&markdown_path, "# Tasks\n- [ ] Resize task\n")
+    async fn test_refused_connection_gets_permission_error() {
+        use rn_desktop_2_lib::session::protocol::DaemonErrorCode;
+
+        let (client, server) = UnixStream::pair().unwrap();
+        let refusal = tokio::spawn(refuse_connection(
+            server,
+            "Connection refused: peer uid 1001 is not the daemon's uid 1000".to_string(),
+        ));
+
+        let (reader, mut writer) = client.into_split();
+        writer
+            .write_all(b"{\"type\":\"handshake\",\"client_version\":1,\"request_id\":3}\n")
             .await
             .unwrap();
+        let mut line = String::new();
+        BufReader::new(reader).read_line(&mut line).await.unwrap();
+        let frame: ResponseFrame = deserialize_message(line.as_bytes()).unwrap();
+        assert_eq!(frame.request_id, Some(3));
+        match frame.response {
+            DaemonResponse::Error { code, message } => {
+                assert_eq!(code, DaemonErrorCode::PermissionDenied);
+                assert!(message.contains("uid 1001"), "{}", message);
+            }
+            other => panic!("Expected Error, got {:?}", other),
+        }
+        refusal.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reload_config_applies_valid_config_only() {
+        use rn_desktop_2_lib::session::protocol::DaemonErrorCode;
+
+        let (config, _temp_dir) = test_config();
+        let settings_file = config.settings_file();
+        std::fs::write(&settings_file, "tail_bytes = 0\n").unwrap();
+        let err = format!("{:#}", DaemonState::new(config.clone()).err().unwrap());
+        assert!(err.contains("tail_bytes"), "unexpected error: {}", err);
 
+        std::fs::write(&settings_file, "tail_bytes = 2048\n").unwrap();
         let state = Arc::new(DaemonState::new(config).unwrap());
         let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+        assert_eq!(state.settings().tail_bytes, 2048);
 
-        let start = DaemonRequest::Start {
-            task_key: "Resize".to_string(),
-            task_id: None,
-            project_path: markdown_path.to_string_lossy().to_string(),
-            shell: Some(vec!["sleep".to_string(), "2".to_string()]),
+        std::fs::write(
+            &settings_file,
+            "tail_bytes = 1024\nnotification_debounce_secs = 0\n",
+        )
+        .unwrap();
+        let profiles_file = state.config.attention_profiles_file();
+        std::fs::write(
+            &profiles_file,
+            "[[profile]]\nname = \"reload-test\"\n\n[[profile.trigger]]\nliteral = \"reload-test-finished\"\ntype = \"completed\"\n",
+        )
+        .unwrap();
+        match handle_request(&state, DaemonRequest::ReloadConfig, &shutdown_tx).await {
+            DaemonResponse::ConfigReloaded {
+                settings,
+                attention_profiles,
+            } => {
+                assert_eq!(settings.tail_bytes, 1024);
+                assert_eq!(settings.notification_debounce_secs, 0);
+                assert_eq!(
+                    attention_profiles,
+                    vec!["reload-test", "claude-code", "build-tools"]
+                );
+            }
+            other => panic!("Expected ConfigReloaded, got {:?}", other),
+        }
+        assert_eq!(state.settings().tail_bytes, 1024);
+
+        // A broken file is reported and the settings in effect stay
+        std::fs::write(&settings_file, "tail_bytes = 1024\nidle_timeout = 5\n").unwrap();
+        match handle_request(&state, DaemonRequest::ReloadConfig, &shutdown_tx).await {
+            DaemonResponse::Error { code, message } => {
+                assert_eq!(code, DaemonErrorCode::InvalidConfig);
+                assert!(
+                    message.contains("idle_timeout"),
+                    "unexpected message: {}",
+                    message
+                );
+            }
+            other => panic!("Expected error response, got {:?}", other),
+        }
+        assert_eq!(state.settings().tail_bytes, 1024);
+        assert_eq!(state.settings().notification_debounce_secs, 0);
+
+        // So is a bad pattern, by name
+        std::fs::write(&settings_file, "tail_bytes = 512\n").unwrap();
+        std::fs::write(
+            &profiles_file,
+            "[[profile]]\nname = \"broken\"\n\n[[profile.trigger]]\nregex = \"deploy (done\"\ntype = \"completed\"\n",
+        )
+        .unwrap();
+        match handle_request(&state, DaemonRequest::ReloadConfig, &shutdown_tx).await {
+            DaemonResponse::Error { message, .. } => {
+                assert!(
+                    message.contains("`deploy (done`"),
+                    "unexpected message: {}",
+                    message
+                );
+            }
+            other => panic!("Expected error response, got {:?}", other),
+        }
+        assert_eq!(state.settings().tail_bytes, 1024);
+    }
+
+    #[tokio::test]
+    async fn test_output_log_setting_reloads_into_handshake() {
+        let (config, _temp_dir) = test_config();
+        let settings_file = config.settings_file();
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+        let handshake = || DaemonRequest::Handshake {
+            client_version: rn_desktop_2_lib::session::protocol::PROTOCOL_VERSION,
+            min_version: None,
+            byte_encoding: None,
         };
-        let response = handle_request(&state, start, &shutdown_tx).await;
-        let session_id = match response {
-            DaemonResponse::SessionStarted { session } => session.id,
-            other => panic!("Expected SessionStarted, got {:?}", other),
+        let announces_output_log = |response: DaemonResponse| match response {
+            DaemonResponse::Handshake { capabilities, .. } => {
+                capabilities.unwrap().contains(&Capability::OutputLog)
+            }
+            other => panic!("Expected Handshake, got {:?}", other),
         };
 
-        let resize_req = DaemonRequest::Resize {
-            session_id,
-            cols: 132,
-            rows: 40,
+        // Off until config.toml asks for it
+        assert!(!announces_output_log(
+            handle_request(&state, handshake(), &shutdown_tx).await
+        ));
+        assert!(state.open_output_log(1).is_none());
+
+        std::fs::write(&settings_file, "output_log_retained_bytes = 16777216\n").unwrap();
+        assert!(matches!(
+            handle_request(&state, DaemonRequest::ReloadConfig, &shutdown_tx).await,
+            DaemonResponse::ConfigReloaded { .. }
+        ));
+        assert!(announces_output_log(
+            handle_request(&state, handshake(), &shutdown_tx).await
+        ));
+        assert!(state.open_output_log(2).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_send_input_to_missing_session_fails() {
+        let (config, _temp_dir) = test_config();
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+
+        let send = DaemonRequest::SendInput {
+            session_id: 404,
+            data: "hello".to_string(),
+            append_newline: false,
         };
-        match handle_request(&state, resize_req, &shutdown_tx).await {
-            DaemonResponse::SessionResized {
-                session_id: sid,
-                cols,
-                rows,
-            } => {
-                assert_eq!(sid, session_id);
-                assert_eq!(cols, 132);
-                assert_eq!(rows, 40);
+        match handle_request(&state, send, &shutdown_tx).await {
+            DaemonResponse::Error { message, .. } => {
+                assert!(
+                    message.contains("not running"),
+                    "Expected 'not running' error. Got: {}",
+                    message
+                );
             }
-            other => panic!("Expected SessionResized response, got {:?}", other),
+            other => panic!("Expected error response, got {:?}", other),
         }
-
-        let _ = handle_request(&state, DaemonRequest::Stop { session_id }, &shutdown_tx).await;
     }
 
     #[tokio::test]
@@ -2034,6 +5487,10 @@ mod tests {
                 "-c".to_string(),
                 script.to_string(),
             ]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
         };
         let response = handle_request(&state, start, &shutdown_tx).await;
         let session_id = match response {
@@ -2082,6 +5539,247 @@ mod tests {
         let _ = handle_request(&state, DaemonRequest::Stop { session_id }, &shutdown_tx).await;
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_attention_profiles_follow_binding_or_foreground_command() {
+        use rn_desktop_2_lib::session::protocol::DaemonErrorCode;
+
+        let (config, temp_dir) = test_config();
+        let markdown_path = temp_dir.path().join("TODO.md");
+        tokio::fs::write(&markdown_path, "# Tasks\n- [ ] Shell\n- [ ] Agent\n")
+            .await
+            .unwrap();
+
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+
+        // An error, then a shell prompt using the claude-code glyph
+        let script =
+            "sleep 0.2; echo 'error: build failed'; sleep 0.3; printf '~/src ❯ '; exec sleep 30";
+        let start = |task: &str, attention_profiles: Vec<String>| DaemonRequest::Start {
+            task_key: task.to_string(),
+            task_id: None,
+            project_path: markdown_path.to_string_lossy().to_string(),
+            shell: Some(vec![
+                "/bin/sh".to_string(),
+                "-c".to_string(),
+                script.to_string(),
+            ]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles,
+        };
+
+        match handle_request(
+            &state,
+            start("Shell", vec!["nope".to_string()]),
+            &shutdown_tx,
+        )
+        .await
+        {
+            DaemonResponse::Error { code, message } => {
+                assert_eq!(code, DaemonErrorCode::InvalidRequest);
+                assert!(
+                    message.contains("'nope'"),
+                    "unexpected message: {}",
+                    message
+                );
+            }
+            other => panic!("Expected Error, got {:?}", other),
+        }
+
+        let shell_id = match handle_request(&state, start("Shell", Vec::new()), &shutdown_tx).await
+        {
+            DaemonResponse::SessionStarted { session } => session.id,
+            other => panic!("Expected SessionStarted, got {:?}", other),
+        };
+        let agent_id = match handle_request(
+            &state,
+            start("Agent", vec!["claude-code".to_string()]),
+            &shutdown_tx,
+        )
+        .await
+        {
+            DaemonResponse::SessionStarted { session } => session.id,
+            other => panic!("Expected SessionStarted, got {:?}", other),
+        };
+
+        let status = |session_id: SessionId| {
+            let state = Arc::clone(&state);
+            let shutdown_tx = shutdown_tx.clone();
+            async move {
+                match handle_request(&state, DaemonRequest::Status { session_id }, &shutdown_tx)
+                    .await
+                {
+                    DaemonResponse::SessionStatus { session } => session,
+                    other => panic!("Expected SessionStatus, got {:?}", other),
+                }
+            }
+        };
+
+        // Bound to claude-code, only the prompt fires
+        assert_eventually_bool(
+            "the bound session to notice the prompt",
+            Duration::from_secs(3),
+            Duration::from_millis(50),
+            || {
+                let status = status(agent_id);
+                async move {
+                    status.await.last_attention.is_some_and(|summary| {
+                        summary.profile == "claude-code"
+                            && summary.attention_type == AttentionType::InputRequired
+                    })
+                }
+            },
+        )
+        .await;
+        let agent = status(agent_id).await;
+        assert_eq!(agent.attention_profiles, vec!["claude-code".to_string()]);
+        assert_eq!(agent.active_attention_profiles, agent.attention_profiles);
+
+        // Running `sleep`, not claude, so the prompt is no prompt for attention
+        let shell = status(shell_id).await;
+        let summary = shell.last_attention.expect("the error was noticed");
+        assert_eq!(summary.profile, "build-tools");
+        assert_eq!(summary.attention_type, AttentionType::Error);
+        assert!(shell.attention_profiles.is_empty());
+        assert!(shell
+            .active_attention_profiles
+            .contains(&"build-tools".to_string()));
+        assert!(!shell
+            .active_attention_profiles
+            .contains(&"claude-code".to_string()));
+
+        for session_id in [shell_id, agent_id] {
+            let _ = handle_request(&state, DaemonRequest::Stop { session_id }, &shutdown_tx).await;
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_attention_inbox_ack_and_attach_marks_seen() {
+        use rn_desktop_2_lib::session::protocol::DaemonErrorCode;
+
+        let (config, temp_dir) = test_config();
+        let markdown_path = temp_dir.path().join("TODO.md");
+        tokio::fs::write(&markdown_path, "# Tasks\n- [ ] Inbox\n")
+            .await
+            .unwrap();
+
+        let state = Arc::new(DaemonState::new(config).unwrap());
+        let (shutdown_tx, _) = tokio::sync::mpsc::channel::<()>(1);
+
+        let script =
+            "sleep 0.2; echo 'error: first'; sleep 0.3; echo 'failed: second'; exec sleep 30";
+        let start = DaemonRequest::Start {
+            task_key: "Inbox".to_string(),
+            task_id: None,
+            project_path: markdown_path.to_string_lossy().to_string(),
+            shell: Some(vec![
+                "/bin/sh".to_string(),
+                "-c".to_string(),
+                script.to_string(),
+            ]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
+        };
+        let session_id = match handle_request(&state, start, &shutdown_tx).await {
+            DaemonResponse::SessionStarted { session } => session.id,
+            other => panic!("Expected SessionStarted, got {:?}", other),
+        };
+
+        let list = |include_acked: bool| {
+            let state = Arc::clone(&state);
+            let shutdown_tx = shutdown_tx.clone();
+            async move {
+                let request = DaemonRequest::AttentionList {
+                    project_path: None,
+                    include_acked,
+                };
+                match handle_request(&state, request, &shutdown_tx).await {
+                    DaemonResponse::AttentionItems { items } => items,
+                    other => panic!("Expected AttentionItems, got {:?}", other),
+                }
+            }
+        };
+
+        assert_eventually_bool(
+            "both errors to reach the inbox",
+            Duration::from_secs(3),
+            Duration::from_millis(50),
+            || {
+                let list = list(false);
+                async move { list.await.len() == 2 }
+            },
+        )
+        .await;
+        let items = list(false).await;
+        assert!(items[0].summary.preview.contains("failed"));
+        assert!(items.iter().all(|item| item.session_id == session_id));
+        let (second, first) = (items[0].summary.id, items[1].summary.id);
+
+        // An unknown ID acknowledges nothing
+        let ack = |ids: Vec<u64>| DaemonRequest::AttentionAck { ids };
+        match handle_request(&state, ack(vec![first, 999]), &shutdown_tx).await {
+            DaemonResponse::Error { code, .. } => assert_eq!(code, DaemonErrorCode::NotFound),
+            other => panic!("Expected Error, got {:?}", other),
+        }
+        assert_eq!(list(false).await.len(), 2);
+
+        let mut updates = state.updates_tx.subscribe();
+        match handle_request(&state, ack(vec![first]), &shutdown_tx).await {
+            DaemonResponse::AttentionAcked { ids } => assert_eq!(ids, vec![first]),
+            other => panic!("Expected AttentionAcked, got {:?}", other),
+        }
+        // Other clients hear about it
+        let mut told = None;
+        while let Ok(envelope) = updates.try_recv() {
+            if let DaemonNotification::AttentionAcked { session_id, ids } = envelope.notification {
+                told = Some((session_id, ids));
+            }
+        }
+        assert_eq!(told, Some((session_id, vec![first])));
+        match handle_request(&state, ack(vec![first]), &shutdown_tx).await {
+            DaemonResponse::AttentionAcked { ids } => assert!(ids.is_empty()),
+            other => panic!("Expected AttentionAcked, got {:?}", other),
+        }
+        let remaining = list(false).await;
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].summary.id, second);
+
+        // Watching read-only or replaying the log doesn't count as seeing it
+        assert!(!attach_sees_attention(AttachMode::Shared, Some(0)));
+        let mode = AttachMode::ReadOnly;
+        let (watcher, _kicked) = state
+            .add_viewer(session_id, mode, attach_sees_attention(mode, None))
+            .await
+            .unwrap();
+        assert_eq!(list(false).await.len(), 1);
+        drop(watcher);
+
+        // Attaching to type sees the rest
+        let mode = AttachMode::Shared;
+        let (lease, _kicked) = state
+            .add_viewer(session_id, mode, attach_sees_attention(mode, None))
+            .await
+            .unwrap();
+        assert!(list(false).await.is_empty());
+        assert_eq!(list(true).await.len(), 2);
+        let session = state
+            .registry
+            .read()
+            .await
+            .get(session_id)
+            .cloned()
+            .unwrap();
+        let last = session.last_attention.unwrap();
+        assert_eq!((last.id, last.acked), (second, true));
+        drop(lease);
+
+        let _ = handle_request(&state, DaemonRequest::Stop { session_id }, &shutdown_tx).await;
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_session_provider_returns_non_empty_tail() {
         use crate::SessionProvider;
@@ -2106,6 +5804,10 @@ mod tests {
                 "-c".to_string(),
                 script.to_string(),
             ]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
         };
 
         let response = handle_request(&state, start, &shutdown_tx).await;
@@ -2196,6 +5898,10 @@ mod tests {
                 "-c".to_string(),
                 script.to_string(),
             ]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
         };
 
         let response = handle_request(&state, start, &shutdown_tx).await;
@@ -2278,6 +5984,10 @@ mod tests {
                 "-c".to_string(),
                 script.to_string(),
             ]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
         };
 
         let response = handle_request(&state, start, &shutdown_tx).await;
@@ -2286,7 +5996,7 @@ mod tests {
             other => panic!("Expected SessionStarted, got {:?}", other),
         };
 
-        // Wait for session to stop (watcher runs every 5s, so allow up to 10s)
+        // Wait for session to stop (exit is reported as soon as the PTY exits)
         assert_eventually(
             "session to stop",
             Duration::from_secs(10),
@@ -2376,6 +6086,10 @@ mod tests {
                 "-c".to_string(),
                 script.to_string(),
             ]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
         };
 
         let response = handle_request(&state, start, &shutdown_tx).await;
@@ -2453,12 +6167,21 @@ mod tests {
             .await
             .unwrap();
 
-        // Start a session to get a task_id
+        // Start a session to get a task_id. Keep it running so its exit doesn't
+        // trigger an automatic capture that would rate-limit the manual one.
         let start_request = DaemonRequest::Start {
             task_key: "Test".to_string(),
             task_id: Some("test.test-task".to_string()),
             project_path: markdown_path.to_string_lossy().to_string(),
-            shell: Some(vec!["echo".to_string(), "hello".to_string()]),
+            shell: Some(vec![
+                "/bin/sh".to_string(),
+                "-c".to_string(),
+                "echo hello; sleep 5".to_string(),
+            ]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
         };
 
         let start_response = handle_request(&state, start_request, &shutdown_tx).await;
@@ -2590,6 +6313,10 @@ mod tests {
                 "-c".to_string(),
                 script.to_string(),
             ]),
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
         };
 
         let response = handle_request(&state, start, &shutdown_tx).await;
@@ -2631,7 +6358,7 @@ mod tests {
         )
         .await;
 
-        // Wait for session to stop (watcher polls every 5s, so allow up to 10s)
+        // Wait for session to stop (exit is reported as soon as the PTY exits)
         assert_eventually(
             "session to stop after producing all output",
             Duration::from_secs(10),
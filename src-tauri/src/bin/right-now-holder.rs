@@ -0,0 +1,15 @@
+// right-now-holder: Owns one session's PTY on behalf of right-now-daemon
+//
+// Launched by the daemon for each session and detached into its own process
+// session, so the PTY, ring buffer and screen survive daemon restarts. A
+// restarted daemon reconnects over the holder socket and adopts the session.
+// See `session::holder` for the protocol.
+
+use anyhow::Result;
+use rn_desktop_2_lib::session::holder::{run_holder, HolderArgs};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = HolderArgs::parse(std::env::args().skip(1))?;
+    run_holder(args).await
+}
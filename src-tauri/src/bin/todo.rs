@@ -1,10 +1,18 @@
 // todo: CLI for interacting with right-now-daemon
 //
 // Commands:
-//   todo start <task words> [--project <path>] [--cmd "<shell command>"] [--background]
-//   todo continue <session-id> [--attach]
+//   todo start <task words> [--project <path>] [--cmd "<shell command>"] [--background] [--record]
+//              [--profile <name>]...
+//   todo continue <session-id> [--attach [--read-only | --exclusive]]
 //   todo list [--project <path>]
 //   todo stop <session-id>
+//   todo restart <session-id>
+//   todo send <session-id> "<text>" [--no-newline]
+//   todo logs <session-id> [--since <offset>] [--bytes <n>]
+//   todo recordings [delete <session-id>]
+//   todo replay <session-id> [--speed <n>]
+//   todo config [show | get <key> | set <key> <value> | reload]
+//   todo attention test [--profile <name>]... [--chunk-size <n>[,<n>...]] <logfile | - | --session <id>>
 //   todo shell-integration [--install | --uninstall] [--shell <zsh|bash|fish>]
 
 use anyhow::{anyhow, Context, Result};
@@ -12,10 +20,16 @@ use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
 #[cfg(unix)]
 use libc::{fcntl, F_GETFL, F_SETFL, O_NONBLOCK};
 use rn_desktop_2_lib::session::{
-    config::Config,
+    attention::{self, AttentionAccumulator, AttentionMatch, ProfileSelection},
+    config::{Config, SettingsError, SETTING_KEYS},
+    journal::{DEFAULT_EVENTS_LIMIT, MAX_EVENTS_LIMIT},
     protocol::{
-        deserialize_message, serialize_message, DaemonRequest, DaemonResponse, SessionStatus,
+        deserialize_message, serialize_message, serialize_message_with, AttachMode, ByteEncoding,
+        DaemonErrorCode, DaemonNotification, DaemonRequest, DaemonResponse, JournalEvent,
+        NotificationEnvelope, ScreenRepaint, SessionStatus, StreamFrame, SubscriptionFilter,
     },
+    recording::{read_recording, CastEvent},
+    runtime::snapshot_env,
     shell_integration::{self, ShellType},
 };
 #[cfg(unix)]
@@ -26,23 +40,50 @@ use signal_hook::{
 use std::{
     borrow::Cow,
     env,
-    io::{self, BufRead, BufReader, Read, Write},
+    io::{self, BufRead, BufReader, IsTerminal, Read, Write},
     net::Shutdown,
     os::fd::{AsRawFd, RawFd},
     os::unix::net::UnixStream,
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::Command,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-const DEFAULT_TAIL_BYTES: usize = 4 * 1024;
+/// Bytes of history printed per `todo logs` page
+const DEFAULT_LOG_PAGE_BYTES: usize = 64 * 1024;
+/// Chunk size for `todo attention test`: what the daemon reads from a PTY at once
+const DEFAULT_ATTENTION_CHUNK_BYTES: usize = 4096;
+/// Options that take a value, so `positional_args` can skip it
+const VALUE_OPTIONS: &[&str] = &[
+    "--project",
+    "-p",
+    "--cmd",
+    "-c",
+    "--tail-bytes",
+    "--since",
+    "--bytes",
+    "--speed",
+    "--limit",
+    "--shell",
+    "--profile",
+    "--chunk-size",
+    "--session",
+];
 const DETACH_BYTE: u8 = 0x1c; // Ctrl-\
 const INPUT_IDLE_SLEEP_MS: u64 = 10;
+/// Leave the alternate screen and restore the cursor after detaching from a TUI
+const LEAVE_ALTERNATE_SCREEN: &[u8] = b"\x1b[m\x1b[?25h\x1b[?1049l";
+/// Reset attributes and show the cursor after a replay on the primary screen
+const RESET_TERMINAL: &[u8] = b"\x1b[m\x1b[?25h";
+/// Longest pause kept between replayed events; agents can sit idle for hours
+const REPLAY_MAX_IDLE: Duration = Duration::from_secs(2);
+/// Keys that stop a replay: q, Ctrl-C and the detach key
+const REPLAY_QUIT_KEYS: &[u8] = &[b'q', 0x03, DETACH_BYTE];
 
 fn print_help() {
     println!(
@@ -56,7 +97,18 @@ COMMANDS:
     continue <id>          Show recent output from a session
     list                   List all sessions
     stop <id>              Stop a running session
+    restart <id>           Relaunch a stopped session with its original command
+    send <id> <text>       Type text into a running session (followed by Enter)
+    logs <id>              Print session output history from the on-disk log
+    recordings             List session recordings (delete <id> to remove one)
+    replay <id>            Play back a recorded session in the terminal
     status <id>            Get status of a specific session
+    events [id]            Show what sessions did, from the daemon's event journal
+    inbox                  List unseen attention events and attach to one
+                           (ack <id>... or ack all to mark them seen)
+    config                 Show daemon settings (get <key>, set <key> <value>, reload)
+    attention test <file>  Run attention profiles over captured output ('-' for
+                           stdin, or --session <id> for a session's recent output)
     shell-integration      Install/uninstall shell prompt integration
     help                   Show this help message
 
@@ -64,7 +116,22 @@ OPTIONS:
     --project <path>   Path to TODO.md file (defaults to current directory)
     --cmd <command>    Shell command to run (for start)
     --background, -b   Start session in background without attaching
+    --record           Record the session for later replay (for start)
+    --profile <name>   Only check output against this attention profile (for
+                       start; repeatable; default: picked by the running command)
     --attach           Attach to PTY output for 'continue'
+    --read-only        Watch without sending keystrokes (with --attach or inbox)
+    --exclusive        Detach other clients and keep them out (with --attach or inbox)
+    --no-newline       Don't press Enter after the text (for send)
+    --since <offset>   Log offset to start from (for logs; defaults to oldest kept)
+                       or event seq to start after (for events)
+    --limit <n>        Most events to show (for events)
+    --follow, -f       Keep printing new events as they happen (for events)
+    --bytes <n>        Bytes of history per page (for logs)
+    --speed <n>        Playback speed multiplier (for replay; default 1)
+    --chunk-size <n>   Bytes fed per chunk (for attention test; default 4096;
+                       a comma-separated list compares several)
+    --session <id>     Test against a session's recent output (for attention test)
     --json             Output in JSON format
 
 EXAMPLES:
@@ -72,9 +139,20 @@ EXAMPLES:
     todo start "run tests" --background      # Start in background
     todo start "run tests" --cmd "npm test"  # Start with specific command
     todo continue 42 --attach                # Attach to existing session
+    todo continue 42 --attach --read-only    # Watch it without typing into it
     todo shell-integration --install         # Install prompt integration
     todo list --project ~/projects/myapp/TODO.md
     todo stop 42
+    todo restart 42                          # Run it again in the same directory
+    todo send 42 "y"                         # Answer a prompt without attaching
+    todo logs 42 --since 65536               # Page through output history
+    todo start "migrate db" --record -b      # Record a background session
+    todo start "fix" --profile claude-code   # Only claude-code prompts notify
+    todo replay 42 --speed 4                 # Watch it back at 4x speed
+    todo events --follow                     # Tail session activity
+    todo inbox ack all                       # Mark every attention event seen
+    todo config set tail_bytes 16384         # Replay more output on attach
+    todo logs 42 | todo attention test --profile pytest --chunk-size 1,4096 -
 
 DETACH:
     Press Ctrl-\ to detach from an attached session
@@ -161,11 +239,24 @@ fn connect_to_daemon(config: &Config) -> Result<UnixStream> {
 }
 
 fn send_request(stream: &mut UnixStream, request: &DaemonRequest) -> Result<DaemonResponse> {
-    use rn_desktop_2_lib::session::protocol::{MAX_RESPONSE_FRAME_SIZE, PROTOCOL_VERSION};
+    send_request_keeping_reader(stream, request).map(|(response, _)| response)
+}
+
+/// Like `send_request`, also returning the reader the response came in on,
+/// which may already hold whatever the daemon sent after it
+fn send_request_keeping_reader(
+    stream: &mut UnixStream,
+    request: &DaemonRequest,
+) -> Result<(DaemonResponse, BufReader<UnixStream>)> {
+    use rn_desktop_2_lib::session::protocol::{
+        DaemonCapabilities, MAX_RESPONSE_FRAME_SIZE, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+    };
 
     // Perform handshake first
     let handshake = DaemonRequest::Handshake {
         client_version: PROTOCOL_VERSION,
+        min_version: Some(MIN_PROTOCOL_VERSION),
+        byte_encoding: Some(ByteEncoding::Base64),
     };
     let handshake_bytes = serialize_message(&handshake)?;
     stream.write_all(&handshake_bytes)?;
@@ -188,18 +279,26 @@ fn send_request(stream: &mut UnixStream, request: &DaemonRequest) -> Result<Daem
     let handshake_response: DaemonResponse =
         deserialize_message(line.as_bytes()).context("Failed to parse handshake response")?;
 
-    match handshake_response {
+    let capabilities = match handshake_response {
         DaemonResponse::Handshake {
-            protocol_version: _,
-        } => {
-            // Handshake successful
-        }
+            protocol_version,
+            capabilities,
+            ..
+        } => DaemonCapabilities {
+            protocol_version,
+            capabilities,
+        },
         DaemonResponse::Error { code: _, message } => {
             anyhow::bail!("Handshake failed: {}", message);
         }
         other => {
             anyhow::bail!("Expected handshake response, got: {:?}", other);
         }
+    };
+
+    // Answer locally rather than send what the daemon can't handle
+    if let Some(response) = capabilities.check(request) {
+        return Ok((response, reader));
     }
 
     // Send the actual request
@@ -220,7 +319,67 @@ fn send_request(stream: &mut UnixStream, request: &DaemonRequest) -> Result<Daem
         );
     }
 
-    deserialize_message(line.as_bytes()).context("Failed to parse daemon response")
+    let response =
+        deserialize_message(line.as_bytes()).context("Failed to parse daemon response")?;
+    Ok((response, reader))
+}
+
+/// How an attached terminal reaches its session
+enum AttachTransport {
+    /// Stream frames on the daemon connection (reader, writer)
+    Stream(BufReader<UnixStream>, UnixStream),
+    /// The session's own attach socket, for daemons without streams
+    Socket(String),
+}
+
+/// Ask the daemon to attach to a session, streaming over `stream` itself
+/// when the daemon supports it and through an attach socket otherwise
+///
+/// Returns `StreamOpened`, `AttachReady` or an error, with how to reach the
+/// session when attaching worked.
+fn request_attach(
+    stream: &mut UnixStream,
+    session_id: u64,
+    tail_bytes: Option<usize>,
+    mode: AttachMode,
+) -> Result<(DaemonResponse, Option<AttachTransport>)> {
+    let open_stream = DaemonRequest::OpenStream {
+        session_id,
+        tail_bytes,
+        offset: None,
+        mode,
+    };
+    match send_request_keeping_reader(stream, &open_stream)? {
+        (
+            DaemonResponse::Error {
+                code: DaemonErrorCode::Unsupported,
+                ..
+            },
+            _,
+        ) => {}
+        (response @ DaemonResponse::StreamOpened { .. }, reader) => {
+            let writer = stream
+                .try_clone()
+                .context("Failed to clone daemon connection for input")?;
+            return Ok((response, Some(AttachTransport::Stream(reader, writer))));
+        }
+        (response, _) => return Ok((response, None)),
+    }
+
+    let attach = DaemonRequest::Attach {
+        session_id,
+        tail_bytes,
+        offset: None,
+        mode,
+    };
+    let response = send_request(stream, &attach)?;
+    let transport = match &response {
+        DaemonResponse::AttachReady { socket_path, .. } => {
+            Some(AttachTransport::Socket(socket_path.clone()))
+        }
+        _ => None,
+    };
+    Ok((response, transport))
 }
 
 fn main() -> Result<()> {
@@ -239,10 +398,21 @@ fn main() -> Result<()> {
     let mut shell_cmd: Option<String> = None;
     let mut tail_bytes: Option<usize> = None;
     let mut attach_mode = false;
+    let mut viewer_mode = AttachMode::Shared;
     let mut background_mode = false;
+    let mut append_newline = true;
     let mut install_mode = false;
     let mut uninstall_mode = false;
     let mut shell_type_arg: Option<String> = None;
+    let mut since_offset: Option<u64> = None;
+    let mut page_bytes: Option<usize> = None;
+    let mut record_mode = false;
+    let mut attention_profiles: Vec<String> = Vec::new();
+    let mut chunk_sizes: Vec<usize> = Vec::new();
+    let mut test_session: Option<u64> = None;
+    let mut replay_speed: f64 = 1.0;
+    let mut event_limit: Option<usize> = None;
+    let mut follow_mode = false;
 
     let mut i = 2;
     while i < args.len() {
@@ -274,12 +444,113 @@ fn main() -> Result<()> {
                     }
                 }
             }
+            "--since" => {
+                i += 1;
+                if i < args.len() {
+                    match args[i].parse::<u64>() {
+                        Ok(v) => since_offset = Some(v),
+                        Err(_) => {
+                            eprintln!("--since must be a byte offset");
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+            "--bytes" => {
+                i += 1;
+                if i < args.len() {
+                    match args[i].parse::<usize>() {
+                        Ok(v) => page_bytes = Some(v),
+                        Err(_) => {
+                            eprintln!("--bytes must be a positive integer");
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+            "--speed" => {
+                i += 1;
+                if i < args.len() {
+                    match args[i].parse::<f64>() {
+                        Ok(v) if v > 0.0 && v.is_finite() => replay_speed = v,
+                        _ => {
+                            eprintln!("--speed must be a positive number");
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+            "--limit" => {
+                i += 1;
+                if i < args.len() {
+                    match args[i].parse::<usize>() {
+                        Ok(v) if v > 0 => event_limit = Some(v),
+                        _ => {
+                            eprintln!("--limit must be a positive integer");
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+            "--follow" | "-f" => {
+                follow_mode = true;
+            }
             "--attach" => {
                 attach_mode = true;
             }
+            "--read-only" => {
+                viewer_mode = AttachMode::ReadOnly;
+            }
+            "--exclusive" => {
+                viewer_mode = AttachMode::Exclusive;
+            }
+            "--record" => {
+                record_mode = true;
+            }
+            "--chunk-size" => {
+                i += 1;
+                if i < args.len() {
+                    for size in args[i].split(',') {
+                        match size.trim().parse::<usize>() {
+                            Ok(v) if v > 0 => chunk_sizes.push(v),
+                            _ => {
+                                eprintln!("--chunk-size must be positive integers");
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                }
+            }
+            "--session" => {
+                i += 1;
+                if i < args.len() {
+                    match args[i].parse::<u64>() {
+                        Ok(v) => test_session = Some(v),
+                        Err(_) => {
+                            eprintln!("--session must be a session ID");
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+            "--profile" => {
+                i += 1;
+                if i < args.len() {
+                    attention_profiles.extend(
+                        args[i]
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|name| !name.is_empty())
+                            .map(str::to_string),
+                    );
+                }
+            }
             "--background" | "-b" => {
                 background_mode = true;
             }
+            "--no-newline" => {
+                append_newline = false;
+            }
             "--install" => {
                 install_mode = true;
             }
@@ -310,6 +581,10 @@ fn main() -> Result<()> {
         eprintln!("--attach is only supported with the 'continue' command");
         std::process::exit(1);
     }
+    if !viewer_mode.is_shared() && !attach_mode && command.as_str() != "inbox" {
+        eprintln!("--read-only and --exclusive are only supported with --attach or inbox");
+        std::process::exit(1);
+    }
 
     match command.as_str() {
         "help" | "--help" | "-h" => {
@@ -346,14 +621,42 @@ fn main() -> Result<()> {
                 vec![shell[0].clone(), "-c".to_string(), cmd]
             });
 
-            let request = DaemonRequest::Start {
+            let mut request = DaemonRequest::Start {
                 task_key: task_key.clone(),
                 task_id,
                 project_path: project_path.to_string_lossy().to_string(),
                 shell,
+                // Run where `todo start` was run, with the caller's environment
+                cwd: env::current_dir()
+                    .ok()
+                    .map(|dir| dir.to_string_lossy().to_string()),
+                env: snapshot_env(env::vars()),
+                record: record_mode,
+                attention_profiles,
             };
 
-            let response = send_request(&mut stream, &request)?;
+            let mut response = send_request(&mut stream, &request)?;
+            if let DaemonResponse::Error {
+                code: DaemonErrorCode::Unsupported,
+                ..
+            } = response
+            {
+                if let DaemonRequest::Start {
+                    record,
+                    attention_profiles,
+                    ..
+                } = &mut request
+                {
+                    // Profiles are newer than recording; the error says so
+                    if *record && attention_profiles.is_empty() {
+                        eprintln!(
+                            "The running daemon can't record sessions; starting without --record"
+                        );
+                        *record = false;
+                        response = send_request(&mut stream, &request)?;
+                    }
+                }
+            }
 
             if json_output {
                 println!("{}", serde_json::to_string_pretty(&response)?);
@@ -371,31 +674,43 @@ fn main() -> Result<()> {
 
                     // Unless --background, immediately attach to the session
                     if !background_mode {
-                        // Send attach request
-                        let attach_request = DaemonRequest::Attach {
-                            session_id: session.id,
-                            tail_bytes: Some(tail_bytes.unwrap_or(DEFAULT_TAIL_BYTES)),
-                        };
-                        let attach_response = send_request(&mut stream, &attach_request)?;
-
-                        match attach_response {
-                            DaemonResponse::AttachReady {
-                                session: attached_session,
-                                tail,
-                                socket_path,
-                            } => {
+                        let (attach_response, transport) = request_attach(
+                            &mut stream,
+                            session.id,
+                            tail_bytes,
+                            AttachMode::Shared,
+                        )?;
+
+                        match (attach_response, transport) {
+                            (
+                                DaemonResponse::StreamOpened {
+                                    session: attached_session,
+                                    tail,
+                                    screen,
+                                    ..
+                                }
+                                | DaemonResponse::AttachReady {
+                                    session: attached_session,
+                                    tail,
+                                    screen,
+                                    ..
+                                },
+                                Some(transport),
+                            ) => {
                                 run_attach_session(
                                     &attached_session,
                                     tail.as_deref(),
-                                    &socket_path,
+                                    screen.as_ref(),
+                                    transport,
+                                    AttachMode::Shared,
                                     config.clone(),
                                 )?;
                             }
-                            DaemonResponse::Error { code: _, message } => {
+                            (DaemonResponse::Error { code: _, message }, _) => {
                                 eprintln!("Error attaching: {}", message);
                                 std::process::exit(1);
                             }
-                            other => {
+                            (other, _) => {
                                 eprintln!("Unexpected attach response: {:?}", other);
                                 std::process::exit(1);
                             }
@@ -425,54 +740,18 @@ fn main() -> Result<()> {
             let mut stream = connect_to_daemon(&config)?;
 
             if attach_mode {
-                let request = DaemonRequest::Attach {
+                attach_to_session(
+                    &config,
+                    &mut stream,
                     session_id,
-                    tail_bytes: Some(tail_bytes.unwrap_or(DEFAULT_TAIL_BYTES)),
-                };
-                let response = send_request(&mut stream, &request)?;
-
-                if json_output {
-                    println!("{}", serde_json::to_string_pretty(&response)?);
-                    return Ok(());
-                }
-
-                match response {
-                    DaemonResponse::AttachReady {
-                        session,
-                        tail,
-                        socket_path,
-                    } => {
-                        if session.status == SessionStatus::Stopped {
-                            eprintln!(
-                                "Session {} is already stopped; nothing to attach to.",
-                                session.id
-                            );
-                            std::process::exit(1);
-                        }
-
-                        // Print metadata, but replay buffer directly during attach.
-                        print_session_summary(&session, None)?;
-                        run_attach_session(
-                            &session,
-                            tail.as_deref(),
-                            &socket_path,
-                            config.clone(),
-                        )?;
-                    }
-                    DaemonResponse::Error { code: _, message } => {
-                        eprintln!("Error: {}", message);
-                        std::process::exit(1);
-                    }
-                    other => {
-                        eprintln!("Unexpected response: {:?}", other);
-                        std::process::exit(1);
-                    }
-                }
-                Ok(())
+                    tail_bytes,
+                    viewer_mode,
+                    json_output,
+                )
             } else {
                 let request = DaemonRequest::Continue {
                     session_id,
-                    tail_bytes: Some(tail_bytes.unwrap_or(DEFAULT_TAIL_BYTES)),
+                    tail_bytes,
                 };
                 let response = send_request(&mut stream, &request)?;
 
@@ -522,6 +801,17 @@ fn main() -> Result<()> {
                                     session.status,
                                     session.deep_link()
                                 );
+                                if let Some(ref command) = session.foreground_command {
+                                    println!(
+                                        "    $ {}{}",
+                                        command,
+                                        session
+                                            .cwd
+                                            .as_deref()
+                                            .map(|cwd| format!("  (in {})", cwd))
+                                            .unwrap_or_default()
+                                    );
+                                }
                             }
                         }
                     }
@@ -570,28 +860,29 @@ fn main() -> Result<()> {
             Ok(())
         }
 
-        "status" => {
+        "restart" => {
             if args.len() < 3 {
-                eprintln!("Usage: todo status <session-id>");
+                eprintln!("Usage: todo restart <session-id>");
                 std::process::exit(1);
             }
 
             let session_id: u64 = args[2].parse().context("Session ID must be a number")?;
 
             let mut stream = connect_to_daemon(&config)?;
-            let request = DaemonRequest::Status { session_id };
+            let request = DaemonRequest::Restart { session_id };
             let response = send_request(&mut stream, &request)?;
 
             if json_output {
                 println!("{}", serde_json::to_string_pretty(&response)?);
             } else {
                 match response {
-                    DaemonResponse::SessionStatus { session } => {
-                        println!("Session {} — {}", session.id, session.task_key);
-                        println!("  Status: {}", session.status);
-                        println!("  Project: {}", session.project_path);
-                        println!("  Created: {}", session.created_at);
-                        println!("  Deep link: {}", session.deep_link());
+                    DaemonResponse::SessionRestarted { session } => {
+                        println!(
+                            "Restarted session {} for '{}'\n  Deep link: {}",
+                            session.id,
+                            session.task_key,
+                            session.deep_link()
+                        );
                     }
                     DaemonResponse::Error { code: _, message } => {
                         eprintln!("Error: {}", message);
@@ -606,103 +897,888 @@ fn main() -> Result<()> {
             Ok(())
         }
 
-        "shell-integration" => {
-            // Determine shell type
-            let shell_type = match shell_type_arg {
-                Some(ref s) => s.parse::<ShellType>()?,
-                None => ShellType::detect().ok_or_else(|| {
-                    anyhow!(
-                        "Could not detect shell type from $SHELL. \
-                         Please specify with --shell <zsh|bash|fish>"
-                    )
-                })?,
+        "send" => {
+            if args.len() < 4 {
+                eprintln!("Usage: todo send <session-id> \"<text>\" [--no-newline]");
+                std::process::exit(1);
+            }
+
+            let session_id: u64 = args[2].parse().context("Session ID must be a number")?;
+            let data = args[3].clone();
+
+            let mut stream = connect_to_daemon(&config)?;
+            let request = DaemonRequest::SendInput {
+                session_id,
+                data,
+                append_newline,
             };
+            let response = send_request(&mut stream, &request)?;
 
-            let rc_path = shell_type.rc_file_path()?;
+            if json_output {
+                println!("{}", serde_json::to_string_pretty(&response)?);
+            } else {
+                match response {
+                    DaemonResponse::InputSent { session_id, bytes } => {
+                        println!("Sent {} bytes to session {}", bytes, session_id);
+                    }
+                    DaemonResponse::Error { code: _, message } => {
+                        eprintln!("Error: {}", message);
+                        std::process::exit(1);
+                    }
+                    _ => {
+                        eprintln!("Unexpected response");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            Ok(())
+        }
 
-            if install_mode && uninstall_mode {
-                eprintln!("Cannot use both --install and --uninstall");
+        "logs" => {
+            if args.len() < 3 {
+                eprintln!("Usage: todo logs <session-id> [--since <offset>] [--bytes <n>]");
                 std::process::exit(1);
             }
 
-            if uninstall_mode {
-                let removed = shell_integration::uninstall(&rc_path)?;
-                if removed {
-                    println!("Removed Right Now integration from {}", rc_path.display());
-                    println!("Restart your shell or run: source {}", rc_path.display());
-                } else {
-                    println!(
-                        "Right Now integration was not installed in {}",
-                        rc_path.display()
-                    );
-                }
-            } else if install_mode {
-                let installed_path = shell_integration::install(shell_type, Some(rc_path.clone()))?;
-                println!(
-                    "Installed Right Now integration to {}",
-                    installed_path.display()
-                );
-                println!();
-                println!(
-                    "Restart your shell or run: source {}",
-                    installed_path.display()
-                );
-                println!();
-                println!("When working in a Right Now session, your prompt will show:");
-                println!("  [#42: Task name] > ");
+            let session_id: u64 = args[2].parse().context("Session ID must be a number")?;
+
+            let mut stream = connect_to_daemon(&config)?;
+            let request = DaemonRequest::Tail {
+                session_id,
+                bytes: Some(page_bytes.unwrap_or(DEFAULT_LOG_PAGE_BYTES)),
+                offset: Some(since_offset.unwrap_or(0)),
+            };
+            let mut response = send_request(&mut stream, &request)?;
+            if let DaemonResponse::Error {
+                code: DaemonErrorCode::Unsupported,
+                ..
+            } = response
+            {
+                // No on-disk log; the ring buffer is the best there is
+                eprintln!("-- the running daemon keeps no output log; showing recent output only");
+                let request = DaemonRequest::Tail {
+                    session_id,
+                    bytes: page_bytes,
+                    offset: None,
+                };
+                response = send_request(&mut stream, &request)?;
+            }
+
+            if json_output {
+                println!("{}", serde_json::to_string_pretty(&response)?);
             } else {
-                // Show status
-                let installed = shell_integration::is_installed(&rc_path)?;
-                println!("Shell: {}", shell_type);
-                println!("RC file: {}", rc_path.display());
-                println!(
-                    "Status: {}",
-                    if installed {
-                        "installed"
-                    } else {
-                        "not installed"
+                match response {
+                    DaemonResponse::SessionTail {
+                        data,
+                        offset,
+                        log_end,
+                        ..
+                    } => {
+                        let mut stdout = io::stdout();
+                        stdout.write_all(&data)?;
+                        stdout.flush()?;
+
+                        // Page footer on stderr keeps stdout pipeable
+                        let start = offset.unwrap_or(0);
+                        let next = start + data.len() as u64;
+                        let end = log_end.unwrap_or(next);
+                        if next < end {
+                            eprintln!(
+                                "\n-- bytes {}..{} of {}; next page: todo logs {} --since {}",
+                                start, next, end, session_id, next
+                            );
+                        }
+                    }
+                    DaemonResponse::Error { code: _, message } => {
+                        eprintln!("Error: {}", message);
+                        std::process::exit(1);
+                    }
+                    _ => {
+                        eprintln!("Unexpected response");
+                        std::process::exit(1);
                     }
-                );
-                println!();
-                if !installed {
-                    println!("To install: todo shell-integration --install");
-                } else {
-                    println!("To uninstall: todo shell-integration --uninstall");
                 }
             }
             Ok(())
         }
 
-        _ => {
-            eprintln!("Unknown command: {}", command);
-            print_help();
-            std::process::exit(1);
-        }
-    }
-}
+        "recordings" => {
+            let mut stream = connect_to_daemon(&config)?;
 
-fn print_session_summary(
-    session: &rn_desktop_2_lib::session::protocol::Session,
-    tail_data: Option<&[u8]>,
-) -> Result<()> {
-    println!(
-        "Session {} — {} ({})",
-        session.id, session.task_key, session.status
-    );
-    println!("Project: {}", session.project_path);
-    println!("Created: {}", session.created_at);
-    println!("Updated: {}", session.updated_at);
-    if let Some(code) = session.exit_code {
-        println!("Exit code: {}", code);
-    }
-    println!("Deep link: {}", session.deep_link());
-    if let Some(attention) = &session.last_attention {
-        println!(
-            "Attention: {} ({}) at {}",
-            attention.attention_type, attention.profile, attention.triggered_at
-        );
-        println!("  {}", attention.preview);
-    }
+            if args.get(2).map(String::as_str) == Some("delete") {
+                let Some(session_id) = args.get(3) else {
+                    eprintln!("Usage: todo recordings delete <session-id>");
+                    std::process::exit(1);
+                };
+                let session_id: u64 = session_id.parse().context("Session ID must be a number")?;
+                let response =
+                    send_request(&mut stream, &DaemonRequest::DeleteRecording { session_id })?;
+
+                if json_output {
+                    println!("{}", serde_json::to_string_pretty(&response)?);
+                } else {
+                    match response {
+                        DaemonResponse::RecordingDeleted { session_id } => {
+                            println!("Deleted recording of session {}", session_id);
+                        }
+                        DaemonResponse::Error { code: _, message } => {
+                            eprintln!("Error: {}", message);
+                            std::process::exit(1);
+                        }
+                        _ => {
+                            eprintln!("Unexpected response");
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                return Ok(());
+            }
+
+            let response = send_request(&mut stream, &DaemonRequest::ListRecordings)?;
+
+            if json_output {
+                println!("{}", serde_json::to_string_pretty(&response)?);
+            } else {
+                match response {
+                    DaemonResponse::RecordingList { recordings } => {
+                        if recordings.is_empty() {
+                            println!("No recordings");
+                        }
+                        for recording in recordings {
+                            println!(
+                                "[{}] {} — {} bytes — {}{}",
+                                recording.session_id,
+                                recording.task_key.as_deref().unwrap_or("(unknown task)"),
+                                recording.size_bytes,
+                                recording.modified_at,
+                                if recording.active {
+                                    " — recording"
+                                } else {
+                                    ""
+                                }
+                            );
+                        }
+                    }
+                    DaemonResponse::Error { code: _, message } => {
+                        eprintln!("Error: {}", message);
+                        std::process::exit(1);
+                    }
+                    _ => {
+                        eprintln!("Unexpected response");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        "replay" => {
+            if args.len() < 3 {
+                eprintln!("Usage: todo replay <session-id> [--speed <n>]");
+                std::process::exit(1);
+            }
+
+            let session_id: u64 = args[2].parse().context("Session ID must be a number")?;
+
+            let mut stream = connect_to_daemon(&config)?;
+            let path = match send_request(&mut stream, &DaemonRequest::ListRecordings)? {
+                DaemonResponse::RecordingList { recordings } => {
+                    match recordings.into_iter().find(|r| r.session_id == session_id) {
+                        Some(recording) => PathBuf::from(recording.path),
+                        None => {
+                            eprintln!("Error: No recording for session {}", session_id);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                DaemonResponse::Error { code: _, message } => {
+                    eprintln!("Error: {}", message);
+                    std::process::exit(1);
+                }
+                _ => {
+                    eprintln!("Unexpected response");
+                    std::process::exit(1);
+                }
+            };
+
+            run_replay(session_id, &path, replay_speed)
+        }
+
+        "status" => {
+            if args.len() < 3 {
+                eprintln!("Usage: todo status <session-id>");
+                std::process::exit(1);
+            }
+
+            let session_id: u64 = args[2].parse().context("Session ID must be a number")?;
+
+            let mut stream = connect_to_daemon(&config)?;
+            let request = DaemonRequest::Status { session_id };
+            let response = send_request(&mut stream, &request)?;
+
+            if json_output {
+                println!("{}", serde_json::to_string_pretty(&response)?);
+            } else {
+                match response {
+                    DaemonResponse::SessionStatus { session } => {
+                        println!("Session {} — {}", session.id, session.task_key);
+                        println!("  Status: {}", session.status);
+                        if let Some(ref command) = session.foreground_command {
+                            println!("  Running: {}", command);
+                        }
+                        if let Some(ref cwd) = session.cwd {
+                            println!("  Directory: {}", cwd);
+                        }
+                        if !session.active_attention_profiles.is_empty() {
+                            println!(
+                                "  Attention profiles: {}{}",
+                                session.active_attention_profiles.join(", "),
+                                if session.attention_profiles.is_empty() {
+                                    " (picked by the running command)"
+                                } else {
+                                    ""
+                                }
+                            );
+                        }
+                        if !session.viewers.is_empty() {
+                            println!("  Attached: {}", describe_viewers(&session));
+                        }
+                        println!("  Project: {}", session.project_path);
+                        println!("  Created: {}", session.created_at);
+                        println!("  Deep link: {}", session.deep_link());
+                    }
+                    DaemonResponse::Error { code: _, message } => {
+                        eprintln!("Error: {}", message);
+                        std::process::exit(1);
+                    }
+                    _ => {
+                        eprintln!("Unexpected response");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        "events" => {
+            let session_ids = match args.get(2).filter(|arg| !arg.starts_with('-')) {
+                Some(id) => vec![id.parse().context("Session ID must be a number")?],
+                None => Vec::new(),
+            };
+            let filter = SubscriptionFilter {
+                projects: project
+                    .map(|p| p.to_string_lossy().to_string())
+                    .into_iter()
+                    .collect(),
+                session_ids,
+                kinds: Vec::new(),
+            };
+            let since_seq = since_offset.unwrap_or(0);
+            let limit = event_limit.unwrap_or(DEFAULT_EVENTS_LIMIT);
+
+            if follow_mode {
+                return follow_events(&config, filter, since_seq, limit, json_output);
+            }
+
+            let mut stream = connect_to_daemon(&config)?;
+            let request = DaemonRequest::Events {
+                since_seq,
+                limit: Some(limit),
+                filter,
+            };
+            let response = send_request(&mut stream, &request)?;
+
+            if json_output {
+                println!("{}", serde_json::to_string_pretty(&response)?);
+            } else {
+                match response {
+                    DaemonResponse::Events { events, latest_seq } => {
+                        if events.is_empty() {
+                            println!("No events");
+                        }
+                        for event in &events {
+                            print_event(event);
+                        }
+                        if let Some(last) = events
+                            .last()
+                            .filter(|last| events.len() >= limit && last.seq < latest_seq)
+                        {
+                            eprintln!(
+                                "-- more events; next page: todo events --since {}",
+                                last.seq
+                            );
+                        }
+                    }
+                    DaemonResponse::Error { code: _, message } => {
+                        eprintln!("Error: {}", message);
+                        std::process::exit(1);
+                    }
+                    _ => {
+                        eprintln!("Unexpected response");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        "inbox" => run_inbox_command(&config, &args[2..], tail_bytes, viewer_mode, json_output),
+
+        "config" => run_config_command(&config, &args[2..], json_output),
+
+        "attention" => run_attention_command(
+            &config,
+            &args[2..],
+            &attention_profiles,
+            &chunk_sizes,
+            test_session,
+            json_output,
+        ),
+
+        "shell-integration" => {
+            // Determine shell type
+            let shell_type = match shell_type_arg {
+                Some(ref s) => s.parse::<ShellType>()?,
+                None => ShellType::detect().ok_or_else(|| {
+                    anyhow!(
+                        "Could not detect shell type from $SHELL. \
+                         Please specify with --shell <zsh|bash|fish>"
+                    )
+                })?,
+            };
+
+            let rc_path = shell_type.rc_file_path()?;
+
+            if install_mode && uninstall_mode {
+                eprintln!("Cannot use both --install and --uninstall");
+                std::process::exit(1);
+            }
+
+            if uninstall_mode {
+                let removed = shell_integration::uninstall(&rc_path)?;
+                if removed {
+                    println!("Removed Right Now integration from {}", rc_path.display());
+                    println!("Restart your shell or run: source {}", rc_path.display());
+                } else {
+                    println!(
+                        "Right Now integration was not installed in {}",
+                        rc_path.display()
+                    );
+                }
+            } else if install_mode {
+                let installed_path = shell_integration::install(shell_type, Some(rc_path.clone()))?;
+                println!(
+                    "Installed Right Now integration to {}",
+                    installed_path.display()
+                );
+                println!();
+                println!(
+                    "Restart your shell or run: source {}",
+                    installed_path.display()
+                );
+                println!();
+                println!("When working in a Right Now session, your prompt will show:");
+                println!("  [#42: Task name] > ");
+            } else {
+                // Show status
+                let installed = shell_integration::is_installed(&rc_path)?;
+                println!("Shell: {}", shell_type);
+                println!("RC file: {}", rc_path.display());
+                println!(
+                    "Status: {}",
+                    if installed {
+                        "installed"
+                    } else {
+                        "not installed"
+                    }
+                );
+                println!();
+                if !installed {
+                    println!("To install: todo shell-integration --install");
+                } else {
+                    println!("To uninstall: todo shell-integration --uninstall");
+                }
+            }
+            Ok(())
+        }
+
+        _ => {
+            eprintln!("Unknown command: {}", command);
+            print_help();
+            std::process::exit(1);
+        }
+    }
+}
+
+/// `todo config`: read config.toml, or change a key and have the daemon reload it
+///
+/// `reload` picks up edits to config.toml and attention.toml
+/// Attach this terminal to a session, as `continue --attach` does
+fn attach_to_session(
+    config: &Config,
+    stream: &mut UnixStream,
+    session_id: u64,
+    tail_bytes: Option<usize>,
+    viewer_mode: AttachMode,
+    json_output: bool,
+) -> Result<()> {
+    let (response, transport) = request_attach(stream, session_id, tail_bytes, viewer_mode)?;
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&response)?);
+        return Ok(());
+    }
+
+    match (response, transport) {
+        (
+            DaemonResponse::StreamOpened {
+                session,
+                tail,
+                screen,
+                ..
+            }
+            | DaemonResponse::AttachReady {
+                session,
+                tail,
+                screen,
+                ..
+            },
+            Some(transport),
+        ) => {
+            if session.status == SessionStatus::Stopped {
+                eprintln!(
+                    "Session {} is already stopped; nothing to attach to.",
+                    session.id
+                );
+                std::process::exit(1);
+            }
+
+            // Print metadata, but replay buffer directly during attach.
+            print_session_summary(&session, None)?;
+            run_attach_session(
+                &session,
+                tail.as_deref(),
+                screen.as_ref(),
+                transport,
+                viewer_mode,
+                config.clone(),
+            )?;
+        }
+        (DaemonResponse::Error { code: _, message }, _) => {
+            eprintln!("Error: {}", message);
+            std::process::exit(1);
+        }
+        (other, _) => {
+            eprintln!("Unexpected response: {:?}", other);
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+/// `todo inbox`: unseen attention events across projects, or `ack` to mark them seen
+///
+/// Listing on a terminal asks which one to attach to; attaching marks that
+/// session's events seen.
+fn run_inbox_command(
+    config: &Config,
+    args: &[String],
+    tail_bytes: Option<usize>,
+    viewer_mode: AttachMode,
+    json_output: bool,
+) -> Result<()> {
+    let positional = positional_args(args);
+    let mut stream = connect_to_daemon(config)?;
+
+    let request = match positional.first().copied() {
+        None | Some("list") => DaemonRequest::AttentionList {
+            project_path: None,
+            include_acked: false,
+        },
+        Some("ack") => match &positional[1..] {
+            [] => {
+                eprintln!("Usage: todo inbox ack <id>... | all");
+                std::process::exit(1);
+            }
+            ["all"] => DaemonRequest::AttentionAckAll {
+                project_path: None,
+                session_id: None,
+            },
+            ids => DaemonRequest::AttentionAck {
+                ids: ids
+                    .iter()
+                    .map(|id| id.parse())
+                    .collect::<Result<_, _>>()
+                    .context("Attention IDs must be numbers")?,
+            },
+        },
+        Some(other) => {
+            eprintln!("Unknown inbox command: {}", other);
+            eprintln!("Usage: todo inbox [list | ack <id>... | ack all]");
+            std::process::exit(1);
+        }
+    };
+
+    let response = send_request(&mut stream, &request)?;
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&response)?);
+        return Ok(());
+    }
+
+    let items = match response {
+        DaemonResponse::AttentionItems { items } => items,
+        DaemonResponse::AttentionAcked { ids } => {
+            match ids.len() {
+                0 => println!("Nothing to acknowledge"),
+                1 => println!("Acknowledged 1 item"),
+                n => println!("Acknowledged {} items", n),
+            }
+            return Ok(());
+        }
+        DaemonResponse::Error { code: _, message } => {
+            eprintln!("Error: {}", message);
+            std::process::exit(1);
+        }
+        _ => {
+            eprintln!("Unexpected response");
+            std::process::exit(1);
+        }
+    };
+
+    if items.is_empty() {
+        println!("Inbox is empty");
+        return Ok(());
+    }
+    for (n, item) in items.iter().enumerate() {
+        println!(
+            "{:>3}. #{} {} [{}] {} — {} ({})",
+            n + 1,
+            item.summary.id,
+            item.summary
+                .triggered_at
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S"),
+            item.session_id,
+            item.task_key,
+            item.summary.attention_type,
+            item.summary.profile,
+        );
+        println!("       {}", item.summary.preview);
+        println!("       {}", item.project_path);
+    }
+
+    if !io::stdin().is_terminal() {
+        return Ok(());
+    }
+    print!("Attach to [1-{}, Enter to quit]: ", items.len());
+    io::stdout().flush()?;
+    let mut choice = String::new();
+    io::stdin().lock().read_line(&mut choice)?;
+    let choice = choice.trim();
+    if choice.is_empty() {
+        return Ok(());
+    }
+    let Some(item) = choice
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|n| items.get(n))
+    else {
+        eprintln!("No item {}", choice);
+        std::process::exit(1);
+    };
+
+    attach_to_session(
+        config,
+        &mut stream,
+        item.session_id,
+        tail_bytes,
+        viewer_mode,
+        false,
+    )
+}
+
+fn run_config_command(config: &Config, args: &[String], json_output: bool) -> Result<()> {
+    let settings_file = config.settings_file();
+    let invalid = |e: SettingsError| anyhow!("{}: {}", settings_file.display(), e);
+
+    match args.first().map(String::as_str) {
+        None | Some("show") => {
+            let settings = config.load_settings().map_err(invalid)?;
+            if json_output {
+                println!("{}", serde_json::to_string_pretty(&settings)?);
+                return Ok(());
+            }
+            println!("# {}", settings_file.display());
+            for (key, description) in SETTING_KEYS {
+                println!("{} = {}  # {}", key, settings.get(key)?, description);
+            }
+        }
+        Some("get") => {
+            let Some(key) = args.get(1) else {
+                eprintln!("Usage: todo config get <key>");
+                std::process::exit(1);
+            };
+            let settings = config.load_settings().map_err(invalid)?;
+            println!("{}", settings.get(key)?);
+        }
+        Some("set") => {
+            let (Some(key), Some(value)) = (args.get(1), args.get(2)) else {
+                eprintln!("Usage: todo config set <key> <value>");
+                std::process::exit(1);
+            };
+            let settings = config.set_setting(key, value).map_err(invalid)?;
+            println!("Set {} = {}", key, settings.get(key)?);
+
+            // Only a daemon that is already running needs telling
+            if let Ok(stream) = UnixStream::connect(&config.socket_path) {
+                reload_daemon_config(stream)?;
+            }
+        }
+        Some("reload") => {
+            let stream = UnixStream::connect(&config.socket_path)
+                .map_err(|_| anyhow!("Daemon not running; it reads the config when it starts"))?;
+            reload_daemon_config(stream)?;
+        }
+        Some(other) => {
+            eprintln!("Unknown config command: {}", other);
+            eprintln!("Usage: todo config [show | get <key> | set <key> <value> | reload]");
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+/// Have a running daemon re-read config.toml and attention.toml
+fn reload_daemon_config(mut stream: UnixStream) -> Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    match send_request(&mut stream, &DaemonRequest::ReloadConfig)? {
+        DaemonResponse::ConfigReloaded {
+            attention_profiles, ..
+        } => {
+            println!("Daemon reloaded its settings");
+            if !attention_profiles.is_empty() {
+                println!("Attention profiles: {}", attention_profiles.join(", "));
+            }
+        }
+        DaemonResponse::Error { code: _, message } => {
+            eprintln!("Daemon did not reload: {}", message);
+            std::process::exit(1);
+        }
+        _ => {
+            eprintln!("Unexpected response");
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+/// Arguments that are neither options nor option values
+fn positional_args(args: &[String]) -> Vec<&str> {
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if VALUE_OPTIONS.contains(&arg.as_str()) {
+            args.next();
+        } else if arg == "-" || !arg.starts_with('-') {
+            positional.push(arg.as_str());
+        }
+    }
+    positional
+}
+
+/// `todo attention test`: show what attention profiles report for some output
+///
+/// Uses the profiles in attention.toml as saved, so edits can be tried
+/// before `todo config reload`. Feeding the same output in several chunk
+/// sizes shows whether a trigger depends on where reads happen to split it.
+fn run_attention_command(
+    config: &Config,
+    args: &[String],
+    profile_names: &[String],
+    chunk_sizes: &[usize],
+    session_id: Option<u64>,
+    json_output: bool,
+) -> Result<()> {
+    const USAGE: &str = "Usage: todo attention test [--profile <name>]... [--chunk-size <n>[,<n>...]] <logfile | - | --session <id>>";
+    if args.first().map(String::as_str) != Some("test") {
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+
+    let profiles_file = config.attention_profiles_file();
+    let profiles = attention::load_profiles(&profiles_file)
+        .map_err(|e| anyhow!("{}: {}", profiles_file.display(), e))?;
+    if let Some(unknown) = profile_names
+        .iter()
+        .find(|name| !profiles.iter().any(|profile| profile.name == **name))
+    {
+        let known: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+        return Err(anyhow!(
+            "Unknown attention profile '{}' (known: {})",
+            unknown,
+            known.join(", ")
+        ));
+    }
+    let named = || ProfileSelection::Named(profile_names.to_vec());
+
+    let (output, selection, source) = match session_id {
+        Some(session_id) => {
+            let mut stream = connect_to_daemon(config)?;
+            let session = match send_request(&mut stream, &DaemonRequest::Status { session_id })? {
+                DaemonResponse::SessionStatus { session } => session,
+                DaemonResponse::Error { message, .. } => return Err(anyhow!(message)),
+                _ => return Err(anyhow!("Unexpected response")),
+            };
+            // The whole ring buffer, not just what an attach replays
+            let request = DaemonRequest::Tail {
+                session_id,
+                bytes: Some(
+                    config
+                        .load_settings()
+                        .unwrap_or_default()
+                        .output_buffer_bytes,
+                ),
+                offset: None,
+            };
+            let output = match send_request(&mut stream, &request)? {
+                DaemonResponse::SessionTail { data, .. } => data,
+                DaemonResponse::Error { message, .. } => return Err(anyhow!(message)),
+                _ => return Err(anyhow!("Unexpected response")),
+            };
+            // Without --profile, the profiles the daemon would pick right now
+            let selection = if profile_names.is_empty() {
+                ProfileSelection::for_session(
+                    &session.attention_profiles,
+                    session.foreground_command.as_deref(),
+                )
+            } else {
+                named()
+            };
+            (output, selection, format!("session {}", session_id))
+        }
+        None => {
+            let Some(&input) = positional_args(&args[1..]).first() else {
+                eprintln!("{}", USAGE);
+                std::process::exit(1);
+            };
+            let output = if input == "-" {
+                let mut output = Vec::new();
+                io::stdin().read_to_end(&mut output)?;
+                output
+            } else {
+                std::fs::read(input).with_context(|| format!("Failed to read {}", input))?
+            };
+            let selection = if profile_names.is_empty() {
+                ProfileSelection::All
+            } else {
+                named()
+            };
+            let source = if input == "-" { "stdin" } else { input };
+            (output, selection, source.to_string())
+        }
+    };
+
+    let selected: Vec<String> = selection
+        .select(&profiles)
+        .into_iter()
+        .map(|profile| profile.name)
+        .collect();
+    let profiles = attention::ProfileSet::new(profiles);
+
+    let chunk_sizes = if chunk_sizes.is_empty() {
+        &[DEFAULT_ATTENTION_CHUNK_BYTES][..]
+    } else {
+        chunk_sizes
+    };
+    let runs: Vec<(usize, Vec<AttentionMatch>)> = chunk_sizes
+        .iter()
+        .map(|&chunk_size| {
+            let mut accumulator = AttentionAccumulator::default()
+                .with_profiles(profiles.clone())
+                .with_selection(selection.clone());
+            let matches = output
+                .chunks(chunk_size)
+                .flat_map(|chunk| accumulator.push_chunk(chunk))
+                .collect();
+            (chunk_size, matches)
+        })
+        .collect();
+
+    if json_output {
+        let runs: Vec<_> = runs
+            .iter()
+            .map(|(chunk_size, matches)| {
+                serde_json::json!({ "chunk_size": chunk_size, "matches": matches })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&runs)?);
+        return Ok(());
+    }
+
+    println!("Profiles: {}", selected.join(", "));
+    for (chunk_size, matches) in &runs {
+        if runs.len() > 1 {
+            println!("\n== chunks of {} bytes ==", chunk_size);
+        }
+        for matched in matches {
+            println!(
+                "[bytes {}..{}] {} — {}",
+                matched.bytes.start, matched.bytes.end, matched.profile, matched.attention_type
+            );
+            for line in matched.preview.lines() {
+                println!("    {}", line);
+            }
+        }
+        println!(
+            "{} match(es) in {} bytes of {}",
+            matches.len(),
+            output.len(),
+            source
+        );
+    }
+
+    let outcome = |matches: &[AttentionMatch]| -> Vec<(String, u64)> {
+        matches
+            .iter()
+            .map(|matched| (matched.profile.clone(), matched.bytes.end))
+            .collect()
+    };
+    if runs
+        .windows(2)
+        .any(|pair| outcome(&pair[0].1) != outcome(&pair[1].1))
+    {
+        eprintln!("\nMatches differ between chunk sizes; a trigger depends on how output is split");
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn print_session_summary(
+    session: &rn_desktop_2_lib::session::protocol::Session,
+    tail_data: Option<&[u8]>,
+) -> Result<()> {
+    println!(
+        "Session {} — {} ({})",
+        session.id, session.task_key, session.status
+    );
+    println!("Project: {}", session.project_path);
+    println!("Created: {}", session.created_at);
+    println!("Updated: {}", session.updated_at);
+    if let Some(code) = session.exit_code {
+        println!("Exit code: {}", code);
+    }
+    println!("Deep link: {}", session.deep_link());
+    if !session.viewers.is_empty() {
+        println!("Attached: {}", describe_viewers(session));
+    }
+    if let Some(attention) = &session.last_attention {
+        println!(
+            "Attention: {} ({}) at {}",
+            attention.attention_type, attention.profile, attention.triggered_at
+        );
+        println!("  {}", attention.preview);
+    }
 
     match (session.status, tail_data) {
         (_, Some(data)) if !data.is_empty() => {
@@ -720,6 +1796,162 @@ fn print_session_summary(
     Ok(())
 }
 
+/// Modes of the clients attached to a session, e.g. "shared, read-only"
+fn describe_viewers(session: &rn_desktop_2_lib::session::protocol::Session) -> String {
+    let modes: Vec<String> = session
+        .viewers
+        .iter()
+        .map(|viewer| viewer.mode.to_string())
+        .collect();
+    modes.join(", ")
+}
+
+/// One line per journaled event
+fn print_event(event: &JournalEvent) {
+    let what = match &event.notification {
+        DaemonNotification::SessionUpdated { session } => match session.exit_code {
+            Some(code) => format!(
+                "[{}] {} — {} (exit {})",
+                session.id, session.task_key, session.status, code
+            ),
+            None => format!("[{}] {} — {}", session.id, session.task_key, session.status),
+        },
+        DaemonNotification::SessionRemoved { session_id } => format!("[{}] removed", session_id),
+        DaemonNotification::Attention {
+            session_id,
+            attention_type,
+            preview,
+            ..
+        } => format!("[{}] {}: {}", session_id, attention_type, preview),
+        DaemonNotification::AttentionAcked { session_id, ids } => {
+            let ids: Vec<String> = ids.iter().map(|id| format!("#{}", id)).collect();
+            format!("[{}] acknowledged {}", session_id, ids.join(", "))
+        }
+        DaemonNotification::Resync { missed } => format!("missed {} events", missed),
+    };
+    println!(
+        "#{} {} {}",
+        event.seq,
+        event
+            .at
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S"),
+        what
+    );
+}
+
+/// Print journaled events after `since_seq`, then new ones as they happen
+///
+/// Subscribes before reading the journal so nothing falls in between, and
+/// goes back to the journal whenever the daemon says it dropped notifications.
+fn follow_events(
+    config: &Config,
+    filter: SubscriptionFilter,
+    since_seq: u64,
+    limit: usize,
+    json_output: bool,
+) -> Result<()> {
+    let mut stream = connect_to_daemon(config)?;
+    let subscribe = DaemonRequest::Subscribe {
+        projects: filter.projects.clone(),
+        session_ids: filter.session_ids.clone(),
+        kinds: filter.kinds.clone(),
+    };
+    match send_request(&mut stream, &subscribe)? {
+        DaemonResponse::Subscribed { .. } => {}
+        DaemonResponse::Error { code: _, message } => anyhow::bail!("{}", message),
+        other => anyhow::bail!("Expected subscription, got: {:?}", other),
+    }
+    stream.set_read_timeout(None)?;
+
+    let page = limit.min(MAX_EVENTS_LIMIT);
+    let request_page = |stream: &mut UnixStream, since_seq| -> Result<()> {
+        let request = DaemonRequest::Events {
+            since_seq,
+            limit: Some(page),
+            filter: filter.clone(),
+        };
+        stream.write_all(&serialize_message(&request)?)?;
+        stream.flush()?;
+        Ok(())
+    };
+    let show = |event: &JournalEvent| -> Result<()> {
+        if json_output {
+            println!("{}", serde_json::to_string(event)?);
+        } else {
+            print_event(event);
+        }
+        Ok(())
+    };
+    // Live notifications aren't timestamped; they happened just now
+    let received = |envelope: NotificationEnvelope| JournalEvent {
+        seq: envelope.seq,
+        at: chrono::Utc::now(),
+        project_path: envelope.project_path,
+        notification: envelope.notification,
+    };
+
+    let mut cursor = since_seq;
+    // Live notifications wait here while the journal is being read
+    let mut pending: Option<Vec<NotificationEnvelope>> = Some(Vec::new());
+    request_page(&mut stream, cursor)?;
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            anyhow::bail!("Daemon closed the connection");
+        }
+
+        if let Ok(envelope) = deserialize_message::<NotificationEnvelope>(line.as_bytes()) {
+            match (&envelope.notification, &mut pending) {
+                (DaemonNotification::Resync { .. }, Some(_)) => {}
+                (DaemonNotification::Resync { .. }, None) => {
+                    pending = Some(Vec::new());
+                    request_page(&mut stream, cursor)?;
+                }
+                (_, Some(pending)) => pending.push(envelope),
+                (_, None) if envelope.seq > cursor => {
+                    cursor = envelope.seq;
+                    show(&received(envelope))?;
+                }
+                (_, None) => {}
+            }
+            continue;
+        }
+
+        match deserialize_message::<DaemonResponse>(line.as_bytes())
+            .context("Failed to parse daemon response")?
+        {
+            DaemonResponse::Events { events, latest_seq } => {
+                let full = events.len() >= page;
+                for event in &events {
+                    if event.seq > cursor {
+                        cursor = event.seq;
+                        show(event)?;
+                    }
+                }
+                if full {
+                    request_page(&mut stream, cursor)?;
+                    continue;
+                }
+
+                // Caught up; whatever arrived meanwhile past the journal follows
+                cursor = cursor.max(latest_seq);
+                for envelope in pending.take().unwrap_or_default() {
+                    if envelope.seq > cursor {
+                        cursor = envelope.seq;
+                        show(&received(envelope))?;
+                    }
+                }
+            }
+            DaemonResponse::Error { code: _, message } => anyhow::bail!("{}", message),
+            other => anyhow::bail!("Unexpected response: {:?}", other),
+        }
+    }
+}
+
 fn sanitize_tail_bytes(data: &[u8]) -> Cow<'_, str> {
     match std::str::from_utf8(data) {
         Ok(text) => Cow::Borrowed(text),
@@ -737,25 +1969,70 @@ fn sanitize_tail_bytes(data: &[u8]) -> Cow<'_, str> {
 fn run_attach_session(
     session: &rn_desktop_2_lib::session::protocol::Session,
     tail_data: Option<&[u8]>,
-    socket_path: &str,
+    screen: Option<&ScreenRepaint>,
+    transport: AttachTransport,
+    mode: AttachMode,
     config: Config,
 ) -> Result<()> {
-    println!("\nAttaching to live session {}\n", session.id);
+    let read_only = mode == AttachMode::ReadOnly;
+    if read_only {
+        println!("\nWatching live session {} (read-only)\n", session.id);
+    } else {
+        println!("\nAttaching to live session {}\n", session.id);
+    }
     println!("Detach with Ctrl-\\");
 
-    let stream = UnixStream::connect(socket_path)
-        .with_context(|| format!("Failed to connect to attach socket '{}'", socket_path))?;
-    stream
+    let session_id = session.id;
+    let (output, writer_stream) = match transport {
+        AttachTransport::Stream(reader, writer) => (AttachOutput::Frames(reader), writer),
+        AttachTransport::Socket(socket_path) => {
+            let stream = UnixStream::connect(&socket_path)
+                .with_context(|| format!("Failed to connect to attach socket '{}'", socket_path))?;
+            let reader_stream = stream
+                .try_clone()
+                .context("Failed to clone attach socket for reading")?;
+            (AttachOutput::Raw(reader_stream), stream)
+        }
+    };
+    let framed = matches!(output, AttachOutput::Frames(_));
+    writer_stream
         .set_read_timeout(None)
-        .context("Failed to configure attach socket read timeout")?;
-    stream
+        .context("Failed to configure attach read timeout")?;
+    writer_stream
         .set_write_timeout(None)
-        .context("Failed to configure attach socket write timeout")?;
-
-    let reader_stream = stream
-        .try_clone()
-        .context("Failed to clone attach socket for reading")?;
-    let writer_stream = stream;
+        .context("Failed to configure attach write timeout")?;
+
+    // Shared with the resize watcher when resizes travel as stream frames
+    let writer = Arc::new(std::sync::Mutex::new(writer_stream));
+    let send_input = |data: &[u8]| -> io::Result<()> {
+        // The daemon would drop it anyway
+        if read_only {
+            return Ok(());
+        }
+        let mut writer = writer.lock().unwrap();
+        if framed {
+            let frame = StreamFrame::Data {
+                session_id,
+                data: data.to_vec(),
+            };
+            write_stream_frame(&mut writer, &frame)
+        } else {
+            writer.write_all(data)
+        }
+    };
+    let resize: ResizeFn = if framed {
+        let writer = Arc::clone(&writer);
+        Arc::new(move |cols, rows| {
+            let frame = StreamFrame::Resize {
+                session_id,
+                cols,
+                rows,
+            };
+            Ok(write_stream_frame(&mut writer.lock().unwrap(), &frame)?)
+        })
+    } else {
+        Arc::new(move |cols, rows| send_resize_request(&config, session_id, cols, rows))
+    };
 
     let raw_mode = RawModeGuard::enable()?;
     let stdin = io::stdin();
@@ -763,33 +2040,41 @@ fn run_attach_session(
     let mut stdin_lock = stdin.lock();
     let stdin_guard = NonBlockingFdGuard::new(stdin_fd)?;
 
-    render_attach_banner(session.id, tail_data)?;
+    render_attach_banner(session.id, tail_data, screen)?;
 
     let running = Arc::new(AtomicBool::new(true));
 
-    if let Ok((cols, rows)) = crossterm::terminal::size() {
-        if let Err(err) = send_resize_request(&config, session.id, cols, rows) {
-            eprintln!("Failed to send initial resize request: {}", err);
+    // Watchers see the session at whatever size its other clients chose
+    let resize_watcher = if read_only {
+        None
+    } else {
+        if let Ok((cols, rows)) = crossterm::terminal::size() {
+            if let Err(err) = resize(cols, rows) {
+                eprintln!("Failed to send initial resize request: {}", err);
+            }
         }
-    }
 
-    let resize_watcher = match ResizeWatcher::start(session.id, config, Arc::clone(&running)) {
-        Ok(watcher) => Some(watcher),
-        Err(err) => {
-            eprintln!("Warning: terminal resize handling disabled ({})", err);
-            None
+        match ResizeWatcher::start(resize, Arc::clone(&running)) {
+            Ok(watcher) => Some(watcher),
+            Err(err) => {
+                eprintln!("Warning: terminal resize handling disabled ({})", err);
+                None
+            }
         }
     };
 
     let reader_running = Arc::clone(&running);
 
     let output_thread = thread::spawn(move || {
-        if let Err(err) = pump_socket_to_stdout(reader_stream, reader_running) {
+        let result = match output {
+            AttachOutput::Raw(reader) => pump_socket_to_stdout(reader, reader_running),
+            AttachOutput::Frames(reader) => pump_stream_frames(reader, session_id, reader_running),
+        };
+        if let Err(err) = result {
             eprintln!("\n[Attach reader error: {}]", err);
         }
     });
 
-    let mut writer = writer_stream;
     let mut detach_requested = false;
     let mut buffer = [0u8; 1024];
     let mut input_error: Option<anyhow::Error> = None;
@@ -805,7 +2090,7 @@ fn run_attach_session(
                 for idx in 0..n {
                     if buffer[idx] == DETACH_BYTE {
                         if idx > chunk_start {
-                            if let Err(err) = writer.write_all(&buffer[chunk_start..idx]) {
+                            if let Err(err) = send_input(&buffer[chunk_start..idx]) {
                                 input_error =
                                     Some(anyhow!("Failed to send input to session: {}", err));
                                 running.store(false, Ordering::SeqCst);
@@ -819,7 +2104,7 @@ fn run_attach_session(
                     }
                 }
                 if !detach_requested && chunk_start < n {
-                    if let Err(err) = writer.write_all(&buffer[chunk_start..n]) {
+                    if let Err(err) = send_input(&buffer[chunk_start..n]) {
                         input_error = Some(anyhow!("Failed to send input to session: {}", err));
                         running.store(false, Ordering::SeqCst);
                         break;
@@ -841,14 +2126,25 @@ fn run_attach_session(
         }
     }
 
-    // Close the socket to signal detach/EOF.
-    let _ = writer.shutdown(Shutdown::Both);
-    drop(writer);
+    // Close the stream or socket to signal detach/EOF.
+    {
+        let mut writer = writer.lock().unwrap();
+        if framed {
+            let _ = write_stream_frame(&mut writer, &StreamFrame::detach(session_id));
+        }
+        let _ = writer.shutdown(Shutdown::Both);
+    }
 
     // Ensure the reader thread notices shutdown.
     running.store(false, Ordering::SeqCst);
     let _ = output_thread.join();
 
+    if screen.map(|s| s.alternate_screen).unwrap_or(false) {
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(LEAVE_ALTERNATE_SCREEN);
+        let _ = stdout.flush();
+    }
+
     drop(stdin_guard);
     drop(raw_mode);
 
@@ -870,10 +2166,116 @@ fn run_attach_session(
     Ok(())
 }
 
-fn render_attach_banner(session_id: u64, tail_data: Option<&[u8]>) -> Result<()> {
+/// Play a recording back in the terminal, honoring its timing
+///
+/// Pauses longer than `REPLAY_MAX_IDLE` are shortened, and resize events are
+/// skipped since the viewer's terminal can't be resized from here.
+fn run_replay(session_id: u64, path: &Path, speed: f64) -> Result<()> {
+    let (header, entries) = read_recording(path)
+        .with_context(|| format!("Failed to read recording '{}'", path.display()))?;
+
+    println!("\nReplaying session {} at {}x speed", session_id, speed);
+    if let Ok((cols, rows)) = crossterm::terminal::size() {
+        if (cols, rows) != (header.width, header.height) {
+            println!(
+                "Recorded at {}x{}; this terminal is {}x{}",
+                header.width, header.height, cols, rows
+            );
+        }
+    }
+    println!("Stop with q\n");
+
+    let raw_mode = RawModeGuard::enable()?;
+    let stdin = io::stdin();
+    let stdin_fd = stdin.as_raw_fd();
+    let mut stdin_lock = stdin.lock();
+    let stdin_guard = NonBlockingFdGuard::new(stdin_fd)?;
+
+    let mut stdout = io::stdout().lock();
+    let started = Instant::now();
+    let mut position = Duration::ZERO;
+    let mut last_time = 0.0;
+    let mut alternate_screen = false;
+    let mut stopped = false;
+
+    for entry in entries {
+        let gap = Duration::from_secs_f64((entry.time - last_time).max(0.0));
+        last_time = entry.time;
+        position += gap.min(REPLAY_MAX_IDLE).div_f64(speed);
+        if wait_for_replay(&mut stdin_lock, started + position)? {
+            stopped = true;
+            break;
+        }
+
+        if let CastEvent::Output(text) = entry.event {
+            // Track the alternate screen so a stopped replay can leave it
+            let enter = text.rfind("\x1b[?1049h");
+            let leave = text.rfind("\x1b[?1049l");
+            if enter.is_some() || leave.is_some() {
+                alternate_screen = enter > leave;
+            }
+            stdout.write_all(text.as_bytes())?;
+            stdout.flush()?;
+        }
+    }
+
+    stdout.write_all(if alternate_screen {
+        LEAVE_ALTERNATE_SCREEN
+    } else {
+        RESET_TERMINAL
+    })?;
+    stdout.flush()?;
+    drop(stdout);
+    drop(stdin_guard);
+    drop(raw_mode);
+
+    if stopped {
+        println!("\n[Replay of session {} stopped]\n", session_id);
+    } else {
+        println!("\n[Replay of session {} finished]\n", session_id);
+    }
+    Ok(())
+}
+
+/// Wait until `deadline`, returning true early if a quit key is pressed
+fn wait_for_replay(stdin: &mut impl Read, deadline: Instant) -> Result<bool> {
+    let mut buffer = [0u8; 64];
+    loop {
+        match stdin.read(&mut buffer) {
+            Ok(n) if buffer[..n].iter().any(|b| REPLAY_QUIT_KEYS.contains(b)) => {
+                return Ok(true);
+            }
+            Ok(_) => {}
+            Err(ref e)
+                if e.kind() == io::ErrorKind::WouldBlock
+                    || e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(anyhow!("Failed reading from stdin: {}", e)),
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(false);
+        }
+        thread::sleep((deadline - now).min(Duration::from_millis(INPUT_IDLE_SLEEP_MS)));
+    }
+}
+
+fn render_attach_banner(
+    session_id: u64,
+    tail_data: Option<&[u8]>,
+    screen: Option<&ScreenRepaint>,
+) -> Result<()> {
     let stdout = io::stdout();
     let mut handle = stdout.lock();
 
+    // Prefer redrawing the daemon's screen model; raw tail replay can start
+    // mid-escape-sequence and garble full-screen programs.
+    if let Some(screen) = screen {
+        handle.write_all(&screen.to_terminal_bytes())?;
+        handle.flush()?;
+        return Ok(());
+    }
+
     match tail_data {
         Some(data) if !data.is_empty() => {
             writeln!(
@@ -894,6 +2296,80 @@ fn render_attach_banner(session_id: u64, tail_data: Option<&[u8]>) -> Result<()>
     Ok(())
 }
 
+/// Where an attached session's output comes from
+enum AttachOutput {
+    /// Raw PTY output on an attach socket
+    Raw(UnixStream),
+    /// Stream frames on the daemon connection
+    Frames(BufReader<UnixStream>),
+}
+
+/// Sends a terminal size to the attached session
+type ResizeFn = Arc<dyn Fn(u16, u16) -> Result<()> + Send + Sync>;
+
+fn write_stream_frame(writer: &mut UnixStream, frame: &StreamFrame) -> io::Result<()> {
+    let bytes = serialize_message_with(frame, ByteEncoding::Base64)?;
+    writer.write_all(&bytes)?;
+    writer.flush()
+}
+
+/// Copy a session's stream frames to stdout until the daemon closes the stream
+fn pump_stream_frames(
+    mut reader: BufReader<UnixStream>,
+    session_id: u64,
+    running: Arc<AtomicBool>,
+) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut handle = stdout.lock();
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                running.store(false, Ordering::SeqCst);
+                return Err(e);
+            }
+        }
+
+        match deserialize_message::<StreamFrame>(line.as_bytes()) {
+            Ok(StreamFrame::Data {
+                session_id: id,
+                data,
+            }) if id == session_id => {
+                handle.write_all(&data)?;
+                handle.flush()?;
+            }
+            Ok(StreamFrame::Close {
+                session_id: id,
+                exited,
+                exit_code,
+                message,
+            }) if id == session_id => {
+                let note = match (exited, exit_code, message) {
+                    (_, _, Some(message)) => format!("\r\n[{}]\r\n", message),
+                    (true, Some(code), None) => {
+                        format!("\r\n[process exited with code {}]\r\n", code)
+                    }
+                    (true, None, None) => "\r\n[process exited]\r\n".to_string(),
+                    (false, _, None) => String::new(),
+                };
+                handle.write_all(note.as_bytes())?;
+                handle.flush()?;
+                break;
+            }
+            // Nothing else is sent on the connection while attached
+            _ => continue,
+        }
+    }
+
+    running.store(false, Ordering::SeqCst);
+    Ok(())
+}
+
 fn pump_socket_to_stdout(mut reader: UnixStream, running: Arc<AtomicBool>) -> io::Result<()> {
     let stdout = io::stdout();
     let mut handle = stdout.lock();
@@ -988,7 +2464,7 @@ struct ResizeWatcher {
 
 #[cfg(unix)]
 impl ResizeWatcher {
-    fn start(session_id: u64, config: Config, running: Arc<AtomicBool>) -> Result<Self> {
+    fn start(resize: ResizeFn, running: Arc<AtomicBool>) -> Result<Self> {
         let mut signals = Signals::new([SIGWINCH])?;
         let handle = signals.handle();
         let thread = thread::spawn(move || {
@@ -997,7 +2473,7 @@ impl ResizeWatcher {
                     break;
                 }
                 if let Ok((cols, rows)) = crossterm::terminal::size() {
-                    if let Err(err) = send_resize_request(&config, session_id, cols, rows) {
+                    if let Err(err) = resize(cols, rows) {
                         eprintln!("Failed to send resize request: {}", err);
                     }
                 }
@@ -1020,7 +2496,7 @@ struct ResizeWatcher;
 
 #[cfg(not(unix))]
 impl ResizeWatcher {
-    fn start(_session_id: u64, _config: Config, _running: Arc<AtomicBool>) -> Result<Self> {
+    fn start(_resize: ResizeFn, _running: Arc<AtomicBool>) -> Result<Self> {
         Ok(Self)
     }
 
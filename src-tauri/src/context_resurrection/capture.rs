@@ -3,7 +3,7 @@
 //! Defines the SessionProvider trait contract that session module implements,
 //! inverting the dependency to avoid coupling CR to session internals.
 
-use crate::context_resurrection::models::{AttentionSummary, SessionStatus};
+use crate::context_resurrection::models::{AttentionSummary, SessionStatus, TerminalScreen};
 
 /// Snapshot of session state provided by session module
 #[derive(Debug, Clone)]
@@ -16,6 +16,12 @@ pub struct SessionSnapshot {
     pub last_attention: Option<AttentionSummary>,
     /// Unsanitized terminal tail (capture.rs sanitizes before storing)
     pub tail: String,
+    /// Unsanitized rendered screen, if the session has a screen model
+    pub screen: Option<TerminalScreen>,
+    /// Unsanitized command line of the foreground process (if running)
+    pub foreground_command: Option<String>,
+    /// Working directory of the foreground process, as last seen
+    pub cwd: Option<String>,
 }
 
 /// Trait implemented by session module to provide snapshot data
@@ -72,11 +78,26 @@ pub fn sanitize_terminal_output(input: &str) -> String {
     sanitized
 }
 
+/// Sanitize a rendered screen the same way as tails
+///
+/// Lines are redacted as one block so multi-line secrets (PEM keys) are caught;
+/// such a redaction collapses their rows, which is acceptable for context.
+fn sanitize_screen(screen: TerminalScreen) -> TerminalScreen {
+    let joined = sanitize_terminal_output(&screen.lines.join("\n"));
+    TerminalScreen {
+        lines: joined.split('\n').map(str::to_string).collect(),
+        cursor_row: screen.cursor_row,
+        cursor_col: screen.cursor_col,
+        title: screen.title.map(|title| sanitize_terminal_output(&title)),
+    }
+}
+
 use crate::context_resurrection::models::{CaptureReason, ContextSnapshotV1, TerminalContext};
-use crate::context_resurrection::store::SnapshotStore;
+use crate::context_resurrection::store::{SnapshotStore, DEFAULT_RETENTION_COUNT};
 use std::collections::HashMap;
 use std::io;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant, SystemTime};
 
@@ -156,6 +177,8 @@ pub struct CaptureService {
     session_provider: Option<Arc<dyn SessionProvider>>,
     state: Arc<Mutex<CaptureState>>,
     clock: Arc<dyn Clock>,
+    /// Snapshots kept per task; older ones are pruned after each capture
+    retention: Arc<AtomicUsize>,
 }
 
 impl CaptureService {
@@ -175,9 +198,15 @@ impl CaptureService {
             session_provider,
             state: Arc::new(Mutex::new(CaptureState::new())),
             clock,
+            retention: Arc::new(AtomicUsize::new(DEFAULT_RETENTION_COUNT)),
         }
     }
 
+    /// Keep `count` snapshots per task from the next capture on
+    pub fn set_retention(&self, count: usize) {
+        self.retention.store(count, Ordering::Relaxed);
+    }
+
     /// Capture a snapshot now with dedup, rate limiting, and per-task locking
     ///
     /// Returns Ok(Some(snapshot)) if capture succeeded, Ok(None) if skipped
@@ -238,6 +267,7 @@ impl CaptureService {
             if let Some(ref provider) = self.session_provider {
                 if let Some(session_snapshot) = provider.get_session_state(sid) {
                     let sanitized_tail = sanitize_terminal_output(&session_snapshot.tail);
+                    let sanitized_screen = session_snapshot.screen.map(sanitize_screen);
 
                     snapshot.terminal = Some(TerminalContext {
                         session_id: sid,
@@ -250,6 +280,11 @@ impl CaptureService {
                             None
                         },
                         tail_path: None, // Phase 1 keeps tail inline; tail file comes later
+                        screen: sanitized_screen,
+                        foreground_command: session_snapshot
+                            .foreground_command
+                            .map(|command| sanitize_terminal_output(&command)),
+                        cwd: session_snapshot.cwd,
                     });
                 }
             }
@@ -279,6 +314,17 @@ impl CaptureService {
             }
         }
 
+        let retain_count = self.retention.load(Ordering::Relaxed);
+        if let Err(e) = self
+            .store
+            .prune_snapshots(project_path, task_id, Some(retain_count))
+        {
+            eprintln!(
+                "Warning: Failed to prune snapshots for task {}: {}",
+                task_id, e
+            );
+        }
+
         // Record successful capture
         {
             let mut state = self.state.lock().unwrap();
@@ -526,6 +572,38 @@ mod tests {
         assert!(result3.is_some(), "Should succeed after 2s rate limit");
     }
 
+    #[test]
+    fn test_capture_prunes_to_retention() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = SnapshotStore::new(temp_dir.path());
+        let clock = Arc::new(TestClock::new());
+        let service = CaptureService::with_clock(store.clone(), None, clock.clone());
+        service.set_retention(2);
+
+        let project_path = temp_dir.path().join("TODO.md");
+        std::fs::write(&project_path, "# TODO.md\n").unwrap();
+
+        for _ in 0..4 {
+            let captured = service
+                .capture_now(
+                    &project_path,
+                    "abc.retention",
+                    "Test",
+                    None,
+                    CaptureReason::Manual,
+                    None,
+                )
+                .unwrap();
+            assert!(captured.is_some());
+            clock.advance(Duration::from_secs(10));
+        }
+
+        let kept = store
+            .list_snapshots(&project_path, "abc.retention", None)
+            .unwrap();
+        assert_eq!(kept.len(), 2);
+    }
+
     #[test]
     fn test_rate_limit_per_task_isolation() {
         let temp_dir = TempDir::new().unwrap();
@@ -582,6 +660,9 @@ mod tests {
                 exit_code: Some(0),
                 last_attention: None,
                 tail: "$ cargo build\n   Compiling...\n   Finished".to_string(),
+                screen: None,
+                foreground_command: None,
+                cwd: None,
             },
         );
 
@@ -638,6 +719,9 @@ mod tests {
                 exit_code: Some(0),
                 last_attention: None,
                 tail: "export API_KEY=secret123\nRunning tests...".to_string(),
+                screen: None,
+                foreground_command: None,
+                cwd: None,
             },
         );
 
@@ -667,6 +751,64 @@ mod tests {
         assert!(tail.contains("Running tests"));
     }
 
+    #[test]
+    fn test_screen_captured_and_sanitized() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = SnapshotStore::new(temp_dir.path());
+        let clock = Arc::new(TestClock::new());
+
+        let provider = Arc::new(MockSessionProvider::new());
+        provider.set(
+            7,
+            SessionSnapshot {
+                status: SessionStatus::Waiting,
+                exit_code: None,
+                last_attention: None,
+                tail: String::new(),
+                screen: Some(TerminalScreen {
+                    lines: vec!["TOKEN=hunter2".to_string(), "> Continue? [y/n]".to_string()],
+                    cursor_row: 1,
+                    cursor_col: 17,
+                    title: Some("agent".to_string()),
+                }),
+                foreground_command: Some("env API_KEY=abc123 ./deploy.sh".to_string()),
+                cwd: Some("/home/me/project".to_string()),
+            },
+        );
+
+        let service = CaptureService::with_clock(store, Some(provider), clock);
+
+        let project_path = temp_dir.path().join("TODO.md");
+        std::fs::write(&project_path, "# TODO\n").unwrap();
+
+        let snapshot = service
+            .capture_now(
+                &project_path,
+                "scr.screen-test",
+                "Screen Test",
+                Some(7),
+                CaptureReason::SessionWaiting,
+                None,
+            )
+            .unwrap()
+            .expect("Capture should succeed");
+
+        let terminal = snapshot.terminal.expect("Terminal should be captured");
+        assert!(terminal.tail_inline.is_none());
+
+        let screen = terminal.screen.expect("Screen should be captured");
+        assert_eq!(screen.lines, vec!["[REDACTED]", "> Continue? [y/n]"]);
+        assert_eq!((screen.cursor_row, screen.cursor_col), (1, 17));
+        assert_eq!(screen.title.as_deref(), Some("agent"));
+
+        let command = terminal
+            .foreground_command
+            .expect("Command should be captured");
+        assert!(!command.contains("abc123"));
+        assert!(command.contains("./deploy.sh"));
+        assert_eq!(terminal.cwd.as_deref(), Some("/home/me/project"));
+    }
+
     #[test]
     fn test_lock_timeout_scenario() {
         // This test simulates lock contention by holding a lock manually
@@ -71,6 +71,29 @@ pub struct TerminalContext {
     /// Path to terminal tail file (sanitized, for large tails)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tail_path: Option<String>,
+    /// Rendered screen at capture time (sanitized), for TUIs that redraw in place
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screen: Option<TerminalScreen>,
+    /// Command running in the foreground at capture time (sanitized)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub foreground_command: Option<String>,
+    /// Working directory of the foreground process at capture time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+}
+
+/// What was visible on the terminal screen at capture time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TerminalScreen {
+    /// Screen rows top to bottom, trailing blanks trimmed
+    pub lines: Vec<String>,
+    /// Zero-based cursor row
+    pub cursor_row: u16,
+    /// Zero-based cursor column
+    pub cursor_col: u16,
+    /// Window title (if the program set one)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
 }
 
 /// Context snapshot (v1 schema)
@@ -197,6 +220,14 @@ mod tests {
             }),
             tail_inline: Some("$ cargo build\n   Compiling...\n   Finished".to_string()),
             tail_path: None,
+            screen: Some(TerminalScreen {
+                lines: vec!["$ cargo build".to_string(), "   Finished".to_string()],
+                cursor_row: 1,
+                cursor_col: 11,
+                title: Some("cargo".to_string()),
+            }),
+            foreground_command: Some("cargo build".to_string()),
+            cwd: Some("/home/me/project".to_string()),
         });
 
         snapshot.user_note = Some("Remember to update docs".to_string());
@@ -402,6 +402,9 @@ mod tests {
                 exit_code: Some(0),
                 last_attention: None,
                 tail: "Build succeeded".to_string(),
+                screen: None,
+                foreground_command: None,
+                cwd: None,
             },
         );
 
@@ -28,7 +28,7 @@ const CLEANUP_AGE_THRESHOLD: Duration = Duration::from_secs(3600);
 const LOCK_TIMEOUT: Duration = Duration::from_millis(500);
 
 /// Default retention count: last N snapshots per task
-const DEFAULT_RETENTION_COUNT: usize = 5;
+pub const DEFAULT_RETENTION_COUNT: usize = 5;
 
 /// Snapshot store for Context Resurrection
 ///
@@ -1017,6 +1017,9 @@ mod tests {
             }),
             tail_inline: None,
             tail_path: Some(tail_file_path.to_string_lossy().to_string()),
+            screen: None,
+            foreground_command: None,
+            cwd: None,
         });
 
         // Write the tail file initially
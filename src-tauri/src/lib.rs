@@ -207,13 +207,17 @@ fn toggle_mini_os_specific_styling(window: tauri::Window, mini: bool) {
 
 #[cfg(unix)]
 #[tauri::command]
-fn session_list(project_path: Option<String>) -> Result<Vec<session::protocol::Session>, String> {
-    use session::daemon_client::{response_to_result, send_request};
+async fn session_list(
+    project_path: Option<String>,
+) -> Result<Vec<session::protocol::Session>, String> {
+    use session::daemon_client::{response_to_result, send_shared_request};
     use session::protocol::{DaemonRequest, DaemonResponse};
 
     let request = DaemonRequest::List { project_path };
 
-    let response = send_request(request).map_err(|e| e.to_string())?;
+    let response = send_shared_request(request)
+        .await
+        .map_err(|e| e.to_string())?;
 
     response_to_result(response, |r| {
         if let DaemonResponse::SessionList { sessions } = r {
@@ -226,13 +230,14 @@ fn session_list(project_path: Option<String>) -> Result<Vec<session::protocol::S
 
 #[cfg(unix)]
 #[tauri::command]
-fn session_start(
+async fn session_start(
     task_key: String,
     task_id: Option<String>,
     project_path: String,
     shell: Option<Vec<String>>,
+    record: Option<bool>,
 ) -> Result<session::protocol::Session, String> {
-    use session::daemon_client::{response_to_result, send_request};
+    use session::daemon_client::{response_to_result, send_shared_request};
     use session::protocol::{DaemonRequest, DaemonResponse};
 
     let request = DaemonRequest::Start {
@@ -240,9 +245,15 @@ fn session_start(
         task_id,
         project_path,
         shell,
+        cwd: None,
+        env: Default::default(),
+        record: record.unwrap_or(false),
+        attention_profiles: Vec::new(),
     };
 
-    let response = send_request(request).map_err(|e| e.to_string())?;
+    let response = send_shared_request(request)
+        .await
+        .map_err(|e| e.to_string())?;
 
     response_to_result(response, |r| {
         if let DaemonResponse::SessionStarted { session } = r {
@@ -255,13 +266,15 @@ fn session_start(
 
 #[cfg(unix)]
 #[tauri::command]
-fn session_stop(session_id: u64) -> Result<session::protocol::Session, String> {
-    use session::daemon_client::{response_to_result, send_request};
+async fn session_stop(session_id: u64) -> Result<session::protocol::Session, String> {
+    use session::daemon_client::{response_to_result, send_shared_request};
     use session::protocol::{DaemonRequest, DaemonResponse};
 
     let request = DaemonRequest::Stop { session_id };
 
-    let response = send_request(request).map_err(|e| e.to_string())?;
+    let response = send_shared_request(request)
+        .await
+        .map_err(|e| e.to_string())?;
 
     response_to_result(response, |r| {
         if let DaemonResponse::SessionStopped { session } = r {
@@ -274,11 +287,32 @@ fn session_stop(session_id: u64) -> Result<session::protocol::Session, String> {
 
 #[cfg(unix)]
 #[tauri::command]
-fn session_continue(
+async fn session_restart(session_id: u64) -> Result<session::protocol::Session, String> {
+    use session::daemon_client::{response_to_result, send_shared_request};
+    use session::protocol::{DaemonRequest, DaemonResponse};
+
+    let request = DaemonRequest::Restart { session_id };
+
+    let response = send_shared_request(request)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    response_to_result(response, |r| {
+        if let DaemonResponse::SessionRestarted { session } = r {
+            Some(session)
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(unix)]
+#[tauri::command]
+async fn session_continue(
     session_id: u64,
     tail_bytes: Option<usize>,
 ) -> Result<serde_json::Value, String> {
-    use session::daemon_client::{response_to_result, send_request};
+    use session::daemon_client::{response_to_result, send_shared_request};
     use session::protocol::{DaemonRequest, DaemonResponse};
 
     let request = DaemonRequest::Continue {
@@ -286,7 +320,9 @@ fn session_continue(
         tail_bytes,
     };
 
-    let response = send_request(request).map_err(|e| e.to_string())?;
+    let response = send_shared_request(request)
+        .await
+        .map_err(|e| e.to_string())?;
 
     response_to_result(response, |r| {
         if let DaemonResponse::SessionContinued { session, tail } = r {
@@ -302,10 +338,131 @@ fn session_continue(
 
 #[cfg(unix)]
 #[tauri::command]
-fn cr_request(
+async fn session_send_input(
+    session_id: u64,
+    data: String,
+    append_newline: bool,
+) -> Result<usize, String> {
+    use session::daemon_client::{response_to_result, send_shared_request};
+    use session::protocol::{DaemonRequest, DaemonResponse};
+
+    let request = DaemonRequest::SendInput {
+        session_id,
+        data,
+        append_newline,
+    };
+
+    let response = send_shared_request(request)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    response_to_result(response, |r| {
+        if let DaemonResponse::InputSent { bytes, .. } = r {
+            Some(bytes)
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(unix)]
+#[tauri::command]
+async fn session_attach(
+    session_id: u64,
+    tail_bytes: Option<usize>,
+    mode: Option<session::protocol::AttachMode>,
+    on_frame: tauri::ipc::Channel<session::protocol::StreamFrame>,
+) -> Result<serde_json::Value, String> {
+    use session::daemon_client::{response_to_result, shared_client};
+    use session::protocol::{DaemonResponse, StreamFrame};
+
+    // Streams share the app's one daemon connection
+    let client = shared_client().await.map_err(|e| e.to_string())?;
+    let (response, mut frames) = client
+        .open_stream(session_id, tail_bytes, None, mode.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let opened = response_to_result(response, |r| {
+        if let DaemonResponse::StreamOpened {
+            session,
+            tail,
+            screen,
+            ..
+        } = r
+        {
+            Some(serde_json::json!({
+                "session": session,
+                "tail": tail,
+                "screen": screen
+            }))
+        } else {
+            None
+        }
+    })?;
+
+    tauri::async_runtime::spawn(async move {
+        while let Some(frame) = frames.recv().await {
+            let closed = matches!(frame, StreamFrame::Close { .. });
+            if on_frame.send(frame).is_err() || closed {
+                return;
+            }
+        }
+        // The connection went away without the daemon closing the stream
+        let _ = on_frame.send(StreamFrame::Close {
+            session_id,
+            exited: false,
+            exit_code: None,
+            message: Some("Lost connection to daemon".to_string()),
+        });
+    });
+    Ok(opened)
+}
+
+#[cfg(unix)]
+async fn send_stream_frame(frame: session::protocol::StreamFrame) -> Result<(), String> {
+    let client = session::daemon_client::shared_client()
+        .await
+        .map_err(|e| e.to_string())?;
+    client
+        .send_stream_frame(&frame)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(unix)]
+#[tauri::command]
+async fn session_stream_input(session_id: u64, data: String) -> Result<(), String> {
+    send_stream_frame(session::protocol::StreamFrame::Data {
+        session_id,
+        data: data.into_bytes(),
+    })
+    .await
+}
+
+#[cfg(unix)]
+#[tauri::command]
+async fn session_stream_resize(session_id: u64, cols: u16, rows: u16) -> Result<(), String> {
+    send_stream_frame(session::protocol::StreamFrame::Resize {
+        session_id,
+        cols,
+        rows,
+    })
+    .await
+}
+
+#[cfg(unix)]
+#[tauri::command]
+async fn session_detach(session_id: u64) -> Result<(), String> {
+    send_stream_frame(session::protocol::StreamFrame::detach(session_id)).await
+}
+
+#[cfg(unix)]
+#[tauri::command]
+async fn cr_request(
     request: session::protocol::DaemonRequest,
 ) -> Result<session::protocol::DaemonResponse, String> {
-    use session::daemon_client::send_request;
+    use session::daemon_client::send_shared_request;
     use session::protocol::DaemonRequest;
 
     match &request {
@@ -320,7 +477,9 @@ fn cr_request(
         }
     }
 
-    send_request(request).map_err(|e| e.to_string())
+    send_shared_request(request)
+        .await
+        .map_err(|e| e.to_string())
 }
 
 #[cfg(all(unix, test))]
@@ -403,6 +562,10 @@ mod cr_request_tests {
             task_id: Some("test.task".to_string()),
             project_path: "/test/TODO.md".to_string(),
             shell: None,
+            cwd: None,
+            env: Default::default(),
+            record: false,
+            attention_profiles: Vec::new(),
         };
         assert!(!is_allowed(&req));
     }
@@ -443,12 +606,57 @@ fn session_stop(_session_id: u64) -> Result<(), String> {
     Err("Session management not yet supported on this platform".to_string())
 }
 
+#[cfg(not(unix))]
+#[tauri::command]
+fn session_restart(_session_id: u64) -> Result<(), String> {
+    Err("Session management not yet supported on this platform".to_string())
+}
+
 #[cfg(not(unix))]
 #[tauri::command]
 fn session_continue(_session_id: u64, _tail_bytes: Option<usize>) -> Result<(), String> {
     Err("Session management not yet supported on this platform".to_string())
 }
 
+#[cfg(not(unix))]
+#[tauri::command]
+fn session_send_input(
+    _session_id: u64,
+    _data: String,
+    _append_newline: bool,
+) -> Result<usize, String> {
+    Err("Session management not yet supported on this platform".to_string())
+}
+
+#[cfg(not(unix))]
+#[tauri::command]
+fn session_attach(
+    _session_id: u64,
+    _tail_bytes: Option<usize>,
+    _mode: Option<session::protocol::AttachMode>,
+    _on_frame: tauri::ipc::Channel<session::protocol::StreamFrame>,
+) -> Result<(), String> {
+    Err("Session management not yet supported on this platform".to_string())
+}
+
+#[cfg(not(unix))]
+#[tauri::command]
+fn session_stream_input(_session_id: u64, _data: String) -> Result<(), String> {
+    Err("Session management not yet supported on this platform".to_string())
+}
+
+#[cfg(not(unix))]
+#[tauri::command]
+fn session_stream_resize(_session_id: u64, _cols: u16, _rows: u16) -> Result<(), String> {
+    Err("Session management not yet supported on this platform".to_string())
+}
+
+#[cfg(not(unix))]
+#[tauri::command]
+fn session_detach(_session_id: u64) -> Result<(), String> {
+    Err("Session management not yet supported on this platform".to_string())
+}
+
 #[cfg(not(unix))]
 #[tauri::command]
 fn cr_request(
@@ -711,7 +919,13 @@ pub fn run() {
             session_list,
             session_start,
             session_stop,
+            session_restart,
             session_continue,
+            session_send_input,
+            session_attach,
+            session_stream_input,
+            session_stream_resize,
+            session_detach,
             cr_request
         ])
         .run(tauri::generate_context!())
@@ -802,7 +1016,13 @@ pub fn create_test_harness_builder() -> tauri::Builder<tauri::Wry> {
             session_list,
             session_start,
             session_stop,
+            session_restart,
             session_continue,
+            session_send_input,
+            session_attach,
+            session_stream_input,
+            session_stream_resize,
+            session_detach,
             cr_request,
             // Test harness commands
             test_harness::test_create_temp_dir,
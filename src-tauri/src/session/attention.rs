@@ -2,19 +2,28 @@
 //!
 //! Scans PTY output for patterns that indicate user attention is needed
 //! (prompts, errors, completion messages) and extracts preview context.
+//! Users can add their own profiles in `attention.toml`, merged with the
+//! defaults. A session is checked against the profiles it was started with,
+//! or else against those whose activation pattern matches its foreground
+//! command (and those without one). Triggers can also require SGR styling,
+//! e.g. red bold text, which is tracked while escape codes are stripped.
 //!
 //! See `docs/attention-notifications.md` for architecture overview.
 
 use crate::session::protocol::AttentionType;
 use once_cell::sync::Lazy;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
 use std::{borrow::Cow, ops::Range};
 
 /// Default attention profiles compiled lazily
 static DEFAULT_PROFILES: Lazy<Vec<AttentionProfile>> = Lazy::new(|| {
     vec![
         AttentionProfile {
-            name: "claude-code",
+            name: "claude-code".to_string(),
+            activation: Some(Regex::new(r"^claude\b").expect("failed to compile activation")),
             triggers: vec![
                 AttentionTrigger::literal(
                     "✔ Submit",
@@ -40,13 +49,15 @@ static DEFAULT_PROFILES: Lazy<Vec<AttentionProfile>> = Lazy::new(|| {
             ],
         },
         AttentionProfile {
-            name: "build-tools",
+            name: "build-tools".to_string(),
+            activation: None,
             triggers: vec![
                 AttentionTrigger::regex(
                     r"(?i)build (succeeded|complete|passed)",
                     AttentionType::Completed,
                     PreviewStrategy::LastLines(5),
-                ),
+                )
+                .expect("failed to compile attention matcher"),
                 AttentionTrigger::regex(
                     r"(?i)(error|failed|failure):",
                     AttentionType::Error,
@@ -54,7 +65,8 @@ static DEFAULT_PROFILES: Lazy<Vec<AttentionProfile>> = Lazy::new(|| {
                         before: 0,
                         after: 80,
                     },
-                ),
+                )
+                .expect("failed to compile attention matcher"),
             ],
         },
     ]
@@ -63,19 +75,25 @@ static DEFAULT_PROFILES: Lazy<Vec<AttentionProfile>> = Lazy::new(|| {
 /// Definition of triggers for a set of related commands/tools.
 #[derive(Debug, Clone)]
 pub struct AttentionProfile {
-    pub name: &'static str,
+    pub name: String,
+    /// Only check sessions whose foreground command matches this (see
+    /// `ProfileSelection`); `None` checks every session
+    pub activation: Option<Regex>,
     pub triggers: Vec<AttentionTrigger>,
 }
 
 #[derive(Debug, Clone)]
 pub struct AttentionTrigger {
-    matcher: Regex,
+    /// `None` for style-only triggers
+    matcher: Option<Regex>,
+    style: Option<StyleFilter>,
     attention_type: AttentionType,
     preview: PreviewStrategy,
 }
 
 impl AttentionTrigger {
-    fn literal(
+    /// Trigger on an exact string, ignoring case when `case_insensitive`
+    pub fn literal(
         pattern: &str,
         case_insensitive: bool,
         attention_type: AttentionType,
@@ -87,23 +105,222 @@ impl AttentionTrigger {
         }
         let regex = Regex::new(&escaped).expect("failed to compile literal attention matcher");
         Self {
-            matcher: regex,
+            matcher: Some(regex),
+            style: None,
             attention_type,
             preview,
         }
     }
 
-    fn regex(pattern: &str, attention_type: AttentionType, preview: PreviewStrategy) -> Self {
-        let regex = Regex::new(pattern).expect("failed to compile attention matcher");
+    /// Trigger on a regular expression (use `(?i)` to ignore case)
+    pub fn regex(
+        pattern: &str,
+        attention_type: AttentionType,
+        preview: PreviewStrategy,
+    ) -> Result<Self, regex::Error> {
+        Ok(Self {
+            matcher: Some(Regex::new(pattern)?),
+            style: None,
+            attention_type,
+            preview,
+        })
+    }
+
+    /// Trigger on visible text styled as `style`, e.g. a reverse-video line,
+    /// once something unstyled follows it
+    pub fn styled(
+        style: StyleFilter,
+        attention_type: AttentionType,
+        preview: PreviewStrategy,
+    ) -> Self {
         Self {
-            matcher: regex,
+            matcher: None,
+            style: Some(style),
             attention_type,
             preview,
         }
     }
+
+    /// Only match text styled as `style`; the pattern is searched within
+    /// each stretch of such text
+    pub fn with_style(mut self, style: StyleFilter) -> Self {
+        self.style = Some(style);
+        self
+    }
+
+    pub fn attention_type(&self) -> AttentionType {
+        self.attention_type
+    }
+
+    /// Where the trigger matches in stripped output; `spans` are the output's
+    /// styles, needed only by styled triggers
+    fn find(&self, haystack: &str, spans: Option<&[StyleSpan]>) -> Option<Range<usize>> {
+        let Some(style) = &self.style else {
+            return self
+                .matcher
+                .as_ref()?
+                .find(haystack)
+                .map(|found| found.range());
+        };
+
+        // Search each maximal stretch of text with the required style
+        let mut spans = spans?
+            .iter()
+            .filter(|span| style.matches(&span.style))
+            .peekable();
+        while let Some(first) = spans.next() {
+            let mut run = first.range.clone();
+            while let Some(next) = spans.next_if(|span| span.range.start == run.end) {
+                run.end = next.range.end;
+            }
+            let text = &haystack[run.clone()];
+            match &self.matcher {
+                Some(matcher) => {
+                    if let Some(found) = matcher.find(text) {
+                        return Some(run.start + found.start()..run.start + found.end());
+                    }
+                }
+                // Wait for the stretch to end, so streamed output matches all of it
+                None if run.end < haystack.len() && !text.trim().is_empty() => return Some(run),
+                None => {}
+            }
+        }
+        None
+    }
 }
 
-#[derive(Debug, Clone)]
+/// Text attributes set by SGR escape sequences (`ESC [ ... m`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextStyle {
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub reverse: bool,
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+}
+
+/// A foreground or background color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    /// Palette index: 0-7 are the basic colors, 8-15 their bright variants
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl TextStyle {
+    /// Apply the parameters of an SGR sequence, e.g. `1;31` or `38;5;208`
+    fn apply_sgr(&mut self, params: &str) {
+        let mut codes = params
+            .split([';', ':'])
+            .map(|code| code.parse::<u16>().unwrap_or(0));
+        while let Some(code) = codes.next() {
+            match code {
+                0 => *self = TextStyle::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                7 => self.reverse = true,
+                22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                23 => self.italic = false,
+                24 => self.underline = false,
+                27 => self.reverse = false,
+                30..=37 => self.fg = Some(Color::Indexed((code - 30) as u8)),
+                38 => self.fg = extended_color(&mut codes),
+                39 => self.fg = None,
+                40..=47 => self.bg = Some(Color::Indexed((code - 40) as u8)),
+                48 => self.bg = extended_color(&mut codes),
+                49 => self.bg = None,
+                90..=97 => self.fg = Some(Color::Indexed((code - 90 + 8) as u8)),
+                100..=107 => self.bg = Some(Color::Indexed((code - 100 + 8) as u8)),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// The color after a 38 or 48: `5;<index>` or `2;<r>;<g>;<b>`
+fn extended_color(codes: &mut impl Iterator<Item = u16>) -> Option<Color> {
+    let mut next = || codes.next().map(|code| code.min(255) as u8);
+    match next()? {
+        5 => Some(Color::Indexed(next()?)),
+        2 => Some(Color::Rgb(next()?, next()?, next()?)),
+        _ => None,
+    }
+}
+
+/// The basic terminal colors, as named in `attention.toml`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorName {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl ColorName {
+    /// Whether `color` is this color or its bright variant (also via the
+    /// 256-color palette); RGB colors never match a name
+    fn matches(self, color: Option<Color>) -> bool {
+        match color {
+            Some(Color::Indexed(index)) if index < 16 => index % 8 == self as u8,
+            _ => false,
+        }
+    }
+}
+
+/// Styling a trigger requires, as written in `attention.toml`:
+/// `style = { fg = "red", bold = true }`. Unset attributes don't matter.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StyleFilter {
+    pub bold: Option<bool>,
+    pub dim: Option<bool>,
+    pub italic: Option<bool>,
+    pub underline: Option<bool>,
+    pub reverse: Option<bool>,
+    pub fg: Option<ColorName>,
+    pub bg: Option<ColorName>,
+}
+
+impl StyleFilter {
+    pub fn matches(&self, style: &TextStyle) -> bool {
+        let flag = |wanted: Option<bool>, actual: bool| wanted.is_none_or(|w| w == actual);
+        flag(self.bold, style.bold)
+            && flag(self.dim, style.dim)
+            && flag(self.italic, style.italic)
+            && flag(self.underline, style.underline)
+            && flag(self.reverse, style.reverse)
+            && self.fg.is_none_or(|name| name.matches(style.fg))
+            && self.bg.is_none_or(|name| name.matches(style.bg))
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == StyleFilter::default()
+    }
+}
+
+/// Bytes of stripped output that share a style
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyleSpan {
+    pub range: Range<usize>,
+    pub style: TextStyle,
+}
+
+/// How a trigger's preview is cut from the output, as written in
+/// `attention.toml`: `{ last_lines = 3 }` or `{ surround = { before = 40, after = 0 } }`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PreviewStrategy {
     /// Use the last N lines of output.
     LastLines(usize),
@@ -137,12 +354,22 @@ impl PreviewStrategy {
     }
 }
 
+impl Default for PreviewStrategy {
+    fn default() -> Self {
+        PreviewStrategy::LastLines(3)
+    }
+}
+
 /// Result of matching a profile.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct AttentionMatch {
-    pub profile: &'static str,
+    pub profile: String,
     pub attention_type: AttentionType,
     pub preview: String,
+    /// Raw output the match was found in: the whole text for `detect_*`, or
+    /// the stream offsets of an accumulator's window, ending with the byte
+    /// that completed the match
+    pub bytes: Range<u64>,
 }
 
 /// Returns the compiled default profiles.
@@ -150,26 +377,329 @@ pub fn default_profiles() -> &'static [AttentionProfile] {
     &DEFAULT_PROFILES
 }
 
-/// Attempts to detect an attention event within the provided chunk of text.
+/// Profiles in effect, shared by the accumulators checking against them
+///
+/// Clones share one set: replacing it changes what every clone's
+/// accumulators check from their next chunk on.
+#[derive(Debug, Clone)]
+pub struct ProfileSet(Arc<RwLock<Arc<Vec<AttentionProfile>>>>);
+
+impl ProfileSet {
+    pub fn new(profiles: Vec<AttentionProfile>) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(profiles))))
+    }
+
+    /// The profiles in effect
+    pub fn current(&self) -> Arc<Vec<AttentionProfile>> {
+        Arc::clone(&self.0.read().unwrap())
+    }
+
+    /// Put other profiles into effect
+    pub fn replace(&self, profiles: Vec<AttentionProfile>) {
+        *self.0.write().unwrap() = Arc::new(profiles);
+    }
+}
+
+impl Default for ProfileSet {
+    /// Just the defaults
+    fn default() -> Self {
+        Self::new(DEFAULT_PROFILES.clone())
+    }
+}
+
+/// Which profiles check a session's output
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ProfileSelection {
+    /// Every profile
+    #[default]
+    All,
+    /// Only the named profiles, as bound with `Start { attention_profiles }`
+    Named(Vec<String>),
+    /// Profiles without an activation pattern, plus those whose pattern
+    /// matches this foreground command line (if known)
+    Foreground(Option<String>),
+}
+
+impl ProfileSelection {
+    /// The profiles a session is bound to or, if none, those picked by its
+    /// foreground command
+    pub fn for_session(bound: &[String], foreground_command: Option<&str>) -> Self {
+        if bound.is_empty() {
+            ProfileSelection::Foreground(foreground_command.map(str::to_string))
+        } else {
+            ProfileSelection::Named(bound.to_vec())
+        }
+    }
+
+    pub fn includes(&self, profile: &AttentionProfile) -> bool {
+        match self {
+            ProfileSelection::All => true,
+            ProfileSelection::Named(names) => names.contains(&profile.name),
+            ProfileSelection::Foreground(command) => match &profile.activation {
+                None => true,
+                Some(activation) => command
+                    .as_deref()
+                    .is_some_and(|command| activation.is_match(&activation_subject(command))),
+            },
+        }
+    }
+
+    /// The selected profiles, in order
+    pub fn select(&self, profiles: &[AttentionProfile]) -> Vec<AttentionProfile> {
+        profiles
+            .iter()
+            .filter(|profile| self.includes(profile))
+            .cloned()
+            .collect()
+    }
+}
+
+/// What activation patterns are matched against: the command line with the
+/// program reduced to its file name (`/usr/bin/python3 -m pytest` becomes
+/// `python3 -m pytest`, a login shell's `-zsh` becomes `zsh`)
+fn activation_subject(command: &str) -> String {
+    let command = command.trim_start();
+    let (program, args) = command
+        .split_once(char::is_whitespace)
+        .unwrap_or((command, ""));
+    let program = program.rsplit('/').next().unwrap_or(program);
+    let program = program.strip_prefix('-').unwrap_or(program);
+    if args.is_empty() {
+        program.to_string()
+    } else {
+        format!("{program} {args}")
+    }
+}
+
+/// A problem with `attention.toml`
+#[derive(Debug, thiserror::Error)]
+pub enum ProfileError {
+    #[error("Failed to read {}: {source}", path.display())]
+    Io {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Malformed attention profiles: {0}")]
+    Parse(String),
+    #[error("Attention profile '{profile}': {message}")]
+    InvalidProfile { profile: String, message: String },
+    #[error("Attention profile '{profile}': invalid regex `{pattern}`: {message}")]
+    InvalidPattern {
+        profile: String,
+        pattern: String,
+        message: String,
+    },
+}
+
+/// `attention.toml` as written by the user
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfilesFile {
+    #[serde(default)]
+    profile: Vec<ProfileSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileSpec {
+    name: String,
+    /// Regex for the foreground commands the profile applies to
+    activate: Option<String>,
+    #[serde(default)]
+    trigger: Vec<TriggerSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TriggerSpec {
+    literal: Option<String>,
+    regex: Option<String>,
+    /// Only for `literal`; regexes say `(?i)` themselves
+    #[serde(default)]
+    case_insensitive: bool,
+    /// Only match text with this styling; without a pattern, any such text
+    style: Option<StyleFilter>,
+    #[serde(rename = "type")]
+    attention_type: AttentionType,
+    #[serde(default)]
+    preview: PreviewStrategy,
+}
+
+impl ProfileSpec {
+    fn compile(self) -> Result<AttentionProfile, ProfileError> {
+        let invalid = |message: &str| ProfileError::InvalidProfile {
+            profile: self.name.clone(),
+            message: message.to_string(),
+        };
+        if self.name.trim().is_empty() {
+            return Err(invalid("name must not be empty"));
+        }
+        if self.trigger.is_empty() {
+            return Err(invalid("needs at least one [[profile.trigger]]"));
+        }
+
+        let mut triggers = Vec::with_capacity(self.trigger.len());
+        for trigger in &self.trigger {
+            let compiled = match (&trigger.literal, &trigger.regex) {
+                (Some(literal), None) if !literal.is_empty() => AttentionTrigger::literal(
+                    literal,
+                    trigger.case_insensitive,
+                    trigger.attention_type,
+                    trigger.preview.clone(),
+                ),
+                (None, Some(pattern)) if trigger.case_insensitive => {
+                    return Err(invalid(&format!(
+                        "case_insensitive only applies to literals; start `{}` with (?i) instead",
+                        pattern
+                    )))
+                }
+                (None, Some(pattern)) => AttentionTrigger::regex(
+                    pattern,
+                    trigger.attention_type,
+                    trigger.preview.clone(),
+                )
+                .map_err(|e| ProfileError::InvalidPattern {
+                    profile: self.name.clone(),
+                    pattern: pattern.clone(),
+                    message: e.to_string(),
+                })?,
+                (Some(_), None) => return Err(invalid("literal must not be empty")),
+                (None, None) if trigger.style.is_some() => AttentionTrigger {
+                    matcher: None,
+                    style: None,
+                    attention_type: trigger.attention_type,
+                    preview: trigger.preview.clone(),
+                },
+                _ => {
+                    return Err(invalid(
+                        "each trigger needs exactly one of literal or regex (or only a style)",
+                    ))
+                }
+            };
+            if let Some(matcher) = compiled.matcher.as_ref().filter(|m| m.is_match("")) {
+                return Err(ProfileError::InvalidPattern {
+                    profile: self.name.clone(),
+                    pattern: matcher.as_str().to_string(),
+                    message: "matches empty output, so it would fire on everything".to_string(),
+                });
+            }
+            let compiled = match &trigger.style {
+                Some(style) if style.is_empty() => {
+                    return Err(invalid("style needs at least one attribute"))
+                }
+                Some(style) => compiled.with_style(style.clone()),
+                None => compiled,
+            };
+            triggers.push(compiled);
+        }
+
+        let activation = match &self.activate {
+            Some(pattern) => {
+                Some(
+                    Regex::new(pattern).map_err(|e| ProfileError::InvalidPattern {
+                        profile: self.name.clone(),
+                        pattern: pattern.clone(),
+                        message: e.to_string(),
+                    })?,
+                )
+            }
+            None => None,
+        };
+
+        Ok(AttentionProfile {
+            name: self.name,
+            activation,
+            triggers,
+        })
+    }
+}
+
+/// Parse `attention.toml` and merge its profiles with the defaults
+///
+/// A profile named like a default replaces it; the others are checked
+/// before the defaults, so specific triggers win over generic ones.
+pub fn parse_profiles(text: &str) -> Result<Vec<AttentionProfile>, ProfileError> {
+    let file: ProfilesFile =
+        toml::from_str(text).map_err(|e| ProfileError::Parse(e.to_string()))?;
+
+    let mut user: Vec<AttentionProfile> = Vec::with_capacity(file.profile.len());
+    for spec in file.profile {
+        if user.iter().any(|profile| profile.name == spec.name) {
+            return Err(ProfileError::InvalidProfile {
+                profile: spec.name,
+                message: "defined more than once".to_string(),
+            });
+        }
+        user.push(spec.compile()?);
+    }
+
+    let mut merged = Vec::with_capacity(user.len() + DEFAULT_PROFILES.len());
+    let mut replacements = Vec::new();
+    for profile in user {
+        if DEFAULT_PROFILES
+            .iter()
+            .any(|default| default.name == profile.name)
+        {
+            replacements.push(profile);
+        } else {
+            merged.push(profile);
+        }
+    }
+    for default in DEFAULT_PROFILES.iter() {
+        match replacements.iter().position(|p| p.name == default.name) {
+            Some(index) => merged.push(replacements.remove(index)),
+            None => merged.push(default.clone()),
+        }
+    }
+    Ok(merged)
+}
+
+/// Load the profiles in `path` merged with the defaults (just the defaults
+/// when there is no such file)
+pub fn load_profiles(path: &Path) -> Result<Vec<AttentionProfile>, ProfileError> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => parse_profiles(&text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(DEFAULT_PROFILES.clone()),
+        Err(source) => Err(ProfileError::Io {
+            path: path.to_path_buf(),
+            source,
+        }),
+    }
+}
+
+/// Attempts to detect an attention event within the provided chunk of text,
+/// using the default profiles.
 pub fn detect_attention(text: &str) -> Option<AttentionMatch> {
-    let sanitized = sanitize_text(text);
+    detect_with_profiles(&DEFAULT_PROFILES, text)
+}
+
+/// Like `detect_attention`, with the given profiles instead of the defaults
+pub fn detect_with_profiles(profiles: &[AttentionProfile], text: &str) -> Option<AttentionMatch> {
+    // Styles are only worth tracking for styled triggers
+    let styled = profiles
+        .iter()
+        .flat_map(|profile| &profile.triggers)
+        .any(|trigger| trigger.style.is_some());
+    let (sanitized, spans) = if styled {
+        let (stripped, spans) = strip_ansi_styled(text);
+        (Cow::Owned(stripped), Some(spans))
+    } else {
+        (sanitize_text(text), None)
+    };
     let haystack = sanitized.as_ref();
 
-    for profile in default_profiles() {
+    for profile in profiles {
         for trigger in &profile.triggers {
-            if let Some(found) = trigger.matcher.find(haystack) {
-                let preview = trigger
-                    .preview
-                    .render(haystack, found.start()..found.end())
-                    .trim()
-                    .to_string();
+            if let Some(found) = trigger.find(haystack, spans.as_deref()) {
+                let preview = trigger.preview.render(haystack, found).trim().to_string();
                 if preview.is_empty() {
                     continue;
                 }
                 return Some(AttentionMatch {
-                    profile: profile.name,
+                    profile: profile.name.clone(),
                     attention_type: trigger.attention_type,
                     preview,
+                    bytes: 0..text.len() as u64,
                 });
             }
         }
@@ -186,6 +716,10 @@ pub const ATTENTION_WINDOW_BYTES: usize = 8 * 1024;
 pub struct AttentionAccumulator {
     buffer: Vec<u8>,
     max_bytes: usize,
+    profiles: ProfileSet,
+    selection: ProfileSelection,
+    /// Bytes pushed so far
+    consumed: u64,
 }
 
 impl AttentionAccumulator {
@@ -193,17 +727,41 @@ impl AttentionAccumulator {
         Self {
             buffer: Vec::with_capacity(max_bytes),
             max_bytes,
+            profiles: ProfileSet::default(),
+            selection: ProfileSelection::All,
+            consumed: 0,
         }
     }
 
+    /// Check against `profiles` (the defaults unless given)
+    pub fn with_profiles(mut self, profiles: ProfileSet) -> Self {
+        self.profiles = profiles;
+        self
+    }
+
+    /// Only check the selected profiles (every profile by default)
+    pub fn with_selection(mut self, selection: ProfileSelection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// Change the profiles checked from the next chunk on, e.g. when the
+    /// foreground command changes
+    pub fn set_selection(&mut self, selection: ProfileSelection) {
+        self.selection = selection;
+    }
+
     /// Push a new chunk of PTY bytes and return any matches that formed across
     /// this chunk boundary.
     pub fn push_chunk(&mut self, chunk: &[u8]) -> Vec<AttentionMatch> {
+        let profiles = self.selection.select(&self.profiles.current());
         let mut matches = Vec::new();
         for &byte in chunk {
             self.buffer.push(byte);
+            self.consumed += 1;
             self.trim_window();
-            if let Some(found) = self.detect_current() {
+            if let Some(mut found) = self.detect_current(&profiles) {
+                found.bytes = self.consumed - self.buffer.len() as u64..self.consumed;
                 matches.push(found);
                 self.buffer.clear();
             }
@@ -218,12 +776,12 @@ impl AttentionAccumulator {
         }
     }
 
-    fn detect_current(&self) -> Option<AttentionMatch> {
+    fn detect_current(&self, profiles: &[AttentionProfile]) -> Option<AttentionMatch> {
         if self.buffer.is_empty() {
             return None;
         }
         let text = String::from_utf8_lossy(&self.buffer);
-        detect_attention(&text)
+        detect_with_profiles(profiles, &text)
     }
 }
 
@@ -238,18 +796,66 @@ pub fn strip_ansi_codes(text: &str) -> String {
     if !contains_escape_sequences(text) {
         return text.to_string();
     }
+    strip(text, None)
+}
+
+/// Like `strip_ansi_codes`, also returning the style of the stripped text as
+/// set by the SGR sequences removed, in order and covering all of it
+pub fn strip_ansi_styled(text: &str) -> (String, Vec<StyleSpan>) {
+    let mut spans = Vec::new();
+    if !contains_escape_sequences(text) {
+        if !text.is_empty() {
+            spans.push(StyleSpan {
+                range: 0..text.len(),
+                style: TextStyle::default(),
+            });
+        }
+        return (text.to_string(), spans);
+    }
+    let stripped = strip(text, Some(&mut spans));
+    (stripped, spans)
+}
 
+fn strip(text: &str, mut spans: Option<&mut Vec<StyleSpan>>) -> String {
     let mut output = String::with_capacity(text.len());
     let mut chars = text.chars().peekable();
+    let mut style = TextStyle::default();
+    let mut params = String::new();
 
     while let Some(ch) = chars.next() {
-        match ch {
-            '\x1b' => handle_escape_sequence(&mut chars),
-            '\u{009b}' => skip_csi(&mut chars),
-            '\u{009d}' => skip_osc(&mut chars),
-            '\u{0090}' | '\u{0098}' => skip_st_terminated(&mut chars),
-            '\r' => {}
-            _ => output.push(ch),
+        let csi = match ch {
+            '\x1b' if chars.next_if_eq(&'[').is_some() => true,
+            '\u{009b}' => true,
+            '\x1b' => {
+                handle_escape_sequence(&mut chars);
+                false
+            }
+            '\u{009d}' => {
+                skip_osc(&mut chars);
+                false
+            }
+            '\u{0090}' | '\u{0098}' => {
+                skip_st_terminated(&mut chars);
+                false
+            }
+            '\r' => false,
+            _ => {
+                let start = output.len();
+                output.push(ch);
+                if let Some(spans) = spans.as_deref_mut() {
+                    match spans.last_mut() {
+                        Some(last) if last.style == style => last.range.end = output.len(),
+                        _ => spans.push(StyleSpan {
+                            range: start..output.len(),
+                            style,
+                        }),
+                    }
+                }
+                false
+            }
+        };
+        if csi && read_csi(&mut chars, &mut params) == Some('m') {
+            style.apply_sgr(&params);
         }
     }
 
@@ -274,7 +880,6 @@ type CharIter<'a> = std::iter::Peekable<std::str::Chars<'a>>;
 
 fn handle_escape_sequence(chars: &mut CharIter<'_>) {
     match chars.next() {
-        Some('[') => skip_csi(chars),
         Some(']') => skip_osc(chars),
         Some('P') | Some('X') | Some('^') | Some('_') => skip_st_terminated(chars),
         Some('%') | Some('(') | Some(')') | Some('*') | Some('+') | Some('-') | Some('.')
@@ -286,12 +891,16 @@ fn handle_escape_sequence(chars: &mut CharIter<'_>) {
     }
 }
 
-fn skip_csi(chars: &mut CharIter<'_>) {
-    while let Some(ch) = chars.next() {
+/// Consume a CSI sequence, collecting its parameters; returns the final byte
+fn read_csi(chars: &mut CharIter<'_>, params: &mut String) -> Option<char> {
+    params.clear();
+    for ch in chars.by_ref() {
         if ('@'..='~').contains(&ch) {
-            break;
+            return Some(ch);
         }
+        params.push(ch);
     }
+    None
 }
 
 fn skip_osc(chars: &mut CharIter<'_>) {
@@ -413,4 +1022,317 @@ mod tests {
         assert_eq!(matches[0].attention_type, AttentionType::Completed);
         assert_eq!(matches[1].attention_type, AttentionType::Error);
     }
+
+    #[test]
+    fn accumulator_reports_stream_offsets_across_chunks() {
+        let output = b"$ make\nBuild succeeded\n$ make test\nerror: 2 tests failed\n";
+        for chunk_size in [1, 7, output.len()] {
+            let mut acc = AttentionAccumulator::new(256);
+            let matches: Vec<AttentionMatch> = output
+                .chunks(chunk_size)
+                .flat_map(|chunk| acc.push_chunk(chunk))
+                .collect();
+            let ranges: Vec<Range<u64>> = matches.iter().map(|m| m.bytes.clone()).collect();
+            // Each window ends where its trigger completed and the next starts after it
+            assert_eq!(ranges, vec![0..22, 22..41], "chunks of {}", chunk_size);
+        }
+    }
+
+    const PYTEST_PROFILE: &str = r#"
+[[profile]]
+name = "pytest"
+
+[[profile.trigger]]
+regex = '=+ \d+ failed'
+type = "error"
+preview = { last_lines = 2 }
+
+[[profile.trigger]]
+literal = "ALL TESTS PASSED"
+case_insensitive = true
+type = "completed"
+preview = { surround = { before = 0, after = 20 } }
+"#;
+
+    #[test]
+    fn user_profiles_are_checked_before_defaults() {
+        let profiles = parse_profiles(PYTEST_PROFILE).unwrap();
+        let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["pytest", "claude-code", "build-tools"]);
+
+        let matched =
+            detect_with_profiles(&profiles, "collected 3\n===== 1 failed, 2 passed").unwrap();
+        assert_eq!(matched.profile, "pytest");
+        assert_eq!(matched.attention_type, AttentionType::Error);
+
+        let matched = detect_with_profiles(&profiles, "all tests passed in 2s").unwrap();
+        assert_eq!(matched.profile, "pytest");
+        assert_eq!(matched.preview, "all tests passed in 2s");
+
+        // Defaults still apply
+        let matched = detect_with_profiles(&profiles, "error: failed to compile").unwrap();
+        assert_eq!(matched.profile, "build-tools");
+    }
+
+    #[test]
+    fn user_profile_replaces_default_of_same_name() {
+        let profiles = parse_profiles(
+            "[[profile]]\nname = \"claude-code\"\n\n[[profile.trigger]]\nliteral = \"Do you want to proceed?\"\ntype = \"decision_point\"\n",
+        )
+        .unwrap();
+        let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["claude-code", "build-tools"]);
+        assert!(detect_with_profiles(&profiles, "✔ Submit").is_none());
+        assert!(detect_with_profiles(&profiles, "Do you want to proceed?").is_some());
+    }
+
+    #[test]
+    fn invalid_profiles_name_the_problem() {
+        let err = parse_profiles(
+            "[[profile]]\nname = \"deploy\"\n\n[[profile.trigger]]\nregex = \"deploy (done\"\ntype = \"completed\"\n",
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("'deploy': invalid regex `deploy (done`"),
+            "unexpected error: {}",
+            err
+        );
+
+        let err = parse_profiles(
+            "[[profile]]\nname = \"both\"\n\n[[profile.trigger]]\nliteral = \"a\"\nregex = \"b\"\ntype = \"error\"\n",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("exactly one of literal or regex"));
+
+        let err = parse_profiles(
+            "[[profile]]\nname = \"greedy\"\n\n[[profile.trigger]]\nregex = \".*\"\ntype = \"error\"\n",
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("`.*`"),
+            "unexpected error: {}",
+            err
+        );
+
+        let err = parse_profiles(
+            "[[profile]]\nname = \"typo\"\n\n[[profile.trigger]]\nliteral = \"x\"\ntype = \"urgent\"\n",
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, ProfileError::Parse(_)),
+            "unexpected error: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn foreground_command_activates_profiles() {
+        let profiles = default_profiles();
+        let names = |selection: ProfileSelection| -> Vec<String> {
+            selection
+                .select(profiles)
+                .into_iter()
+                .map(|p| p.name)
+                .collect()
+        };
+
+        // A plain shell prompt using ❯ doesn't fire claude-code
+        let shell = ProfileSelection::for_session(&[], Some("-zsh"));
+        assert_eq!(names(shell.clone()), vec!["build-tools"]);
+        assert!(detect_with_profiles(&shell.select(profiles), "~/src ❯ ").is_none());
+
+        let claude = ProfileSelection::for_session(&[], Some("/usr/local/bin/claude --resume"));
+        assert_eq!(names(claude.clone()), vec!["claude-code", "build-tools"]);
+        let matched = detect_with_profiles(&claude.select(profiles), "~/src ❯ ").unwrap();
+        assert_eq!(matched.profile, "claude-code");
+
+        assert_eq!(
+            names(ProfileSelection::for_session(&[], None)),
+            vec!["build-tools"]
+        );
+        assert_eq!(
+            names(ProfileSelection::for_session(
+                &["claude-code".to_string()],
+                Some("zsh")
+            )),
+            vec!["claude-code"]
+        );
+    }
+
+    #[test]
+    fn user_profiles_can_set_activation() {
+        // `activate` belongs to the profile, not a trigger
+        let err =
+            parse_profiles(&format!("{PYTEST_PROFILE}activate = '\\bpytest\\b'\n")).unwrap_err();
+        assert!(
+            matches!(err, ProfileError::Parse(_)),
+            "unexpected error: {}",
+            err
+        );
+
+        let profiles = parse_profiles(&PYTEST_PROFILE.replace(
+            "name = \"pytest\"\n",
+            "name = \"pytest\"\nactivate = '\\bpytest\\b'\n",
+        ))
+        .unwrap();
+        let selection = ProfileSelection::for_session(&[], Some("/usr/bin/python3 -m pytest -x"));
+        assert!(selection.includes(&profiles[0]));
+        assert!(!ProfileSelection::for_session(&[], Some("make test")).includes(&profiles[0]));
+
+        let err = parse_profiles(
+            "[[profile]]\nname = \"bad\"\nactivate = \"(\"\n\n[[profile.trigger]]\nliteral = \"x\"\ntype = \"error\"\n",
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("'bad': invalid regex `(`"),
+            "unexpected error: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn strip_ansi_styled_tracks_sgr_state() {
+        let output = "ok \x1b[1;31merror\x1b[22m:\x1b[0m x \x1b[7;38;5;9mbar\x1b[m";
+        let (text, spans) = strip_ansi_styled(output);
+        assert_eq!(text, strip_ansi_codes(output));
+        assert_eq!(text, "ok error: x bar");
+        let red = Some(Color::Indexed(1));
+        assert_eq!(
+            spans,
+            vec![
+                StyleSpan {
+                    range: 0..3,
+                    style: TextStyle::default()
+                },
+                StyleSpan {
+                    range: 3..8,
+                    style: TextStyle {
+                        bold: true,
+                        fg: red,
+                        ..TextStyle::default()
+                    }
+                },
+                StyleSpan {
+                    range: 8..9,
+                    style: TextStyle {
+                        fg: red,
+                        ..TextStyle::default()
+                    }
+                },
+                StyleSpan {
+                    range: 9..12,
+                    style: TextStyle::default()
+                },
+                StyleSpan {
+                    range: 12..15,
+                    style: TextStyle {
+                        reverse: true,
+                        fg: Some(Color::Indexed(9)),
+                        ..TextStyle::default()
+                    }
+                },
+            ]
+        );
+    }
+
+    const STYLED_PROFILE: &str = r#"
+[[profile]]
+name = "styled"
+
+[[profile.trigger]]
+regex = '(?i)error'
+style = { fg = "red", bold = true }
+type = "error"
+preview = { surround = { before = 0, after = 20 } }
+
+[[profile.trigger]]
+style = { reverse = true }
+type = "decision_point"
+"#;
+
+    #[test]
+    fn styled_triggers_match_on_emphasis_and_color() {
+        let profiles: Vec<AttentionProfile> = parse_profiles(STYLED_PROFILE)
+            .unwrap()
+            .into_iter()
+            .filter(|profile| profile.name == "styled")
+            .collect();
+
+        // The word alone in an ordinary log line doesn't count
+        assert!(
+            detect_with_profiles(&profiles, "INFO retrying after error budget reset\n").is_none()
+        );
+        assert!(detect_with_profiles(&profiles, "\x1b[31merror\x1b[0m: not bold\n").is_none());
+
+        let matched = detect_with_profiles(
+            &profiles,
+            "INFO all fine\n\x1b[1m\x1b[91mERROR\x1b[0m disk full\n",
+        )
+        .unwrap();
+        assert_eq!(matched.attention_type, AttentionType::Error);
+        assert_eq!(matched.preview, "ERROR disk full");
+
+        // A reverse-video line fires once it has ended
+        assert!(detect_with_profiles(&profiles, "\x1b[7m Allow this? (y/n) ").is_none());
+        let matched =
+            detect_with_profiles(&profiles, "menu\n\x1b[7m Allow this? (y/n) \x1b[27m\n").unwrap();
+        assert_eq!(matched.attention_type, AttentionType::DecisionPoint);
+
+        // Growing output, as an accumulator sees it, fires on the styled word
+        let stream = "log error line\n\x1b[1;31mError:\x1b[0m build broke\n";
+        let (end, matched) = (1..=stream.len())
+            .filter(|&end| stream.is_char_boundary(end))
+            .find_map(|end| detect_with_profiles(&profiles, &stream[..end]).map(|m| (end, m)))
+            .unwrap();
+        assert_eq!(&stream[..end], "log error line\n\x1b[1;31mError");
+        assert_eq!(matched.attention_type, AttentionType::Error);
+    }
+
+    #[test]
+    fn invalid_styles_are_rejected() {
+        let err = parse_profiles(
+            "[[profile]]\nname = \"s\"\n\n[[profile.trigger]]\nregex = \"x\"\nstyle = { fg = \"mauve\" }\ntype = \"error\"\n",
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, ProfileError::Parse(_)),
+            "unexpected error: {}",
+            err
+        );
+
+        let err = parse_profiles(
+            "[[profile]]\nname = \"s\"\n\n[[profile.trigger]]\nstyle = {}\ntype = \"error\"\n",
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("at least one attribute"),
+            "unexpected error: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn replaced_profiles_apply_only_to_their_set() {
+        let profiles = ProfileSet::default();
+        let mut watched = AttentionAccumulator::new(256).with_profiles(profiles.clone());
+        let mut other = AttentionAccumulator::new(256);
+
+        profiles.replace(
+            parse_profiles(
+                "[[profile]]\nname = \"deploy\"\n\n[[profile.trigger]]\nliteral = \"SHIPPED\"\ntype = \"completed\"\n",
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(watched.push_chunk(b"SHIPPED\n").len(), 1);
+        assert!(other.push_chunk(b"SHIPPED\n").is_empty());
+    }
+
+    #[test]
+    fn missing_profiles_file_means_defaults() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let profiles = load_profiles(&temp_dir.path().join("attention.toml")).unwrap();
+        assert_eq!(profiles.len(), default_profiles().len());
+    }
 }
@@ -1,7 +1,17 @@
 // Environment configuration helpers for the daemon
-// Handles platform-specific paths for sockets, PID files, and data directories
-
+// Handles platform-specific paths for sockets, PID files, and data directories,
+// and the daemon's tunables in `config.toml` under the state directory
+
+use crate::context_resurrection::store::DEFAULT_RETENTION_COUNT;
+use crate::session::notify::DEFAULT_DEBOUNCE;
+use crate::session::output_log::{LogLimits, DEFAULT_SEGMENT_BYTES};
+use crate::session::persistence;
+use crate::session::protocol::SessionId;
+use crate::session::recording;
+use crate::session::runtime::{RuntimeTuning, DEFAULT_IDLE_TIMEOUT, DEFAULT_OUTPUT_BUFFER_SIZE};
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// Configuration for daemon paths and settings
 #[derive(Debug, Clone)]
@@ -100,23 +110,139 @@ impl Config {
         self.state_dir.join("sessions.json")
     }
 
+    /// Get the config.toml file path
+    pub fn settings_file(&self) -> PathBuf {
+        self.state_dir.join("config.toml")
+    }
+
+    /// Get the attention.toml file path, where users declare attention profiles
+    pub fn attention_profiles_file(&self) -> PathBuf {
+        self.state_dir.join("attention.toml")
+    }
+
+    /// Load and validate config.toml, with defaults for anything it leaves out
+    /// (or all of it, when there is no file)
+    pub fn load_settings(&self) -> Result<Settings, SettingsError> {
+        let path = self.settings_file();
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Settings::parse(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Settings::default()),
+            Err(source) => Err(SettingsError::Io { path, source }),
+        }
+    }
+
+    /// Set one key in config.toml, keeping the others as they are
+    ///
+    /// Returns the settings now in effect for the file. Nothing is written
+    /// when the key or value is invalid.
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<Settings, SettingsError> {
+        let path = self.settings_file();
+        let mut table = match std::fs::read_to_string(&path) {
+            Ok(text) => text
+                .parse::<toml::Table>()
+                .map_err(|e| SettingsError::Parse(e.to_string()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => toml::Table::new(),
+            Err(source) => return Err(SettingsError::Io { path, source }),
+        };
+
+        let mut settings = Settings::from_table(table.clone())?;
+        settings.set(key, value)?;
+        table.insert(
+            key.to_string(),
+            toml::Value::Integer(settings.get(key)? as i64),
+        );
+
+        self.ensure_dirs().map_err(|source| SettingsError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        let text = toml::to_string(&table).map_err(|e| SettingsError::Parse(e.to_string()))?;
+        persistence::atomic_write(&path, &text).map_err(SettingsError::Write)?;
+        Ok(settings)
+    }
+
     /// Ensure both runtime and state directories exist with appropriate permissions
+    ///
+    /// A runtime directory that already exists is left as it is, so a shared
+    /// one isn't silently locked down; the daemon refuses to serve from it
+    /// instead (see `check_runtime_dir`).
     pub fn ensure_dirs(&self) -> std::io::Result<()> {
+        // Create runtime directory with 0700 permissions on Unix (first, in
+        // case it is also the state directory)
+        if !self.runtime_dir.exists() {
+            std::fs::create_dir_all(&self.runtime_dir)?;
+
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(
+                    &self.runtime_dir,
+                    std::fs::Permissions::from_mode(0o700),
+                )?;
+            }
+        }
+
         // Create state directory (for durable data)
         std::fs::create_dir_all(&self.state_dir)?;
 
-        // Create runtime directory with 0700 permissions on Unix
-        std::fs::create_dir_all(&self.runtime_dir)?;
+        Ok(())
+    }
 
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            std::fs::set_permissions(&self.runtime_dir, std::fs::Permissions::from_mode(0o700))?;
+    /// Check that the runtime directory belongs to this user and nobody else
+    /// can enter it, since the daemon's sockets live there
+    #[cfg(unix)]
+    pub fn check_runtime_dir(&self) -> std::io::Result<()> {
+        use std::io::{Error, ErrorKind};
+        use std::os::unix::fs::MetadataExt;
+
+        let metadata = std::fs::metadata(&self.runtime_dir)?;
+        let uid = crate::session::peer::current_uid();
+        if metadata.uid() != uid {
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                format!(
+                    "Runtime directory {} is owned by uid {}, not {}",
+                    self.runtime_dir.display(),
+                    metadata.uid(),
+                    uid
+                ),
+            ));
+        }
+        let mode = metadata.mode() & 0o777;
+        if mode & 0o077 != 0 {
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                format!(
+                    "Runtime directory {} is open to other users (mode {:o}); run `chmod 700 {}`",
+                    self.runtime_dir.display(),
+                    mode,
+                    self.runtime_dir.display()
+                ),
+            ));
         }
-
         Ok(())
     }
 
+    /// Directory holding a session's on-disk output log
+    pub fn session_log_dir(&self, session_id: SessionId) -> PathBuf {
+        self.state_dir.join("logs").join(session_id.to_string())
+    }
+
+    /// Directory holding asciicast recordings of sessions that opted in
+    pub fn recordings_dir(&self) -> PathBuf {
+        self.state_dir.join("recordings")
+    }
+
+    /// Directory holding the daemon's event journal
+    pub fn events_dir(&self) -> PathBuf {
+        self.state_dir.join("events")
+    }
+
+    /// asciicast file a session records to
+    pub fn session_recording_path(&self, session_id: SessionId) -> PathBuf {
+        recording::recording_path(&self.recordings_dir(), session_id)
+    }
+
     /// Path to the current project marker file
     pub fn current_project_file(&self) -> PathBuf {
         self.state_dir.join("current_project.txt")
@@ -229,6 +355,259 @@ impl Config {
     }
 }
 
+/// Bytes of output replayed by tail, continue and attach when the client doesn't say
+pub const DEFAULT_TAIL_BYTES: usize = 4 * 1024;
+
+/// Idle time before a context capture (10 minutes)
+pub const DEFAULT_IDLE_CAPTURE: Duration = Duration::from_secs(10 * 60);
+
+/// Keys in config.toml, with what each one tunes
+pub const SETTING_KEYS: &[(&str, &str)] = &[
+    (
+        "idle_timeout_secs",
+        "Seconds without output before a session counts as waiting",
+    ),
+    (
+        "output_buffer_bytes",
+        "Bytes of recent output each session keeps in memory",
+    ),
+    (
+        "tail_bytes",
+        "Bytes of output replayed by tail, continue and attach by default",
+    ),
+    (
+        "idle_capture_secs",
+        "Seconds a session waits before its context is captured",
+    ),
+    (
+        "notification_debounce_secs",
+        "Minimum seconds between attention notifications for a session",
+    ),
+    ("snapshot_retention", "Context snapshots kept per task"),
+    (
+        "output_log_segment_bytes",
+        "Size at which a session's output log starts a new segment file",
+    ),
+    (
+        "output_log_retained_bytes",
+        "Bytes of output history kept on disk per session; 0 (the default) turns the log off",
+    ),
+];
+
+/// Daemon tunables, read from config.toml
+///
+/// Reloaded on SIGHUP or `DaemonRequest::ReloadConfig`. The idle timeout,
+/// output buffer size and output log limits apply to sessions started after a
+/// reload; the rest take effect at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub idle_timeout_secs: u64,
+    pub output_buffer_bytes: usize,
+    pub tail_bytes: usize,
+    pub idle_capture_secs: u64,
+    pub notification_debounce_secs: u64,
+    pub snapshot_retention: usize,
+    pub output_log_segment_bytes: u64,
+    pub output_log_retained_bytes: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            idle_timeout_secs: DEFAULT_IDLE_TIMEOUT.as_secs(),
+            output_buffer_bytes: DEFAULT_OUTPUT_BUFFER_SIZE,
+            tail_bytes: DEFAULT_TAIL_BYTES,
+            idle_capture_secs: DEFAULT_IDLE_CAPTURE.as_secs(),
+            notification_debounce_secs: DEFAULT_DEBOUNCE.as_secs(),
+            snapshot_retention: DEFAULT_RETENTION_COUNT,
+            output_log_segment_bytes: DEFAULT_SEGMENT_BYTES,
+            // Off unless asked for: the log keeps everything a session prints
+            output_log_retained_bytes: 0,
+        }
+    }
+}
+
+/// A problem with config.toml or a value meant for it
+#[derive(Debug, thiserror::Error)]
+pub enum SettingsError {
+    #[error("Failed to access {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Failed to write config: {0:#}")]
+    Write(anyhow::Error),
+    #[error("Malformed config: {0}")]
+    Parse(String),
+    #[error("Unknown config key '{0}' (see `todo config show` for the keys)")]
+    UnknownKey(String),
+    #[error("Invalid {key}: {message}")]
+    InvalidValue { key: String, message: String },
+}
+
+impl Settings {
+    /// Parse and validate the contents of a config.toml
+    pub fn parse(text: &str) -> Result<Self, SettingsError> {
+        let table = text
+            .parse::<toml::Table>()
+            .map_err(|e| SettingsError::Parse(e.to_string()))?;
+        Self::from_table(table)
+    }
+
+    fn from_table(table: toml::Table) -> Result<Self, SettingsError> {
+        if let Some(key) = table
+            .keys()
+            .find(|key| !SETTING_KEYS.iter().any(|(known, _)| known == key))
+        {
+            return Err(SettingsError::UnknownKey(key.clone()));
+        }
+        for (key, value) in &table {
+            if !matches!(value.as_integer(), Some(n) if n >= 0) {
+                return Err(SettingsError::InvalidValue {
+                    key: key.clone(),
+                    message: format!("{} is not a whole number", value),
+                });
+            }
+        }
+        let settings: Settings = table
+            .try_into()
+            .map_err(|e: toml::de::Error| SettingsError::Parse(e.message().to_string()))?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Check every value is in its allowed range
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        fn check(key: &str, value: u64, min: u64, max: u64) -> Result<(), SettingsError> {
+            if (min..=max).contains(&value) {
+                Ok(())
+            } else {
+                Err(SettingsError::InvalidValue {
+                    key: key.to_string(),
+                    message: format!("{} is out of range ({}..={})", value, min, max),
+                })
+            }
+        }
+
+        check("idle_timeout_secs", self.idle_timeout_secs, 1, 24 * 60 * 60)?;
+        check(
+            "output_buffer_bytes",
+            self.output_buffer_bytes as u64,
+            4 * 1024,
+            64 * 1024 * 1024,
+        )?;
+        check(
+            "tail_bytes",
+            self.tail_bytes as u64,
+            1,
+            self.output_buffer_bytes as u64,
+        )?;
+        check(
+            "idle_capture_secs",
+            self.idle_capture_secs,
+            1,
+            7 * 24 * 60 * 60,
+        )?;
+        check(
+            "notification_debounce_secs",
+            self.notification_debounce_secs,
+            0,
+            60 * 60,
+        )?;
+        check(
+            "snapshot_retention",
+            self.snapshot_retention as u64,
+            1,
+            1000,
+        )?;
+        check(
+            "output_log_segment_bytes",
+            self.output_log_segment_bytes,
+            64 * 1024,
+            64 * 1024 * 1024,
+        )?;
+        // A log keeps at least the segment it is writing
+        if self.output_log_retained_bytes != 0 {
+            check(
+                "output_log_retained_bytes",
+                self.output_log_retained_bytes,
+                self.output_log_segment_bytes,
+                4 * 1024 * 1024 * 1024,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Value of one key
+    pub fn get(&self, key: &str) -> Result<u64, SettingsError> {
+        Ok(match key {
+            "idle_timeout_secs" => self.idle_timeout_secs,
+            "output_buffer_bytes" => self.output_buffer_bytes as u64,
+            "tail_bytes" => self.tail_bytes as u64,
+            "idle_capture_secs" => self.idle_capture_secs,
+            "notification_debounce_secs" => self.notification_debounce_secs,
+            "snapshot_retention" => self.snapshot_retention as u64,
+            "output_log_segment_bytes" => self.output_log_segment_bytes,
+            "output_log_retained_bytes" => self.output_log_retained_bytes,
+            _ => return Err(SettingsError::UnknownKey(key.to_string())),
+        })
+    }
+
+    /// Set one key from its text form, validating the result
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), SettingsError> {
+        let mut updated = *self;
+        // Known key first, so a typo isn't reported as a bad value
+        let field: &mut dyn FnMut(u64) = match key {
+            "idle_timeout_secs" => &mut |n| updated.idle_timeout_secs = n,
+            "output_buffer_bytes" => &mut |n| updated.output_buffer_bytes = n as usize,
+            "tail_bytes" => &mut |n| updated.tail_bytes = n as usize,
+            "idle_capture_secs" => &mut |n| updated.idle_capture_secs = n,
+            "notification_debounce_secs" => &mut |n| updated.notification_debounce_secs = n,
+            "snapshot_retention" => &mut |n| updated.snapshot_retention = n as usize,
+            "output_log_segment_bytes" => &mut |n| updated.output_log_segment_bytes = n,
+            "output_log_retained_bytes" => &mut |n| updated.output_log_retained_bytes = n,
+            _ => return Err(SettingsError::UnknownKey(key.to_string())),
+        };
+        let number: u64 = value
+            .trim()
+            .parse()
+            .map_err(|_| SettingsError::InvalidValue {
+                key: key.to_string(),
+                message: format!("'{}' is not a whole number", value),
+            })?;
+        field(number);
+        updated.validate()?;
+        *self = updated;
+        Ok(())
+    }
+
+    /// What sessions spawned under these settings get
+    pub fn runtime_tuning(&self) -> RuntimeTuning {
+        RuntimeTuning {
+            idle_timeout: Duration::from_secs(self.idle_timeout_secs),
+            output_buffer_size: self.output_buffer_bytes,
+        }
+    }
+
+    /// Rotation and retention for output logs of sessions spawned under these
+    /// settings; None when logging is off
+    pub fn output_log_limits(&self) -> Option<LogLimits> {
+        (self.output_log_retained_bytes != 0).then_some(LogLimits {
+            segment_bytes: self.output_log_segment_bytes,
+            retained_bytes: self.output_log_retained_bytes,
+        })
+    }
+
+    pub fn idle_capture(&self) -> Duration {
+        Duration::from_secs(self.idle_capture_secs)
+    }
+
+    pub fn notification_debounce(&self) -> Duration {
+        Duration::from_secs(self.notification_debounce_secs)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -349,6 +728,58 @@ mod tests {
 
         // Verify state_dir exists (permissions not restricted)
         assert!(config.state_dir.exists());
+        config.check_runtime_dir().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_shared_runtime_dir_is_left_alone_and_refused() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let shared = temp_dir.path().join("shared");
+        std::fs::create_dir(&shared).unwrap();
+        std::fs::set_permissions(&shared, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let config = Config {
+            runtime_dir: shared.clone(),
+            state_dir: shared.clone(),
+            socket_path: shared.join("daemon.sock"),
+            pid_file: shared.join("daemon.pid"),
+        };
+
+        config.ensure_dirs().unwrap();
+        let mode = std::fs::metadata(&shared).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o755);
+
+        let err = config.check_runtime_dir().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+        assert!(err.to_string().contains("chmod 700"), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_writable_runtime_dir_is_refused() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config {
+            runtime_dir: temp_dir.path().to_path_buf(),
+            state_dir: temp_dir.path().to_path_buf(),
+            socket_path: temp_dir.path().join("daemon.sock"),
+            pid_file: temp_dir.path().join("daemon.pid"),
+        };
+        // Group-writable, then world-writable
+        for mode in [0o770, 0o703] {
+            std::fs::set_permissions(temp_dir.path(), std::fs::Permissions::from_mode(mode))
+                .unwrap();
+            let err = config.check_runtime_dir().unwrap_err();
+            assert_eq!(
+                err.kind(),
+                std::io::ErrorKind::PermissionDenied,
+                "{:o}",
+                mode
+            );
+        }
     }
 
     #[test]
@@ -379,4 +810,81 @@ mod tests {
         assert!(config.socket_path.starts_with(&config.runtime_dir));
         assert!(config.pid_file.starts_with(&config.runtime_dir));
     }
+
+    #[test]
+    fn test_settings_parse_and_validate() {
+        assert_eq!(Settings::parse("").unwrap(), Settings::default());
+
+        let settings = Settings::parse("tail_bytes = 8192\nsnapshot_retention = 10\n").unwrap();
+        assert_eq!(settings.tail_bytes, 8192);
+        assert_eq!(settings.snapshot_retention, 10);
+        assert_eq!(settings.idle_timeout_secs, DEFAULT_IDLE_TIMEOUT.as_secs());
+
+        let err = Settings::parse("tail_byts = 8192").unwrap_err();
+        assert!(matches!(err, SettingsError::UnknownKey(ref key) if key == "tail_byts"));
+
+        // The tail can't be longer than the buffer it comes from
+        let err = Settings::parse("output_buffer_bytes = 8192\ntail_bytes = 16384").unwrap_err();
+        assert!(
+            err.to_string().contains("Invalid tail_bytes"),
+            "unexpected error: {}",
+            err
+        );
+
+        let err = Settings::parse("idle_timeout_secs = \"30s\"").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid idle_timeout_secs: \"30s\" is not a whole number"
+        );
+        assert!(matches!(
+            Settings::parse("tail_bytes = ").unwrap_err(),
+            SettingsError::Parse(_)
+        ));
+
+        assert_eq!(Settings::default().output_log_limits(), None);
+        let on = Settings::parse("output_log_retained_bytes = 16777216").unwrap();
+        assert_eq!(
+            on.output_log_limits(),
+            Some(LogLimits {
+                segment_bytes: DEFAULT_SEGMENT_BYTES,
+                retained_bytes: 16 * 1024 * 1024,
+            })
+        );
+        // Retention below one segment would delete what is being written
+        let err = Settings::parse(
+            "output_log_segment_bytes = 1048576\noutput_log_retained_bytes = 65536",
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Invalid output_log_retained_bytes"),
+            "unexpected error: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_set_setting_keeps_other_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config {
+            runtime_dir: temp_dir.path().to_path_buf(),
+            state_dir: temp_dir.path().to_path_buf(),
+            socket_path: temp_dir.path().join("daemon.sock"),
+            pid_file: temp_dir.path().join("daemon.pid"),
+        };
+        assert_eq!(config.load_settings().unwrap(), Settings::default());
+
+        std::fs::write(config.settings_file(), "snapshot_retention = 3\n").unwrap();
+        let settings = config.set_setting("tail_bytes", "2048").unwrap();
+        assert_eq!(settings.tail_bytes, 2048);
+        assert_eq!(settings.snapshot_retention, 3);
+        assert_eq!(config.load_settings().unwrap(), settings);
+
+        // Bad values leave the file alone
+        let err = config.set_setting("tail_bytes", "lots").unwrap_err();
+        assert!(err.to_string().contains("not a whole number"));
+        assert!(config.set_setting("snapshot_retention", "0").is_err());
+        assert!(config.set_setting("nope", "1").is_err());
+        assert_eq!(config.load_settings().unwrap(), settings);
+    }
 }
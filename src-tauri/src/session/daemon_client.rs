@@ -3,14 +3,34 @@
 
 use super::config::Config;
 use super::protocol::{
-    deserialize_message, serialize_message, DaemonNotification, DaemonRequest, DaemonResponse,
+    deserialize_message, serialize_message, serialize_message_with, AttachMode, ByteEncoding,
+    Capability, DaemonCapabilities, DaemonErrorCode, DaemonRequest, DaemonResponse,
+    NotificationEnvelope, RequestFrame, RequestId, ResponseFrame, SessionId, StreamFrame,
+    MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
 };
 use crate::cli_paths::resolve_daemon_path;
 use anyhow::{Context, Result};
+use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Write};
 use std::os::unix::net::UnixStream;
+use std::path::Path;
 use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
 use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::net::unix::OwnedWriteHalf;
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex};
+
+/// How long to wait for any one response before giving up on it
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Notifications buffered for slow subscribers of a `DaemonClient`
+const NOTIFICATION_BUFFER: usize = 100;
+
+/// Frames buffered per attach stream before reading from the daemon waits
+/// on its consumer
+const STREAM_FRAME_BUFFER: usize = 256;
 
 /// Connect to the daemon, starting it if necessary
 fn connect_or_start_daemon(config: &Config) -> Result<UnixStream> {
@@ -58,18 +78,9 @@ fn connect_or_start_daemon(config: &Config) -> Result<UnixStream> {
     ))
 }
 
-/// Send a request to the daemon and receive a response
-/// Handles notification messages that may arrive before the response
-///
-/// For CR requests, transport failures (connect/timeout) return Ok(DaemonResponse::Error)
-/// to enable structured error handling in the TypeScript layer.
-pub fn send_request(request: DaemonRequest) -> Result<DaemonResponse> {
-    use super::protocol::DaemonErrorCode;
-
-    let config = Config::from_env();
-
-    // Check if this is a CR request (needs structured error handling)
-    let is_cr_request = matches!(
+/// Check if this is a CR request (needs structured error handling)
+fn is_cr_request(request: &DaemonRequest) -> bool {
+    matches!(
         request,
         DaemonRequest::CrLatest { .. }
             | DaemonRequest::CrList { .. }
@@ -77,7 +88,16 @@ pub fn send_request(request: DaemonRequest) -> Result<DaemonResponse> {
             | DaemonRequest::CrCaptureNow { .. }
             | DaemonRequest::CrDeleteTask { .. }
             | DaemonRequest::CrDeleteProject { .. }
-    );
+    )
+}
+
+/// Send a request to the daemon over a new connection and receive a response
+///
+/// For CR requests, transport failures (connect/timeout) return Ok(DaemonResponse::Error)
+/// to enable structured error handling in the TypeScript layer.
+pub fn send_request(request: DaemonRequest) -> Result<DaemonResponse> {
+    let config = Config::from_env();
+    let is_cr_request = is_cr_request(&request);
 
     // Connect to daemon (or start it)
     let mut stream = match connect_or_start_daemon(&config) {
@@ -104,11 +124,11 @@ pub fn send_request(request: DaemonRequest) -> Result<DaemonResponse> {
     }
 
     // Perform protocol handshake first
-    {
-        use super::protocol::PROTOCOL_VERSION;
-
+    let capabilities = {
         let handshake = DaemonRequest::Handshake {
             client_version: PROTOCOL_VERSION,
+            min_version: Some(MIN_PROTOCOL_VERSION),
+            byte_encoding: Some(ByteEncoding::Base64),
         };
         let handshake_bytes = match serialize_message(&handshake) {
             Ok(b) => b,
@@ -158,10 +178,13 @@ pub fn send_request(request: DaemonRequest) -> Result<DaemonResponse> {
         // Check for version mismatch
         match handshake_response {
             DaemonResponse::Handshake {
-                protocol_version: _,
-            } => {
-                // Handshake successful
-            }
+                protocol_version,
+                capabilities,
+                ..
+            } => DaemonCapabilities {
+                protocol_version,
+                capabilities,
+            },
             DaemonResponse::Error { code, message } if code == DaemonErrorCode::VersionMismatch => {
                 if is_cr_request {
                     return Ok(DaemonResponse::Error { code, message });
@@ -179,6 +202,11 @@ pub fn send_request(request: DaemonRequest) -> Result<DaemonResponse> {
                 return Err(anyhow::anyhow!(msg));
             }
         }
+    };
+
+    // Don't send what the daemon can't handle
+    if let Some(response) = capabilities.check(&request) {
+        return Ok(response);
     }
 
     // Send the actual request
@@ -226,85 +254,420 @@ pub fn send_request(request: DaemonRequest) -> Result<DaemonResponse> {
 
 /// Helper to read a response from the daemon, enforcing frame size limits
 fn read_response(stream: &mut UnixStream, is_cr_request: bool) -> Result<DaemonResponse> {
-    use super::protocol::{DaemonErrorCode, MAX_RESPONSE_FRAME_SIZE};
+    use super::protocol::MAX_RESPONSE_FRAME_SIZE;
 
     let mut reader = BufReader::new(stream);
     let mut line = String::new();
 
-    loop {
-        line.clear();
-        match reader.read_line(&mut line) {
-            Ok(0) => {
-                if is_cr_request {
-                    return Ok(DaemonResponse::Error {
-                        code: DaemonErrorCode::DaemonUnavailable,
-                        message: "Daemon closed connection unexpectedly".to_string(),
-                    });
-                }
-                return Err(anyhow::anyhow!("Daemon closed connection unexpectedly"));
-            }
-            Ok(_) => {
-                // Enforce max response frame size (10MB)
-                if line.len() > MAX_RESPONSE_FRAME_SIZE {
-                    if is_cr_request {
-                        return Ok(DaemonResponse::Error {
-                            code: DaemonErrorCode::Internal,
-                            message: format!(
-                                "Response frame too large: {} bytes (max {})",
-                                line.len(),
-                                MAX_RESPONSE_FRAME_SIZE
-                            ),
-                        });
-                    }
-                    return Err(anyhow::anyhow!(
-                        "Response frame too large: {} bytes (max {})",
-                        line.len(),
-                        MAX_RESPONSE_FRAME_SIZE
-                    ));
-                }
+    match reader.read_line(&mut line) {
+        Ok(0) => {
+            if is_cr_request {
+                return Ok(DaemonResponse::Error {
+                    code: DaemonErrorCode::DaemonUnavailable,
+                    message: "Daemon closed connection unexpectedly".to_string(),
+                });
             }
-            Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+            return Err(anyhow::anyhow!("Daemon closed connection unexpectedly"));
+        }
+        Ok(_) => {
+            // Enforce max response frame size (10MB)
+            if line.len() > MAX_RESPONSE_FRAME_SIZE {
                 if is_cr_request {
                     return Ok(DaemonResponse::Error {
-                        code: DaemonErrorCode::Timeout,
-                        message: "Daemon read timeout".to_string(),
+                        code: DaemonErrorCode::Internal,
+                        message: format!(
+                            "Response frame too large: {} bytes (max {})",
+                            line.len(),
+                            MAX_RESPONSE_FRAME_SIZE
+                        ),
                     });
                 }
-                return Err(e.into());
+                return Err(anyhow::anyhow!(
+                    "Response frame too large: {} bytes (max {})",
+                    line.len(),
+                    MAX_RESPONSE_FRAME_SIZE
+                ));
             }
-            Err(e) if is_cr_request => {
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+            if is_cr_request {
                 return Ok(DaemonResponse::Error {
-                    code: DaemonErrorCode::DaemonUnavailable,
-                    message: format!("Failed to read response from daemon: {}", e),
+                    code: DaemonErrorCode::Timeout,
+                    message: "Daemon read timeout".to_string(),
                 });
             }
-            Err(e) => return Err(e.into()),
+            return Err(e.into());
         }
+        Err(e) if is_cr_request => {
+            return Ok(DaemonResponse::Error {
+                code: DaemonErrorCode::DaemonUnavailable,
+                message: format!("Failed to read response from daemon: {}", e),
+            });
+        }
+        Err(e) => return Err(e.into()),
+    }
 
-        // Try to parse as notification first (we ignore these for now)
-        if let Ok(_notification) = deserialize_message::<DaemonNotification>(line.as_bytes()) {
-            eprintln!("Received daemon notification (ignored for now)");
-            continue;
+    // Connections that haven't subscribed receive no notifications, so the
+    // next line is always the response
+    match deserialize_message::<DaemonResponse>(line.as_bytes()) {
+        Ok(response) => Ok(response),
+        Err(e) if is_cr_request => Ok(DaemonResponse::Error {
+            code: DaemonErrorCode::Internal,
+            message: format!("Failed to parse daemon response: {}", e),
+        }),
+        Err(e) => Err(anyhow::anyhow!(
+            "Failed to parse daemon response: {} (line: {})",
+            e,
+            line.trim()
+        )),
+    }
+}
+
+type PendingResponses = Arc<StdMutex<HashMap<RequestId, oneshot::Sender<DaemonResponse>>>>;
+
+type StreamRoutes = Arc<StdMutex<HashMap<SessionId, mpsc::Sender<StreamFrame>>>>;
+
+/// A persistent, pipelined connection to the daemon
+///
+/// Every request carries a fresh `request_id`, so many can be in flight at
+/// once; a reader task hands each response to the caller waiting on its id.
+/// Notifications (after a `Subscribe` request) go to `notifications()`, and
+/// frames of attach streams to whoever opened them with `open_stream`.
+pub struct DaemonClient {
+    writer: Mutex<OwnedWriteHalf>,
+    pending: PendingResponses,
+    streams: StreamRoutes,
+    next_id: AtomicU64,
+    notifications: broadcast::Sender<NotificationEnvelope>,
+    closed: Arc<AtomicBool>,
+    capabilities: DaemonCapabilities,
+}
+
+impl DaemonClient {
+    /// Connect to the daemon at `socket_path` and perform the handshake
+    pub async fn connect(socket_path: &Path) -> Result<Self> {
+        let stream = tokio::net::UnixStream::connect(socket_path)
+            .await
+            .with_context(|| format!("Failed to connect to {}", socket_path.display()))?;
+        Self::handshake(stream).await
+    }
+
+    /// Connect to the daemon, starting it if necessary
+    pub async fn connect_or_start(config: &Config) -> Result<Self> {
+        let config = config.clone();
+        let stream = tokio::task::spawn_blocking(move || connect_or_start_daemon(&config))
+            .await
+            .context("Daemon connection task failed")??;
+        stream.set_nonblocking(true)?;
+        Self::handshake(tokio::net::UnixStream::from_std(stream)?).await
+    }
+
+    async fn handshake(stream: tokio::net::UnixStream) -> Result<Self> {
+        let (reader, writer) = stream.into_split();
+        let pending: PendingResponses = Arc::default();
+        let streams: StreamRoutes = Arc::default();
+        let (notifications, _) = broadcast::channel(NOTIFICATION_BUFFER);
+        let closed = Arc::new(AtomicBool::new(false));
+        tokio::spawn(read_responses(
+            tokio::io::BufReader::new(reader),
+            Arc::clone(&pending),
+            Arc::clone(&streams),
+            notifications.clone(),
+            Arc::clone(&closed),
+        ));
+
+        let mut client = Self {
+            writer: Mutex::new(writer),
+            pending,
+            streams,
+            next_id: AtomicU64::new(1),
+            notifications,
+            closed,
+            capabilities: DaemonCapabilities::default(),
+        };
+        let handshake = DaemonRequest::Handshake {
+            client_version: PROTOCOL_VERSION,
+            min_version: Some(MIN_PROTOCOL_VERSION),
+            byte_encoding: Some(ByteEncoding::Base64),
+        };
+        match client.request(handshake).await? {
+            DaemonResponse::Handshake {
+                protocol_version,
+                capabilities,
+                ..
+            } => {
+                client.capabilities = DaemonCapabilities {
+                    protocol_version,
+                    capabilities,
+                };
+                Ok(client)
+            }
+            DaemonResponse::Error {
+                code: DaemonErrorCode::VersionMismatch,
+                message,
+            } => Err(anyhow::anyhow!("Protocol version mismatch: {}", message)),
+            other => Err(anyhow::anyhow!(
+                "Expected handshake response, got: {:?}",
+                other
+            )),
         }
+    }
 
-        // Try to parse as response
-        match deserialize_message::<DaemonResponse>(line.as_bytes()) {
-            Ok(response) => return Ok(response),
-            Err(e) if is_cr_request => {
-                return Ok(DaemonResponse::Error {
-                    code: DaemonErrorCode::Internal,
-                    message: format!("Failed to parse daemon response: {}", e),
-                });
+    /// Send a request and wait for its response
+    ///
+    /// Requests needing a capability the daemon didn't announce are answered
+    /// locally with `DaemonErrorCode::Unsupported`.
+    pub async fn request(&self, request: DaemonRequest) -> Result<DaemonResponse> {
+        if let Some(response) = self.capabilities.check(&request) {
+            return Ok(response);
+        }
+
+        let request_id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (response_tx, response_rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(request_id, response_tx);
+        // The reader may have shut down before the request was registered
+        if self.is_closed() {
+            self.pending.lock().unwrap().remove(&request_id);
+            anyhow::bail!("Daemon closed connection unexpectedly");
+        }
+
+        let frame = RequestFrame {
+            request_id: Some(request_id),
+            request,
+        };
+        let written = async {
+            let bytes = serialize_message(&frame)?;
+            let mut writer = self.writer.lock().await;
+            writer.write_all(&bytes).await?;
+            writer.flush().await?;
+            anyhow::Ok(())
+        }
+        .await;
+        if let Err(e) = written {
+            self.pending.lock().unwrap().remove(&request_id);
+            return Err(e.context("Failed to send request to daemon"));
+        }
+
+        match tokio::time::timeout(REQUEST_TIMEOUT, response_rx).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(_)) => Err(anyhow::anyhow!("Daemon closed connection unexpectedly")),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&request_id);
+                // A late answer without an id would go to the wrong request
+                if !self.capabilities.supports(Capability::RequestIds) {
+                    self.closed.store(true, Ordering::SeqCst);
+                }
+                Err(TimedOut.into())
+            }
+        }
+    }
+
+    /// Open an attach stream for a session on this connection
+    ///
+    /// Returns the daemon's answer (`StreamOpened`, or an error) and the
+    /// session's frames from then on: output, ending with a `Close` when the
+    /// session exits. The receiver ends at once if the stream didn't open.
+    /// Streams for several sessions can be open at the same time. The
+    /// receiver is bounded: while it's full, nothing more is read from the
+    /// connection, so the daemon holds back too.
+    pub async fn open_stream(
+        &self,
+        session_id: SessionId,
+        tail_bytes: Option<usize>,
+        offset: Option<u64>,
+        mode: AttachMode,
+    ) -> Result<(DaemonResponse, mpsc::Receiver<StreamFrame>)> {
+        // Route frames before asking, so output right after the response isn't missed
+        let (frames_tx, frames_rx) = mpsc::channel(STREAM_FRAME_BUFFER);
+        self.streams.lock().unwrap().insert(session_id, frames_tx);
+
+        let request = DaemonRequest::OpenStream {
+            session_id,
+            tail_bytes,
+            offset,
+            mode,
+        };
+        let response = self.request(request).await;
+        if !matches!(response, Ok(DaemonResponse::StreamOpened { .. })) {
+            self.streams.lock().unwrap().remove(&session_id);
+        }
+        Ok((response?, frames_rx))
+    }
+
+    /// Send input, a resize or a `Close` on an open attach stream
+    ///
+    /// Sending `Close` detaches; the stream's receiver gets it too, then ends.
+    pub async fn send_stream_frame(&self, frame: &StreamFrame) -> Result<()> {
+        if let StreamFrame::Close { session_id, .. } = frame {
+            let frames_tx = self.streams.lock().unwrap().remove(session_id);
+            if let Some(frames_tx) = frames_tx {
+                let _ = frames_tx.send(frame.clone()).await;
+            }
+        }
+        // Any daemon with streams reads base64
+        let bytes = serialize_message_with(frame, ByteEncoding::Base64)?;
+        let mut writer = self.writer.lock().await;
+        writer.write_all(&bytes).await?;
+        writer.flush().await?;
+        Ok(())
+    }
+
+    /// What the daemon announced in the handshake
+    pub fn capabilities(&self) -> &DaemonCapabilities {
+        &self.capabilities
+    }
+
+    /// Notifications delivered on this connection once subscribed
+    pub fn notifications(&self) -> broadcast::Receiver<NotificationEnvelope> {
+        self.notifications.subscribe()
+    }
+
+    /// The daemon hung up; every later request fails
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+}
+
+/// No response arrived within `REQUEST_TIMEOUT`
+#[derive(Debug, thiserror::Error)]
+#[error("Daemon read timeout")]
+pub struct TimedOut;
+
+/// Route responses to their waiting requests, stream frames to their
+/// streams and notifications to subscribers
+async fn read_responses(
+    mut reader: tokio::io::BufReader<tokio::net::unix::OwnedReadHalf>,
+    pending: PendingResponses,
+    streams: StreamRoutes,
+    notifications: broadcast::Sender<NotificationEnvelope>,
+    closed: Arc<AtomicBool>,
+) {
+    use super::protocol::MAX_RESPONSE_FRAME_SIZE;
+
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match reader.read_line(&mut line).await {
+            Ok(0) => break,
+            Ok(_) if line.len() > MAX_RESPONSE_FRAME_SIZE => {
+                eprintln!(
+                    "Dropping daemon frame: {} bytes (max {})",
+                    line.len(),
+                    MAX_RESPONSE_FRAME_SIZE
+                );
+            }
+            Ok(_) => {
+                if let Ok(envelope) = deserialize_message::<NotificationEnvelope>(line.as_bytes()) {
+                    let _ = notifications.send(envelope);
+                    continue;
+                }
+                if let Ok(frame) = deserialize_message::<StreamFrame>(line.as_bytes()) {
+                    let session_id = frame.session_id();
+                    let frames_tx = {
+                        let mut streams = streams.lock().unwrap();
+                        if matches!(frame, StreamFrame::Close { .. }) {
+                            streams.remove(&session_id)
+                        } else {
+                            streams.get(&session_id).cloned()
+                        }
+                    };
+                    // Waiting here on a slow consumer holds back the daemon
+                    if let Some(frames_tx) = frames_tx {
+                        let _ = frames_tx.send(frame).await;
+                    }
+                    continue;
+                }
+                match deserialize_message::<ResponseFrame>(line.as_bytes()) {
+                    Ok(ResponseFrame {
+                        request_id: Some(request_id),
+                        response,
+                    }) => {
+                        if let Some(response_tx) = pending.lock().unwrap().remove(&request_id) {
+                            let _ = response_tx.send(response);
+                        }
+                    }
+                    Ok(ResponseFrame {
+                        request_id: None,
+                        response,
+                    }) => {
+                        // Daemons without request IDs answer in order
+                        let mut pending = pending.lock().unwrap();
+                        let oldest = pending.keys().min().copied();
+                        match oldest.and_then(|request_id| pending.remove(&request_id)) {
+                            Some(response_tx) => {
+                                let _ = response_tx.send(response);
+                            }
+                            None => {
+                                eprintln!("Dropping unexpected daemon response: {:?}", response)
+                            }
+                        }
+                    }
+                    Err(e) => eprintln!("Failed to parse daemon response: {}", e),
+                }
             }
             Err(e) => {
-                return Err(anyhow::anyhow!(
-                    "Failed to parse daemon response: {} (line: {})",
-                    e,
-                    line.trim()
-                ));
+                eprintln!("Error reading from daemon: {}", e);
+                break;
             }
         }
     }
+
+    closed.store(true, Ordering::SeqCst);
+    // Dropping the senders fails every request still waiting and ends every stream
+    pending.lock().unwrap().clear();
+    streams.lock().unwrap().clear();
+}
+
+/// The process-wide persistent connection used by `send_shared_request`
+/// and the app's attach streams
+static SHARED_CLIENT: Mutex<Option<Arc<DaemonClient>>> = Mutex::const_new(None);
+
+/// The process-wide persistent connection, (re)connecting if necessary
+pub async fn shared_client() -> Result<Arc<DaemonClient>> {
+    let mut shared = SHARED_CLIENT.lock().await;
+    match shared.as_ref() {
+        Some(client) if !client.is_closed() => Ok(Arc::clone(client)),
+        _ => {
+            let client = DaemonClient::connect_or_start(&Config::from_env()).await?;
+            Ok(Arc::clone(shared.insert(Arc::new(client))))
+        }
+    }
+}
+
+/// Send a request over a persistent connection shared by the whole process
+///
+/// Connects (starting the daemon if necessary) on first use and again after
+/// the daemon hangs up. As with `send_request`, transport failures for CR
+/// requests come back as `DaemonResponse::Error`.
+pub async fn send_shared_request(request: DaemonRequest) -> Result<DaemonResponse> {
+    let is_cr_request = is_cr_request(&request);
+
+    let client = match shared_client().await {
+        Ok(client) => client,
+        Err(e) if is_cr_request => {
+            return Ok(DaemonResponse::Error {
+                code: DaemonErrorCode::DaemonUnavailable,
+                message: format!("Failed to connect to daemon: {}", e),
+            });
+        }
+        Err(e) => return Err(e),
+    };
+
+    match client.request(request).await {
+        Ok(response) => Ok(response),
+        Err(e) if is_cr_request => {
+            let code = if e.is::<TimedOut>() {
+                DaemonErrorCode::Timeout
+            } else {
+                DaemonErrorCode::DaemonUnavailable
+            };
+            Ok(DaemonResponse::Error {
+                code,
+                message: format!("Failed to read response: {}", e),
+            })
+        }
+        Err(e) => Err(e),
+    }
 }
 
 /// Helper to convert DaemonResponse to user-facing error messages
@@ -0,0 +1,121 @@
+// Foreground process lookup for PTY sessions
+//
+// A session's PTY is shared by its shell and whatever the shell is running.
+// The terminal's foreground process group says which of them owns the
+// terminal right now; the group leader's command line and working directory
+// are read from /proc, so they only resolve on Linux.
+
+use std::fs;
+use std::path::Path;
+
+const PROC_ROOT: &str = "/proc";
+
+/// The process in the foreground of a session's terminal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForegroundProcess {
+    pub pid: u32,
+    /// Command line with arguments joined by spaces
+    pub command: String,
+    /// Current working directory (None if it can't be read)
+    pub cwd: Option<String>,
+}
+
+/// Resolve the leader of foreground process group `pgid`
+pub fn resolve(pgid: i32) -> Option<ForegroundProcess> {
+    resolve_in(Path::new(PROC_ROOT), pgid)
+}
+
+/// Foreground process group of the terminal controlling `pid`
+///
+/// Stands in for tcgetpgrp when the PTY master lives in another process.
+pub fn terminal_pgid_of(pid: u32) -> Option<i32> {
+    terminal_pgid_in(Path::new(PROC_ROOT), pid)
+}
+
+fn resolve_in(proc_root: &Path, pgid: i32) -> Option<ForegroundProcess> {
+    let pid = u32::try_from(pgid).ok().filter(|&pid| pid > 0)?;
+    let dir = proc_root.join(pid.to_string());
+
+    let cmdline = fs::read(dir.join("cmdline")).ok()?;
+    let mut command = cmdline
+        .split(|&b| b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(String::from_utf8_lossy)
+        .collect::<Vec<_>>()
+        .join(" ");
+    // Zombies keep their name but lose their command line
+    if command.is_empty() {
+        command = fs::read_to_string(dir.join("comm"))
+            .ok()?
+            .trim_end()
+            .to_string();
+    }
+
+    let cwd = fs::read_link(dir.join("cwd"))
+        .ok()
+        .map(|path| path.to_string_lossy().into_owned());
+
+    Some(ForegroundProcess { pid, command, cwd })
+}
+
+fn terminal_pgid_in(proc_root: &Path, pid: u32) -> Option<i32> {
+    let stat = fs::read_to_string(proc_root.join(pid.to_string()).join("stat")).ok()?;
+    // The command name is parenthesised and may itself contain spaces or ')'
+    let (_, fields) = stat.rsplit_once(')')?;
+    // state ppid pgrp session tty_nr tpgid ...
+    let tpgid: i32 = fields.split_whitespace().nth(5)?.parse().ok()?;
+    (tpgid > 0).then_some(tpgid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn reads_tpgid_after_awkward_command_names() {
+        let root = TempDir::new().unwrap();
+        let dir = root.path().join("42");
+        fs::create_dir(&dir).unwrap();
+
+        fs::write(
+            dir.join("stat"),
+            "42 (my) (odd name) S 1 42 42 34816 4242 4194560",
+        )
+        .unwrap();
+        assert_eq!(terminal_pgid_in(root.path(), 42), Some(4242));
+
+        // No controlling terminal
+        fs::write(dir.join("stat"), "42 (daemon) S 1 42 42 0 -1 4194560").unwrap();
+        assert_eq!(terminal_pgid_in(root.path(), 42), None);
+        assert_eq!(terminal_pgid_in(root.path(), 7), None);
+    }
+
+    #[test]
+    fn joins_command_line_and_falls_back_to_comm() {
+        let root = TempDir::new().unwrap();
+        let dir = root.path().join("42");
+        fs::create_dir(&dir).unwrap();
+
+        fs::write(dir.join("cmdline"), b"cargo\0watch\0-x\0test\0").unwrap();
+        let process = resolve_in(root.path(), 42).unwrap();
+        assert_eq!(process.pid, 42);
+        assert_eq!(process.command, "cargo watch -x test");
+        assert_eq!(process.cwd, None);
+
+        fs::write(dir.join("cmdline"), b"").unwrap();
+        fs::write(dir.join("comm"), "defunct\n").unwrap();
+        assert_eq!(resolve_in(root.path(), 42).unwrap().command, "defunct");
+
+        assert_eq!(resolve_in(root.path(), 0), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn resolves_this_process() {
+        let process = resolve(std::process::id() as i32).unwrap();
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(process.cwd.as_deref(), cwd.to_str());
+        assert!(!process.command.is_empty());
+    }
+}
@@ -0,0 +1,396 @@
+// Daemon handoff for zero-downtime upgrades
+//
+// A newly started daemon that finds an older one running sends it
+// `DaemonRequest::Handoff`. The old daemon pauses its PTY readers, saves the
+// registry and replies with `DaemonResponse::HandoffReady`, passing its control
+// listener, in-process PTY masters and attach listeners along with the reply
+// over SCM_RIGHTS. It then exits without touching the sessions, and the new
+// daemon carries on serving them. Sessions owned by holder processes carry no
+// PTY descriptor; the new daemon just reconnects to their holders.
+
+use crate::session::protocol::{
+    deserialize_message, serialize_message, DaemonCapabilities, DaemonRequest, DaemonResponse,
+    HandoffSession, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+};
+use anyhow::{Context, Result};
+use std::io::{self, BufRead, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::time::Duration;
+
+/// Set to take over from a running daemon even if it isn't older (e.g. to
+/// restart into a reinstalled build)
+pub const FORCE_HANDOFF_ENV: &str = "RIGHT_NOW_FORCE_HANDOFF";
+
+/// Most descriptors one SCM_RIGHTS message may carry (Linux SCM_MAX_FD)
+pub const MAX_HANDOFF_FDS: usize = 253;
+
+/// How long the new daemon waits for the old one to send its state
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A session and its descriptors, as sent (`RawFd`) or received (`OwnedFd`)
+#[derive(Debug)]
+pub struct HandoffEntry<F> {
+    pub session: HandoffSession,
+    /// PTY master for in-process sessions; None for held sessions
+    pub pty: Option<F>,
+    /// Listening attach socket, if the session had one
+    pub attach_listener: Option<F>,
+}
+
+/// Everything a replacement daemon receives from the old one
+#[derive(Debug)]
+pub struct Handoff {
+    /// The old daemon's listening control socket
+    pub control_listener: OwnedFd,
+    pub sessions: Vec<HandoffEntry<OwnedFd>>,
+}
+
+/// Ask the daemon listening on `socket_path` what it supports
+///
+/// A replacement only takes over from daemons older than itself.
+pub fn probe_daemon(socket_path: &Path) -> Result<DaemonCapabilities> {
+    let stream = UnixStream::connect(socket_path)
+        .with_context(|| format!("Failed to connect to {}", socket_path.display()))?;
+    stream.set_read_timeout(Some(HANDOFF_TIMEOUT))?;
+    let handshake = DaemonRequest::Handshake {
+        client_version: PROTOCOL_VERSION,
+        min_version: Some(MIN_PROTOCOL_VERSION),
+        byte_encoding: None,
+    };
+    (&stream).write_all(&serialize_message(&handshake)?)?;
+
+    let mut line = String::new();
+    io::BufReader::new(&stream)
+        .read_line(&mut line)
+        .context("Failed to read handshake")?;
+    match deserialize_message::<DaemonResponse>(line.as_bytes())? {
+        DaemonResponse::Handshake {
+            protocol_version,
+            capabilities,
+            ..
+        } => Ok(DaemonCapabilities {
+            protocol_version,
+            capabilities,
+        }),
+        DaemonResponse::Error { message, .. } => anyhow::bail!("Handshake failed: {}", message),
+        other => anyhow::bail!("Expected handshake, got {:?}", other),
+    }
+}
+
+/// Ask the daemon listening on `socket_path` to hand over its sessions
+pub fn request_handoff(socket_path: &Path) -> Result<Handoff> {
+    let stream = UnixStream::connect(socket_path)
+        .with_context(|| format!("Failed to connect to {}", socket_path.display()))?;
+    stream.set_read_timeout(Some(HANDOFF_TIMEOUT))?;
+    (&stream).write_all(&serialize_message(&DaemonRequest::Handoff)?)?;
+
+    let mut pending = Vec::new();
+    let mut fds = Vec::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = recv_with_fds(&stream, &mut buf, &mut fds)?;
+        if n == 0 {
+            anyhow::bail!("Daemon closed the connection before handing off");
+        }
+        pending.extend_from_slice(&buf[..n]);
+
+        while let Some(pos) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=pos).collect();
+            // Anything else on the connection is a broadcast notification
+            match deserialize_message::<DaemonResponse>(&line) {
+                Ok(DaemonResponse::HandoffReady { sessions }) => {
+                    return assemble_handoff(sessions, fds);
+                }
+                Ok(DaemonResponse::Error { message, .. }) => {
+                    anyhow::bail!("Daemon refused handoff: {}", message);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Match received descriptors up with the sessions they belong to
+fn assemble_handoff(sessions: Vec<HandoffSession>, fds: Vec<OwnedFd>) -> Result<Handoff> {
+    let mut fds = fds.into_iter();
+    let mut next_fd = |what: &str| {
+        fds.next()
+            .with_context(|| format!("Handoff is missing the {} descriptor", what))
+    };
+
+    let control_listener = next_fd("control listener")?;
+    let mut entries = Vec::with_capacity(sessions.len());
+    for session in sessions {
+        let pty = if session.held {
+            None
+        } else {
+            Some(next_fd("PTY")?)
+        };
+        let attach_listener = if session.attach_listener {
+            Some(next_fd("attach listener")?)
+        } else {
+            None
+        };
+        entries.push(HandoffEntry {
+            session,
+            pty,
+            attach_listener,
+        });
+    }
+
+    Ok(Handoff {
+        control_listener,
+        sessions: entries,
+    })
+}
+
+/// Send the `HandoffReady` reply with all descriptors attached
+pub fn send_handoff(
+    stream: &UnixStream,
+    control_listener: RawFd,
+    entries: Vec<HandoffEntry<RawFd>>,
+) -> Result<()> {
+    let mut fds = vec![control_listener];
+    let sessions = entries
+        .into_iter()
+        .map(|entry| {
+            let mut session = entry.session;
+            session.held = entry.pty.is_none();
+            session.attach_listener = entry.attach_listener.is_some();
+            fds.extend(entry.pty);
+            fds.extend(entry.attach_listener);
+            session
+        })
+        .collect();
+    anyhow::ensure!(
+        fds.len() <= MAX_HANDOFF_FDS,
+        "Too many descriptors to hand off ({} > {})",
+        fds.len(),
+        MAX_HANDOFF_FDS
+    );
+
+    let frame = serialize_message(&DaemonResponse::HandoffReady { sessions })?;
+    send_with_fds(stream, &frame, &fds).context("Failed to send handoff")
+}
+
+/// Send `data`, attaching `fds` to the first chunk
+///
+/// `data` must not be empty: descriptors can only ride along with payload bytes.
+pub fn send_with_fds(stream: &UnixStream, data: &[u8], fds: &[RawFd]) -> io::Result<()> {
+    let fd_bytes = std::mem::size_of_val(fds);
+    // SAFETY: CMSG_SPACE is a pure size computation
+    let space = unsafe { libc::CMSG_SPACE(fd_bytes as u32) } as usize;
+    // u64 storage keeps the control buffer aligned for cmsghdr
+    let mut control = vec![0u64; space.div_ceil(8)];
+
+    let mut iov = libc::iovec {
+        iov_base: data.as_ptr() as *mut libc::c_void,
+        iov_len: data.len(),
+    };
+    // SAFETY: msghdr is plain old data; all-zero is a valid empty header
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+
+    if !fds.is_empty() {
+        msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        msg.msg_controllen = space as _;
+        // SAFETY: the control buffer holds CMSG_SPACE(fd_bytes) aligned bytes,
+        // so the first header and its data fit
+        unsafe {
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_SOCKET;
+            (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(fd_bytes as u32) as _;
+            std::ptr::copy_nonoverlapping(
+                fds.as_ptr(),
+                libc::CMSG_DATA(cmsg) as *mut RawFd,
+                fds.len(),
+            );
+        }
+    }
+
+    let sent = loop {
+        // SAFETY: msg points at live buffers for the duration of the call
+        let n = unsafe { libc::sendmsg(stream.as_raw_fd(), &msg, 0) };
+        if n >= 0 {
+            break n as usize;
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    };
+
+    let mut writer = stream;
+    writer.write_all(&data[sent..])
+}
+
+/// Receive into `buf`, collecting any descriptors that arrive with the data
+pub fn recv_with_fds(
+    stream: &UnixStream,
+    buf: &mut [u8],
+    fds: &mut Vec<OwnedFd>,
+) -> io::Result<usize> {
+    // SAFETY: CMSG_SPACE is a pure size computation
+    let space = unsafe { libc::CMSG_SPACE((MAX_HANDOFF_FDS * std::mem::size_of::<RawFd>()) as u32) }
+        as usize;
+    let mut control = vec![0u64; space.div_ceil(8)];
+
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+        iov_len: buf.len(),
+    };
+    // SAFETY: msghdr is plain old data; all-zero is a valid empty header
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = space as _;
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    let flags = libc::MSG_CMSG_CLOEXEC;
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    let flags = 0;
+
+    let received = loop {
+        // SAFETY: msg points at live buffers for the duration of the call
+        let n = unsafe { libc::recvmsg(stream.as_raw_fd(), &mut msg, flags) };
+        if n >= 0 {
+            break n as usize;
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    };
+
+    // SAFETY: the kernel filled in well-formed control messages within
+    // msg_controllen, which the CMSG_* macros walk
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                let data = libc::CMSG_DATA(cmsg) as *const RawFd;
+                let len = (*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize;
+                for i in 0..len / std::mem::size_of::<RawFd>() {
+                    let fd = std::ptr::read_unaligned(data.add(i));
+                    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+                    libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+                    fds.push(OwnedFd::from_raw_fd(fd));
+                }
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+
+    if msg.msg_flags & libc::MSG_CTRUNC != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Descriptors were truncated in transit",
+        ));
+    }
+
+    Ok(received)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::protocol::DaemonNotification;
+    use std::io::{BufRead, BufReader, Read};
+    use std::os::unix::net::UnixListener;
+
+    fn pipe() -> (std::fs::File, std::fs::File) {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        unsafe {
+            (
+                std::fs::File::from_raw_fd(fds[0]),
+                std::fs::File::from_raw_fd(fds[1]),
+            )
+        }
+    }
+
+    #[test]
+    fn descriptors_survive_the_trip() {
+        let (sender, receiver) = UnixStream::pair().unwrap();
+        let (mut read_end, write_end) = pipe();
+
+        send_with_fds(&sender, b"x", &[write_end.as_raw_fd()]).unwrap();
+        drop(write_end);
+
+        let mut fds = Vec::new();
+        let mut buf = [0u8; 8];
+        assert_eq!(recv_with_fds(&receiver, &mut buf, &mut fds).unwrap(), 1);
+        assert_eq!(fds.len(), 1);
+
+        // Writing through the received descriptor reaches the original pipe
+        let mut passed = std::fs::File::from(fds.pop().unwrap());
+        passed.write_all(b"hello").unwrap();
+        drop(passed);
+        let mut out = String::new();
+        read_end.read_to_string(&mut out).unwrap();
+        assert_eq!(out, "hello");
+    }
+
+    #[test]
+    fn request_handoff_skips_notifications_and_pairs_descriptors() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let socket_path = dir.path().join("daemon.sock");
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        let old_daemon = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut line = String::new();
+            BufReader::new(&stream).read_line(&mut line).unwrap();
+            assert!(matches!(
+                deserialize_message::<DaemonRequest>(line.as_bytes()).unwrap(),
+                DaemonRequest::Handoff
+            ));
+
+            // A broadcast that raced the request
+            let notification = DaemonNotification::SessionRemoved { session_id: 9 };
+            (&stream)
+                .write_all(&serialize_message(&notification).unwrap())
+                .unwrap();
+
+            let (_pty_read, pty) = pipe();
+            let session = |session_id| HandoffSession {
+                session_id,
+                held: false,
+                pid: Some(100 + session_id as u32),
+                tail: Vec::new(),
+                screen: None,
+                idle: false,
+                attach_listener: false,
+            };
+            let entries = vec![
+                HandoffEntry {
+                    session: session(1),
+                    pty: Some(pty.as_raw_fd()),
+                    attach_listener: Some(listener.as_raw_fd()),
+                },
+                HandoffEntry {
+                    session: session(2),
+                    pty: None,
+                    attach_listener: None,
+                },
+            ];
+            send_handoff(&stream, listener.as_raw_fd(), entries).unwrap();
+        });
+
+        let handoff = request_handoff(&socket_path).unwrap();
+        old_daemon.join().unwrap();
+
+        assert_eq!(handoff.sessions.len(), 2);
+        let first = &handoff.sessions[0];
+        assert!(!first.session.held && first.session.attach_listener);
+        assert!(first.pty.is_some() && first.attach_listener.is_some());
+        let second = &handoff.sessions[1];
+        assert!(second.session.held && !second.session.attach_listener);
+        assert!(second.pty.is_none() && second.attach_listener.is_none());
+    }
+}
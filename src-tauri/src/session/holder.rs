@@ -0,0 +1,820 @@
+// Per-session PTY holder processes
+//
+// A holder owns a single session's PTY, ring buffer and screen model in a small
+// process of its own (dtach-style) and serves them over a local socket. The
+// daemon drives sessions through holders instead of owning PTY masters itself,
+// so a daemon crash or upgrade no longer kills running sessions: the restarted
+// daemon reconnects to the holders it finds and adopts their sessions.
+//
+// Wire format is line-delimited JSON like the daemon protocol. On connect the
+// holder sends `HolderMessage::Hello` with a snapshot of the session, then
+// streams output and state transitions; the daemon sends `HolderRequest`s.
+
+use crate::session::output_log::{LogLimits, OutputLog};
+use crate::session::peer;
+use crate::session::protocol::{
+    pty_bytes, serialize_message_with, ByteEncoding, ScreenRepaint, SessionId,
+};
+use crate::session::runtime::{PtyEvent, PtyRuntime, RuntimeTuning, SpawnSpec};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{broadcast, mpsc};
+
+/// Environment variable overriding the holder binary location
+pub const HOLDER_BIN_ENV: &str = "RIGHT_NOW_HOLDER";
+
+/// Holder binary name, expected next to the daemon binary
+const HOLDER_BIN_NAME: &str = "right-now-holder";
+
+/// Ring buffer bytes replayed to each new connection
+const HELLO_TAIL_BYTES: usize = 64 * 1024;
+
+/// How long the daemon waits for a freshly spawned holder to accept connections
+const HOLDER_READY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a holder keeps an exited session around for a daemon to collect
+const EXIT_LINGER: Duration = Duration::from_secs(60);
+
+/// Messages sent from a holder to the daemon
+///
+/// Byte payloads are written as base64 and read back from arrays as well, as
+/// holders from before that wrote them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HolderMessage {
+    /// First message on every connection
+    Hello {
+        session_id: SessionId,
+        /// PID of the session's child process
+        pid: Option<u32>,
+        /// Recent output from the holder's ring buffer
+        #[serde(with = "pty_bytes")]
+        tail: Vec<u8>,
+        /// Current screen, for seeding the daemon's screen model
+        screen: ScreenRepaint,
+        idle: bool,
+        /// False if the child already exited; `exit_code` then holds its status
+        alive: bool,
+        exit_code: Option<i32>,
+    },
+    /// PTY produced output
+    Output {
+        #[serde(with = "pty_bytes")]
+        data: Vec<u8>,
+    },
+    /// Output was dropped on the way to this connection; replace the mirror
+    /// with this snapshot and carry on from there
+    Resync {
+        #[serde(with = "pty_bytes")]
+        tail: Vec<u8>,
+        screen: ScreenRepaint,
+        idle: bool,
+    },
+    /// PTY became active after being idle
+    Active,
+    /// PTY became idle
+    Idle,
+    /// Child process exited
+    Exited { exit_code: Option<i32> },
+}
+
+/// Requests sent from the daemon to a holder
+///
+/// Written with arrays for byte payloads, which holders spawned by older
+/// builds still read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HolderRequest {
+    /// Write bytes to the PTY
+    Input {
+        #[serde(with = "pty_bytes")]
+        data: Vec<u8>,
+    },
+    /// Resize the PTY window
+    Resize { cols: u16, rows: u16 },
+    /// Kill the child and shut the holder down
+    Stop,
+}
+
+/// Frame a message for the daemon
+fn encode_message(message: &HolderMessage) -> serde_json::Result<Vec<u8>> {
+    serialize_message_with(message, ByteEncoding::Base64)
+}
+
+/// Socket path for a session's holder
+pub fn socket_path(runtime_dir: &Path, session_id: SessionId) -> PathBuf {
+    runtime_dir.join(format!("holder-{}.sock", session_id))
+}
+
+/// Locate the holder binary: `RIGHT_NOW_HOLDER`, else next to the current executable
+pub fn find_holder_binary() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var(HOLDER_BIN_ENV) {
+        let path = PathBuf::from(path);
+        return path.is_file().then_some(path);
+    }
+
+    let exe = std::env::current_exe().ok()?;
+    let path = exe.parent()?.join(HOLDER_BIN_NAME);
+    path.is_file().then_some(path)
+}
+
+/// Everything a holder needs to start its session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HolderArgs {
+    pub socket_path: PathBuf,
+    pub session_id: SessionId,
+    pub task_key: String,
+    pub project_path: String,
+    /// Directory for the session's output log; nothing is logged when None
+    pub log_dir: Option<PathBuf>,
+    /// Rotation and retention for the output log, from the daemon's settings
+    pub log_limits: LogLimits,
+    /// asciicast file to record the session to; nothing is recorded when None
+    pub recording: Option<PathBuf>,
+    /// Working directory; the home directory when None
+    pub cwd: Option<PathBuf>,
+    /// Variables set on top of the holder's environment
+    ///
+    /// `spawn_holder` puts these in the holder's own environment rather than
+    /// on its command line, where any local user could read them, so a
+    /// spawned holder parses its arguments with none.
+    pub env: BTreeMap<String, String>,
+    /// Shell command; the user's default shell when None
+    pub shell: Option<Vec<String>>,
+    /// Idle timeout and ring buffer size, from the daemon's settings
+    pub tuning: RuntimeTuning,
+}
+
+impl HolderArgs {
+    /// Render as command-line arguments for the holder binary
+    fn to_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "--socket".to_string(),
+            self.socket_path.to_string_lossy().to_string(),
+            "--session-id".to_string(),
+            self.session_id.to_string(),
+            "--task-key".to_string(),
+            self.task_key.clone(),
+            "--project".to_string(),
+            self.project_path.clone(),
+            "--idle-timeout-secs".to_string(),
+            self.tuning.idle_timeout.as_secs().to_string(),
+            "--output-buffer-bytes".to_string(),
+            self.tuning.output_buffer_size.to_string(),
+        ];
+        if let Some(ref log_dir) = self.log_dir {
+            args.push("--log-dir".to_string());
+            args.push(log_dir.to_string_lossy().to_string());
+            args.push("--log-segment-bytes".to_string());
+            args.push(self.log_limits.segment_bytes.to_string());
+            args.push("--log-retained-bytes".to_string());
+            args.push(self.log_limits.retained_bytes.to_string());
+        }
+        if let Some(ref recording) = self.recording {
+            args.push("--record".to_string());
+            args.push(recording.to_string_lossy().to_string());
+        }
+        if let Some(ref cwd) = self.cwd {
+            args.push("--cwd".to_string());
+            args.push(cwd.to_string_lossy().to_string());
+        }
+        if let Some(ref shell) = self.shell {
+            args.push("--".to_string());
+            args.extend(shell.iter().cloned());
+        }
+        args
+    }
+
+    /// Parse the holder binary's arguments (excluding the program name)
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut args = args.into_iter();
+        let mut socket_path = None;
+        let mut session_id = None;
+        let mut task_key = None;
+        let mut project_path = None;
+        let mut log_dir = None;
+        let mut recording = None;
+        let mut cwd = None;
+        let mut shell = None;
+        let mut tuning = RuntimeTuning::default();
+        let mut log_limits = LogLimits::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--socket" => socket_path = args.next().map(PathBuf::from),
+                "--session-id" => {
+                    session_id = Some(
+                        args.next()
+                            .context("--session-id requires a value")?
+                            .parse()
+                            .context("Invalid --session-id")?,
+                    )
+                }
+                "--task-key" => task_key = args.next(),
+                "--idle-timeout-secs" => {
+                    tuning.idle_timeout = Duration::from_secs(
+                        args.next()
+                            .context("--idle-timeout-secs requires a value")?
+                            .parse()
+                            .context("Invalid --idle-timeout-secs")?,
+                    )
+                }
+                "--output-buffer-bytes" => {
+                    tuning.output_buffer_size = args
+                        .next()
+                        .context("--output-buffer-bytes requires a value")?
+                        .parse()
+                        .context("Invalid --output-buffer-bytes")?
+                }
+                "--project" => project_path = args.next(),
+                "--log-dir" => log_dir = args.next().map(PathBuf::from),
+                "--log-segment-bytes" => {
+                    log_limits.segment_bytes = args
+                        .next()
+                        .context("--log-segment-bytes requires a value")?
+                        .parse()
+                        .context("Invalid --log-segment-bytes")?
+                }
+                "--log-retained-bytes" => {
+                    log_limits.retained_bytes = args
+                        .next()
+                        .context("--log-retained-bytes requires a value")?
+                        .parse()
+                        .context("Invalid --log-retained-bytes")?
+                }
+                "--record" => recording = args.next().map(PathBuf::from),
+                "--cwd" => cwd = args.next().map(PathBuf::from),
+                "--" => {
+                    shell = Some(args.by_ref().collect());
+                    break;
+                }
+                other => anyhow::bail!("Unknown argument: {}", other),
+            }
+        }
+
+        Ok(Self {
+            socket_path: socket_path.context("Missing --socket")?,
+            session_id: session_id.context("Missing --session-id")?,
+            task_key: task_key.context("Missing --task-key")?,
+            project_path: project_path.context("Missing --project")?,
+            log_dir,
+            log_limits,
+            recording,
+            cwd,
+            env: BTreeMap::new(),
+            shell,
+            tuning,
+        })
+    }
+}
+
+/// Launch a detached holder process and connect to it
+///
+/// The holder runs in its own process session so it is not taken down with the
+/// daemon's process group or controlling terminal.
+pub fn spawn_holder(holder_bin: &Path, args: &HolderArgs) -> Result<PtyRuntime> {
+    use std::os::unix::process::CommandExt;
+
+    let _ = std::fs::remove_file(&args.socket_path);
+
+    let mut command = Command::new(holder_bin);
+    command
+        .args(args.to_args())
+        .envs(&args.env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // SAFETY: setsid is async-signal-safe and touches no parent state
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setsid() == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to launch holder {}", holder_bin.display()))?;
+
+    // Take down a holder we won't be using, along with its child and socket
+    let abandon = |child: &mut std::process::Child, error: anyhow::Error| {
+        let _ = child.kill();
+        let _ = child.wait();
+        let _ = std::fs::remove_file(&args.socket_path);
+        error
+    };
+
+    let deadline = Instant::now() + HOLDER_READY_TIMEOUT;
+    let runtime = loop {
+        if let Some(status) = child.try_wait()? {
+            anyhow::bail!("Holder exited during startup ({})", status);
+        }
+        if args.socket_path.exists() {
+            match PtyRuntime::connect_holder(args.session_id, &args.socket_path, args.tuning) {
+                Ok(runtime) => break runtime,
+                Err(e) if Instant::now() >= deadline => return Err(abandon(&mut child, e)),
+                Err(_) => {}
+            }
+        } else if Instant::now() >= deadline {
+            return Err(abandon(
+                &mut child,
+                anyhow::anyhow!(
+                    "Holder did not start listening within {:?}",
+                    HOLDER_READY_TIMEOUT
+                ),
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+
+    // Reap the holder when it exits so it doesn't linger as a zombie
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+
+    Ok(runtime)
+}
+
+/// Holder-wide events reported by connections
+enum HolderControl {
+    /// A client asked for the session to be stopped
+    Stop,
+    /// A client that saw the session exit has disconnected
+    ExitCollected,
+}
+
+/// Run a holder until its session is stopped or its exit has been collected
+pub async fn run_holder(args: HolderArgs) -> Result<()> {
+    let _ = std::fs::remove_file(&args.socket_path);
+    let listener = UnixListener::bind(&args.socket_path)
+        .with_context(|| format!("Failed to bind {}", args.socket_path.display()))?;
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&args.socket_path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    let socket_id = socket_file_id(&args.socket_path);
+
+    // The holder owns the PTY, so it keeps the log and recording even while no
+    // daemon is connected
+    let log = match args.log_dir {
+        Some(ref dir) => match OutputLog::open(dir, args.log_limits) {
+            Ok(log) => Some(log),
+            Err(e) => {
+                eprintln!("Failed to open output log {}: {}", dir.display(), e);
+                None
+            }
+        },
+        None => None,
+    };
+    let spec = SpawnSpec {
+        shell: args.shell.clone(),
+        cwd: args.cwd.clone(),
+        env: args.env.clone(),
+        tuning: args.tuning,
+    };
+    let runtime = PtyRuntime::spawn_with_outputs(
+        args.session_id,
+        &spec,
+        &args.task_key,
+        &args.project_path,
+        log,
+        args.recording.as_deref(),
+    );
+    let runtime = match runtime {
+        Ok(runtime) => Arc::new(StdMutex::new(runtime)),
+        Err(e) => {
+            let _ = std::fs::remove_file(&args.socket_path);
+            return Err(e);
+        }
+    };
+
+    let mut events = runtime.lock().unwrap().subscribe_events();
+    let (control_tx, mut control_rx) = mpsc::channel::<HolderControl>(8);
+    // Set once the child exits; the holder shuts down when it passes
+    let mut linger_until =
+        (!runtime.lock().unwrap().is_alive()).then(|| tokio::time::Instant::now() + EXIT_LINGER);
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                if let Ok((stream, _)) = accepted {
+                    // The socket sits in the runtime dir, but a shared
+                    // RIGHT_NOW_DAEMON_DIR must not hand the PTY to another user
+                    if let Some(message) = peer::peer_refusal(&stream) {
+                        eprintln!("Holder socket for session {}: {}", args.session_id, message);
+                        continue;
+                    }
+                    let runtime = Arc::clone(&runtime);
+                    let control_tx = control_tx.clone();
+                    tokio::spawn(async move {
+                        let _ = serve_connection(runtime, stream, control_tx).await;
+                    });
+                }
+            }
+            control = control_rx.recv() => match control {
+                Some(HolderControl::Stop) => {
+                    runtime.lock().unwrap().stop();
+                    break;
+                }
+                Some(HolderControl::ExitCollected) | None => break,
+            },
+            event = events.recv(), if linger_until.is_none() => match event {
+                Ok(PtyEvent::Exited { .. }) | Err(broadcast::error::RecvError::Closed) => {
+                    linger_until = Some(tokio::time::Instant::now() + EXIT_LINGER);
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {
+                    if !runtime.lock().unwrap().is_alive() {
+                        linger_until = Some(tokio::time::Instant::now() + EXIT_LINGER);
+                    }
+                }
+                Ok(_) => {}
+            },
+            _ = sleep_until(linger_until) => break,
+        }
+    }
+
+    // A restarted session's new holder may already have bound the same path
+    if socket_id.is_some() && socket_file_id(&args.socket_path) == socket_id {
+        let _ = std::fs::remove_file(&args.socket_path);
+    }
+    Ok(())
+}
+
+/// Device and inode of the socket file at `path`, to tell our socket from a successor's
+fn socket_file_id(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|m| (m.dev(), m.ino()))
+}
+
+/// Sleep until `deadline`, or forever when there is none
+async fn sleep_until(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Serve one daemon connection: hello, then events out and requests in
+async fn serve_connection(
+    runtime: Arc<StdMutex<PtyRuntime>>,
+    stream: UnixStream,
+    control_tx: mpsc::Sender<HolderControl>,
+) -> Result<()> {
+    let (hello, mut events, input_tx) = {
+        let runtime = runtime.lock().unwrap();
+        let (events, tail, screen) = runtime.subscribe_with_snapshot(HELLO_TAIL_BYTES);
+        let hello = HolderMessage::Hello {
+            session_id: runtime.session_id(),
+            pid: runtime.pid(),
+            tail,
+            screen,
+            idle: runtime.is_idle(),
+            alive: runtime.is_alive(),
+            exit_code: runtime.exit_code(),
+        };
+        (hello, events, runtime.input_sender())
+    };
+    let mut exited = matches!(hello, HolderMessage::Hello { alive: false, .. });
+
+    let (reader, mut writer) = stream.into_split();
+    writer.write_all(&encode_message(&hello)?).await?;
+    let mut lines = BufReader::new(reader).lines();
+
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let line = match line {
+                    Ok(Some(line)) => line,
+                    Ok(None) | Err(_) => break,
+                };
+                match serde_json::from_str::<HolderRequest>(&line) {
+                    Ok(HolderRequest::Input { data }) => {
+                        let _ = input_tx.send(data).await;
+                    }
+                    Ok(HolderRequest::Resize { cols, rows }) => {
+                        let _ = runtime.lock().unwrap().resize(cols, rows);
+                    }
+                    Ok(HolderRequest::Stop) => {
+                        let _ = control_tx.send(HolderControl::Stop).await;
+                        return Ok(());
+                    }
+                    Err(e) => eprintln!("Invalid holder request: {}", e),
+                }
+            }
+            event = events.recv(), if !exited => {
+                let message = match event {
+                    Ok(PtyEvent::Output(data)) => HolderMessage::Output { data },
+                    Ok(PtyEvent::Active) => HolderMessage::Active,
+                    Ok(PtyEvent::Idle) => HolderMessage::Idle,
+                    Ok(PtyEvent::Exited { exit_code }) => HolderMessage::Exited { exit_code },
+                    // A mirror that missed output can't be patched up chunk by
+                    // chunk; start it over from a fresh snapshot
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        let (resync, exited) = {
+                            let runtime = runtime.lock().unwrap();
+                            let (fresh, tail, screen) =
+                                runtime.subscribe_with_snapshot(HELLO_TAIL_BYTES);
+                            events = fresh;
+                            let resync = HolderMessage::Resync {
+                                tail,
+                                screen,
+                                idle: runtime.is_idle(),
+                            };
+                            // The exit may have been among the dropped events
+                            let exited = (!runtime.is_alive()).then(|| HolderMessage::Exited {
+                                exit_code: runtime.exit_code(),
+                            });
+                            (resync, exited)
+                        };
+                        match exited {
+                            Some(exited) => {
+                                writer.write_all(&encode_message(&resync)?).await?;
+                                exited
+                            }
+                            None => resync,
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => continue,
+                };
+                exited = matches!(message, HolderMessage::Exited { .. });
+                writer.write_all(&encode_message(&message)?).await?;
+            }
+        }
+    }
+
+    if exited {
+        let _ = control_tx.send(HolderControl::ExitCollected).await;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holder_args_round_trip() {
+        let args = HolderArgs {
+            socket_path: PathBuf::from("/tmp/right-now/holder-7.sock"),
+            session_id: 7,
+            task_key: "Write -- docs".to_string(),
+            project_path: "/tmp/TODO.md".to_string(),
+            log_dir: Some(PathBuf::from("/tmp/right-now/logs/7")),
+            log_limits: LogLimits {
+                segment_bytes: 256 * 1024,
+                retained_bytes: 4 * 1024 * 1024,
+            },
+            recording: Some(PathBuf::from("/tmp/right-now/recordings/7.cast")),
+            cwd: Some(PathBuf::from("/home/me/project")),
+            env: BTreeMap::from([("LANG".to_string(), "C.UTF-8=x".to_string())]),
+            shell: Some(vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "--help".to_string(),
+            ]),
+            tuning: RuntimeTuning {
+                idle_timeout: Duration::from_secs(90),
+                output_buffer_size: 128 * 1024,
+            },
+        };
+        // The environment goes through the holder's own, never its argv
+        let argv = args.to_args();
+        assert!(
+            !argv.iter().any(|arg| arg.contains("C.UTF-8")),
+            "{:?}",
+            argv
+        );
+        assert_eq!(
+            HolderArgs::parse(argv).unwrap(),
+            HolderArgs {
+                env: BTreeMap::new(),
+                ..args.clone()
+            }
+        );
+
+        let default_shell = HolderArgs {
+            log_dir: None,
+            log_limits: LogLimits::default(),
+            recording: None,
+            cwd: None,
+            env: BTreeMap::new(),
+            shell: None,
+            ..args
+        };
+        assert_eq!(
+            HolderArgs::parse(default_shell.to_args()).unwrap(),
+            default_shell
+        );
+    }
+
+    #[test]
+    fn output_goes_out_as_base64_and_arrays_still_parse() {
+        let frame = encode_message(&HolderMessage::Output {
+            data: b"hi\x1b[0m".to_vec(),
+        })
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(frame.clone()).unwrap(),
+            "{\"type\":\"output\",\"data\":\"aGkbWzBt\"}\n"
+        );
+
+        let older = br#"{"type":"output","data":[104,105,27,91,48,109]}"#;
+        for line in [&frame[..], &older[..]] {
+            match crate::session::protocol::deserialize_message(line).unwrap() {
+                HolderMessage::Output { data } => assert_eq!(data, b"hi\x1b[0m"),
+                other => panic!("Expected Output, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn holder_args_reject_missing_socket() {
+        let err = HolderArgs::parse(vec!["--session-id".to_string(), "1".to_string()])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("--socket"), "unexpected error: {}", err);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn daemon_reconnect_sees_running_session() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let args = HolderArgs {
+            socket_path: socket_path(temp_dir.path(), 3),
+            session_id: 3,
+            task_key: "Holder task".to_string(),
+            project_path: "/tmp/TODO.md".to_string(),
+            log_dir: Some(temp_dir.path().join("logs")),
+            log_limits: LogLimits::default(),
+            recording: None,
+            cwd: None,
+            env: BTreeMap::new(),
+            shell: Some(vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "printf 'before\\n'; read line; echo \"got $line\"; sleep 5".to_string(),
+            ]),
+            tuning: RuntimeTuning::default(),
+        };
+        let holder = tokio::spawn(run_holder(args.clone()));
+
+        let wait_for = |runtime: &PtyRuntime, needle: &str| {
+            let deadline = Instant::now() + Duration::from_secs(3);
+            while Instant::now() < deadline {
+                let tail = runtime.get_recent_output_blocking(1024);
+                if String::from_utf8_lossy(&tail).contains(needle) {
+                    return true;
+                }
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            false
+        };
+
+        let connect = || {
+            let deadline = Instant::now() + Duration::from_secs(3);
+            loop {
+                match PtyRuntime::connect_holder(3, &args.socket_path, RuntimeTuning::default()) {
+                    Ok(runtime) => return runtime,
+                    Err(e) if Instant::now() >= deadline => panic!("connect failed: {}", e),
+                    Err(_) => std::thread::sleep(Duration::from_millis(20)),
+                }
+            }
+        };
+
+        // First "daemon" connects, sees output, then goes away
+        let first = connect();
+        assert!(first.is_held());
+        assert!(wait_for(&first, "before"));
+        drop(first);
+
+        // A reconnecting "daemon" gets the history and can keep driving the session
+        let mut second = connect();
+        assert!(second.is_alive());
+        assert!(second.pid().is_some());
+        assert!(wait_for(&second, "before"));
+        assert_eq!(second.screen_text().lines[0], "before");
+        second
+            .send_input(b"again\r".to_vec())
+            .await
+            .expect("send input");
+        assert!(wait_for(&second, "got again"));
+
+        // The holder logged everything, including output from before the reconnect
+        let logged = crate::session::output_log::read_log(&temp_dir.path().join("logs"), 0, 4096)
+            .expect("holder keeps an output log");
+        let logged = String::from_utf8_lossy(&logged.data);
+        assert!(logged.contains("before") && logged.contains("got again"));
+
+        second.stop();
+        tokio::time::timeout(Duration::from_secs(3), holder)
+            .await
+            .expect("holder should exit after stop")
+            .unwrap()
+            .unwrap();
+        assert!(!args.socket_path.exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn lagging_connection_resyncs_instead_of_stopping() {
+        use crate::session::protocol::SessionStatus;
+        use std::io::BufRead;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let args = HolderArgs {
+            socket_path: socket_path(temp_dir.path(), 4),
+            session_id: 4,
+            task_key: "Flood".to_string(),
+            project_path: "/tmp/TODO.md".to_string(),
+            log_dir: None,
+            log_limits: LogLimits::default(),
+            recording: None,
+            cwd: None,
+            env: BTreeMap::new(),
+            shell: Some(vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "sleep 0.3; yes flood | head -c 1500000; echo flood-done; sleep 30".to_string(),
+            ]),
+            tuning: RuntimeTuning::default(),
+        };
+        let holder = tokio::spawn(run_holder(args.clone()));
+
+        let deadline = Instant::now() + Duration::from_secs(3);
+        let stream = loop {
+            match std::os::unix::net::UnixStream::connect(&args.socket_path) {
+                Ok(stream) => break stream,
+                Err(e) if Instant::now() >= deadline => panic!("connect failed: {}", e),
+                Err(_) => std::thread::sleep(Duration::from_millis(20)),
+            }
+        };
+        // A daemon's mirror, reading as fast as it can
+        let mirror =
+            PtyRuntime::connect_holder(4, &args.socket_path, RuntimeTuning::default()).unwrap();
+        // Don't read while the output floods far past the event channel
+        std::thread::sleep(Duration::from_secs(2));
+
+        let socket = args.socket_path.clone();
+        let messages = tokio::task::spawn_blocking(move || {
+            stream
+                .set_read_timeout(Some(Duration::from_secs(10)))
+                .unwrap();
+            let mut lines = std::io::BufReader::new(stream).lines();
+            let mut seen = Vec::new();
+            while let Some(Ok(line)) = lines.next() {
+                let message: HolderMessage = serde_json::from_str(&line).unwrap();
+                let done = match &message {
+                    HolderMessage::Output { data } | HolderMessage::Resync { tail: data, .. } => {
+                        String::from_utf8_lossy(data).contains("flood-done")
+                    }
+                    _ => false,
+                };
+                seen.push(message);
+                if done {
+                    break;
+                }
+            }
+
+            // The lagging mirror was started over, and the session is still running
+            let runtime = PtyRuntime::connect_holder(4, &socket, RuntimeTuning::default())
+                .expect("holder still serving");
+            (seen, runtime)
+        })
+        .await
+        .unwrap();
+        let (seen, mut runtime) = messages;
+        assert!(seen
+            .iter()
+            .any(|message| matches!(message, HolderMessage::Resync { .. })));
+        assert!(!seen
+            .iter()
+            .any(|message| matches!(message, HolderMessage::Exited { .. })));
+        assert!(runtime.is_alive());
+        assert_ne!(runtime.inferred_status(), SessionStatus::Stopped);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while Instant::now() < deadline {
+            let tail = mirror.get_recent_output_blocking(1024);
+            if String::from_utf8_lossy(&tail).contains("flood-done") {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        let tail = mirror.get_recent_output_blocking(1024);
+        assert!(String::from_utf8_lossy(&tail).contains("flood-done"));
+        assert!(mirror.is_alive());
+        drop(mirror);
+
+        runtime.stop();
+        tokio::time::timeout(Duration::from_secs(3), holder)
+            .await
+            .expect("holder should exit after stop")
+            .unwrap()
+            .unwrap();
+    }
+}
@@ -0,0 +1,322 @@
+// Event journal for the daemon
+//
+// Appends every notification the daemon broadcasts to JSONL segment files
+// under the state directory, so clients can find out what happened while they
+// weren't listening, even across daemon restarts. Each line is a
+// `JournalEvent`. Segments are named after the seq of their first event, rotate
+// at a fixed size, and the oldest are deleted once too many accumulate.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use super::protocol::{JournalEvent, SubscriptionFilter};
+
+/// Size at which the current segment is closed and a new one started
+pub const DEFAULT_SEGMENT_BYTES: u64 = 1024 * 1024; // 1MB
+
+/// Segments kept before the oldest is deleted
+pub const DEFAULT_RETAINED_SEGMENTS: usize = 8;
+
+/// Events returned by a query that doesn't set a limit
+pub const DEFAULT_EVENTS_LIMIT: usize = 200;
+
+/// Most events returned by a single query, keeping responses within frame limits
+pub const MAX_EVENTS_LIMIT: usize = 1000;
+
+const SEGMENT_EXTENSION: &str = "jsonl";
+
+/// Rotation and retention settings for a journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JournalLimits {
+    pub segment_bytes: u64,
+    pub retained_segments: usize,
+}
+
+impl Default for JournalLimits {
+    fn default() -> Self {
+        Self {
+            segment_bytes: DEFAULT_SEGMENT_BYTES,
+            retained_segments: DEFAULT_RETAINED_SEGMENTS,
+        }
+    }
+}
+
+/// Append-only writer for the daemon's event journal
+pub struct EventJournal {
+    dir: PathBuf,
+    limits: JournalLimits,
+    /// First seqs of the segments on disk, oldest first
+    segments: Vec<u64>,
+    current: File,
+    current_len: u64,
+    last_seq: u64,
+}
+
+impl EventJournal {
+    /// Open the journal in `dir`, continuing after the last event it holds
+    pub fn open(dir: &Path, limits: JournalLimits) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let mut segments = list_segments(dir)?;
+        if segments.is_empty() {
+            segments.push(1);
+        }
+        let first_seq = *segments.last().unwrap();
+
+        let mut current = open_segment(dir, first_seq)?;
+        let last_seq = last_seq_in(&segment_path(dir, first_seq))?.unwrap_or(first_seq - 1);
+        let mut current_len = current.metadata()?.len();
+        // A crash mid-write leaves a partial line; don't glue the next event to it
+        if current_len > 0 && !ends_with_newline(&segment_path(dir, first_seq))? {
+            current.write_all(b"\n")?;
+            current_len += 1;
+        }
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            limits,
+            segments,
+            current,
+            current_len,
+            last_seq,
+        })
+    }
+
+    /// Seq of the newest event (0 for an empty journal)
+    pub fn last_seq(&self) -> u64 {
+        self.last_seq
+    }
+
+    /// Append an event, whose seq must follow `last_seq`
+    ///
+    /// The seq is taken even if the write fails, so seqs stay unique.
+    pub fn append(&mut self, event: &JournalEvent) -> io::Result<()> {
+        self.last_seq = event.seq;
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+        self.current.write_all(&line)?;
+        self.current_len += line.len() as u64;
+        self.rotate_if_full()
+    }
+
+    fn rotate_if_full(&mut self) -> io::Result<()> {
+        if self.current_len < self.limits.segment_bytes {
+            return Ok(());
+        }
+
+        let next_seq = self.last_seq + 1;
+        self.current = open_segment(&self.dir, next_seq)?;
+        self.current_len = 0;
+        self.segments.push(next_seq);
+
+        while self.segments.len() > self.limits.retained_segments.max(1) {
+            let oldest = self.segments.remove(0);
+            match fs::remove_file(segment_path(&self.dir, oldest)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read up to `limit` events after `since_seq` matching `filter`, oldest first
+///
+/// Events older than the oldest retained segment are gone; lines that don't
+/// parse (a write in progress, or one cut short by a crash) are skipped.
+pub fn read_events(
+    dir: &Path,
+    since_seq: u64,
+    limit: usize,
+    filter: &SubscriptionFilter,
+) -> io::Result<Vec<JournalEvent>> {
+    let segments = match list_segments(dir) {
+        Ok(segments) => segments,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let limit = limit.min(MAX_EVENTS_LIMIT);
+
+    let mut events = Vec::new();
+    for (i, &first_seq) in segments.iter().enumerate() {
+        // Skip segments that end before the cursor
+        if segments
+            .get(i + 1)
+            .is_some_and(|&next| next <= since_seq + 1)
+        {
+            continue;
+        }
+        let file = match File::open(segment_path(dir, first_seq)) {
+            Ok(file) => file,
+            // Pruned by the writer since the listing; later segments still count
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        for line in BufReader::new(file).lines() {
+            if events.len() >= limit {
+                return Ok(events);
+            }
+            let Ok(event) = serde_json::from_str::<JournalEvent>(&line?) else {
+                continue;
+            };
+            if event.seq > since_seq
+                && filter.accepts(event.project_path.as_deref(), &event.notification)
+            {
+                events.push(event);
+            }
+        }
+    }
+    Ok(events)
+}
+
+fn segment_path(dir: &Path, first_seq: u64) -> PathBuf {
+    dir.join(format!("{:020}.{}", first_seq, SEGMENT_EXTENSION))
+}
+
+fn open_segment(dir: &Path, first_seq: u64) -> io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(segment_path(dir, first_seq))
+}
+
+/// Seq of the last complete event in a segment
+fn last_seq_in(path: &Path) -> io::Result<Option<u64>> {
+    let mut last = None;
+    for line in BufReader::new(File::open(path)?).lines() {
+        if let Ok(event) = serde_json::from_str::<JournalEvent>(&line?) {
+            last = Some(event.seq);
+        }
+    }
+    Ok(last)
+}
+
+fn ends_with_newline(path: &Path) -> io::Result<bool> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::End(-1))?;
+    let mut byte = [0u8; 1];
+    file.read_exact(&mut byte)?;
+    Ok(byte[0] == b'\n')
+}
+
+/// First seqs of the segments in `dir`, oldest first
+fn list_segments(dir: &Path) -> io::Result<Vec<u64>> {
+    let mut segments = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(SEGMENT_EXTENSION) {
+            continue;
+        }
+        if let Some(first_seq) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.parse::<u64>().ok())
+        {
+            segments.push(first_seq);
+        }
+    }
+    segments.sort_unstable();
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::protocol::DaemonNotification;
+    use tempfile::TempDir;
+
+    const SMALL: JournalLimits = JournalLimits {
+        segment_bytes: 200,
+        retained_segments: 3,
+    };
+
+    fn removed(journal: &mut EventJournal, project: &str, session_id: u64) {
+        let event = JournalEvent {
+            seq: journal.last_seq() + 1,
+            at: chrono::Utc::now(),
+            project_path: Some(project.to_string()),
+            notification: DaemonNotification::SessionRemoved { session_id },
+        };
+        journal.append(&event).unwrap();
+    }
+
+    fn seqs(events: &[JournalEvent]) -> Vec<u64> {
+        events.iter().map(|event| event.seq).collect()
+    }
+
+    #[test]
+    fn reads_after_cursor_with_filter_and_limit() {
+        let dir = TempDir::new().unwrap();
+        let limits = JournalLimits {
+            retained_segments: 10,
+            ..SMALL
+        };
+        let mut journal = EventJournal::open(dir.path(), limits).unwrap();
+        for session_id in 1..=6 {
+            let project = if session_id % 2 == 0 { "/a" } else { "/b" };
+            removed(&mut journal, project, session_id);
+        }
+        assert!(list_segments(dir.path()).unwrap().len() > 1);
+
+        let all = SubscriptionFilter::default();
+        assert_eq!(
+            seqs(&read_events(dir.path(), 0, 100, &all).unwrap()),
+            [1, 2, 3, 4, 5, 6]
+        );
+        assert_eq!(
+            seqs(&read_events(dir.path(), 4, 100, &all).unwrap()),
+            [5, 6]
+        );
+        assert_eq!(seqs(&read_events(dir.path(), 1, 2, &all).unwrap()), [2, 3]);
+
+        let project_a = SubscriptionFilter {
+            projects: vec!["/a".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            seqs(&read_events(dir.path(), 0, 100, &project_a).unwrap()),
+            [2, 4, 6]
+        );
+    }
+
+    #[test]
+    fn reopening_continues_seqs_and_prunes_old_segments() {
+        let dir = TempDir::new().unwrap();
+        {
+            let mut journal = EventJournal::open(dir.path(), SMALL).unwrap();
+            removed(&mut journal, "/a", 1);
+        }
+        // A write cut short by a crash
+        let first = segment_path(dir.path(), 1);
+        OpenOptions::new()
+            .append(true)
+            .open(&first)
+            .unwrap()
+            .write_all(b"{\"seq\":2,\"at\"")
+            .unwrap();
+
+        let mut journal = EventJournal::open(dir.path(), SMALL).unwrap();
+        assert_eq!(journal.last_seq(), 1);
+        for session_id in 2..=20 {
+            removed(&mut journal, "/a", session_id);
+        }
+
+        let events = read_events(dir.path(), 0, 100, &SubscriptionFilter::default()).unwrap();
+        assert_eq!(list_segments(dir.path()).unwrap().len(), 3);
+        assert!(events[0].seq > 1);
+        assert_eq!(events.last().unwrap().seq, 20);
+        assert!(events.windows(2).all(|pair| pair[1].seq == pair[0].seq + 1));
+    }
+
+    #[test]
+    fn missing_journal_has_no_events() {
+        let dir = TempDir::new().unwrap();
+        let events = read_events(
+            &dir.path().join("events"),
+            0,
+            10,
+            &SubscriptionFilter::default(),
+        );
+        assert!(events.unwrap().is_empty());
+    }
+}
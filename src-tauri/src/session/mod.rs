@@ -3,13 +3,30 @@
 
 pub mod attention;
 pub mod config;
+pub mod foreground;
+pub mod journal;
 pub mod markdown;
 pub mod notify;
+pub mod output_log;
 pub mod persistence;
 pub mod protocol;
+pub mod recording;
 pub mod runtime;
+pub mod screen;
 pub mod shell_integration;
 
 // Daemon client (Unix only for now)
 #[cfg(unix)]
 pub mod daemon_client;
+
+// Per-session PTY holder processes (Unix only for now)
+#[cfg(unix)]
+pub mod holder;
+
+// Daemon-to-daemon session handoff for upgrades (Unix only)
+#[cfg(unix)]
+pub mod handoff;
+
+// Peer uid checks on accepted socket connections (Unix only)
+#[cfg(unix)]
+pub mod peer;
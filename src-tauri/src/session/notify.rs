@@ -11,25 +11,44 @@ use std::time::{Duration, Instant};
 
 use crate::session::protocol::AttentionType;
 
-/// Minimum time between notifications for the same session
-const DEBOUNCE_DURATION: Duration = Duration::from_secs(5);
+/// Default minimum time between notifications for the same session
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_secs(5);
 
 /// Tracks the last notification time for debouncing
-#[derive(Default)]
 pub struct NotificationDebouncer {
     last_notify: Option<Instant>,
+    cooldown: Duration,
+}
+
+impl Default for NotificationDebouncer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl NotificationDebouncer {
     pub fn new() -> Self {
-        Self { last_notify: None }
+        Self::with_cooldown(DEFAULT_DEBOUNCE)
+    }
+
+    /// Debounce with a cooldown other than the default
+    pub fn with_cooldown(cooldown: Duration) -> Self {
+        Self {
+            last_notify: None,
+            cooldown,
+        }
+    }
+
+    /// Change the cooldown, keeping the time of the last notification
+    pub fn set_cooldown(&mut self, cooldown: Duration) {
+        self.cooldown = cooldown;
     }
 
     /// Returns true if enough time has passed since the last notification
     pub fn should_notify(&mut self) -> bool {
         let now = Instant::now();
         match self.last_notify {
-            Some(last) if now.duration_since(last) < DEBOUNCE_DURATION => false,
+            Some(last) if now.duration_since(last) < self.cooldown => false,
             _ => {
                 self.last_notify = Some(now);
                 true
@@ -205,6 +224,16 @@ mod tests {
         assert!(debouncer.should_notify());
     }
 
+    #[test]
+    fn debouncer_without_cooldown_allows_every_notification() {
+        let mut debouncer = NotificationDebouncer::with_cooldown(Duration::ZERO);
+        assert!(debouncer.should_notify());
+        assert!(debouncer.should_notify());
+
+        debouncer.set_cooldown(Duration::from_secs(60));
+        assert!(!debouncer.should_notify());
+    }
+
     #[test]
     fn escape_osc_removes_control_chars() {
         assert_eq!(escape_osc("hello\x07world"), "helloworld");
@@ -0,0 +1,274 @@
+// On-disk output log for PTY sessions
+//
+// Appends everything a session's PTY writes to segment files under the state
+// directory, so history outlives the daemon and reaches further back than the
+// in-memory ring buffer. Bytes are addressed by their offset from the start of
+// the session. Each segment is named after the offset of its first byte, which
+// lets readers locate any offset from the directory listing alone. Segments
+// rotate at a fixed size and the oldest are deleted once the log exceeds its
+// retention cap.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Size at which the current segment is closed and a new one started
+pub const DEFAULT_SEGMENT_BYTES: u64 = 1024 * 1024; // 1MB
+
+/// Bytes of history kept per session before the oldest segments are deleted
+pub const DEFAULT_RETAINED_BYTES: u64 = 16 * 1024 * 1024; // 16MB
+
+/// Most bytes returned by a single read, keeping responses within frame limits
+pub const MAX_LOG_READ_BYTES: usize = 1024 * 1024; // 1MB
+
+const SEGMENT_EXTENSION: &str = "log";
+
+/// Rotation and retention settings for an output log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogLimits {
+    pub segment_bytes: u64,
+    pub retained_bytes: u64,
+}
+
+impl Default for LogLimits {
+    fn default() -> Self {
+        Self {
+            segment_bytes: DEFAULT_SEGMENT_BYTES,
+            retained_bytes: DEFAULT_RETAINED_BYTES,
+        }
+    }
+}
+
+/// A range of logged output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRange {
+    /// Offset of the first byte of `data`
+    pub offset: u64,
+    pub data: Vec<u8>,
+    /// Offset of the oldest byte still retained
+    pub start: u64,
+    /// Offset just past the newest byte logged
+    pub end: u64,
+}
+
+/// Append-only writer for one session's output log
+pub struct OutputLog {
+    dir: PathBuf,
+    limits: LogLimits,
+    /// Start offsets of the segments on disk, oldest first
+    segments: Vec<u64>,
+    current: File,
+    end: u64,
+}
+
+impl OutputLog {
+    /// Open the log in `dir`, continuing after whatever it already holds
+    pub fn open(dir: &Path, limits: LogLimits) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let existing = list_segments(dir)?;
+
+        let (segments, end) = match existing.last() {
+            Some(&(start, len)) => (existing.iter().map(|&(s, _)| s).collect(), start + len),
+            None => (vec![0], 0),
+        };
+        let current = open_segment(dir, *segments.last().unwrap())?;
+
+        let mut log = Self {
+            dir: dir.to_path_buf(),
+            limits,
+            segments,
+            current,
+            end,
+        };
+        log.rotate_if_full()?;
+        Ok(log)
+    }
+
+    /// Append output to the log
+    pub fn append(&mut self, data: &[u8]) -> io::Result<()> {
+        self.current.write_all(data)?;
+        self.end += data.len() as u64;
+        self.rotate_if_full()
+    }
+
+    /// Offset just past the newest byte logged
+    pub fn end_offset(&self) -> u64 {
+        self.end
+    }
+
+    fn rotate_if_full(&mut self) -> io::Result<()> {
+        let current_start = *self.segments.last().unwrap();
+        if self.end - current_start < self.limits.segment_bytes {
+            return Ok(());
+        }
+
+        self.current = open_segment(&self.dir, self.end)?;
+        self.segments.push(self.end);
+
+        // Drop whole segments while the rest still cover the retention cap
+        while self.segments.len() > 1 && self.end - self.segments[1] >= self.limits.retained_bytes {
+            let oldest = self.segments.remove(0);
+            match fs::remove_file(segment_path(&self.dir, oldest)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read up to `max_len` bytes of the log in `dir`, starting at `offset`
+///
+/// Offsets older than the oldest retained byte start at that byte instead;
+/// offsets past the end return no data. Reads are capped at
+/// `MAX_LOG_READ_BYTES`.
+pub fn read_log(dir: &Path, offset: u64, max_len: usize) -> io::Result<LogRange> {
+    let segments = list_segments(dir)?;
+    let (Some(&(start, _)), Some(&(last_start, last_len))) = (segments.first(), segments.last())
+    else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No output log in {}", dir.display()),
+        ));
+    };
+    let end = last_start + last_len;
+    let offset = offset.clamp(start, end);
+    let want = (max_len.min(MAX_LOG_READ_BYTES) as u64).min(end - offset) as usize;
+
+    let mut data = Vec::with_capacity(want);
+    for &(segment_start, segment_len) in &segments {
+        let position = offset + data.len() as u64;
+        if data.len() >= want || segment_start + segment_len <= position {
+            continue;
+        }
+        let mut file = match File::open(segment_path(dir, segment_start)) {
+            Ok(file) => file,
+            // Pruned by the writer since the listing; later segments still count
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        file.seek(SeekFrom::Start(position.saturating_sub(segment_start)))?;
+        file.take((want - data.len()) as u64)
+            .read_to_end(&mut data)?;
+    }
+
+    Ok(LogRange {
+        offset,
+        data,
+        start,
+        end,
+    })
+}
+
+/// Delete the log in `dir`, if any
+pub fn remove_log(dir: &Path) -> io::Result<()> {
+    match fs::remove_dir_all(dir) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn segment_path(dir: &Path, start: u64) -> PathBuf {
+    dir.join(format!("{:020}.{}", start, SEGMENT_EXTENSION))
+}
+
+fn open_segment(dir: &Path, start: u64) -> io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(segment_path(dir, start))
+}
+
+/// Segments in `dir` as (start offset, length), oldest first
+fn list_segments(dir: &Path) -> io::Result<Vec<(u64, u64)>> {
+    let mut segments = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(SEGMENT_EXTENSION) {
+            continue;
+        }
+        let Some(start) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.parse::<u64>().ok())
+        else {
+            continue;
+        };
+        match entry.metadata() {
+            Ok(metadata) => segments.push((start, metadata.len())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    segments.sort_unstable();
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const SMALL: LogLimits = LogLimits {
+        segment_bytes: 10,
+        retained_bytes: 25,
+    };
+
+    #[test]
+    fn reads_across_segments_by_offset() {
+        let dir = TempDir::new().unwrap();
+        let mut log = OutputLog::open(dir.path(), SMALL).unwrap();
+        log.append(b"0123456789").unwrap();
+        log.append(b"abcdef").unwrap();
+        log.append(b"ghij").unwrap();
+        assert_eq!(log.end_offset(), 20);
+        assert_eq!(list_segments(dir.path()).unwrap().len(), 3);
+
+        let range = read_log(dir.path(), 7, 6).unwrap();
+        assert_eq!(range.offset, 7);
+        assert_eq!(range.data, b"789abc");
+        assert_eq!((range.start, range.end), (0, 20));
+
+        // Reading past the end returns nothing
+        assert!(read_log(dir.path(), 50, 10).unwrap().data.is_empty());
+    }
+
+    #[test]
+    fn prunes_oldest_segments_beyond_retention() {
+        let dir = TempDir::new().unwrap();
+        let mut log = OutputLog::open(dir.path(), SMALL).unwrap();
+        for chunk in [b"aaaaaaaaaa", b"bbbbbbbbbb", b"cccccccccc", b"dddddddddd"] {
+            log.append(chunk).unwrap();
+        }
+
+        // Offsets before the retained window start at its first byte
+        let range = read_log(dir.path(), 0, 100).unwrap();
+        assert_eq!(range.start, 10);
+        assert_eq!(range.offset, 10);
+        assert_eq!(range.data, b"bbbbbbbbbbccccccccccdddddddddd");
+    }
+
+    #[test]
+    fn reopening_continues_offsets() {
+        let dir = TempDir::new().unwrap();
+        OutputLog::open(dir.path(), SMALL)
+            .unwrap()
+            .append(b"before")
+            .unwrap();
+
+        let mut log = OutputLog::open(dir.path(), SMALL).unwrap();
+        assert_eq!(log.end_offset(), 6);
+        log.append(b"-after").unwrap();
+
+        let range = read_log(dir.path(), 0, 100).unwrap();
+        assert_eq!(range.data, b"before-after");
+    }
+
+    #[test]
+    fn missing_log_is_not_found() {
+        let dir = TempDir::new().unwrap();
+        let err = read_log(dir.path(), 0, 10).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
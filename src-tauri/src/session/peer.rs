@@ -0,0 +1,61 @@
+// Peer credential checks for the daemon's sockets
+//
+// The runtime directory is private to the user running the daemon, but that
+// alone doesn't hold when `RIGHT_NOW_DAEMON_DIR` points somewhere shared, so
+// every connection accepted on the control socket or an attach socket is also
+// checked against the daemon's own uid (SO_PEERCRED on Linux, getpeereid on
+// macOS).
+
+use tokio::net::UnixStream;
+
+/// Effective uid of this process
+pub fn current_uid() -> u32 {
+    // SAFETY: geteuid takes no arguments, touches no memory and cannot fail
+    unsafe { libc::geteuid() }
+}
+
+/// Why the peer on `stream` may not use the daemon, if it may not
+pub fn peer_refusal(stream: &UnixStream) -> Option<String> {
+    refusal(stream.peer_cred().map(|cred| cred.uid()), current_uid())
+}
+
+/// Why a peer running as `peer_uid` may not use a daemon running as `uid`
+fn refusal(peer_uid: std::io::Result<u32>, uid: u32) -> Option<String> {
+    match peer_uid {
+        Ok(peer_uid) if peer_uid == uid => None,
+        Ok(peer_uid) => Some(format!(
+            "Connection refused: peer uid {} is not the daemon's uid {}",
+            peer_uid, uid
+        )),
+        Err(e) => Some(format!(
+            "Connection refused: could not read peer credentials: {}",
+            e
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_own_connections_are_allowed() {
+        let (client, server) = UnixStream::pair().unwrap();
+        assert_eq!(peer_refusal(&server), None);
+        assert_eq!(peer_refusal(&client), None);
+    }
+
+    #[test]
+    fn test_foreign_uids_are_refused() {
+        let uid = current_uid();
+        let message = refusal(Ok(uid + 1), uid).expect("foreign uid refused");
+        assert!(
+            message.contains(&format!("peer uid {}", uid + 1)),
+            "{}",
+            message
+        );
+
+        let unreadable = std::io::Error::other("no creds");
+        assert!(refusal(Err(unreadable), uid).is_some());
+    }
+}
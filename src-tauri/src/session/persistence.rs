@@ -2,21 +2,83 @@
 // Sessions are persisted to $APPDATA/right-now/sessions.json with file locking
 
 use crate::session::config::Config;
-use crate::session::protocol::{Session, SessionId};
+use crate::session::protocol::{AttentionId, AttentionItem, AttentionSummary, Session, SessionId};
 use anyhow::{Context, Result};
 use fs2::FileExt;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::{self, File, OpenOptions};
 use std::io::{Read, Write};
 use std::path::PathBuf;
 
+/// Attention events kept in the inbox; beyond this the oldest go, acknowledged ones first
+pub const MAX_ATTENTION_ITEMS: usize = 500;
+
 /// Session registry persisted to disk
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct SessionRegistry {
     /// Next session ID to assign
     pub next_id: SessionId,
     /// Map of session ID to session data
+    #[serde(with = "session_records")]
     pub sessions: HashMap<SessionId, Session>,
+    /// Attention inbox, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attention: Vec<AttentionItem>,
+    /// Last attention ID assigned
+    #[serde(default)]
+    pub last_attention_id: AttentionId,
+}
+
+/// Sessions as stored in sessions.json: the protocol type plus the environment
+/// snapshot, which `Session` leaves out of everything sent to clients
+mod session_records {
+    use super::*;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize)]
+    struct RecordRef<'a> {
+        #[serde(flatten)]
+        session: &'a Session,
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        env: &'a BTreeMap<String, String>,
+    }
+
+    #[derive(Deserialize)]
+    struct Record {
+        #[serde(flatten)]
+        session: Session,
+        #[serde(default)]
+        env: BTreeMap<String, String>,
+    }
+
+    pub fn serialize<S: Serializer>(
+        sessions: &HashMap<SessionId, Session>,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_map(sessions.iter().map(|(id, session)| {
+            (
+                id,
+                RecordRef {
+                    session,
+                    env: &session.env,
+                },
+            )
+        }))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<HashMap<SessionId, Session>, D::Error> {
+        let records = HashMap::<SessionId, Record>::deserialize(deserializer)?;
+        Ok(records
+            .into_iter()
+            .map(|(id, record)| {
+                let mut session = record.session;
+                session.env = record.env;
+                (id, session)
+            })
+            .collect())
+    }
 }
 
 impl SessionRegistry {
@@ -132,6 +194,74 @@ impl SessionRegistry {
             s.project_path == project_path && s.task_key.to_lowercase() == task_key.to_lowercase()
         })
     }
+
+    /// Record an attention event as a session's latest and add it to the inbox
+    ///
+    /// Returns the summary with its inbox ID, or None if there is no such session.
+    pub fn add_attention(
+        &mut self,
+        session_id: SessionId,
+        mut summary: AttentionSummary,
+    ) -> Option<AttentionSummary> {
+        let session = self.sessions.get_mut(&session_id)?;
+        self.last_attention_id += 1;
+        summary.id = self.last_attention_id;
+        summary.acked = false;
+        session.last_attention = Some(summary.clone());
+        self.attention.push(AttentionItem {
+            session_id,
+            task_key: session.task_key.clone(),
+            project_path: session.project_path.clone(),
+            summary: summary.clone(),
+        });
+
+        if self.attention.len() > MAX_ATTENTION_ITEMS {
+            let oldest = self
+                .attention
+                .iter()
+                .position(|item| item.summary.acked)
+                .unwrap_or(0);
+            self.attention.remove(oldest);
+        }
+        Some(summary)
+    }
+
+    /// Inbox items, newest first, optionally only a project's and only unacknowledged ones
+    pub fn attention_items(
+        &self,
+        project_path: Option<&str>,
+        include_acked: bool,
+    ) -> Vec<AttentionItem> {
+        self.attention
+            .iter()
+            .rev()
+            .filter(|item| project_path.is_none_or(|path| item.project_path == path))
+            .filter(|item| include_acked || !item.summary.acked)
+            .cloned()
+            .collect()
+    }
+
+    /// Acknowledge the unacknowledged inbox items `select` picks, returning their IDs
+    pub fn ack_attention(&mut self, select: impl Fn(&AttentionItem) -> bool) -> Vec<AttentionId> {
+        let mut acked = Vec::new();
+        for item in &mut self.attention {
+            if item.summary.acked || !select(item) {
+                continue;
+            }
+            item.summary.acked = true;
+            acked.push(item.summary.id);
+            // The session's latest event is the same one
+            if let Some(last) = self
+                .sessions
+                .get_mut(&item.session_id)
+                .and_then(|session| session.last_attention.as_mut())
+                .filter(|last| last.id == item.summary.id)
+            {
+                last.acked = true;
+            }
+        }
+        acked
+    }
 }
 
 /// Atomically save data to a file using write-to-temp + rename
@@ -169,7 +299,7 @@ pub fn atomic_write(path: &PathBuf, contents: &str) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::session::protocol::SessionStatus;
+    use crate::session::protocol::{AttentionType, SessionStatus};
     use tempfile::TempDir;
 
     fn test_config() -> (Config, TempDir) {
@@ -210,6 +340,32 @@ mod tests {
         assert_eq!(loaded_session.status, SessionStatus::Running);
     }
 
+    #[test]
+    fn test_env_is_persisted_but_not_sent() {
+        let (config, _temp) = test_config();
+        let mut registry = SessionRegistry::default();
+
+        let id = registry.allocate_id();
+        let mut session = Session::new(id, "Env".to_string(), None, "/test/TODO.md".to_string());
+        session.env = [("RN_FLAVOR".to_string(), "mint".to_string())].into();
+        let wire = serde_json::to_string(&session).unwrap();
+        assert!(!wire.contains("RN_FLAVOR"), "env leaked: {}", wire);
+        registry.insert(session);
+
+        registry.save(&config).unwrap();
+
+        let loaded = SessionRegistry::load(&config).unwrap();
+        assert_eq!(
+            loaded
+                .get(id)
+                .unwrap()
+                .env
+                .get("RN_FLAVOR")
+                .map(String::as_str),
+            Some("mint")
+        );
+    }
+
     #[test]
     fn test_find_by_task_key() {
         let mut registry = SessionRegistry::default();
@@ -280,6 +436,83 @@ mod tests {
         assert_eq!(found.unwrap().task_key, "Build feature - backend");
     }
 
+    #[test]
+    fn test_attention_inbox_roundtrip_and_ack() {
+        let (config, _temp) = test_config();
+        let mut registry = SessionRegistry::default();
+        for (task, project) in [("Build", "/a/TODO.md"), ("Deploy", "/b/TODO.md")] {
+            let id = registry.allocate_id();
+            registry.insert(Session::new(
+                id,
+                task.to_string(),
+                None,
+                project.to_string(),
+            ));
+        }
+        let summary = |preview: &str| AttentionSummary {
+            id: 0,
+            profile: "build-tools".to_string(),
+            attention_type: AttentionType::Error,
+            preview: preview.to_string(),
+            triggered_at: chrono::Utc::now(),
+            acked: false,
+        };
+        let first = registry.add_attention(0, summary("error: one")).unwrap();
+        let second = registry.add_attention(1, summary("error: two")).unwrap();
+        let third = registry.add_attention(0, summary("error: three")).unwrap();
+        assert!(registry.add_attention(7, summary("nobody")).is_none());
+        assert_eq!((first.id, second.id, third.id), (1, 2, 3));
+
+        registry.save(&config).unwrap();
+        let mut registry = SessionRegistry::load(&config).unwrap();
+        let ids = |items: Vec<AttentionItem>| -> Vec<AttentionId> {
+            items.iter().map(|item| item.summary.id).collect()
+        };
+        assert_eq!(ids(registry.attention_items(None, false)), vec![3, 2, 1]);
+        assert_eq!(
+            ids(registry.attention_items(Some("/b/TODO.md"), false)),
+            vec![2]
+        );
+        assert_eq!(registry.attention_items(None, false)[0].task_key, "Build");
+
+        // Acking a session's items marks its latest event seen too
+        assert_eq!(
+            registry.ack_attention(|item| item.session_id == 0),
+            vec![1, 3]
+        );
+        assert!(
+            registry
+                .get(0)
+                .unwrap()
+                .last_attention
+                .as_ref()
+                .unwrap()
+                .acked
+        );
+        assert!(
+            !registry
+                .get(1)
+                .unwrap()
+                .last_attention
+                .as_ref()
+                .unwrap()
+                .acked
+        );
+        assert_eq!(ids(registry.attention_items(None, false)), vec![2]);
+        assert_eq!(ids(registry.attention_items(None, true)), vec![3, 2, 1]);
+        assert!(registry
+            .ack_attention(|item| item.session_id == 0)
+            .is_empty());
+
+        // A full inbox drops acknowledged items before unseen ones
+        for n in 1..MAX_ATTENTION_ITEMS {
+            registry.add_attention(1, summary(&format!("error: {}", n)));
+        }
+        assert_eq!(registry.attention.len(), MAX_ATTENTION_ITEMS);
+        assert_eq!(registry.attention[0].summary.id, 2);
+        assert_eq!(registry.attention[1].summary.id, 4);
+    }
+
     #[test]
     fn test_atomic_write() {
         let temp_dir = TempDir::new().unwrap();
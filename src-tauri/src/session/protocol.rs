@@ -3,13 +3,19 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 use crate::context_resurrection::models::ContextSnapshotV1;
+use crate::session::config::Settings;
 
 /// Protocol version for daemon <-> client communication
-/// Increment when making breaking changes to the protocol
+/// Increment when making breaking changes to the protocol; additions are
+/// announced as a `Capability` instead
 pub const PROTOCOL_VERSION: u32 = 1;
 
+/// Oldest protocol version this build still speaks
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
 /// Maximum size for incoming request frames (server-side limit)
 /// Reject frames larger than 1MB to prevent memory exhaustion
 pub const MAX_REQUEST_FRAME_SIZE: usize = 1024 * 1024; // 1 MB
@@ -56,6 +62,9 @@ impl std::str::FromStr for SessionStatus {
 /// Unique session identifier
 pub type SessionId = u64;
 
+/// Client-chosen identifier matching a response to its request
+pub type RequestId = u64;
+
 /// Types of attention events detected in output.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -77,13 +86,70 @@ impl std::fmt::Display for AttentionType {
     }
 }
 
+/// ID of an attention event in the daemon's inbox, unique across sessions
+pub type AttentionId = u64;
+
 /// Summary of an attention event.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AttentionSummary {
+    /// Inbox ID (0 for events recorded before the inbox)
+    #[serde(default)]
+    pub id: AttentionId,
     pub profile: String,
     pub attention_type: AttentionType,
     pub preview: String,
     pub triggered_at: DateTime<Utc>,
+    /// Seen: acknowledged in the inbox, or the session was attached since
+    #[serde(default)]
+    pub acked: bool,
+}
+
+/// An attention event in the daemon's inbox, with the session it came from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttentionItem {
+    pub session_id: SessionId,
+    pub task_key: String,
+    pub project_path: String,
+    #[serde(flatten)]
+    pub summary: AttentionSummary,
+}
+
+/// How a client attaches to a session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachMode {
+    /// Typing alongside any other attached clients
+    #[default]
+    Shared,
+    /// The only client: detaches the others, and refuses new ones until it detaches
+    Exclusive,
+    /// Watching only; input from the client is dropped
+    ReadOnly,
+}
+
+impl AttachMode {
+    pub fn is_shared(&self) -> bool {
+        *self == AttachMode::Shared
+    }
+}
+
+impl std::fmt::Display for AttachMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttachMode::Shared => write!(f, "shared"),
+            AttachMode::Exclusive => write!(f, "exclusive"),
+            AttachMode::ReadOnly => write!(f, "read-only"),
+        }
+    }
+}
+
+/// A client currently attached to a session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttachViewer {
+    /// Daemon-assigned ID, unique while the daemon runs
+    pub id: u64,
+    pub mode: AttachMode,
+    pub attached_at: DateTime<Utc>,
 }
 
 /// Session metadata stored in persistence and exchanged via protocol
@@ -104,9 +170,29 @@ pub struct Session {
     /// PID of the PTY child process (if running)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pty_pid: Option<u32>,
-    /// Shell command being executed
+    /// Shell command being executed (the user's default shell when None)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub shell_command: Option<Vec<String>>,
+    /// Working directory the PTY was spawned in (the home directory when None)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spawn_cwd: Option<String>,
+    /// Filtered environment the PTY was spawned with, reapplied on restart.
+    /// Only the registry persists it; it is never sent to clients
+    #[serde(skip)]
+    pub env: BTreeMap<String, String>,
+    /// Command line of the process in the foreground of the terminal (while running)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub foreground_command: Option<String>,
+    /// Working directory of the foreground process, as last seen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Attention profiles the session was started with; when empty, profiles
+    /// are picked by the foreground command
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attention_profiles: Vec<String>,
+    /// Attention profiles checking the session's output right now (while running)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub active_attention_profiles: Vec<String>,
     /// When the session was created
     pub created_at: DateTime<Utc>,
     /// When the session was last updated
@@ -117,6 +203,12 @@ pub struct Session {
     /// Last detected attention event, if any
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub last_attention: Option<AttentionSummary>,
+    /// Session was started with its output recorded to an asciicast file
+    #[serde(default)]
+    pub recording: bool,
+    /// Clients attached right now (never persisted across daemon restarts)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub viewers: Vec<AttachViewer>,
 }
 
 impl Session {
@@ -135,10 +227,18 @@ impl Session {
             status: SessionStatus::Running,
             pty_pid: None,
             shell_command: None,
+            spawn_cwd: None,
+            env: BTreeMap::new(),
+            foreground_command: None,
+            cwd: None,
+            attention_profiles: Vec::new(),
+            active_attention_profiles: Vec::new(),
             created_at: now,
             updated_at: now,
             exit_code: None,
             last_attention: None,
+            recording: false,
+            viewers: Vec::new(),
         }
     }
 
@@ -147,6 +247,126 @@ impl Session {
     }
 }
 
+/// Synthesized redraw of a session's terminal, produced by the daemon's screen model
+///
+/// Lets attaching clients restore the exact visible screen instead of replaying
+/// raw output that may begin mid-escape-sequence.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScreenRepaint {
+    pub rows: u16,
+    pub cols: u16,
+    /// Zero-based cursor row
+    pub cursor_row: u16,
+    /// Zero-based cursor column
+    pub cursor_col: u16,
+    pub cursor_visible: bool,
+    /// Program is on the alternate screen (editors, pagers, full-screen TUIs)
+    pub alternate_screen: bool,
+    /// Window title set via OSC 0/2
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub title: String,
+    /// Primary-screen lines that scrolled off the top, oldest first, with SGR formatting
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        with = "pty_bytes::list"
+    )]
+    pub scrollback: Vec<Vec<u8>>,
+    /// Escape sequences that clear the screen, redraw every visible cell with its
+    /// attributes, and restore cursor and input modes
+    #[serde(with = "pty_bytes")]
+    pub screen: Vec<u8>,
+}
+
+/// An asciicast recording kept by the daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingInfo {
+    pub session_id: SessionId,
+    /// Task of the recorded session, if it is still in the registry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_key: Option<String>,
+    /// Absolute path to the `.cast` file
+    pub path: String,
+    pub size_bytes: u64,
+    pub modified_at: DateTime<Utc>,
+    /// The session is still running and writing to the recording
+    pub active: bool,
+}
+
+/// Rendered terminal screen as plain text, for tools that want to read what's
+/// on screen rather than parse raw output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScreenText {
+    pub rows: u16,
+    pub cols: u16,
+    /// One entry per screen row, top to bottom, with trailing blanks trimmed
+    pub lines: Vec<String>,
+    /// Zero-based cursor row
+    pub cursor_row: u16,
+    /// Zero-based cursor column
+    pub cursor_col: u16,
+    /// Window title set via OSC 0/2
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub title: String,
+    /// Program is on the alternate screen (editors, pagers, full-screen TUIs)
+    #[serde(default)]
+    pub alternate_screen: bool,
+}
+
+/// A running session transferred to a replacement daemon during an upgrade
+///
+/// File descriptors travel alongside the `HandoffReady` response over
+/// SCM_RIGHTS: the control listener first, then for each session in order its
+/// PTY master (unless `held`) and its attach listener (if `attach_listener`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandoffSession {
+    pub session_id: SessionId,
+    /// PTY is owned by a holder process; the new daemon reconnects to it
+    /// instead of receiving the PTY master
+    pub held: bool,
+    /// PID of the session's child process
+    pub pid: Option<u32>,
+    /// Recent output from the ring buffer (empty for held sessions)
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "pty_bytes")]
+    pub tail: Vec<u8>,
+    /// Current screen (None for held sessions)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screen: Option<ScreenRepaint>,
+    pub idle: bool,
+    /// An attach listener fd follows this session's PTY fd
+    pub attach_listener: bool,
+}
+
+impl ScreenRepaint {
+    /// Bytes that reproduce this screen on a real terminal
+    ///
+    /// Scrollback is printed first and pushed off the top so it lands in the
+    /// terminal's own history, then the visible cells are painted over a clean
+    /// screen (switching to the alternate screen when the program is using it)
+    /// and the window title is restored.
+    pub fn to_terminal_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        if !self.alternate_screen && !self.scrollback.is_empty() {
+            for line in &self.scrollback {
+                out.extend_from_slice(line);
+                out.extend_from_slice(b"\r\n");
+            }
+            for _ in 0..self.rows {
+                out.extend_from_slice(b"\r\n");
+            }
+        }
+        if self.alternate_screen {
+            out.extend_from_slice(b"\x1b[?1049h");
+        }
+        out.extend_from_slice(&self.screen);
+        if !self.title.is_empty() {
+            let title: String = self.title.chars().filter(|c| !c.is_control()).collect();
+            out.extend_from_slice(format!("\x1b]2;{}\x07", title).as_bytes());
+        }
+        out
+    }
+}
+
 // ============================================================================
 // Client -> Daemon requests
 // ============================================================================
@@ -157,8 +377,14 @@ impl Session {
 pub enum DaemonRequest {
     /// Protocol version handshake (must be first request from client)
     Handshake {
-        /// Client's expected protocol version
+        /// Newest protocol version the client speaks
         client_version: u32,
+        /// Oldest protocol version the client speaks (defaults to `client_version`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_version: Option<u32>,
+        /// Encoding the client would like byte payloads in
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        byte_encoding: Option<ByteEncoding>,
     },
     /// Start a new session for a task
     Start {
@@ -172,6 +398,26 @@ pub enum DaemonRequest {
         /// Optional shell command to run (defaults to $SHELL)
         #[serde(skip_serializing_if = "Option::is_none")]
         shell: Option<Vec<String>>,
+        /// Working directory for the PTY (defaults to the home directory)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cwd: Option<String>,
+        /// Environment variables to set for the PTY; filtered before use and
+        /// stored with the session
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        env: BTreeMap<String, String>,
+        /// Record output and resizes to an asciicast file for later replay
+        #[serde(default)]
+        record: bool,
+        /// Only check output against these attention profiles, instead of
+        /// picking them by the foreground command
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        attention_profiles: Vec<String>,
+    },
+    /// Relaunch a stopped session's command in its original working directory
+    /// and environment, under the same session ID
+    Restart {
+        /// Session ID to restart
+        session_id: SessionId,
     },
     /// Continue/attach to an existing session
     Continue {
@@ -194,6 +440,37 @@ pub enum DaemonRequest {
         /// Number of bytes of recent output to replay before streaming live
         #[serde(skip_serializing_if = "Option::is_none")]
         tail_bytes: Option<usize>,
+        /// Replay from this offset of the on-disk output log instead, with
+        /// `tail_bytes` capping the length
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        offset: Option<u64>,
+        /// Whether the client types alongside others, alone, or not at all
+        #[serde(default, skip_serializing_if = "AttachMode::is_shared")]
+        mode: AttachMode,
+    },
+    /// Open an attach stream for a session on this connection
+    ///
+    /// Output then arrives as `StreamFrame`s on the same connection, and
+    /// input, resizes and detaching go back the same way. One connection can
+    /// carry streams for several sessions.
+    OpenStream {
+        /// Session ID to attach
+        session_id: SessionId,
+        /// Number of bytes of recent output to replay before streaming live
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tail_bytes: Option<usize>,
+        /// Replay from this offset of the on-disk output log instead, with
+        /// `tail_bytes` capping the length
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        offset: Option<u64>,
+        /// Whether the client types alongside others, alone, or not at all
+        #[serde(default, skip_serializing_if = "AttachMode::is_shared")]
+        mode: AttachMode,
+    },
+    /// Get the rendered screen of a session as text lines
+    Screen {
+        /// Session ID to render
+        session_id: SessionId,
     },
     /// Resize a running session's PTY
     Resize {
@@ -204,6 +481,16 @@ pub enum DaemonRequest {
         /// Rows of the terminal
         rows: u16,
     },
+    /// Write input to a running session's PTY without attaching
+    SendInput {
+        /// Session ID to send input to
+        session_id: SessionId,
+        /// Text to write to the PTY
+        data: String,
+        /// Append a carriage return (Enter) after the text
+        #[serde(default)]
+        append_newline: bool,
+    },
     /// Stop a running session
     Stop {
         /// Session ID to stop
@@ -216,16 +503,78 @@ pub enum DaemonRequest {
         /// Maximum bytes to return from the ring buffer
         #[serde(skip_serializing_if = "Option::is_none")]
         bytes: Option<usize>,
+        /// Read from this offset of the on-disk output log instead of the
+        /// ring buffer, with `bytes` capping the length
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        offset: Option<u64>,
     },
     /// Get status of a specific session
     Status {
         /// Session ID to query
         session_id: SessionId,
     },
+    /// List asciicast recordings of sessions
+    ListRecordings,
+    /// Delete a session's recording (the session must not be running)
+    DeleteRecording {
+        /// Session ID whose recording to delete
+        session_id: SessionId,
+    },
+    /// Receive notifications on this connection, replacing any earlier
+    /// subscription. Empty lists match everything.
+    Subscribe {
+        /// Only sessions belonging to these TODO.md files
+        #[serde(default)]
+        projects: Vec<String>,
+        /// Only these sessions
+        #[serde(default)]
+        session_ids: Vec<SessionId>,
+        /// Only these kinds of notification
+        #[serde(default)]
+        kinds: Vec<NotificationKind>,
+    },
+    /// Stop receiving notifications on this connection
+    Unsubscribe,
+    /// Full registry state, for clients recovering from a `Resync`
+    Snapshot,
+    /// Journaled notifications after `since_seq`, oldest first
+    Events {
+        #[serde(default)]
+        since_seq: u64,
+        /// Most events to return (defaults to `journal::DEFAULT_EVENTS_LIMIT`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit: Option<usize>,
+        #[serde(default)]
+        filter: SubscriptionFilter,
+    },
     /// Ping to check if daemon is alive
     Ping,
     /// Request daemon to shut down gracefully
     Shutdown,
+    /// Ask the running daemon to hand its sessions and sockets over to the
+    /// caller, a newer daemon taking its place, and then exit
+    Handoff,
+    /// Re-read config.toml and attention.toml and apply them; what is in
+    /// effect stays when either file is invalid
+    ReloadConfig,
+    /// Attention inbox items, newest first
+    AttentionList {
+        /// Only items from sessions of this project
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        project_path: Option<String>,
+        /// Include items already acknowledged
+        #[serde(default)]
+        include_acked: bool,
+    },
+    /// Acknowledge attention inbox items
+    AttentionAck { ids: Vec<AttentionId> },
+    /// Acknowledge every attention inbox item, or a project's or session's
+    AttentionAckAll {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        project_path: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session_id: Option<SessionId>,
+    },
     /// Get latest snapshot for a task (or any task if task_id is None)
     CrLatest {
         project_path: String,
@@ -261,6 +610,218 @@ pub enum DaemonRequest {
     CrDeleteProject { project_path: String },
 }
 
+/// Optional protocol features, announced by the daemon during the handshake
+///
+/// Clients check these before sending requests that an older or differently
+/// configured daemon might not handle, rather than comparing versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// `SendInput` requests
+    SendInput,
+    /// `Screen` requests
+    Screen,
+    /// `Handoff` to a replacement daemon
+    Handoff,
+    /// `Tail` and `Attach` reads from the on-disk output log (`offset`)
+    OutputLog,
+    /// Recording sessions (`Start { record }`) and managing recordings
+    Recording,
+    /// `Restart` requests
+    Restart,
+    /// `Subscribe`/`Unsubscribe` and notification envelopes
+    Subscribe,
+    /// Responses echo `request_id`, so requests can be pipelined
+    RequestIds,
+    /// Notification seqs, `Resync` and `Snapshot`
+    Snapshot,
+    /// `Events` queries against the event journal
+    Journal,
+    /// `OpenStream` and attach streams multiplexed on the connection
+    Streams,
+    /// Exclusive and read-only `mode`s for `Attach` and `OpenStream`
+    AttachModes,
+    /// `ReloadConfig` requests
+    ReloadConfig,
+    /// Binding sessions to attention profiles (`Start { attention_profiles }`)
+    AttentionProfiles,
+    /// `AttentionList`, `AttentionAck` and `AttentionAckAll`
+    AttentionInbox,
+    /// Announced by a newer daemon; not understood by this build
+    #[serde(other)]
+    Unknown,
+}
+
+impl Capability {
+    /// Every capability this build implements
+    pub const ALL: &'static [Capability] = &[
+        Capability::SendInput,
+        Capability::Screen,
+        Capability::Handoff,
+        Capability::OutputLog,
+        Capability::Recording,
+        Capability::Restart,
+        Capability::Subscribe,
+        Capability::RequestIds,
+        Capability::Snapshot,
+        Capability::Journal,
+        Capability::Streams,
+        Capability::AttachModes,
+        Capability::ReloadConfig,
+        Capability::AttentionProfiles,
+        Capability::AttentionInbox,
+    ];
+}
+
+impl std::fmt::Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Capability::SendInput => write!(f, "send_input"),
+            Capability::Screen => write!(f, "screen"),
+            Capability::Handoff => write!(f, "handoff"),
+            Capability::OutputLog => write!(f, "output_log"),
+            Capability::Recording => write!(f, "recording"),
+            Capability::Restart => write!(f, "restart"),
+            Capability::Subscribe => write!(f, "subscribe"),
+            Capability::RequestIds => write!(f, "request_ids"),
+            Capability::Snapshot => write!(f, "snapshot"),
+            Capability::Journal => write!(f, "journal"),
+            Capability::Streams => write!(f, "streams"),
+            Capability::AttachModes => write!(f, "attach_modes"),
+            Capability::ReloadConfig => write!(f, "reload_config"),
+            Capability::AttentionProfiles => write!(f, "attention_profiles"),
+            Capability::AttentionInbox => write!(f, "attention_inbox"),
+            Capability::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+impl DaemonRequest {
+    /// Capabilities the daemon must announce to handle this request
+    pub fn required_capabilities(&self) -> Vec<Capability> {
+        let mut required = Vec::new();
+        match self {
+            DaemonRequest::SendInput { .. } => required.push(Capability::SendInput),
+            DaemonRequest::Screen { .. } => required.push(Capability::Screen),
+            DaemonRequest::Handoff => required.push(Capability::Handoff),
+            DaemonRequest::Attach { mode, offset, .. } => {
+                if !mode.is_shared() {
+                    required.push(Capability::AttachModes);
+                }
+                if offset.is_some() {
+                    required.push(Capability::OutputLog);
+                }
+            }
+            DaemonRequest::OpenStream { mode, .. } => {
+                required.push(Capability::Streams);
+                if !mode.is_shared() {
+                    required.push(Capability::AttachModes);
+                }
+            }
+            DaemonRequest::Tail {
+                offset: Some(_), ..
+            } => required.push(Capability::OutputLog),
+            DaemonRequest::Start {
+                record,
+                attention_profiles,
+                ..
+            } => {
+                if *record {
+                    required.push(Capability::Recording);
+                }
+                if !attention_profiles.is_empty() {
+                    required.push(Capability::AttentionProfiles);
+                }
+            }
+            DaemonRequest::ListRecordings | DaemonRequest::DeleteRecording { .. } => {
+                required.push(Capability::Recording)
+            }
+            DaemonRequest::Restart { .. } => required.push(Capability::Restart),
+            DaemonRequest::Subscribe { .. } | DaemonRequest::Unsubscribe => {
+                required.push(Capability::Subscribe)
+            }
+            DaemonRequest::Snapshot => required.push(Capability::Snapshot),
+            DaemonRequest::Events { .. } => required.push(Capability::Journal),
+            DaemonRequest::ReloadConfig => required.push(Capability::ReloadConfig),
+            DaemonRequest::AttentionList { .. }
+            | DaemonRequest::AttentionAck { .. }
+            | DaemonRequest::AttentionAckAll { .. } => required.push(Capability::AttentionInbox),
+            _ => {}
+        }
+        required
+    }
+}
+
+/// Pick the protocol version for a connection from the client's range
+///
+/// Errors with a message for the user when the ranges don't overlap.
+pub fn negotiate_version(client_version: u32, min_version: Option<u32>) -> Result<u32, String> {
+    if client_version < MIN_PROTOCOL_VERSION {
+        return Err("Daemon is newer than app—please update the app.".to_string());
+    }
+    if min_version.unwrap_or(client_version) > PROTOCOL_VERSION {
+        return Err("Daemon is outdated—please restart daemon.".to_string());
+    }
+    Ok(client_version.min(PROTOCOL_VERSION))
+}
+
+/// What a daemon announced in its handshake
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DaemonCapabilities {
+    pub protocol_version: u32,
+    /// None when the daemon predates capabilities
+    pub capabilities: Option<Vec<Capability>>,
+}
+
+impl DaemonCapabilities {
+    /// Whether the daemon announced `capability`
+    ///
+    /// Daemons that predate capabilities get the benefit of the doubt and
+    /// answer for themselves.
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities
+            .as_ref()
+            .is_none_or(|capabilities| capabilities.contains(&capability))
+    }
+
+    /// Whether this build is newer than the daemon that announced these
+    ///
+    /// Newer means a higher protocol version or, at the same version, every
+    /// capability the daemon announced plus some it lacks. Capabilities the
+    /// daemon doesn't know by name (`Unknown`) make it the newer one;
+    /// `OutputLog` is left out since it can be switched off.
+    pub fn is_older_than_this_build(&self) -> bool {
+        match self.protocol_version.cmp(&PROTOCOL_VERSION) {
+            std::cmp::Ordering::Less => true,
+            std::cmp::Ordering::Greater => false,
+            std::cmp::Ordering::Equal => match &self.capabilities {
+                None => true,
+                Some(announced) => {
+                    !announced.contains(&Capability::Unknown)
+                        && Capability::ALL.iter().any(|capability| {
+                            *capability != Capability::OutputLog && !announced.contains(capability)
+                        })
+                }
+            },
+        }
+    }
+
+    /// The error to answer `request` with locally, if the daemon can't handle it
+    pub fn check(&self, request: &DaemonRequest) -> Option<DaemonResponse> {
+        let capability = request
+            .required_capabilities()
+            .into_iter()
+            .find(|&capability| !self.supports(capability))?;
+        Some(DaemonResponse::Error {
+            code: DaemonErrorCode::Unsupported,
+            message: format!(
+                "The running daemon doesn't support {} (it may be outdated, or have it disabled)",
+                capability
+            ),
+        })
+    }
+}
+
 // ============================================================================
 // Daemon -> Client responses
 // ============================================================================
@@ -285,6 +846,12 @@ pub enum DaemonErrorCode {
     Timeout,
     /// Protocol version mismatch between client and daemon
     VersionMismatch,
+    /// The daemon lacks a capability the request needs
+    Unsupported,
+    /// The connection comes from another user
+    PermissionDenied,
+    /// config.toml or attention.toml can't be parsed or has invalid values
+    InvalidConfig,
 }
 
 /// Response message from daemon to CLI/UI
@@ -293,17 +860,34 @@ pub enum DaemonErrorCode {
 pub enum DaemonResponse {
     /// Protocol version handshake acknowledgment
     Handshake {
-        /// Daemon's protocol version
+        /// Version the connection speaks: the newest both sides support
         protocol_version: u32,
+        /// Oldest protocol version the daemon speaks
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_protocol_version: Option<u32>,
+        /// Optional features the daemon supports (None from daemons that
+        /// predate capabilities)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        capabilities: Option<Vec<Capability>>,
+        /// Encoding of byte payloads on this connection from now on (None:
+        /// arrays, as with daemons that predate the choice)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        byte_encoding: Option<ByteEncoding>,
     },
     /// Session was started successfully
     SessionStarted { session: Session },
     /// Session was continued (attached)
     SessionContinued {
         session: Session,
-        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "pty_bytes::option"
+        )]
         tail: Option<Vec<u8>>,
     },
+    /// Stopped session was relaunched
+    SessionRestarted { session: Session },
     /// Session was stopped
     SessionStopped { session: Session },
     /// List of sessions matching the query
@@ -313,26 +897,106 @@ pub enum DaemonResponse {
     /// Attach socket is ready for streaming
     AttachReady {
         session: Session,
-        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "pty_bytes::option"
+        )]
         tail: Option<Vec<u8>>,
+        /// Repaint of the current screen; preferred over replaying `tail` when present
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        screen: Option<ScreenRepaint>,
+        /// Log offset of the first byte of `tail` when it was read from the output log
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        offset: Option<u64>,
         socket_path: String,
     },
+    /// Attach stream is open on this connection; `StreamFrame`s follow
+    StreamOpened {
+        session: Session,
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "pty_bytes::option"
+        )]
+        tail: Option<Vec<u8>>,
+        /// Repaint of the current screen; preferred over replaying `tail` when present
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        screen: Option<ScreenRepaint>,
+        /// Log offset of the first byte of `tail` when it was read from the output log
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        offset: Option<u64>,
+    },
+    /// Rendered screen of a session
+    SessionScreen {
+        session_id: SessionId,
+        screen: ScreenText,
+    },
     /// Session PTY was resized
     SessionResized {
         session_id: SessionId,
         cols: u16,
         rows: u16,
     },
+    /// Input was written to the session PTY
+    InputSent {
+        session_id: SessionId,
+        /// Number of bytes written (including any appended newline)
+        bytes: usize,
+    },
     /// Recent output tail for a session
     SessionTail {
         session_id: SessionId,
         /// UTF-8 bytes from the PTY ring buffer (may be partial UTF-8)
+        #[serde(with = "pty_bytes")]
         data: Vec<u8>,
+        /// Log offset of the first byte of `data` when read from the output log
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        offset: Option<u64>,
+        /// Offset just past the newest byte in the output log
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        log_end: Option<u64>,
+    },
+    /// Recordings kept by the daemon, ordered by session ID
+    RecordingList { recordings: Vec<RecordingInfo> },
+    /// A session's recording was deleted
+    RecordingDeleted { session_id: SessionId },
+    /// Notifications matching the filter will follow on this connection
+    Subscribed { filter: SubscriptionFilter },
+    /// Notifications stopped for this connection
+    Unsubscribed,
+    /// Every session, reflecting all notifications up to and including `seq`
+    ///
+    /// Notifications with a higher seq may already be reflected too; they
+    /// carry full session state, so replaying them is harmless.
+    Snapshot { seq: u64, sessions: Vec<Session> },
+    /// Journaled notifications, oldest first
+    ///
+    /// Every event up to `latest_seq` was considered, so when fewer than
+    /// `limit` come back the next query can start after `latest_seq`.
+    Events {
+        events: Vec<JournalEvent>,
+        latest_seq: u64,
     },
     /// Pong response
     Pong,
     /// Shutdown acknowledged
     ShuttingDown,
+    /// Daemon state for a replacement daemon; file descriptors follow over
+    /// SCM_RIGHTS as described on `HandoffSession`
+    HandoffReady { sessions: Vec<HandoffSession> },
+    /// config.toml and attention.toml were reloaded; these settings and
+    /// attention profiles are now in effect
+    ConfigReloaded {
+        settings: Settings,
+        /// Names of the attention profiles, in the order they are checked
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        attention_profiles: Vec<String>,
+    },
+    /// Attention inbox items, newest first
+    AttentionItems { items: Vec<AttentionItem> },
+    /// These items were acknowledged (leaving out any that already were)
+    AttentionAcked { ids: Vec<AttentionId> },
     /// Context Resurrection snapshot (single)
     CrSnapshot { snapshot: Option<ContextSnapshotV1> },
     /// Context Resurrection snapshots (list)
@@ -346,11 +1010,94 @@ pub enum DaemonResponse {
     },
 }
 
+/// A request as written to the daemon
+///
+/// Requests carrying a `request_id` may be pipelined: the daemon handles them
+/// concurrently and echoes the id on each response, which can arrive in any
+/// order. Requests without one are answered in order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestFrame {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<RequestId>,
+    #[serde(flatten)]
+    pub request: DaemonRequest,
+}
+
+/// A response as written by the daemon, carrying its request's `request_id`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseFrame {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<RequestId>,
+    #[serde(flatten)]
+    pub response: DaemonResponse,
+}
+
+/// A frame on an attach stream opened with `DaemonRequest::OpenStream`
+///
+/// Sent in both directions without a reply, keyed by session. Tagged with
+/// `"type"` values no request or response uses, so frames can share the
+/// connection with them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum StreamFrame {
+    /// PTY output (from the daemon) or input (from the client)
+    #[serde(rename = "stream_data")]
+    Data {
+        session_id: SessionId,
+        #[serde(with = "pty_bytes")]
+        data: Vec<u8>,
+    },
+    /// The client's terminal changed size
+    #[serde(rename = "stream_resize")]
+    Resize {
+        session_id: SessionId,
+        cols: u16,
+        rows: u16,
+    },
+    /// The stream is over: the client detached, or (from the daemon) the
+    /// session exited or can't be streamed
+    #[serde(rename = "stream_close")]
+    Close {
+        session_id: SessionId,
+        /// The session's process exited
+        #[serde(default)]
+        exited: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        exit_code: Option<i32>,
+        /// Why the daemon closed the stream, when it's an error
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+    },
+}
+
+impl StreamFrame {
+    pub fn session_id(&self) -> SessionId {
+        match self {
+            StreamFrame::Data { session_id, .. }
+            | StreamFrame::Resize { session_id, .. }
+            | StreamFrame::Close { session_id, .. } => *session_id,
+        }
+    }
+
+    /// Close frame sent by a client to detach
+    pub fn detach(session_id: SessionId) -> Self {
+        StreamFrame::Close {
+            session_id,
+            exited: false,
+            exit_code: None,
+            message: None,
+        }
+    }
+}
+
 // ============================================================================
 // Daemon -> Client push notifications (broadcast)
 // ============================================================================
 
 /// Push notification from daemon to subscribed clients
+// Notifications are built once and serialized straight away; boxing the
+// session would only add an allocation
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum DaemonNotification {
@@ -361,11 +1108,115 @@ pub enum DaemonNotification {
     /// Session output triggered attention
     Attention {
         session_id: SessionId,
+        /// Inbox ID, for acknowledging it
+        #[serde(default)]
+        attention_id: AttentionId,
         profile: String,
         attention_type: AttentionType,
         preview: String,
         triggered_at: DateTime<Utc>,
     },
+    /// A session's inbox items were acknowledged, by request or by a client
+    /// attaching to it
+    AttentionAcked {
+        session_id: SessionId,
+        ids: Vec<AttentionId>,
+    },
+    /// This connection fell behind and `missed` notifications were dropped;
+    /// request a `Snapshot` to catch up
+    Resync { missed: u64 },
+}
+
+impl DaemonNotification {
+    pub fn kind(&self) -> NotificationKind {
+        match self {
+            DaemonNotification::SessionUpdated { .. } => NotificationKind::SessionUpdated,
+            DaemonNotification::SessionRemoved { .. } => NotificationKind::SessionRemoved,
+            DaemonNotification::Attention { .. } => NotificationKind::Attention,
+            DaemonNotification::AttentionAcked { .. } => NotificationKind::AttentionAcked,
+            DaemonNotification::Resync { .. } => NotificationKind::Resync,
+        }
+    }
+
+    /// The session the notification is about (None for `Resync`)
+    pub fn session_id(&self) -> Option<SessionId> {
+        match self {
+            DaemonNotification::SessionUpdated { session } => Some(session.id),
+            DaemonNotification::SessionRemoved { session_id }
+            | DaemonNotification::Attention { session_id, .. }
+            | DaemonNotification::AttentionAcked { session_id, .. } => Some(*session_id),
+            DaemonNotification::Resync { .. } => None,
+        }
+    }
+}
+
+/// Kinds of notification a connection can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    SessionUpdated,
+    SessionRemoved,
+    Attention,
+    AttentionAcked,
+    /// Always delivered, whatever the filter
+    Resync,
+}
+
+/// A notification as written to a subscribed connection
+///
+/// Tagged `"type": "notification"`, which no `DaemonResponse` uses, so a
+/// notification can never be read as a response on a shared stream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename = "notification")]
+pub struct NotificationEnvelope {
+    /// Position in the daemon's notification stream, increasing by one per
+    /// notification. A `Resync` carries the latest seq at the time it's sent.
+    pub seq: u64,
+    /// TODO.md of the session the notification is about (None for `Resync`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_path: Option<String>,
+    pub notification: DaemonNotification,
+}
+
+/// A notification as kept in the daemon's event journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEvent {
+    pub seq: u64,
+    pub at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_path: Option<String>,
+    pub notification: DaemonNotification,
+}
+
+/// Which notifications a connection receives; empty lists match everything
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubscriptionFilter {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub projects: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub session_ids: Vec<SessionId>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub kinds: Vec<NotificationKind>,
+}
+
+impl SubscriptionFilter {
+    pub fn matches(&self, envelope: &NotificationEnvelope) -> bool {
+        self.accepts(envelope.project_path.as_deref(), &envelope.notification)
+    }
+
+    /// Whether `notification` about a session in `project_path` matches
+    pub fn accepts(&self, project_path: Option<&str>, notification: &DaemonNotification) -> bool {
+        if notification.kind() == NotificationKind::Resync {
+            return true;
+        }
+        (self.projects.is_empty()
+            || project_path.is_some_and(|path| self.projects.iter().any(|p| p == path)))
+            && (self.session_ids.is_empty()
+                || notification
+                    .session_id()
+                    .is_some_and(|id| self.session_ids.contains(&id)))
+            && (self.kinds.is_empty() || self.kinds.contains(&notification.kind()))
+    }
 }
 
 // ============================================================================
@@ -379,6 +1230,155 @@ pub fn serialize_message<T: Serialize>(msg: &T) -> Result<Vec<u8>, serde_json::E
     Ok(bytes)
 }
 
+/// How byte payloads (PTY output, screen repaints) are written in JSON
+///
+/// Arrays of numbers take about four bytes of JSON per byte; base64 takes
+/// four per three. Readers accept either, whatever was negotiated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ByteEncoding {
+    /// Standard base64 strings
+    Base64,
+    /// Arrays of numbers, understood by every peer; encodings this build does
+    /// not know fall back to it
+    #[default]
+    #[serde(other)]
+    Array,
+}
+
+/// Serialize a message like `serialize_message`, writing byte payloads as `encoding`
+pub fn serialize_message_with<T: Serialize>(
+    msg: &T,
+    encoding: ByteEncoding,
+) -> Result<Vec<u8>, serde_json::Error> {
+    match encoding {
+        ByteEncoding::Array => serialize_message(msg),
+        ByteEncoding::Base64 => {
+            let mut bytes = Vec::new();
+            msg.serialize(&mut serde_json::Serializer::with_formatter(
+                &mut bytes,
+                Base64Formatter,
+            ))?;
+            bytes.push(b'\n');
+            Ok(bytes)
+        }
+    }
+}
+
+/// JSON formatter writing byte payloads as base64 strings instead of arrays
+struct Base64Formatter;
+
+impl serde_json::ser::Formatter for Base64Formatter {
+    fn write_byte_array<W: ?Sized + std::io::Write>(
+        &mut self,
+        writer: &mut W,
+        value: &[u8],
+    ) -> std::io::Result<()> {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+        writer.write_all(b"\"")?;
+        writer.write_all(STANDARD.encode(value).as_bytes())?;
+        writer.write_all(b"\"")
+    }
+}
+
+/// Serde helpers for byte payload fields
+///
+/// Written as serde bytes, which `serialize_message_with` renders in the
+/// connection's encoding (arrays otherwise, including on disk), and read back
+/// from either form so old peers and fixtures keep parsing.
+pub(crate) mod pty_bytes {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde::de::{self, SeqAccess, Visitor};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(bytes)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        deserializer.deserialize_any(BytesVisitor)
+    }
+
+    struct BytesVisitor;
+
+    impl<'de> Visitor<'de> for BytesVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("an array of bytes or a base64 string")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Vec<u8>, E> {
+            STANDARD.decode(value).map_err(E::custom)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+            let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(64 * 1024));
+            while let Some(byte) = seq.next_element()? {
+                bytes.push(byte);
+            }
+            Ok(bytes)
+        }
+    }
+
+    /// One payload, for fields holding several
+    struct Bytes(Vec<u8>);
+
+    /// One borrowed payload, for writing fields holding several
+    struct BytesRef<'a>(&'a [u8]);
+
+    impl Serialize for BytesRef<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize(self.0, serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Bytes {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserialize(deserializer).map(Bytes)
+        }
+    }
+
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            bytes: &Option<Vec<u8>>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match bytes {
+                Some(bytes) => serializer.serialize_some(&BytesRef(bytes)),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Vec<u8>>, D::Error> {
+            Ok(Option::<Bytes>::deserialize(deserializer)?.map(|bytes| bytes.0))
+        }
+    }
+
+    pub mod list {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            list: &[Vec<u8>],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(list.iter().map(|bytes| BytesRef(bytes)))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<Vec<u8>>, D::Error> {
+            let list = Vec::<Bytes>::deserialize(deserializer)?;
+            Ok(list.into_iter().map(|bytes| bytes.0).collect())
+        }
+    }
+}
+
 /// Deserialize a message from JSON bytes (strips trailing newline)
 pub fn deserialize_message<T: for<'de> Deserialize<'de>>(
     bytes: &[u8],
@@ -419,7 +1419,15 @@ mod tests {
                 "-lc".to_string(),
                 "npm run dev".to_string(),
             ]),
+            cwd: Some("/path".to_string()),
+            env: [("EDITOR".to_string(), "vim".to_string())].into(),
+            record: true,
+            attention_profiles: vec!["claude-code".to_string()],
         };
+        assert_eq!(
+            req.required_capabilities(),
+            vec![Capability::Recording, Capability::AttentionProfiles]
+        );
 
         let bytes = serialize_message(&req).unwrap();
         let parsed: DaemonRequest = deserialize_message(&bytes).unwrap();
@@ -429,8 +1437,16 @@ mod tests {
             task_id,
             project_path,
             shell,
+            cwd,
+            env,
+            record,
+            attention_profiles,
         } = parsed
         {
+            assert!(record);
+            assert_eq!(attention_profiles, vec!["claude-code".to_string()]);
+            assert_eq!(cwd.as_deref(), Some("/path"));
+            assert_eq!(env.get("EDITOR").map(String::as_str), Some("vim"));
             assert_eq!(task_key, "Implement reports");
             assert_eq!(task_id, Some("abc.implement-reports".to_string()));
             assert_eq!(project_path, "/path/TODO.md");
@@ -447,6 +1463,66 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_start_defaults_record_to_false() {
+        let json = r#"{"type":"start","task_key":"Docs","project_path":"/path/TODO.md"}"#;
+        let parsed: DaemonRequest = deserialize_message(json.as_bytes()).unwrap();
+        match parsed {
+            DaemonRequest::Start {
+                cwd,
+                env,
+                record,
+                attention_profiles,
+                ..
+            } => {
+                assert!(!record);
+                assert!(cwd.is_none());
+                assert!(env.is_empty());
+                assert!(attention_profiles.is_empty());
+            }
+            other => panic!("Wrong variant: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_send_input_defaults_append_newline_to_false() {
+        let json = r#"{"type":"send_input","session_id":7,"data":"y"}"#;
+        let parsed: DaemonRequest = deserialize_message(json.as_bytes()).unwrap();
+
+        match parsed {
+            DaemonRequest::SendInput {
+                session_id,
+                data,
+                append_newline,
+            } => {
+                assert_eq!(session_id, 7);
+                assert_eq!(data, "y");
+                assert!(!append_newline);
+            }
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_screen_repaint_terminal_bytes() {
+        let mut repaint = ScreenRepaint {
+            rows: 2,
+            cols: 10,
+            cursor_row: 0,
+            cursor_col: 0,
+            cursor_visible: true,
+            alternate_screen: false,
+            title: String::new(),
+            scrollback: vec![b"old".to_vec()],
+            screen: b"<screen>".to_vec(),
+        };
+        assert_eq!(repaint.to_terminal_bytes(), b"old\r\n\r\n\r\n<screen>");
+
+        // Alternate screen programs don't get primary scrollback pushed underneath
+        repaint.alternate_screen = true;
+        assert_eq!(repaint.to_terminal_bytes(), b"\x1b[?1049h<screen>");
+    }
+
     #[test]
     fn test_session_deep_link() {
         let session = Session::new(
@@ -458,4 +1534,141 @@ mod tests {
         assert_eq!(session.deep_link(), "todos://session/42");
         assert_eq!(session.task_id, Some("abc.test-task".to_string()));
     }
+
+    #[test]
+    fn test_notification_envelope_is_not_a_response() {
+        let envelope = NotificationEnvelope {
+            seq: 12,
+            project_path: Some("/test/TODO.md".to_string()),
+            notification: DaemonNotification::SessionRemoved { session_id: 3 },
+        };
+        let bytes = serialize_message(&envelope).unwrap();
+        let json = String::from_utf8_lossy(&bytes);
+        assert!(json.starts_with(r#"{"type":"notification""#), "{}", json);
+
+        assert!(deserialize_message::<DaemonResponse>(&bytes).is_err());
+        let parsed: NotificationEnvelope = deserialize_message(&bytes).unwrap();
+        assert_eq!(parsed.seq, 12);
+        assert!(matches!(
+            parsed.notification,
+            DaemonNotification::SessionRemoved { session_id: 3 }
+        ));
+
+        let response = serialize_message(&DaemonResponse::Pong).unwrap();
+        assert!(deserialize_message::<NotificationEnvelope>(&response).is_err());
+    }
+
+    #[test]
+    fn test_subscription_filter_matches() {
+        let envelope = |project: &str, session_id| NotificationEnvelope {
+            seq: 1,
+            project_path: Some(project.to_string()),
+            notification: DaemonNotification::SessionRemoved { session_id },
+        };
+
+        let everything = SubscriptionFilter::default();
+        assert!(everything.matches(&envelope("/a/TODO.md", 1)));
+
+        let filter = SubscriptionFilter {
+            projects: vec!["/a/TODO.md".to_string()],
+            session_ids: vec![1, 2],
+            kinds: vec![NotificationKind::SessionRemoved],
+        };
+        assert!(filter.matches(&envelope("/a/TODO.md", 2)));
+        assert!(!filter.matches(&envelope("/b/TODO.md", 2)));
+        assert!(!filter.matches(&envelope("/a/TODO.md", 3)));
+
+        let attention_only = SubscriptionFilter {
+            kinds: vec![NotificationKind::Attention],
+            ..Default::default()
+        };
+        assert!(!attention_only.matches(&envelope("/a/TODO.md", 1)));
+
+        // A lagging connection hears about it whatever it subscribed to
+        let resync = NotificationEnvelope {
+            seq: 40,
+            project_path: None,
+            notification: DaemonNotification::Resync { missed: 8 },
+        };
+        assert!(filter.matches(&resync));
+        assert!(attention_only.matches(&resync));
+    }
+
+    #[test]
+    fn test_negotiate_version_accepts_overlapping_ranges() {
+        assert_eq!(
+            negotiate_version(PROTOCOL_VERSION, None),
+            Ok(PROTOCOL_VERSION)
+        );
+        // Newer clients that still speak this version are met at it
+        assert_eq!(
+            negotiate_version(PROTOCOL_VERSION + 3, Some(MIN_PROTOCOL_VERSION)),
+            Ok(PROTOCOL_VERSION)
+        );
+        assert!(negotiate_version(PROTOCOL_VERSION + 1, None)
+            .unwrap_err()
+            .contains("outdated"));
+        assert!(negotiate_version(MIN_PROTOCOL_VERSION - 1, None)
+            .unwrap_err()
+            .contains("update the app"));
+    }
+
+    #[test]
+    fn test_only_older_daemons_are_taken_over() {
+        let announced = |protocol_version, capabilities: &[Capability]| DaemonCapabilities {
+            protocol_version,
+            capabilities: Some(capabilities.to_vec()),
+        };
+        let all_but_log: Vec<Capability> = Capability::ALL
+            .iter()
+            .copied()
+            .filter(|&capability| capability != Capability::OutputLog)
+            .collect();
+
+        // The same build, with or without its output log
+        assert!(!announced(PROTOCOL_VERSION, Capability::ALL).is_older_than_this_build());
+        assert!(!announced(PROTOCOL_VERSION, &all_but_log).is_older_than_this_build());
+        // Missing a feature this build has
+        assert!(announced(PROTOCOL_VERSION, &all_but_log[1..]).is_older_than_this_build());
+        // Knows something this build doesn't
+        let mut newer = Capability::ALL.to_vec();
+        newer.push(Capability::Unknown);
+        assert!(!announced(PROTOCOL_VERSION, &newer).is_older_than_this_build());
+        assert!(!announced(PROTOCOL_VERSION + 1, &[]).is_older_than_this_build());
+        assert!(DaemonCapabilities {
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: None,
+        }
+        .is_older_than_this_build());
+    }
+
+    #[test]
+    fn test_frames_carry_request_id_alongside_message() {
+        let frame = RequestFrame {
+            request_id: Some(7),
+            request: DaemonRequest::Status { session_id: 3 },
+        };
+        let bytes = serialize_message(&frame).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["type"], "status");
+        assert_eq!(json["request_id"], 7);
+        assert_eq!(json["session_id"], 3);
+
+        // Old clients send bare requests, and read responses that carry an id
+        let parsed: RequestFrame = deserialize_message(br#"{"type":"ping"}"#).unwrap();
+        assert_eq!(parsed.request_id, None);
+        assert!(matches!(parsed.request, DaemonRequest::Ping));
+
+        let response = ResponseFrame {
+            request_id: Some(7),
+            response: DaemonResponse::Pong,
+        };
+        let bytes = serialize_message(&response).unwrap();
+        assert!(matches!(
+            deserialize_message::<DaemonResponse>(&bytes),
+            Ok(DaemonResponse::Pong)
+        ));
+        let parsed: ResponseFrame = deserialize_message(&bytes).unwrap();
+        assert_eq!(parsed.request_id, Some(7));
+    }
 }
@@ -0,0 +1,396 @@
+// asciicast v2 recordings of PTY sessions
+//
+// Sessions started with recording on write their output and window size
+// changes to an asciicast v2 file: a JSON header line followed by one
+// `[seconds, code, data]` event per line, playable with `todo replay` or any
+// asciinema-compatible player. Like the output log, the recording is written
+// by whichever process owns the PTY. Reopening a recording keeps appending to
+// it, with times still measured from the original header timestamp.
+
+use crate::session::protocol::SessionId;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const ASCIICAST_VERSION: u32 = 2;
+
+const RECORDING_EXTENSION: &str = "cast";
+
+/// First line of an asciicast v2 file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CastHeader {
+    pub version: u32,
+    pub width: u16,
+    pub height: u16,
+    /// Unix time the recording started, in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+/// Something that happened during a recording
+#[derive(Debug, Clone, PartialEq)]
+pub enum CastEvent {
+    /// Terminal output (`"o"`)
+    Output(String),
+    /// Window size change (`"r"`)
+    Resize { cols: u16, rows: u16 },
+}
+
+/// A recorded event and its time in seconds since the recording started
+#[derive(Debug, Clone, PartialEq)]
+pub struct CastEntry {
+    pub time: f64,
+    pub event: CastEvent,
+}
+
+/// Appends events to one session's recording
+pub struct Recording {
+    file: File,
+    started: SystemTime,
+    /// Time of the last event written; keeps times monotonic across reopens
+    last_time: f64,
+    /// Trailing bytes of an output chunk that ended mid UTF-8 character
+    pending: Vec<u8>,
+}
+
+impl Recording {
+    /// Open the recording at `path`, starting a new one at the given size if
+    /// there isn't one yet
+    pub fn open(path: &Path, cols: u16, rows: u16, title: Option<&str>) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        if let Some((header, last_time)) = read_existing(path)? {
+            let file = OpenOptions::new().append(true).open(path)?;
+            let started = UNIX_EPOCH + Duration::from_secs(header.timestamp.unwrap_or(0));
+            let mut recording = Self {
+                file,
+                started,
+                last_time,
+                pending: Vec::new(),
+            };
+            if (header.width, header.height) != (cols, rows) {
+                recording.resize(cols, rows)?;
+            }
+            return Ok(recording);
+        }
+
+        // Times count from the whole second in the header so reopening lines up
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let header = CastHeader {
+            version: ASCIICAST_VERSION,
+            width: cols,
+            height: rows,
+            timestamp: Some(timestamp),
+            title: title.map(str::to_string),
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)?;
+        let mut line = serde_json::to_vec(&header)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+
+        Ok(Self {
+            file,
+            started: UNIX_EPOCH + Duration::from_secs(timestamp),
+            last_time: 0.0,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Record a chunk of PTY output
+    ///
+    /// asciicast output is text, so a character split across chunks is held
+    /// back until the rest of it arrives; invalid bytes become U+FFFD.
+    pub fn output(&mut self, data: &[u8]) -> io::Result<()> {
+        self.pending.extend_from_slice(data);
+        let text = take_utf8(&mut self.pending);
+        if text.is_empty() {
+            return Ok(());
+        }
+        self.write_event("o", &text)
+    }
+
+    /// Record a window size change
+    pub fn resize(&mut self, cols: u16, rows: u16) -> io::Result<()> {
+        self.write_event("r", &format!("{}x{}", cols, rows))
+    }
+
+    fn write_event(&mut self, code: &str, data: &str) -> io::Result<()> {
+        let elapsed = self
+            .started
+            .elapsed()
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0);
+        self.last_time = elapsed.max(self.last_time);
+        // Millisecond precision keeps lines short
+        let time = (self.last_time * 1000.0).round() / 1000.0;
+        let mut line = serde_json::to_vec(&(time, code, data))?;
+        line.push(b'\n');
+        self.file.write_all(&line)
+    }
+}
+
+/// Split off the longest valid UTF-8 prefix of `buf` as text
+///
+/// Leaves an incomplete trailing character in `buf` and replaces invalid
+/// sequences with U+FFFD.
+fn take_utf8(buf: &mut Vec<u8>) -> String {
+    let mut text = String::new();
+    let mut rest = buf.as_slice();
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(valid) => {
+                text.push_str(valid);
+                rest = &[];
+                break;
+            }
+            Err(e) => {
+                let (valid, after) = rest.split_at(e.valid_up_to());
+                text.push_str(&String::from_utf8_lossy(valid));
+                match e.error_len() {
+                    Some(len) => {
+                        text.push(char::REPLACEMENT_CHARACTER);
+                        rest = &after[len..];
+                    }
+                    None => {
+                        rest = after;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    let kept = rest.len();
+    buf.drain(..buf.len() - kept);
+    text
+}
+
+/// Header and last event time of an existing recording, if there is one
+fn read_existing(path: &Path) -> io::Result<Option<(CastHeader, f64)>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut lines = BufReader::new(file).lines();
+    let header = match lines.next() {
+        Some(line) => match serde_json::from_str::<CastHeader>(&line?) {
+            Ok(header) => header,
+            // Not a recording we can continue; start over
+            Err(_) => return Ok(None),
+        },
+        None => return Ok(None),
+    };
+    let mut last_time = 0.0;
+    for line in lines {
+        if let Ok((time, _, _)) = serde_json::from_str::<(f64, String, String)>(&line?) {
+            last_time = time;
+        }
+    }
+    Ok(Some((header, last_time)))
+}
+
+/// Read a whole recording
+///
+/// Events with codes other than output and resize (input, markers) are skipped.
+pub fn read_recording(path: &Path) -> io::Result<(CastHeader, Vec<CastEntry>)> {
+    let mut lines = BufReader::new(File::open(path)?).lines();
+    let header_line = lines
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Empty recording"))??;
+    let header: CastHeader = serde_json::from_str(&header_line)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if header.version != ASCIICAST_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unsupported asciicast version {}", header.version),
+        ));
+    }
+
+    let mut entries = Vec::new();
+    for line in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (time, code, data) = serde_json::from_str::<(f64, String, String)>(&line)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let event = match code.as_str() {
+            "o" => CastEvent::Output(data),
+            "r" => match data.split_once('x').map(|(c, r)| (c.parse(), r.parse())) {
+                Some((Ok(cols), Ok(rows))) => CastEvent::Resize { cols, rows },
+                _ => continue,
+            },
+            _ => continue,
+        };
+        entries.push(CastEntry { time, event });
+    }
+    Ok((header, entries))
+}
+
+/// Recording file for a session in `dir`
+pub fn recording_path(dir: &Path, session_id: SessionId) -> PathBuf {
+    dir.join(format!("{}.{}", session_id, RECORDING_EXTENSION))
+}
+
+/// A recording file found on disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingFile {
+    pub session_id: SessionId,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub modified: SystemTime,
+}
+
+/// Recordings in `dir`, ordered by session ID
+pub fn list_recordings(dir: &Path) -> io::Result<Vec<RecordingFile>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut recordings = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(RECORDING_EXTENSION) {
+            continue;
+        }
+        let Some(session_id) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.parse::<SessionId>().ok())
+        else {
+            continue;
+        };
+        let metadata = match fs::metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        recordings.push(RecordingFile {
+            session_id,
+            size_bytes: metadata.len(),
+            modified: metadata.modified()?,
+            path,
+        });
+    }
+    recordings.sort_by_key(|r| r.session_id);
+    Ok(recordings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn records_output_and_resizes() {
+        let dir = TempDir::new().unwrap();
+        let path = recording_path(dir.path(), 4);
+        let mut recording = Recording::open(&path, 80, 24, Some("Demo")).unwrap();
+        recording.output(b"hello\r\n").unwrap();
+        recording.resize(100, 30).unwrap();
+        recording.output(b"\x1b[1mbold\x1b[m").unwrap();
+        drop(recording);
+
+        let (header, entries) = read_recording(&path).unwrap();
+        assert_eq!((header.width, header.height), (80, 24));
+        assert_eq!(header.title.as_deref(), Some("Demo"));
+        let events: Vec<_> = entries.iter().map(|e| e.event.clone()).collect();
+        assert_eq!(
+            events,
+            vec![
+                CastEvent::Output("hello\r\n".to_string()),
+                CastEvent::Resize {
+                    cols: 100,
+                    rows: 30
+                },
+                CastEvent::Output("\x1b[1mbold\x1b[m".to_string()),
+            ]
+        );
+        assert!(entries.windows(2).all(|w| w[0].time <= w[1].time));
+    }
+
+    #[test]
+    fn holds_back_split_characters() {
+        let dir = TempDir::new().unwrap();
+        let path = recording_path(dir.path(), 1);
+        let mut recording = Recording::open(&path, 80, 24, None).unwrap();
+        let check = "✓".as_bytes();
+        recording.output(&check[..1]).unwrap();
+        recording.output(&check[1..]).unwrap();
+        recording.output(b"\xffok").unwrap();
+        drop(recording);
+
+        let (_, entries) = read_recording(&path).unwrap();
+        let events: Vec<_> = entries.into_iter().map(|e| e.event).collect();
+        assert_eq!(
+            events,
+            vec![
+                CastEvent::Output("✓".to_string()),
+                CastEvent::Output("\u{fffd}ok".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reopening_appends_to_the_same_recording() {
+        let dir = TempDir::new().unwrap();
+        let path = recording_path(dir.path(), 2);
+        Recording::open(&path, 80, 24, None)
+            .unwrap()
+            .output(b"before")
+            .unwrap();
+
+        let mut reopened = Recording::open(&path, 120, 40, None).unwrap();
+        reopened.output(b"after").unwrap();
+        drop(reopened);
+
+        let (header, entries) = read_recording(&path).unwrap();
+        assert_eq!((header.width, header.height), (80, 24));
+        let events: Vec<_> = entries.into_iter().map(|e| e.event).collect();
+        assert_eq!(
+            events,
+            vec![
+                CastEvent::Output("before".to_string()),
+                CastEvent::Resize {
+                    cols: 120,
+                    rows: 40
+                },
+                CastEvent::Output("after".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_recordings_by_session() {
+        let dir = TempDir::new().unwrap();
+        for id in [12, 3] {
+            Recording::open(&recording_path(dir.path(), id), 80, 24, None).unwrap();
+        }
+        fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let ids: Vec<_> = list_recordings(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|r| r.session_id)
+            .collect();
+        assert_eq!(ids, vec![3, 12]);
+        assert!(list_recordings(&dir.path().join("missing"))
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -4,14 +4,38 @@
 // - Spawning PTY child processes
 // - Reading output
 // - Sending input
-// - Tracking idle/activity status
+// - Emitting idle/activity/exit events as they happen
 // - Graceful and forced termination
-
-use crate::session::protocol::{SessionId, SessionStatus};
+//
+// A runtime either owns its PTY directly or mirrors one owned by a holder
+// process (see `holder.rs`), so the daemon can use both the same way. A PTY
+// master handed over by a previous daemon (see `handoff.rs`) is owned directly,
+// but its child belongs to that daemon's process tree.
+
+use crate::session::foreground::{self, ForegroundProcess};
+#[cfg(unix)]
+use crate::session::holder::{HolderMessage, HolderRequest};
+use crate::session::output_log::OutputLog;
+#[cfg(unix)]
+use crate::session::protocol::{deserialize_message, serialize_message, HandoffSession};
+use crate::session::protocol::{ScreenRepaint, ScreenText, SessionId, SessionStatus};
+use crate::session::recording::Recording;
+use crate::session::screen::ScreenModel;
 use anyhow::{Context, Result};
 use portable_pty::{native_pty_system, CommandBuilder, MasterPty, PtySize};
+use std::collections::BTreeMap;
+#[cfg(unix)]
+use std::fs::File;
+#[cfg(unix)]
+use std::io::BufRead;
 use std::io::{Read, Write};
+#[cfg(unix)]
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc as std_mpsc;
 use std::sync::{Arc, Mutex as StdMutex};
 use std::time::{Duration, Instant};
 use tokio::sync::{broadcast, mpsc, oneshot};
@@ -20,15 +44,94 @@ use tokio::sync::{broadcast, mpsc, oneshot};
 const DEFAULT_COLS: u16 = 80;
 const DEFAULT_ROWS: u16 = 24;
 
-/// Duration of inactivity before transitioning to Waiting status
-const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Default duration of inactivity before transitioning to Waiting status
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long to wait for the reader to drain remaining output after the child exits
+const EXIT_DRAIN_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// How long to wait for a holder's hello after connecting
+#[cfg(unix)]
+const HOLDER_HELLO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a mirror keeps trying to reconnect to a holder after losing its connection
+#[cfg(unix)]
+const HOLDER_RECONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How often a pausable reader wakes up to check for a pause request
+#[cfg(unix)]
+const READER_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
-/// Output buffer size for the ring buffer
-const OUTPUT_BUFFER_SIZE: usize = 64 * 1024; // 64KB
+/// How long to wait for a reader to park before handing its PTY off
+#[cfg(unix)]
+const READER_PARK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Default output buffer size for the ring buffer
+pub const DEFAULT_OUTPUT_BUFFER_SIZE: usize = 64 * 1024; // 64KB
+
+/// Tunables applied to sessions as they are spawned or adopted
+///
+/// Sessions keep the values they started with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuntimeTuning {
+    /// Inactivity before a session is reported idle
+    pub idle_timeout: Duration,
+    /// Capacity of each session's output ring buffer
+    pub output_buffer_size: usize,
+}
+
+impl RuntimeTuning {
+    pub const DEFAULT: Self = Self {
+        idle_timeout: DEFAULT_IDLE_TIMEOUT,
+        output_buffer_size: DEFAULT_OUTPUT_BUFFER_SIZE,
+    };
+}
+
+impl Default for RuntimeTuning {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
 
 /// Maximum number of display characters exposed via RIGHT_NOW_TASK_DISPLAY
 const TASK_DISPLAY_MAX_CHARS: usize = 160;
 
+/// Variables the runtime sets itself for every session
+const SESSION_ENV_VARS: &[&str] = &[
+    "RIGHT_NOW_SESSION_ID",
+    "RIGHT_NOW_TASK_KEY",
+    "RIGHT_NOW_PROJECT",
+    "RIGHT_NOW_TASK_DISPLAY",
+];
+
+/// Shell and terminal state that shouldn't carry over into a new process
+const VOLATILE_ENV_VARS: &[&str] = &[
+    "_",
+    "OLDPWD",
+    "PWD",
+    "SHLVL",
+    "TERM_SESSION_ID",
+    "ITERM_SESSION_ID",
+    "WINDOWID",
+    "TMUX",
+    "TMUX_PANE",
+    "STY",
+];
+
+/// Name fragments marking variables that may hold credentials
+const SECRET_ENV_MARKERS: &[&str] = &[
+    "TOKEN",
+    "SECRET",
+    "PASSWORD",
+    "PASSWD",
+    "CREDENTIAL",
+    "API_KEY",
+    "ACCESS_KEY",
+    "PRIVATE_KEY",
+    "AUTH",
+    "COOKIE",
+];
+
 /// Events emitted by the PTY runtime
 #[derive(Debug, Clone)]
 pub enum PtyEvent {
@@ -36,7 +139,7 @@ pub enum PtyEvent {
     Output(Vec<u8>),
     /// PTY became active (after being idle)
     Active,
-    /// PTY became idle (no output for IDLE_TIMEOUT)
+    /// PTY became idle (no output for the idle timeout)
     Idle,
     /// PTY process exited
     Exited { exit_code: Option<i32> },
@@ -74,6 +177,39 @@ fn sanitize_task_display(task_key: &str) -> String {
     result.trim().to_string()
 }
 
+/// Keep the environment variables worth storing with a session and
+/// reapplying when it restarts
+///
+/// Drops variables that look like credentials (the snapshot is persisted in
+/// sessions.json), per-session variables the runtime sets itself, and shell
+/// state like `PWD` or `SHLVL` that belongs to the process it came from.
+pub fn snapshot_env(vars: impl IntoIterator<Item = (String, String)>) -> BTreeMap<String, String> {
+    vars.into_iter()
+        .filter(|(name, _)| {
+            let upper = name.to_ascii_uppercase();
+            !name.is_empty()
+                && !SESSION_ENV_VARS.contains(&name.as_str())
+                && !VOLATILE_ENV_VARS.contains(&name.as_str())
+                && !SECRET_ENV_MARKERS
+                    .iter()
+                    .any(|marker| upper.contains(marker))
+        })
+        .collect()
+}
+
+/// What to run in a session's PTY, and where
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpawnSpec {
+    /// Shell command; the user's default login shell when None
+    pub shell: Option<Vec<String>>,
+    /// Working directory; the home directory when None
+    pub cwd: Option<PathBuf>,
+    /// Variables set on top of the spawning process's environment
+    pub env: BTreeMap<String, String>,
+    /// Idle timeout and ring buffer size
+    pub tuning: RuntimeTuning,
+}
+
 /// Handle for sending input to and controlling a PTY session
 pub struct PtyRuntime {
     session_id: SessionId,
@@ -81,22 +217,304 @@ pub struct PtyRuntime {
     input_tx: mpsc::Sender<Vec<u8>>,
     /// Broadcast channel for PTY events
     event_tx: broadcast::Sender<PtyEvent>,
-    /// Handle to the PTY master for resizing
-    master: Arc<StdMutex<Box<dyn MasterPty + Send>>>,
-    /// Shutdown signal sender
-    shutdown_tx: Option<oneshot::Sender<()>>,
+    /// Where the PTY lives and how to resize or stop it
+    backend: Backend,
     /// Flag indicating if the session is still alive
     alive: Arc<AtomicBool>,
     /// PID of the child process (if available)
     child_pid: Option<u32>,
-    /// Ring buffer of recent output for resumable reads
-    output_buffer: Arc<StdMutex<RingBuffer>>,
-    /// Last activity timestamp
-    last_activity: Arc<StdMutex<Instant>>,
+    /// Ring buffer, screen model and activity state fed by the reader
+    sink: OutputSink,
     /// Cached exit code once the PTY terminates
     exit_code: Arc<StdMutex<Option<i32>>>,
 }
 
+/// Owner of the PTY behind a runtime
+enum Backend {
+    /// PTY master owned by this process
+    Local {
+        /// Handle to the PTY master for resizing
+        master: Arc<StdMutex<Box<dyn MasterPty + Send>>>,
+        /// Shutdown signal sender
+        shutdown_tx: Option<oneshot::Sender<()>>,
+        /// Lets a handoff stop the reader without closing the PTY
+        #[cfg(unix)]
+        pause: Arc<ReaderPause>,
+    },
+    /// PTY owned by a holder process, controlled over its socket
+    #[cfg(unix)]
+    Holder {
+        /// Write half of the holder connection (input, resize and stop frames)
+        control: Arc<StdMutex<UnixStream>>,
+    },
+    /// PTY master handed over by a previous daemon
+    ///
+    /// The child isn't ours to wait on, so its exit is noticed when the PTY
+    /// closes and the exit code is unknown.
+    #[cfg(unix)]
+    Adopted {
+        master: File,
+        pause: Arc<ReaderPause>,
+    },
+}
+
+/// Everything an output chunk updates: ring buffer, screen model and activity
+#[derive(Clone)]
+struct OutputSink {
+    event_tx: broadcast::Sender<PtyEvent>,
+    /// Ring buffer of recent output for resumable reads
+    output_buffer: Arc<StdMutex<RingBuffer>>,
+    /// Terminal emulator tracking what the session's screen currently shows
+    screen: Arc<StdMutex<ScreenModel>>,
+    /// Output activity and idle state shared with the reader and idle tasks
+    activity: Activity,
+    /// On-disk log, when this process owns the PTY and logging is enabled
+    log: Arc<StdMutex<Option<OutputLog>>>,
+    /// asciicast recording, when this process owns the PTY and the session records
+    recording: Arc<StdMutex<Option<Recording>>>,
+}
+
+impl OutputSink {
+    fn new(
+        event_tx: broadcast::Sender<PtyEvent>,
+        rows: u16,
+        cols: u16,
+        output_buffer_size: usize,
+    ) -> Self {
+        Self {
+            event_tx,
+            output_buffer: Arc::new(StdMutex::new(RingBuffer::new(output_buffer_size))),
+            screen: Arc::new(StdMutex::new(ScreenModel::new(rows, cols))),
+            activity: Activity::new(),
+            log: Arc::new(StdMutex::new(None)),
+            recording: Arc::new(StdMutex::new(None)),
+        }
+    }
+
+    fn with_log(self, log: Option<OutputLog>) -> Self {
+        *self.log.lock().unwrap() = log;
+        self
+    }
+
+    /// Record to the asciicast file at `path`, continuing it if it exists
+    ///
+    /// A recording that can't be opened is reported and skipped.
+    fn with_recording(self, path: Option<&Path>, title: Option<&str>) -> Self {
+        if let Some(path) = path {
+            let (rows, cols) = self.screen.lock().unwrap().size();
+            match Recording::open(path, cols, rows, title) {
+                Ok(recording) => *self.recording.lock().unwrap() = Some(recording),
+                Err(e) => eprintln!("Failed to open recording {}: {}", path.display(), e),
+            }
+        }
+        self
+    }
+
+    /// Seed the ring buffer, screen and idle state from a snapshot taken elsewhere
+    #[cfg(unix)]
+    fn from_snapshot(
+        event_tx: broadcast::Sender<PtyEvent>,
+        tail: &[u8],
+        repaint: &ScreenRepaint,
+        idle: bool,
+        output_buffer_size: usize,
+    ) -> Self {
+        let sink = Self::new(event_tx, repaint.rows, repaint.cols, output_buffer_size);
+        sink.output_buffer.lock().unwrap().push(tail);
+        sink.screen
+            .lock()
+            .unwrap()
+            .process(&repaint.to_terminal_bytes());
+        sink.activity.idle.store(idle, Ordering::SeqCst);
+        sink
+    }
+
+    /// Replace the ring buffer, screen and idle state with a holder's snapshot
+    ///
+    /// Used when output between the mirror and the holder was lost, so the
+    /// mirror can't be brought up to date chunk by chunk.
+    #[cfg(unix)]
+    fn resync(&self, tail: &[u8], repaint: &ScreenRepaint, idle: bool) {
+        {
+            let mut screen = self.screen.lock().unwrap();
+            *screen = ScreenModel::new(repaint.rows, repaint.cols);
+            screen.process(&repaint.to_terminal_bytes());
+            let mut output_buffer = self.output_buffer.lock().unwrap();
+            *output_buffer = RingBuffer::new(output_buffer.capacity);
+            output_buffer.push(tail);
+        }
+        self.activity.set_idle(idle, &self.event_tx);
+    }
+
+    /// Apply an output chunk to the ring buffer and screen model, then announce it
+    ///
+    /// Leaves the idle state first. The output event is sent while the screen
+    /// lock is held so snapshots taken by `subscribe_with_snapshot` never
+    /// overlap with delivered events.
+    fn record(&self, data: Vec<u8>) {
+        // Update activity timestamp (always succeeds), leaving the idle state
+        self.activity.record_output(&self.event_tx);
+        let mut screen = self.screen.lock().unwrap();
+        self.output_buffer.lock().unwrap().push(&data);
+        // Keep the screen model in step with the bytes the program wrote
+        screen.process(&data);
+        let mut log = self.log.lock().unwrap();
+        if let Some(Err(e)) = log.as_mut().map(|log| log.append(&data)) {
+            // Keep the session going without its log rather than failing every write
+            eprintln!("Output log write failed, disabling log: {}", e);
+            *log = None;
+        }
+        let mut recording = self.recording.lock().unwrap();
+        if let Some(Err(e)) = recording.as_mut().map(|r| r.output(&data)) {
+            eprintln!("Recording write failed, stopping recording: {}", e);
+            *recording = None;
+        }
+        // Non-blocking - dropped if nobody listens; the ring buffer already has the data
+        let _ = self.event_tx.send(PtyEvent::Output(data));
+    }
+}
+
+/// Pause switch for a reader that polls its PTY instead of blocking in read
+///
+/// A paused reader stops consuming output, leaving it in the kernel buffer for
+/// whoever reads the PTY next.
+#[cfg(unix)]
+struct ReaderPause {
+    /// PTY master descriptor the reader polls
+    fd: RawFd,
+    requested: AtomicBool,
+    parked: AtomicBool,
+}
+
+#[cfg(unix)]
+impl ReaderPause {
+    fn new(fd: RawFd) -> Arc<Self> {
+        Arc::new(Self {
+            fd,
+            requested: AtomicBool::new(false),
+            parked: AtomicBool::new(false),
+        })
+    }
+
+    /// Wait until output is readable, parking while a pause is requested
+    ///
+    /// Returns false once `alive` is cleared.
+    fn wait_readable(&self, alive: &AtomicBool) -> bool {
+        loop {
+            if !alive.load(Ordering::SeqCst) {
+                return false;
+            }
+            if self.requested.load(Ordering::SeqCst) {
+                self.parked.store(true, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(10));
+                continue;
+            }
+            self.parked.store(false, Ordering::SeqCst);
+
+            let mut pollfd = libc::pollfd {
+                fd: self.fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: pollfd is a valid single-element array for the call
+            let ready =
+                unsafe { libc::poll(&mut pollfd, 1, READER_POLL_INTERVAL.as_millis() as i32) };
+            // Errors and hangups fall through to read, which reports them
+            if ready != 0 {
+                return true;
+            }
+        }
+    }
+
+    /// Ask the reader to stop and wait until it has
+    fn park(&self) -> Result<()> {
+        self.requested.store(true, Ordering::SeqCst);
+        let deadline = Instant::now() + READER_PARK_TIMEOUT;
+        while !self.parked.load(Ordering::SeqCst) {
+            if Instant::now() >= deadline {
+                self.requested.store(false, Ordering::SeqCst);
+                anyhow::bail!("PTY reader did not pause");
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        Ok(())
+    }
+
+    fn resume(&self) {
+        self.requested.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Output activity tracking shared by the reader and idle watcher tasks
+///
+/// Both transitions happen under the timestamp lock so an idle check can't
+/// interleave with new output and report a stale Idle.
+#[derive(Clone)]
+struct Activity {
+    /// When the PTY last produced output
+    last_output: Arc<StdMutex<Instant>>,
+    /// Set once the PTY has been quiet for the idle timeout, cleared on new output
+    idle: Arc<AtomicBool>,
+}
+
+impl Activity {
+    fn new() -> Self {
+        Self {
+            last_output: Arc::new(StdMutex::new(Instant::now())),
+            idle: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Record output, emitting `PtyEvent::Active` when leaving the idle state
+    fn record_output(&self, event_tx: &broadcast::Sender<PtyEvent>) {
+        let mut last_output = self.last_output.lock().unwrap();
+        *last_output = Instant::now();
+        if self.idle.swap(false, Ordering::SeqCst) {
+            let _ = event_tx.send(PtyEvent::Active);
+        }
+    }
+
+    /// Emit `PtyEvent::Idle` if quiet for `idle_timeout`; returns how long until
+    /// the next check is worthwhile
+    fn check_idle(
+        &self,
+        idle_timeout: Duration,
+        event_tx: &broadcast::Sender<PtyEvent>,
+    ) -> Duration {
+        let last_output = self.last_output.lock().unwrap();
+        let elapsed = last_output.elapsed();
+        if elapsed >= idle_timeout {
+            if !self.idle.swap(true, Ordering::SeqCst) {
+                let _ = event_tx.send(PtyEvent::Idle);
+            }
+            // Already idle; the next transition can't happen sooner than this
+            idle_timeout
+        } else {
+            idle_timeout - elapsed
+        }
+    }
+
+    /// Mirror an idle/active transition reported by a holder process
+    #[cfg(unix)]
+    fn set_idle(&self, idle: bool, event_tx: &broadcast::Sender<PtyEvent>) {
+        let mut last_output = self.last_output.lock().unwrap();
+        if !idle {
+            *last_output = Instant::now();
+        }
+        if self.idle.swap(idle, Ordering::SeqCst) != idle {
+            let _ = event_tx.send(if idle {
+                PtyEvent::Idle
+            } else {
+                PtyEvent::Active
+            });
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.idle.load(Ordering::SeqCst)
+    }
+}
+
 /// Simple ring buffer for storing recent PTY output
 struct RingBuffer {
     data: Vec<u8>,
@@ -144,6 +562,23 @@ impl PtyRuntime {
         shell: Option<Vec<String>>,
         task_key: &str,
         project_path: &str,
+    ) -> Result<Self> {
+        let spec = SpawnSpec {
+            shell,
+            ..SpawnSpec::default()
+        };
+        Self::spawn_with_outputs(session_id, &spec, task_key, project_path, None, None)
+    }
+
+    /// Spawn a new PTY session as described by `spec`, appending its output to
+    /// `log` and recording it to the asciicast file at `recording` as well
+    pub fn spawn_with_outputs(
+        session_id: SessionId,
+        spec: &SpawnSpec,
+        task_key: &str,
+        project_path: &str,
+        log: Option<OutputLog>,
+        recording: Option<&Path>,
     ) -> Result<Self> {
         let pty_system = native_pty_system();
 
@@ -160,7 +595,7 @@ impl PtyRuntime {
         let portable_pty::PtyPair { master, slave } = pair;
 
         // Build command with environment variables for shell integration
-        let mut cmd = build_shell_command(shell);
+        let mut cmd = build_shell_command(spec);
         cmd.env("RIGHT_NOW_SESSION_ID", session_id.to_string());
         cmd.env("RIGHT_NOW_TASK_KEY", task_key);
         cmd.env("RIGHT_NOW_PROJECT", project_path);
@@ -179,23 +614,38 @@ impl PtyRuntime {
         let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
 
         let alive = Arc::new(AtomicBool::new(true));
-        let output_buffer = Arc::new(StdMutex::new(RingBuffer::new(OUTPUT_BUFFER_SIZE)));
-        let last_activity = Arc::new(StdMutex::new(Instant::now()));
+        let sink = OutputSink::new(
+            event_tx.clone(),
+            DEFAULT_ROWS,
+            DEFAULT_COLS,
+            spec.tuning.output_buffer_size,
+        )
+        .with_log(log)
+        .with_recording(recording, Some(&sanitize_task_display(task_key)));
         let exit_code = Arc::new(StdMutex::new(None));
 
         let reader = master
             .try_clone_reader()
             .context("Failed to clone PTY reader")?;
         let writer = master.take_writer().context("Failed to take PTY writer")?;
+        #[cfg(unix)]
+        let pause = ReaderPause::new(master.as_raw_fd().context("PTY master has no descriptor")?);
         let master_handle = Arc::new(StdMutex::new(master));
 
+        // The reader drops `drained_tx` on EOF; the wait task uses it to flush
+        // trailing output into the ring buffer before announcing the exit.
+        let (drained_tx, drained_rx) = std_mpsc::channel::<()>();
+        // The wait task drops `exit_tx` when the child is gone, waking the idle watcher.
+        let (exit_tx, exit_rx) = std_mpsc::channel::<()>();
+
         // Spawn background tasks for I/O
         Self::spawn_reader_task(
             reader,
-            event_tx.clone(),
+            sink.clone(),
             Arc::clone(&alive),
-            Arc::clone(&output_buffer),
-            Arc::clone(&last_activity),
+            drained_tx,
+            #[cfg(unix)]
+            Arc::clone(&pause),
         );
 
         Self::spawn_writer_task(writer, input_rx, Arc::clone(&alive));
@@ -206,18 +656,31 @@ impl PtyRuntime {
             shutdown_rx,
             Arc::clone(&alive),
             Arc::clone(&exit_code),
+            drained_rx,
+            exit_tx,
+        );
+
+        Self::spawn_idle_task(
+            event_tx.clone(),
+            Arc::clone(&alive),
+            sink.activity.clone(),
+            spec.tuning.idle_timeout,
+            exit_rx,
         );
 
         Ok(Self {
             session_id,
             input_tx,
             event_tx,
-            master: master_handle,
-            shutdown_tx: Some(shutdown_tx),
+            backend: Backend::Local {
+                master: master_handle,
+                shutdown_tx: Some(shutdown_tx),
+                #[cfg(unix)]
+                pause,
+            },
             alive,
             child_pid,
-            output_buffer,
-            last_activity,
+            sink,
             exit_code,
         })
     }
@@ -225,20 +688,28 @@ impl PtyRuntime {
     /// Spawn the reader task that reads PTY output
     ///
     /// Note: Events are sent via broadcast and dropped if no listeners are active.
-    /// The ring buffer and activity timestamp are always updated regardless of
-    /// event delivery, preventing deadlock when nothing drains the channel.
+    /// The ring buffer, screen model and activity timestamp are always updated
+    /// regardless of event delivery, preventing deadlock when nothing drains the channel.
+    /// Output events are sent under the screen lock; see `subscribe_with_snapshot`.
+    /// On Unix the reader polls the PTY first so a handoff can pause it.
     fn spawn_reader_task(
         mut reader: Box<dyn Read + Send>,
-        event_tx: broadcast::Sender<PtyEvent>,
+        sink: OutputSink,
         alive: Arc<AtomicBool>,
-        output_buffer: Arc<StdMutex<RingBuffer>>,
-        last_activity: Arc<StdMutex<Instant>>,
+        drained_tx: std_mpsc::Sender<()>,
+        #[cfg(unix)] pause: Arc<ReaderPause>,
     ) {
         std::thread::spawn(move || {
+            // Held until the reader stops so the wait task can tell when output is drained
+            let _drained_tx = drained_tx;
             let mut buf = [0u8; 4096];
-            let mut was_idle = false;
 
             loop {
+                #[cfg(unix)]
+                if !pause.wait_readable(&alive) {
+                    break;
+                }
+                #[cfg(not(unix))]
                 if !alive.load(Ordering::SeqCst) {
                     break;
                 }
@@ -248,31 +719,7 @@ impl PtyRuntime {
                         // EOF - PTY closed
                         break;
                     }
-                    Ok(n) => {
-                        let data = buf[..n].to_vec();
-
-                        // Update output buffer (always succeeds)
-                        {
-                            let mut buffer = output_buffer.lock().unwrap();
-                            buffer.push(&data);
-                        }
-
-                        // Update activity timestamp (always succeeds)
-                        {
-                            let mut activity = last_activity.lock().unwrap();
-                            *activity = Instant::now();
-                        }
-
-                        // Send activity event if we were idle (non-blocking)
-                        if was_idle {
-                            was_idle = false;
-                            let _ = event_tx.send(PtyEvent::Active);
-                        }
-
-                        // Send output event (non-blocking - drop if channel full)
-                        // The ring buffer already has the data, so dropping events is safe
-                        let _ = event_tx.send(PtyEvent::Output(data));
-                    }
+                    Ok(n) => sink.record(buf[..n].to_vec()),
                     Err(e) => {
                         eprintln!("PTY read error: {}", e);
                         break;
@@ -311,8 +758,12 @@ impl PtyRuntime {
         mut shutdown_rx: oneshot::Receiver<()>,
         alive: Arc<AtomicBool>,
         exit_code: Arc<StdMutex<Option<i32>>>,
+        drained_rx: std_mpsc::Receiver<()>,
+        exit_tx: std_mpsc::Sender<()>,
     ) {
         std::thread::spawn(move || {
+            // Dropped when this task returns, which wakes the idle watcher
+            let _exit_tx = exit_tx;
             loop {
                 // Check for shutdown signal
                 match shutdown_rx.try_recv() {
@@ -332,6 +783,10 @@ impl PtyRuntime {
                 // Try to wait with timeout
                 match child.try_wait() {
                     Ok(Some(status)) => {
+                        // Give the reader a moment to drain trailing output so the
+                        // ring buffer is complete when listeners see the exit
+                        let _ = drained_rx.recv_timeout(EXIT_DRAIN_TIMEOUT);
+
                         // Child exited - set alive flag first (always succeeds)
                         alive.store(false, Ordering::SeqCst);
                         let code_value = Some(status.exit_code() as i32);
@@ -364,6 +819,31 @@ impl PtyRuntime {
         });
     }
 
+    /// Spawn the task that emits `PtyEvent::Idle` once output has been quiet for `idle_timeout`
+    ///
+    /// Sleeps until the next possible idle deadline rather than polling, and exits as
+    /// soon as the child is gone. `PtyEvent::Active` is emitted by the reader task.
+    fn spawn_idle_task(
+        event_tx: broadcast::Sender<PtyEvent>,
+        alive: Arc<AtomicBool>,
+        activity: Activity,
+        idle_timeout: Duration,
+        exit_rx: std_mpsc::Receiver<()>,
+    ) {
+        std::thread::spawn(move || loop {
+            if !alive.load(Ordering::SeqCst) {
+                break;
+            }
+
+            let wait = activity.check_idle(idle_timeout, &event_tx);
+
+            match exit_rx.recv_timeout(wait) {
+                Err(std_mpsc::RecvTimeoutError::Timeout) => continue,
+                _ => break,
+            }
+        });
+    }
+
     /// Get the session ID
     pub fn session_id(&self) -> SessionId {
         self.session_id
@@ -379,6 +859,31 @@ impl PtyRuntime {
         self.alive.load(Ordering::SeqCst)
     }
 
+    /// The process that currently owns the terminal, if it can be resolved
+    ///
+    /// Reads the foreground process group from the PTY master where this
+    /// process has it, and from the child's /proc entry for holder PTYs.
+    pub fn foreground_process(&self) -> Option<ForegroundProcess> {
+        if !self.is_alive() {
+            return None;
+        }
+        let pgid = match &self.backend {
+            #[cfg(unix)]
+            Backend::Local { master, .. } => master.lock().unwrap().process_group_leader(),
+            #[cfg(not(unix))]
+            Backend::Local { .. } => None,
+            #[cfg(unix)]
+            Backend::Holder { .. } => foreground::terminal_pgid_of(self.child_pid?),
+            #[cfg(unix)]
+            Backend::Adopted { master, .. } => {
+                // SAFETY: tcgetpgrp only reads the terminal's foreground group
+                let pgid = unsafe { libc::tcgetpgrp(master.as_raw_fd()) };
+                (pgid > 0).then_some(pgid)
+            }
+        }?;
+        foreground::resolve(pgid)
+    }
+
     /// Send input to the PTY
     pub async fn send_input(&self, data: Vec<u8>) -> Result<()> {
         self.input_tx
@@ -389,15 +894,44 @@ impl PtyRuntime {
 
     /// Resize the PTY window
     pub fn resize(&self, cols: u16, rows: u16) -> Result<()> {
-        let master = self.master.lock().unwrap();
-        master
-            .resize(PtySize {
-                rows,
-                cols,
-                pixel_width: 0,
-                pixel_height: 0,
-            })
-            .context("Failed to resize PTY")
+        match &self.backend {
+            Backend::Local { master, .. } => {
+                let master = master.lock().unwrap();
+                master
+                    .resize(PtySize {
+                        rows,
+                        cols,
+                        pixel_width: 0,
+                        pixel_height: 0,
+                    })
+                    .context("Failed to resize PTY")?;
+            }
+            #[cfg(unix)]
+            Backend::Holder { control } => {
+                write_holder_request(control, &HolderRequest::Resize { cols, rows })
+                    .context("Failed to resize PTY")?;
+            }
+            #[cfg(unix)]
+            Backend::Adopted { master, .. } => {
+                let size = libc::winsize {
+                    ws_row: rows,
+                    ws_col: cols,
+                    ws_xpixel: 0,
+                    ws_ypixel: 0,
+                };
+                // SAFETY: TIOCSWINSZ reads a winsize from the pointer
+                if unsafe { libc::ioctl(master.as_raw_fd(), libc::TIOCSWINSZ, &size) } != 0 {
+                    return Err(std::io::Error::last_os_error()).context("Failed to resize PTY");
+                }
+            }
+        }
+        self.sink.screen.lock().unwrap().resize(rows, cols);
+        let mut recording = self.sink.recording.lock().unwrap();
+        if let Some(Err(e)) = recording.as_mut().map(|r| r.resize(cols, rows)) {
+            eprintln!("Recording write failed, stopping recording: {}", e);
+            *recording = None;
+        }
+        Ok(())
     }
 
     /// Subscribe to PTY events (output, idle/active, exit)
@@ -405,6 +939,22 @@ impl PtyRuntime {
         self.event_tx.subscribe()
     }
 
+    /// Subscribe to PTY events along with the output tail and screen as of the
+    /// moment of subscribing
+    ///
+    /// Every output chunk lands either in the snapshot or as a later
+    /// `PtyEvent::Output`, never both, so a mirror can be rebuilt without gaps
+    /// or duplicates.
+    pub fn subscribe_with_snapshot(
+        &self,
+        max_bytes: usize,
+    ) -> (broadcast::Receiver<PtyEvent>, Vec<u8>, ScreenRepaint) {
+        let mut screen = self.sink.screen.lock().unwrap();
+        let events = self.event_tx.subscribe();
+        let tail = self.sink.output_buffer.lock().unwrap().get_tail(max_bytes);
+        (events, tail, screen.repaint())
+    }
+
     /// Clone of the input sender for streaming input
     pub fn input_sender(&self) -> mpsc::Sender<Vec<u8>> {
         self.input_tx.clone()
@@ -417,7 +967,17 @@ impl PtyRuntime {
 
     /// Get recent output without requiring async context (used by daemon request handlers)
     pub fn get_recent_output_blocking(&self, max_bytes: usize) -> Vec<u8> {
-        self.output_buffer.lock().unwrap().get_tail(max_bytes)
+        self.sink.output_buffer.lock().unwrap().get_tail(max_bytes)
+    }
+
+    /// Synthesize a repaint of the session's current screen for attaching clients
+    pub fn screen_repaint(&self) -> ScreenRepaint {
+        self.sink.screen.lock().unwrap().repaint()
+    }
+
+    /// Render the session's current screen as plain text lines
+    pub fn screen_text(&self) -> ScreenText {
+        self.sink.screen.lock().unwrap().text()
     }
 
     /// Get the cached exit code if the PTY has terminated
@@ -426,9 +986,10 @@ impl PtyRuntime {
     }
 
     /// Check if the PTY has been idle for longer than the threshold
+    ///
+    /// Mirrors the last `PtyEvent::Idle`/`PtyEvent::Active` emitted.
     pub fn is_idle(&self) -> bool {
-        let last = *self.last_activity.lock().unwrap();
-        last.elapsed() > IDLE_TIMEOUT
+        self.sink.activity.is_idle()
     }
 
     /// Get the current inferred status based on activity
@@ -445,21 +1006,412 @@ impl PtyRuntime {
     /// Stop the PTY session
     pub fn stop(&mut self) {
         self.alive.store(false, Ordering::SeqCst);
-        if let Some(tx) = self.shutdown_tx.take() {
-            let _ = tx.send(());
+        match &mut self.backend {
+            Backend::Local { shutdown_tx, .. } => {
+                if let Some(tx) = shutdown_tx.take() {
+                    let _ = tx.send(());
+                }
+            }
+            #[cfg(unix)]
+            Backend::Holder { control } => {
+                let _ = write_holder_request(control, &HolderRequest::Stop);
+            }
+            #[cfg(unix)]
+            Backend::Adopted { .. } => {
+                // Hang up the child like closing its terminal would
+                if let Some(pid) = self.child_pid {
+                    // SAFETY: kill has no memory safety requirements
+                    unsafe { libc::kill(pid as libc::pid_t, libc::SIGHUP) };
+                }
+            }
+        }
+    }
+
+    /// Whether the PTY is owned by a holder process rather than this process
+    pub fn is_held(&self) -> bool {
+        match self.backend {
+            #[cfg(unix)]
+            Backend::Holder { .. } => true,
+            _ => false,
         }
     }
 }
 
+#[cfg(unix)]
+impl PtyRuntime {
+    /// Connect to a holder process and mirror the session it owns
+    ///
+    /// The holder greets each connection with its ring buffer tail and screen,
+    /// which seed the local copies; later output is streamed as it happens.
+    /// Dropping the runtime only disconnects, leaving the session running.
+    pub fn connect_holder(
+        session_id: SessionId,
+        socket_path: &Path,
+        tuning: RuntimeTuning,
+    ) -> Result<Self> {
+        let (stream, reader, hello) = open_holder_connection(session_id, socket_path)?;
+
+        let (input_tx, input_rx) = mpsc::channel::<Vec<u8>>(100);
+        let (event_tx, _) = broadcast::channel::<PtyEvent>(200);
+
+        let alive = Arc::new(AtomicBool::new(hello.alive));
+        let sink = OutputSink::from_snapshot(
+            event_tx.clone(),
+            &hello.tail,
+            &hello.screen,
+            hello.idle,
+            tuning.output_buffer_size,
+        );
+        let exit_code = Arc::new(StdMutex::new(hello.exit_code));
+        let control = Arc::new(StdMutex::new(stream));
+
+        Self::spawn_holder_reader_task(
+            HolderLink {
+                session_id,
+                socket_path: socket_path.to_path_buf(),
+                control: Arc::clone(&control),
+            },
+            reader,
+            sink.clone(),
+            Arc::clone(&alive),
+            Arc::clone(&exit_code),
+        );
+        Self::spawn_holder_writer_task(Arc::clone(&control), input_rx, Arc::clone(&alive));
+
+        Ok(Self {
+            session_id,
+            input_tx,
+            event_tx,
+            backend: Backend::Holder { control },
+            alive,
+            child_pid: hello.pid,
+            sink,
+            exit_code,
+        })
+    }
+
+    /// Take over a PTY master handed off by a previous daemon
+    ///
+    /// The handoff carries the old daemon's ring buffer tail and screen, which
+    /// seed the local copies; output the old reader left unread is still in
+    /// the PTY and arrives as usual. Pass the session's reopened `log` and its
+    /// `recording` path to keep logging and recording where the old daemon
+    /// stopped.
+    pub fn adopt_handoff(
+        session: &HandoffSession,
+        master: OwnedFd,
+        log: Option<OutputLog>,
+        recording: Option<&Path>,
+        tuning: RuntimeTuning,
+    ) -> Result<Self> {
+        let master = File::from(master);
+        let reader = master.try_clone().context("Failed to clone PTY reader")?;
+        let writer = master.try_clone().context("Failed to clone PTY writer")?;
+
+        let (input_tx, input_rx) = mpsc::channel::<Vec<u8>>(100);
+        let (event_tx, _) = broadcast::channel::<PtyEvent>(200);
+
+        let alive = Arc::new(AtomicBool::new(true));
+        let repaint = match &session.screen {
+            Some(repaint) => repaint.clone(),
+            None => ScreenModel::new(DEFAULT_ROWS, DEFAULT_COLS).repaint(),
+        };
+        let sink = OutputSink::from_snapshot(
+            event_tx.clone(),
+            &session.tail,
+            &repaint,
+            session.idle,
+            tuning.output_buffer_size,
+        )
+        .with_log(log)
+        .with_recording(recording, None);
+        let exit_code = Arc::new(StdMutex::new(None));
+        let pause = ReaderPause::new(master.as_raw_fd());
+
+        let (drained_tx, drained_rx) = std_mpsc::channel::<()>();
+        let (exit_tx, exit_rx) = std_mpsc::channel::<()>();
+
+        Self::spawn_reader_task(
+            Box::new(reader),
+            sink.clone(),
+            Arc::clone(&alive),
+            drained_tx,
+            Arc::clone(&pause),
+        );
+        Self::spawn_writer_task(Box::new(writer), input_rx, Arc::clone(&alive));
+
+        // Without the child to wait on, the PTY closing is the exit signal
+        let exit_alive = Arc::clone(&alive);
+        let exit_events = event_tx.clone();
+        std::thread::spawn(move || {
+            let _exit_tx = exit_tx;
+            let _ = drained_rx.recv();
+            if exit_alive.swap(false, Ordering::SeqCst) {
+                let _ = exit_events.send(PtyEvent::Exited { exit_code: None });
+            }
+        });
+
+        Self::spawn_idle_task(
+            event_tx.clone(),
+            Arc::clone(&alive),
+            sink.activity.clone(),
+            tuning.idle_timeout,
+            exit_rx,
+        );
+
+        Ok(Self {
+            session_id: session.session_id,
+            input_tx,
+            event_tx,
+            backend: Backend::Adopted { master, pause },
+            alive,
+            child_pid: session.pid,
+            sink,
+            exit_code,
+        })
+    }
+
+    /// Stop reading the PTY and describe the session for a handoff
+    ///
+    /// Returns the PTY master to pass along, or None for held sessions, whose
+    /// holder the next daemon reconnects to. Once the reader is paused the
+    /// tail and screen stay exact: unread output waits in the PTY. Call
+    /// `resume_after_handoff` if the handoff doesn't go through.
+    pub fn detach_for_handoff(&self) -> Result<(HandoffSession, Option<RawFd>)> {
+        let mut session = HandoffSession {
+            session_id: self.session_id,
+            held: true,
+            pid: self.child_pid,
+            tail: Vec::new(),
+            screen: None,
+            idle: self.is_idle(),
+            attach_listener: false,
+        };
+
+        let (pause, fd) = match &self.backend {
+            Backend::Holder { .. } => return Ok((session, None)),
+            Backend::Local { master, pause, .. } => {
+                let fd = master.lock().unwrap().as_raw_fd();
+                (pause, fd.context("PTY master has no descriptor")?)
+            }
+            Backend::Adopted { master, pause } => (pause, master.as_raw_fd()),
+        };
+
+        pause.park()?;
+        session.held = false;
+        session.tail = self.get_recent_output_blocking(usize::MAX);
+        session.screen = Some(self.screen_repaint());
+        session.idle = self.is_idle();
+        Ok((session, Some(fd)))
+    }
+
+    /// Resume reading after a handoff that didn't go through
+    pub fn resume_after_handoff(&self) {
+        match &self.backend {
+            Backend::Local { pause, .. } | Backend::Adopted { pause, .. } => pause.resume(),
+            Backend::Holder { .. } => {}
+        }
+    }
+
+    /// Spawn the task that applies holder messages to the local mirror
+    ///
+    /// The session is reported exited when the holder says so. A connection
+    /// that closes without an exit message is reopened from a fresh hello;
+    /// only when the holder can't be reached any more is the session reported
+    /// exited with an unknown code, since its child went with it.
+    fn spawn_holder_reader_task(
+        link: HolderLink,
+        mut reader: std::io::BufReader<UnixStream>,
+        sink: OutputSink,
+        alive: Arc<AtomicBool>,
+        exit_code: Arc<StdMutex<Option<i32>>>,
+    ) {
+        std::thread::spawn(move || {
+            let mut line = String::new();
+            let final_code = loop {
+                line.clear();
+                if matches!(reader.read_line(&mut line), Ok(0) | Err(_)) {
+                    // Stopped or dropped through this runtime
+                    if !alive.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    match link.reconnect() {
+                        Some((new_reader, hello)) => {
+                            sink.resync(&hello.tail, &hello.screen, hello.idle);
+                            reader = new_reader;
+                            if !hello.alive {
+                                break hello.exit_code;
+                            }
+                            continue;
+                        }
+                        None => break None,
+                    }
+                }
+                match deserialize_message::<HolderMessage>(line.as_bytes()) {
+                    Ok(HolderMessage::Output { data }) => sink.record(data),
+                    Ok(HolderMessage::Resync { tail, screen, idle }) => {
+                        sink.resync(&tail, &screen, idle)
+                    }
+                    Ok(HolderMessage::Idle) => sink.activity.set_idle(true, &sink.event_tx),
+                    Ok(HolderMessage::Active) => sink.activity.set_idle(false, &sink.event_tx),
+                    Ok(HolderMessage::Exited { exit_code }) => break exit_code,
+                    Ok(HolderMessage::Hello { .. }) => {}
+                    Err(e) => eprintln!("Invalid holder message: {}", e),
+                }
+            };
+
+            // Already stopped through this runtime; nobody is waiting for an exit
+            if !alive.swap(false, Ordering::SeqCst) {
+                return;
+            }
+            *exit_code.lock().unwrap() = final_code;
+            let _ = sink.event_tx.send(PtyEvent::Exited {
+                exit_code: final_code,
+            });
+        });
+    }
+
+    /// Spawn the task that forwards input to the holder
+    fn spawn_holder_writer_task(
+        control: Arc<StdMutex<UnixStream>>,
+        mut input_rx: mpsc::Receiver<Vec<u8>>,
+        alive: Arc<AtomicBool>,
+    ) {
+        std::thread::spawn(move || {
+            while let Some(data) = input_rx.blocking_recv() {
+                if !alive.load(Ordering::SeqCst) {
+                    break;
+                }
+                if write_holder_request(&control, &HolderRequest::Input { data }).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
 impl Drop for PtyRuntime {
     fn drop(&mut self) {
-        self.stop();
+        match &self.backend {
+            Backend::Local { .. } => self.stop(),
+            #[cfg(unix)]
+            Backend::Adopted { .. } => self.stop(),
+            // Held sessions outlive this process; dropping just disconnects
+            #[cfg(unix)]
+            Backend::Holder { control } => {
+                self.alive.store(false, Ordering::SeqCst);
+                let _ = control.lock().unwrap().shutdown(std::net::Shutdown::Both);
+            }
+        }
     }
 }
 
+/// What a holder says when a connection opens
+#[cfg(unix)]
+struct HolderHello {
+    pid: Option<u32>,
+    tail: Vec<u8>,
+    screen: ScreenRepaint,
+    idle: bool,
+    alive: bool,
+    exit_code: Option<i32>,
+}
+
+/// Connect to a holder and read its hello
+///
+/// Returns the stream (for requests), a reader for the messages that follow
+/// and the hello.
+#[cfg(unix)]
+fn open_holder_connection(
+    session_id: SessionId,
+    socket_path: &Path,
+) -> Result<(UnixStream, std::io::BufReader<UnixStream>, HolderHello)> {
+    let stream = UnixStream::connect(socket_path)
+        .with_context(|| format!("Failed to connect to holder at {}", socket_path.display()))?;
+    stream.set_read_timeout(Some(HOLDER_HELLO_TIMEOUT))?;
+    let mut reader = std::io::BufReader::new(stream.try_clone()?);
+
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .context("Failed to read holder hello")?;
+    let hello = match deserialize_message::<HolderMessage>(line.as_bytes())
+        .context("Invalid holder hello")?
+    {
+        HolderMessage::Hello {
+            session_id: held_id,
+            pid,
+            tail,
+            screen,
+            idle,
+            alive,
+            exit_code,
+        } => {
+            anyhow::ensure!(
+                held_id == session_id,
+                "Holder at {} serves session {}, not {}",
+                socket_path.display(),
+                held_id,
+                session_id
+            );
+            HolderHello {
+                pid,
+                tail,
+                screen,
+                idle,
+                alive,
+                exit_code,
+            }
+        }
+        other => anyhow::bail!("Expected holder hello, got {:?}", other),
+    };
+    stream.set_read_timeout(None)?;
+    Ok((stream, reader, hello))
+}
+
+/// Where a mirror's holder listens, for reconnecting after a lost connection
+#[cfg(unix)]
+struct HolderLink {
+    session_id: SessionId,
+    socket_path: PathBuf,
+    /// Shared with the writer task and `PtyRuntime`, which write requests to it
+    control: Arc<StdMutex<UnixStream>>,
+}
+
+#[cfg(unix)]
+impl HolderLink {
+    /// Reopen the connection, swapping the new stream in for requests
+    ///
+    /// Returns None once the holder is gone: its socket was removed or stays
+    /// unreachable for `HOLDER_RECONNECT_TIMEOUT`.
+    fn reconnect(&self) -> Option<(std::io::BufReader<UnixStream>, HolderHello)> {
+        let deadline = Instant::now() + HOLDER_RECONNECT_TIMEOUT;
+        while self.socket_path.exists() {
+            match open_holder_connection(self.session_id, &self.socket_path) {
+                Ok((stream, reader, hello)) => {
+                    *self.control.lock().unwrap() = stream;
+                    return Some((reader, hello));
+                }
+                Err(_) if Instant::now() >= deadline => return None,
+                Err(_) => std::thread::sleep(Duration::from_millis(50)),
+            }
+        }
+        None
+    }
+}
+
+/// Write one frame to a holder connection
+#[cfg(unix)]
+fn write_holder_request(control: &StdMutex<UnixStream>, request: &HolderRequest) -> Result<()> {
+    let frame = serialize_message(request)?;
+    let mut stream = control.lock().unwrap();
+    stream.write_all(&frame)?;
+    Ok(())
+}
+
 /// Build a CommandBuilder from shell arguments or default
-fn build_shell_command(shell: Option<Vec<String>>) -> CommandBuilder {
-    match shell {
+fn build_shell_command(spec: &SpawnSpec) -> CommandBuilder {
+    let mut cmd = match &spec.shell {
         Some(args) if !args.is_empty() => {
             let mut cmd = CommandBuilder::new(&args[0]);
             for arg in args.iter().skip(1) {
@@ -474,7 +1426,14 @@ fn build_shell_command(shell: Option<Vec<String>>) -> CommandBuilder {
             cmd.arg("-l"); // Login shell
             cmd
         }
+    };
+    if let Some(cwd) = &spec.cwd {
+        cmd.cwd(cwd);
+    }
+    for (name, value) in &spec.env {
+        cmd.env(name, value);
     }
+    cmd
 }
 
 #[cfg(test)]
@@ -533,6 +1492,131 @@ mod tests {
         assert!(got_exit, "Process should have exited");
     }
 
+    #[tokio::test]
+    async fn test_idle_and_active_events() {
+        // Stay quiet long enough to go idle, then produce output again
+        let shell = vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            "sleep 0.5; echo wake; sleep 5".to_string(),
+        ];
+        let spec = SpawnSpec {
+            shell: Some(shell),
+            tuning: RuntimeTuning {
+                idle_timeout: Duration::from_millis(200),
+                ..RuntimeTuning::default()
+            },
+            ..SpawnSpec::default()
+        };
+        let mut runtime =
+            PtyRuntime::spawn_with_outputs(1, &spec, "Idle task", "/tmp/TODO.md", None, None)
+                .expect("Failed to spawn");
+        let mut events = runtime.subscribe_events();
+
+        let mut transitions = Vec::new();
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(3);
+        while transitions.len() < 2 {
+            match tokio::time::timeout_at(deadline, events.recv()).await {
+                Ok(Ok(PtyEvent::Idle)) => {
+                    assert!(runtime.is_idle());
+                    transitions.push("idle");
+                }
+                Ok(Ok(PtyEvent::Active)) => transitions.push("active"),
+                Ok(Ok(_)) | Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+                Ok(Err(_)) | Err(_) => break,
+            }
+        }
+
+        assert_eq!(transitions, vec!["idle", "active"]);
+        assert!(!runtime.is_idle());
+        runtime.stop();
+    }
+
+    #[tokio::test]
+    async fn test_exit_event_follows_final_output() {
+        let shell = vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            "echo last-words; exit 3".to_string(),
+        ];
+        let runtime = PtyRuntime::spawn(1, Some(shell), "Exit task", "/tmp/TODO.md")
+            .expect("Failed to spawn");
+        let mut events = runtime.subscribe_events();
+
+        let exit_code = loop {
+            match tokio::time::timeout(Duration::from_secs(3), events.recv()).await {
+                Ok(Ok(PtyEvent::Exited { exit_code })) => break exit_code,
+                Ok(Ok(_)) | Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+                other => panic!("Expected exit event, got {:?}", other),
+            }
+        };
+
+        assert_eq!(exit_code, Some(3));
+        assert!(!runtime.is_alive());
+        let tail = runtime.get_recent_output_blocking(1024);
+        assert!(
+            String::from_utf8_lossy(&tail).contains("last-words"),
+            "Output should be buffered before the exit event"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_output_is_logged_and_recorded() {
+        use crate::session::output_log::{read_log, LogLimits};
+        use crate::session::recording::{read_recording, CastEvent};
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let log_dir = dir.path().join("log");
+        let cast_path = dir.path().join("1.cast");
+        let log = OutputLog::open(&log_dir, LogLimits::default()).unwrap();
+        let spec = SpawnSpec {
+            shell: Some(vec!["echo".to_string(), "logged-output".to_string()]),
+            ..SpawnSpec::default()
+        };
+        let runtime = PtyRuntime::spawn_with_outputs(
+            1,
+            &spec,
+            "Log task",
+            "/tmp/TODO.md",
+            Some(log),
+            Some(&cast_path),
+        )
+        .expect("Failed to spawn");
+        let mut events = runtime.subscribe_events();
+
+        loop {
+            match tokio::time::timeout(Duration::from_secs(3), events.recv()).await {
+                Ok(Ok(PtyEvent::Exited { .. })) => break,
+                Ok(Ok(_)) | Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+                other => panic!("Expected exit event, got {:?}", other),
+            }
+        }
+
+        let range = read_log(&log_dir, 0, 4096).unwrap();
+        assert_eq!(range.offset, 0);
+        assert!(
+            String::from_utf8_lossy(&range.data).contains("logged-output"),
+            "Output should be on disk"
+        );
+        assert_eq!(
+            range.data,
+            runtime.get_recent_output_blocking(4096),
+            "Log and ring buffer should agree"
+        );
+
+        let (header, entries) = read_recording(&cast_path).unwrap();
+        assert_eq!((header.width, header.height), (DEFAULT_COLS, DEFAULT_ROWS));
+        assert_eq!(header.title.as_deref(), Some("Log task"));
+        let recorded: String = entries
+            .into_iter()
+            .filter_map(|entry| match entry.event {
+                CastEvent::Output(text) => Some(text),
+                CastEvent::Resize { .. } => None,
+            })
+            .collect();
+        assert!(recorded.contains("logged-output"));
+    }
+
     #[tokio::test]
     async fn test_pty_environment_variables() {
         // Use unique values that couldn't accidentally exist in the environment
@@ -588,6 +1672,101 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_spawn_uses_cwd_and_env() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let spec = SpawnSpec {
+            shell: Some(vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "echo \"cwd=$(pwd) flavor=$RN_TEST_FLAVOR\"".to_string(),
+            ]),
+            cwd: Some(dir.path().to_path_buf()),
+            env: BTreeMap::from([("RN_TEST_FLAVOR".to_string(), "mint".to_string())]),
+
+            ..SpawnSpec::default()
+        };
+        let runtime =
+            PtyRuntime::spawn_with_outputs(1, &spec, "Cwd task", "/tmp/TODO.md", None, None)
+                .expect("Failed to spawn");
+        let mut events = runtime.subscribe_events();
+        loop {
+            match tokio::time::timeout(Duration::from_secs(3), events.recv()).await {
+                Ok(Ok(PtyEvent::Exited { .. })) => break,
+                Ok(Ok(_)) | Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+                other => panic!("Expected exit event, got {:?}", other),
+            }
+        }
+
+        let output = String::from_utf8_lossy(&runtime.get_recent_output_blocking(1024)).to_string();
+        let cwd = dir.path().canonicalize().unwrap();
+        assert!(
+            output.contains(&format!("cwd={}", cwd.display())),
+            "unexpected output: {}",
+            output
+        );
+        assert!(
+            output.contains("flavor=mint"),
+            "unexpected output: {}",
+            output
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_foreground_process_reports_command_and_cwd() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let work_dir = dir.path().join("work");
+        std::fs::create_dir(&work_dir).unwrap();
+        let spec = SpawnSpec {
+            shell: Some(vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "cd work && exec sleep 30".to_string(),
+            ]),
+            cwd: Some(dir.path().to_path_buf()),
+            env: BTreeMap::new(),
+
+            ..SpawnSpec::default()
+        };
+        let mut runtime =
+            PtyRuntime::spawn_with_outputs(1, &spec, "Fg task", "/tmp/TODO.md", None, None)
+                .expect("Failed to spawn");
+
+        let work_dir = work_dir.canonicalize().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(3);
+        let process = loop {
+            match runtime.foreground_process() {
+                Some(process) if process.command == "sleep 30" => break process,
+                _ if Instant::now() < deadline => {
+                    tokio::time::sleep(Duration::from_millis(20)).await
+                }
+                other => panic!("Expected sleep in the foreground, got {:?}", other),
+            }
+        };
+        assert_eq!(process.cwd.as_deref(), work_dir.to_str());
+
+        runtime.stop();
+    }
+
+    #[test]
+    fn snapshot_env_drops_secrets_and_session_state() {
+        let vars = [
+            ("PATH", "/usr/bin"),
+            ("LANG", "en_US.UTF-8"),
+            ("GITHUB_TOKEN", "ghp_x"),
+            ("aws_secret_access_key", "x"),
+            ("DB_PASSWORD", "x"),
+            ("RIGHT_NOW_SESSION_ID", "4"),
+            ("PWD", "/tmp"),
+            ("SHLVL", "2"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        let kept: Vec<_> = snapshot_env(vars).into_keys().collect();
+        assert_eq!(kept, vec!["LANG", "PATH"]);
+    }
+
     #[test]
     fn sanitize_removes_control_characters_and_newlines() {
         let input = "Line1\nLine2\t\x07";
@@ -0,0 +1,216 @@
+// Server-side terminal screen model for PTY sessions
+//
+// Feeds PTY output through a VT100/xterm emulator so the daemon always knows
+// what the session's screen looks like. Attaching clients get a synthesized
+// repaint of that screen instead of a raw byte replay, which would otherwise
+// start mid-escape-sequence and garble full-screen TUIs.
+
+use crate::session::protocol::{ScreenRepaint, ScreenText};
+
+/// Lines of scrollback retained per session (primary screen only)
+pub const SCROLLBACK_LINES: usize = 1000;
+
+/// Reset SGR attributes at the end of each scrollback line
+const SGR_RESET: &[u8] = b"\x1b[m";
+
+/// Captures OSC window title updates, which vt100 reports through callbacks
+#[derive(Default)]
+struct TitleTracker {
+    title: String,
+}
+
+impl vt100::Callbacks for TitleTracker {
+    fn set_window_title(&mut self, _: &mut vt100::Screen, title: &[u8]) {
+        self.title = String::from_utf8_lossy(title).into_owned();
+    }
+}
+
+/// VT100 screen state for a single session
+pub struct ScreenModel {
+    parser: vt100::Parser<TitleTracker>,
+}
+
+impl ScreenModel {
+    pub fn new(rows: u16, cols: u16) -> Self {
+        Self {
+            parser: vt100::Parser::new_with_callbacks(
+                rows,
+                cols,
+                SCROLLBACK_LINES,
+                TitleTracker::default(),
+            ),
+        }
+    }
+
+    /// Feed raw PTY output into the emulator
+    pub fn process(&mut self, bytes: &[u8]) {
+        self.parser.process(bytes);
+    }
+
+    /// Track PTY resizes so the model wraps lines the same way the program does
+    pub fn resize(&mut self, rows: u16, cols: u16) {
+        self.parser.screen_mut().set_size(rows, cols);
+    }
+
+    /// Current screen dimensions as (rows, cols)
+    pub fn size(&self) -> (u16, u16) {
+        self.parser.screen().size()
+    }
+
+    /// Synthesize a repaint of the current screen, cursor and modes
+    pub fn repaint(&mut self) -> ScreenRepaint {
+        let scrollback = self.scrollback_lines();
+        let screen = self.parser.screen();
+        let (rows, cols) = screen.size();
+        let (cursor_row, cursor_col) = screen.cursor_position();
+
+        ScreenRepaint {
+            rows,
+            cols,
+            cursor_row,
+            cursor_col,
+            cursor_visible: !screen.hide_cursor(),
+            alternate_screen: screen.alternate_screen(),
+            title: self.parser.callbacks().title.clone(),
+            scrollback,
+            screen: screen.state_formatted(),
+        }
+    }
+
+    /// Render the visible screen as plain text lines
+    pub fn text(&self) -> ScreenText {
+        let screen = self.parser.screen();
+        let (rows, cols) = screen.size();
+        let (cursor_row, cursor_col) = screen.cursor_position();
+
+        ScreenText {
+            rows,
+            cols,
+            lines: screen
+                .rows(0, cols)
+                .map(|line| line.trim_end().to_string())
+                .collect(),
+            cursor_row,
+            cursor_col,
+            title: self.parser.callbacks().title.clone(),
+            alternate_screen: screen.alternate_screen(),
+        }
+    }
+
+    /// Formatted scrollback lines above the visible screen, oldest first
+    ///
+    /// vt100 only exposes scrollback through the viewport offset, so walk the
+    /// offset back to zero a page at a time. While a program is on the
+    /// alternate screen the primary scrollback isn't reachable and this is empty.
+    fn scrollback_lines(&mut self) -> Vec<Vec<u8>> {
+        let screen = self.parser.screen_mut();
+        screen.set_scrollback(usize::MAX);
+        let total = screen.scrollback();
+        let (rows, cols) = screen.size();
+
+        let mut lines = Vec::with_capacity(total);
+        let mut offset = total;
+        while offset > 0 {
+            self.parser.screen_mut().set_scrollback(offset);
+            // At this offset the first `offset` visible rows come from scrollback
+            let take = offset.min(rows as usize);
+            for mut row in self.parser.screen().rows_formatted(0, cols).take(take) {
+                row.extend_from_slice(SGR_RESET);
+                lines.push(row);
+            }
+            offset -= take;
+        }
+
+        self.parser.screen_mut().set_scrollback(0);
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repaint_reproduces_visible_screen() {
+        let mut model = ScreenModel::new(5, 20);
+        model.process(b"hello\r\n\x1b[1;31mred\x1b[m world");
+
+        let repaint = model.repaint();
+        assert_eq!((repaint.rows, repaint.cols), (5, 20));
+        assert_eq!((repaint.cursor_row, repaint.cursor_col), (1, 9));
+        assert!(repaint.cursor_visible);
+        assert!(!repaint.alternate_screen);
+        assert!(repaint.scrollback.is_empty());
+
+        // Feeding the repaint into a fresh emulator yields the same screen
+        let mut replay = vt100::Parser::new(5, 20, 0);
+        replay.process(&repaint.screen);
+        assert_eq!(replay.screen().contents(), "hello\nred world");
+        assert!(replay.screen().cell(1, 0).unwrap().bold());
+        assert_eq!(replay.screen().cursor_position(), (1, 9));
+    }
+
+    #[test]
+    fn repaint_tracks_alternate_screen_and_title() {
+        let mut model = ScreenModel::new(4, 10);
+        model.process(b"shell$ \x1b]0;editor\x07\x1b[?1049h\x1b[?25l\x1b[2;3HTUI");
+
+        let repaint = model.repaint();
+        assert!(repaint.alternate_screen);
+        assert!(!repaint.cursor_visible);
+        assert_eq!(repaint.title, "editor");
+
+        let mut replay = vt100::Parser::new(4, 10, 0);
+        replay.process(&repaint.screen);
+        assert_eq!(replay.screen().contents(), "\n  TUI");
+    }
+
+    #[test]
+    fn repaint_includes_scrollback_oldest_first() {
+        let mut model = ScreenModel::new(3, 10);
+        for i in 0..8 {
+            model.process(format!("line{}\r\n", i).as_bytes());
+        }
+
+        let repaint = model.repaint();
+        let lines: Vec<String> = repaint
+            .scrollback
+            .iter()
+            .map(|line| {
+                let mut parser = vt100::Parser::new(1, 10, 0);
+                parser.process(line);
+                parser.screen().contents()
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            vec!["line0", "line1", "line2", "line3", "line4", "line5"]
+        );
+
+        // Walking scrollback must leave the live viewport untouched
+        let mut replay = vt100::Parser::new(3, 10, 0);
+        replay.process(&repaint.screen);
+        assert_eq!(replay.screen().contents(), "line6\nline7");
+    }
+
+    #[test]
+    fn text_renders_redrawn_screen_not_byte_history() {
+        let mut model = ScreenModel::new(3, 20);
+        // A progress line redrawn in place, then a title update
+        model.process(b"progress 10%\rprogress 99%\r\n\x1b]2;build\x07done   ");
+
+        let text = model.text();
+        assert_eq!(text.lines, vec!["progress 99%", "done", ""]);
+        assert_eq!((text.cursor_row, text.cursor_col), (1, 7));
+        assert_eq!(text.title, "build");
+        assert!(!text.alternate_screen);
+    }
+
+    #[test]
+    fn resize_updates_model_dimensions() {
+        let mut model = ScreenModel::new(24, 80);
+        model.resize(40, 132);
+        assert_eq!(model.size(), (40, 132));
+        assert_eq!(model.repaint().cols, 132);
+    }
+}
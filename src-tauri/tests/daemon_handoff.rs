@@ -0,0 +1,148 @@
+//! Integration test: A newer daemon takes over a running daemon's sessions
+//!
+//! 1. Start the daemon with in-process PTYs (no holder) and a long-running session
+//! 2. Verify a second daemon of the same build leaves it alone
+//! 3. Start one told to take over anyway, and verify the first daemon hands
+//!    off and exits
+//! 4. Verify the session is still Running, with its history and input intact
+//!
+//! Run with: cargo test --test daemon_handoff
+
+mod helpers;
+
+use helpers::daemon_guard::{find_daemon_binary, is_process_running, DaemonGuard};
+use rn_desktop_2_lib::session::protocol::{
+    deserialize_message, serialize_message, DaemonRequest, DaemonResponse, SessionId, SessionStatus,
+};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+/// Keeps PTYs inside the daemon so the handoff has to pass them along
+const NO_HOLDER: &[(&str, &str)] = &[("RIGHT_NOW_HOLDER", "/nonexistent/right-now-holder")];
+
+fn request(data_dir: &Path, request: &DaemonRequest) -> DaemonResponse {
+    let mut stream = UnixStream::connect(data_dir.join("daemon.sock")).expect("connect");
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    stream
+        .write_all(&serialize_message(request).unwrap())
+        .unwrap();
+
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).expect("read response");
+    deserialize_message(line.as_bytes()).expect("parse response")
+}
+
+fn tail_contains(data_dir: &Path, session_id: SessionId, needle: &str) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        let continue_req = DaemonRequest::Continue {
+            session_id,
+            tail_bytes: Some(4096),
+        };
+        if let DaemonResponse::SessionContinued {
+            tail: Some(tail), ..
+        } = request(data_dir, &continue_req)
+        {
+            if String::from_utf8_lossy(&tail).contains(needle) {
+                return true;
+            }
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    false
+}
+
+#[test]
+fn test_new_daemon_takes_over_running_session() {
+    let temp_dir = TempDir::new().unwrap();
+    let data_dir = temp_dir.path().join("daemon");
+    std::fs::create_dir_all(&data_dir).unwrap();
+    let todo_file = temp_dir.path().join("TODO.md");
+    std::fs::write(&todo_file, "# Tasks\n- [ ] Long running agent\n").unwrap();
+
+    let mut old_daemon = match DaemonGuard::start_with_env(&data_dir, NO_HOLDER) {
+        Ok(guard) => guard,
+        Err(err) if err.is_missing_binary() => {
+            eprintln!("Skipping test: {}", err);
+            return;
+        }
+        Err(err) => panic!("Failed to start daemon: {}", err),
+    };
+
+    let start = DaemonRequest::Start {
+        task_key: "Long running".to_string(),
+        task_id: None,
+        project_path: todo_file.to_string_lossy().to_string(),
+        shell: Some(vec![
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            "echo before-upgrade; read line; echo \"after-upgrade $line\"; sleep 30".to_string(),
+        ]),
+        cwd: None,
+        env: Default::default(),
+        record: false,
+        attention_profiles: Vec::new(),
+    };
+    let session_id = match request(&data_dir, &start) {
+        DaemonResponse::SessionStarted { session } => session.id,
+        other => panic!("Expected SessionStarted, got {:?}", other),
+    };
+    assert!(tail_contains(&data_dir, session_id, "before-upgrade"));
+    assert!(!data_dir
+        .join(format!("holder-{}.sock", session_id))
+        .exists());
+
+    // Not newer, so it doesn't take over
+    let status = Command::new(find_daemon_binary().unwrap())
+        .env("RIGHT_NOW_DAEMON_DIR", &data_dir)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(!status.success());
+    assert!(is_process_running(old_daemon.pid()));
+    assert!(tail_contains(&data_dir, session_id, "before-upgrade"));
+
+    // Stands in for a newer build
+    let new_daemon = DaemonGuard::start_with_env(&data_dir, &[("RIGHT_NOW_FORCE_HANDOFF", "1")])
+        .expect("daemon starts");
+    assert!(
+        old_daemon.wait_for_exit(Duration::from_secs(10)),
+        "Old daemon should exit after handing off"
+    );
+
+    let session = match request(&data_dir, &DaemonRequest::List { project_path: None }) {
+        DaemonResponse::SessionList { sessions } => sessions
+            .into_iter()
+            .find(|s| s.id == session_id)
+            .expect("session still registered"),
+        other => panic!("Expected SessionList, got {:?}", other),
+    };
+    assert_ne!(session.status, SessionStatus::Stopped);
+
+    // History from the old daemon is there, and the PTY still takes input
+    assert!(tail_contains(&data_dir, session_id, "before-upgrade"));
+    let send = DaemonRequest::SendInput {
+        session_id,
+        data: "ok".to_string(),
+        append_newline: true,
+    };
+    assert!(matches!(
+        request(&data_dir, &send),
+        DaemonResponse::InputSent { .. }
+    ));
+    assert!(tail_contains(&data_dir, session_id, "after-upgrade ok"));
+
+    match request(&data_dir, &DaemonRequest::Stop { session_id }) {
+        DaemonResponse::SessionStopped { .. } => {}
+        other => panic!("Expected SessionStopped, got {:?}", other),
+    }
+    assert!(is_process_running(new_daemon.pid()));
+}
@@ -13,18 +13,36 @@ const INITIAL_DELAY_MS: u64 = 50;
 const MAX_DELAY_MS: u64 = 1_000;
 
 /// RAII wrapper that ensures the daemon process is cleaned up.
+///
+/// Session holder processes outlive the daemon, so they are stopped too.
 pub struct DaemonGuard {
     child: Child,
     data_dir: PathBuf,
+    stop_holders: bool,
 }
 
 impl DaemonGuard {
     /// Start the daemon using the compiled binary.
+    #[allow(dead_code)] // The handoff test always passes an environment
     pub fn start(data_dir: &Path) -> Result<Self, DaemonError> {
+        Self::start_with_env(data_dir, &[])
+    }
+
+    /// Start the daemon with extra environment variables.
+    pub fn start_with_env(data_dir: &Path, envs: &[(&str, &str)]) -> Result<Self, DaemonError> {
         let daemon_bin = find_daemon_binary().ok_or(DaemonError::BinaryNotFound)?;
 
+        // The daemon refuses runtime directories other users can enter
+        #[cfg(unix)]
+        if data_dir.exists() {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(data_dir, std::fs::Permissions::from_mode(0o700))
+                .map_err(DaemonError::SpawnFailed)?;
+        }
+
         let child = Command::new(&daemon_bin)
             .env("RIGHT_NOW_DAEMON_DIR", data_dir)
+            .envs(envs.iter().copied())
             .stdout(Stdio::null())
             .stderr(Stdio::null())
             .spawn()
@@ -33,6 +51,7 @@ impl DaemonGuard {
         let guard = Self {
             child,
             data_dir: data_dir.to_path_buf(),
+            stop_holders: true,
         };
 
         println!(
@@ -50,6 +69,25 @@ impl DaemonGuard {
         self.child.id()
     }
 
+    /// Kill the daemon without stopping its session holders, like a crash would.
+    #[allow(dead_code)] // Not every test binary simulates crashes
+    pub fn crash(mut self) {
+        self.stop_holders = false;
+    }
+
+    /// Wait for the daemon to exit on its own, reaping it.
+    #[allow(dead_code)] // Only the handoff test expects the daemon to exit by itself
+    pub fn wait_for_exit(&mut self, timeout: Duration) -> bool {
+        let start = Instant::now();
+        while start.elapsed() < timeout {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(INITIAL_DELAY_MS));
+        }
+        false
+    }
+
     fn wait_for_ready(&self) -> Result<(), WaitError> {
         let pid_file = self.data_dir.join("daemon.pid");
         wait_for_file_content(
@@ -63,6 +101,10 @@ impl DaemonGuard {
 
 impl Drop for DaemonGuard {
     fn drop(&mut self) {
+        if self.stop_holders {
+            stop_holders(&self.data_dir);
+        }
+
         if let Ok(Some(_)) = self.child.try_wait() {
             return;
         }
@@ -80,12 +122,41 @@ impl Drop for DaemonGuard {
     }
 }
 
+/// Ask every session holder in `data_dir` to stop its session and exit.
+#[cfg(unix)]
+fn stop_holders(data_dir: &Path) {
+    use rn_desktop_2_lib::session::holder::HolderRequest;
+    use rn_desktop_2_lib::session::protocol::serialize_message;
+    use std::io::Write;
+    use std::os::unix::net::UnixStream;
+
+    let Ok(entries) = std::fs::read_dir(data_dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if !(name.starts_with("holder-") && name.ends_with(".sock")) {
+            continue;
+        }
+        if let Ok(mut stream) = UnixStream::connect(entry.path()) {
+            let stop = serialize_message(&HolderRequest::Stop).expect("serialize stop");
+            let _ = stream.write_all(&stop);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn stop_holders(_data_dir: &Path) {}
+
 /// Start the daemon and return a guard that will clean it up on drop.
+#[allow(dead_code)] // The handoff test always passes an environment
 pub fn start_daemon(data_dir: &Path) -> Result<DaemonGuard, DaemonError> {
     DaemonGuard::start(data_dir)
 }
 
 /// Wait for a process to exit, used by tests to ensure no orphans remain.
+#[allow(dead_code)] // Not every test binary checks for orphans
 pub fn wait_for_process_exit(pid: u32, timeout: Duration) -> bool {
     let start = Instant::now();
     let mut delay = Duration::from_millis(INITIAL_DELAY_MS);
@@ -119,7 +190,8 @@ fn process_is_running(_pid: u32) -> bool {
     false
 }
 
-fn find_daemon_binary() -> Option<PathBuf> {
+#[allow(dead_code)] // Only the handoff test runs the binary itself
+pub fn find_daemon_binary() -> Option<PathBuf> {
     let manifest_dir = env!("CARGO_MANIFEST_DIR");
 
     let debug_path = PathBuf::from(manifest_dir).join("../target/debug/right-now-daemon");
@@ -5,7 +5,11 @@
 use rn_desktop_2_lib::context_resurrection::models::{
     AttentionType, CaptureReason, ContextSnapshotV1, SessionStatus, TerminalContext,
 };
-use rn_desktop_2_lib::session::protocol::{DaemonErrorCode, DaemonRequest, DaemonResponse};
+use rn_desktop_2_lib::session::protocol::{
+    deserialize_message, serialize_message_with, AttachMode, ByteEncoding, Capability,
+    DaemonCapabilities, DaemonErrorCode, DaemonRequest, DaemonResponse, RequestFrame,
+    ResponseFrame, StreamFrame,
+};
 use std::path::PathBuf;
 
 fn fixture_path(name: &str) -> PathBuf {
@@ -336,8 +340,54 @@ fn test_handshake_request() {
     let req: DaemonRequest = serde_json::from_str(&json).unwrap();
 
     match req {
-        DaemonRequest::Handshake { client_version } => {
+        DaemonRequest::Handshake {
+            client_version,
+            min_version,
+            byte_encoding,
+        } => {
             assert_eq!(client_version, 1);
+            assert_eq!(min_version, None);
+            assert_eq!(byte_encoding, None);
+        }
+        _ => panic!("Expected Handshake variant"),
+    }
+}
+
+#[test]
+fn test_handshake_request_with_version_range() {
+    let json = read_fixture("handshake_request_range.json");
+    let req: DaemonRequest = serde_json::from_str(&json).unwrap();
+
+    match req {
+        DaemonRequest::Handshake {
+            client_version,
+            min_version,
+            ..
+        } => {
+            assert_eq!(client_version, 2);
+            assert_eq!(min_version, Some(1));
+        }
+        _ => panic!("Expected Handshake variant"),
+    }
+}
+
+#[test]
+fn test_handshake_request_with_byte_encoding() {
+    let json = read_fixture("handshake_request_base64.json");
+    let req: DaemonRequest = serde_json::from_str(&json).unwrap();
+
+    match req {
+        DaemonRequest::Handshake { byte_encoding, .. } => {
+            assert_eq!(byte_encoding, Some(ByteEncoding::Base64));
+        }
+        _ => panic!("Expected Handshake variant"),
+    }
+
+    // Encodings from newer clients fall back to arrays instead of failing
+    let json = json.replace("base64", "zstd");
+    match serde_json::from_str::<DaemonRequest>(&json).unwrap() {
+        DaemonRequest::Handshake { byte_encoding, .. } => {
+            assert_eq!(byte_encoding, Some(ByteEncoding::Array));
         }
         _ => panic!("Expected Handshake variant"),
     }
@@ -349,13 +399,373 @@ fn test_handshake_response() {
     let resp: DaemonResponse = serde_json::from_str(&json).unwrap();
 
     match resp {
-        DaemonResponse::Handshake { protocol_version } => {
+        DaemonResponse::Handshake {
+            protocol_version,
+            min_protocol_version,
+            capabilities,
+            byte_encoding,
+        } => {
             assert_eq!(protocol_version, 1);
+            assert_eq!(min_protocol_version, None);
+            assert_eq!(byte_encoding, None);
+            // Daemons from before capabilities are trusted to answer for themselves
+            let daemon = DaemonCapabilities {
+                protocol_version,
+                capabilities,
+            };
+            assert!(daemon.supports(Capability::Journal));
+        }
+        _ => panic!("Expected Handshake variant"),
+    }
+}
+
+#[test]
+fn test_handshake_response_with_capabilities() {
+    let json = read_fixture("handshake_response_capabilities.json");
+    let resp: DaemonResponse = serde_json::from_str(&json).unwrap();
+
+    match resp {
+        DaemonResponse::Handshake {
+            protocol_version,
+            min_protocol_version,
+            capabilities,
+            ..
+        } => {
+            assert_eq!(min_protocol_version, Some(1));
+            // Capabilities from newer daemons don't break parsing
+            assert_eq!(
+                capabilities.as_deref(),
+                Some(
+                    &[
+                        Capability::SendInput,
+                        Capability::Screen,
+                        Capability::Journal,
+                        Capability::Unknown
+                    ][..]
+                )
+            );
+
+            let daemon = DaemonCapabilities {
+                protocol_version,
+                capabilities,
+            };
+            assert!(daemon.supports(Capability::SendInput));
+            assert!(!daemon.supports(Capability::Restart));
+            assert!(daemon
+                .check(&DaemonRequest::Events {
+                    since_seq: 0,
+                    limit: None,
+                    filter: Default::default(),
+                })
+                .is_none());
+            assert!(matches!(
+                daemon.check(&DaemonRequest::Restart { session_id: 1 }),
+                Some(DaemonResponse::Error {
+                    code: DaemonErrorCode::Unsupported,
+                    ..
+                })
+            ));
+            assert!(daemon.check(&DaemonRequest::Ping).is_none());
         }
         _ => panic!("Expected Handshake variant"),
     }
 }
 
+const TAIL_BYTES: &[u8] = b"\x1b[32mok\x1b[0m\r\n";
+
+#[test]
+fn test_session_tail_byte_arrays_still_parse() {
+    let json = read_fixture("session_tail.json");
+    let resp: DaemonResponse = serde_json::from_str(&json).unwrap();
+
+    match resp {
+        DaemonResponse::SessionTail {
+            session_id, data, ..
+        } => {
+            assert_eq!(session_id, 42);
+            assert_eq!(data, TAIL_BYTES);
+        }
+        _ => panic!("Expected SessionTail variant"),
+    }
+}
+
+#[test]
+fn test_session_tail_base64_parses_to_same_bytes() {
+    let legacy: DaemonResponse = serde_json::from_str(&read_fixture("session_tail.json")).unwrap();
+    let base64: DaemonResponse =
+        serde_json::from_str(&read_fixture("session_tail_base64.json")).unwrap();
+
+    match (legacy, base64) {
+        (
+            DaemonResponse::SessionTail { data: legacy, .. },
+            DaemonResponse::SessionTail { data: base64, .. },
+        ) => assert_eq!(legacy, base64),
+        _ => panic!("Expected SessionTail variants"),
+    }
+}
+
+#[test]
+fn test_attach_tails_in_base64() {
+    let json = read_fixture("session_continued_base64.json");
+    match serde_json::from_str::<DaemonResponse>(&json).unwrap() {
+        DaemonResponse::SessionContinued { session, tail } => {
+            assert_eq!(session.id, 42);
+            assert_eq!(tail.as_deref(), Some(TAIL_BYTES));
+        }
+        _ => panic!("Expected SessionContinued variant"),
+    }
+
+    let json = read_fixture("attach_ready_base64.json");
+    match serde_json::from_str::<DaemonResponse>(&json).unwrap() {
+        DaemonResponse::AttachReady {
+            tail, socket_path, ..
+        } => {
+            assert_eq!(tail.as_deref(), Some(TAIL_BYTES));
+            assert_eq!(socket_path, "/tmp/right-now/attach-42.sock");
+        }
+        _ => panic!("Expected AttachReady variant"),
+    }
+}
+
+#[test]
+fn test_byte_encodings_round_trip() {
+    let response = DaemonResponse::SessionTail {
+        session_id: 42,
+        data: TAIL_BYTES.to_vec(),
+        offset: None,
+        log_end: None,
+    };
+
+    let array = serialize_message_with(&response, ByteEncoding::Array).unwrap();
+    let base64 = serialize_message_with(&response, ByteEncoding::Base64).unwrap();
+    assert!(String::from_utf8_lossy(&array).contains("[27,91,51,50,"));
+    assert!(String::from_utf8_lossy(&base64).contains("\"G1szMm1vaxtbMG0NCg==\""));
+    assert!(base64.len() < array.len());
+
+    for bytes in [array, base64] {
+        match deserialize_message::<DaemonResponse>(&bytes).unwrap() {
+            DaemonResponse::SessionTail { data, .. } => assert_eq!(data, TAIL_BYTES),
+            _ => panic!("Expected SessionTail variant"),
+        }
+    }
+}
+
+#[test]
+fn test_open_stream_request() {
+    let json = read_fixture("open_stream_request.json");
+    let frame: RequestFrame = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(frame.request_id, Some(7));
+    match frame.request {
+        DaemonRequest::OpenStream {
+            session_id,
+            tail_bytes,
+            offset,
+            mode,
+        } => {
+            assert_eq!(session_id, 42);
+            assert_eq!(tail_bytes, Some(4096));
+            assert_eq!(offset, None);
+            assert_eq!(mode, AttachMode::Shared);
+        }
+        _ => panic!("Expected OpenStream variant"),
+    }
+}
+
+#[test]
+fn test_attach_modes() {
+    let json = read_fixture("attach_read_only_request.json");
+    let request: DaemonRequest = serde_json::from_str(&json).unwrap();
+    match &request {
+        DaemonRequest::Attach { mode, .. } => assert_eq!(*mode, AttachMode::ReadOnly),
+        _ => panic!("Expected Attach variant"),
+    }
+    assert_eq!(
+        request.required_capabilities(),
+        vec![Capability::AttachModes]
+    );
+
+    // Shared is the default and stays off the wire, so older daemons accept it
+    let shared = DaemonRequest::Attach {
+        session_id: 42,
+        tail_bytes: None,
+        offset: None,
+        mode: AttachMode::Shared,
+    };
+    assert!(!serde_json::to_string(&shared).unwrap().contains("mode"));
+    assert!(shared.required_capabilities().is_empty());
+
+    // Every capability a request leans on is checked, not just the first
+    let replay = DaemonRequest::Attach {
+        session_id: 42,
+        tail_bytes: None,
+        offset: Some(1024),
+        mode: AttachMode::ReadOnly,
+    };
+    assert_eq!(
+        replay.required_capabilities(),
+        vec![Capability::AttachModes, Capability::OutputLog]
+    );
+    let without_log = DaemonCapabilities {
+        protocol_version: 1,
+        capabilities: Some(vec![Capability::AttachModes]),
+    };
+    match without_log.check(&replay) {
+        Some(DaemonResponse::Error { code, message }) => {
+            assert_eq!(code, DaemonErrorCode::Unsupported);
+            assert!(message.contains("output_log"), "{}", message);
+        }
+        other => panic!("Expected Unsupported error, got {:?}", other),
+    }
+
+    let json = read_fixture("stream_opened_viewers.json");
+    match serde_json::from_str::<DaemonResponse>(&json).unwrap() {
+        DaemonResponse::StreamOpened { session, .. } => {
+            let modes: Vec<AttachMode> = session.viewers.iter().map(|v| v.mode).collect();
+            assert_eq!(modes, vec![AttachMode::Shared, AttachMode::ReadOnly]);
+            assert_eq!(session.viewers[1].id, 4);
+        }
+        _ => panic!("Expected StreamOpened variant"),
+    }
+    // Sessions from before viewers were tracked have none
+    let json = read_fixture("attach_ready_base64.json");
+    match serde_json::from_str::<DaemonResponse>(&json).unwrap() {
+        DaemonResponse::AttachReady { session, .. } => assert!(session.viewers.is_empty()),
+        _ => panic!("Expected AttachReady variant"),
+    }
+}
+
+#[test]
+fn test_reload_config() {
+    let request: DaemonRequest = serde_json::from_str(r#"{"type":"reload_config"}"#).unwrap();
+    assert!(matches!(request, DaemonRequest::ReloadConfig));
+    assert_eq!(
+        request.required_capabilities(),
+        vec![Capability::ReloadConfig]
+    );
+
+    let json = read_fixture("config_reloaded.json");
+    match serde_json::from_str::<DaemonResponse>(&json).unwrap() {
+        DaemonResponse::ConfigReloaded {
+            settings,
+            attention_profiles,
+        } => {
+            assert_eq!(settings.tail_bytes, 16384);
+            assert_eq!(settings.idle_timeout_secs, 30);
+            assert_eq!(attention_profiles[0], "pytest");
+        }
+        _ => panic!("Expected ConfigReloaded variant"),
+    }
+
+    let json = read_fixture("error_invalid_config.json");
+    match serde_json::from_str::<DaemonResponse>(&json).unwrap() {
+        DaemonResponse::Error { code, message } => {
+            assert_eq!(code, DaemonErrorCode::InvalidConfig);
+            assert!(message.contains("tail_bytes"));
+        }
+        _ => panic!("Expected Error variant"),
+    }
+}
+
+#[test]
+fn test_attention_profiles() {
+    let json = read_fixture("start_attention_profiles.json");
+    let request: DaemonRequest = serde_json::from_str(&json).unwrap();
+    match &request {
+        DaemonRequest::Start {
+            attention_profiles, ..
+        } => assert_eq!(attention_profiles, &vec!["claude-code".to_string()]),
+        _ => panic!("Expected Start variant"),
+    }
+    assert_eq!(
+        request.required_capabilities(),
+        vec![Capability::AttentionProfiles]
+    );
+
+    let json = read_fixture("session_status_attention_profiles.json");
+    match serde_json::from_str::<DaemonResponse>(&json).unwrap() {
+        DaemonResponse::SessionStatus { session } => {
+            assert_eq!(session.attention_profiles, vec!["claude-code".to_string()]);
+            assert_eq!(
+                session.active_attention_profiles,
+                session.attention_profiles
+            );
+        }
+        _ => panic!("Expected SessionStatus variant"),
+    }
+    // Sessions from before profiles were bound pick them by command
+    let json = read_fixture("stream_opened_viewers.json");
+    match serde_json::from_str::<DaemonResponse>(&json).unwrap() {
+        DaemonResponse::StreamOpened { session, .. } => {
+            assert!(session.attention_profiles.is_empty());
+            assert!(session.active_attention_profiles.is_empty());
+        }
+        _ => panic!("Expected StreamOpened variant"),
+    }
+}
+
+#[test]
+fn test_attention_inbox() {
+    use rn_desktop_2_lib::session::protocol::AttentionType;
+
+    let json = read_fixture("attention_ack.json");
+    let request: DaemonRequest = serde_json::from_str(&json).unwrap();
+    match &request {
+        DaemonRequest::AttentionAck { ids } => assert_eq!(ids, &vec![7, 8]),
+        _ => panic!("Expected AttentionAck variant"),
+    }
+    assert_eq!(
+        request.required_capabilities(),
+        vec![Capability::AttentionInbox]
+    );
+
+    let json = read_fixture("attention_items.json");
+    match serde_json::from_str::<DaemonResponse>(&json).unwrap() {
+        DaemonResponse::AttentionItems { items } => {
+            assert_eq!(items.len(), 2);
+            assert_eq!(items[0].session_id, 42);
+            assert_eq!(items[0].summary.id, 8);
+            assert_eq!(
+                items[0].summary.attention_type,
+                AttentionType::InputRequired
+            );
+            assert!(items.iter().all(|item| !item.summary.acked));
+        }
+        _ => panic!("Expected AttentionItems variant"),
+    }
+}
+
+#[test]
+fn test_stream_frames() {
+    let json = read_fixture("stream_data.json");
+    assert_eq!(
+        serde_json::from_str::<StreamFrame>(&json).unwrap(),
+        StreamFrame::Data {
+            session_id: 42,
+            data: TAIL_BYTES.to_vec(),
+        }
+    );
+    // Frames share connections with requests and responses, so neither may accept them
+    assert!(serde_json::from_str::<RequestFrame>(&json).is_err());
+    assert!(serde_json::from_str::<ResponseFrame>(&json).is_err());
+
+    let json = read_fixture("stream_close.json");
+    assert_eq!(
+        serde_json::from_str::<StreamFrame>(&json).unwrap(),
+        StreamFrame::Close {
+            session_id: 42,
+            exited: true,
+            exit_code: Some(0),
+            message: None,
+        }
+    );
+    assert!(serde_json::from_str::<ResponseFrame>(&json).is_err());
+
+    // ...and frames never accept them
+    let json = read_fixture("handshake_response.json");
+    assert!(serde_json::from_str::<StreamFrame>(&json).is_err());
+}
+
 #[test]
 fn test_error_version_mismatch() {
     let json = read_fixture("error_version_mismatch.json");
@@ -42,7 +42,11 @@ fn test_server_rejects_oversized_request() {
     let mut stream = connect_daemon().expect("Failed to connect to daemon");
 
     // First, perform handshake
-    let handshake = DaemonRequest::Handshake { client_version: 1 };
+    let handshake = DaemonRequest::Handshake {
+        client_version: 1,
+        min_version: None,
+        byte_encoding: None,
+    };
     let response = send_request(&mut stream, &handshake).expect("Handshake failed");
     match response {
         DaemonResponse::Handshake { .. } => {}
@@ -124,7 +128,11 @@ fn test_normal_sized_request_accepted() {
     let mut stream = connect_daemon().expect("Failed to connect to daemon");
 
     // Perform handshake
-    let handshake = DaemonRequest::Handshake { client_version: 1 };
+    let handshake = DaemonRequest::Handshake {
+        client_version: 1,
+        min_version: None,
+        byte_encoding: None,
+    };
     let response = send_request(&mut stream, &handshake).expect("Handshake failed");
     match response {
         DaemonResponse::Handshake { .. } => {}
@@ -1,7 +1,7 @@
 // SessionClient - TypeScript bridge to right-now-daemon
 // Provides methods to interact with terminal sessions via Tauri commands
 
-import { invoke } from "@tauri-apps/api/core";
+import { Channel, invoke } from "@tauri-apps/api/core";
 
 /**
  * Session status enum matching the Rust protocol
@@ -26,10 +26,35 @@ export enum AttentionType {
  * Attention summary
  */
 export interface AttentionSummary {
+  id?: number; // Inbox ID (absent or 0 for events from before the inbox)
   profile: string;
   attention_type: AttentionType;
   preview: string;
   triggered_at: string; // ISO 8601 timestamp
+  acked?: boolean; // Acknowledged, or the session was attached since
+}
+
+/**
+ * Attention inbox item: an attention summary with the session it came from
+ */
+export interface AttentionItem extends AttentionSummary {
+  session_id: number;
+  task_key: string;
+  project_path: string;
+}
+
+/**
+ * How a client attaches to a session
+ */
+export type AttachMode = "shared" | "exclusive" | "read_only";
+
+/**
+ * A client currently attached to a session
+ */
+export interface AttachViewer {
+  id: number;
+  mode: AttachMode;
+  attached_at: string; // ISO 8601 timestamp
 }
 
 /**
@@ -43,10 +68,17 @@ export interface Session {
   status: SessionStatus;
   pty_pid?: number;
   shell_command?: string[];
+  spawn_cwd?: string; // Working directory the PTY was spawned in
+  foreground_command?: string; // Command line of the process in the foreground
+  cwd?: string; // Working directory of the foreground process, as last seen
+  attention_profiles?: string[]; // Profiles the session was started with (else picked by command)
+  active_attention_profiles?: string[]; // Profiles checking its output right now
   created_at: string; // ISO 8601 timestamp
   updated_at: string; // ISO 8601 timestamp
   exit_code?: number;
   last_attention?: AttentionSummary;
+  recording?: boolean; // Output is being recorded for `todo replay`
+  viewers?: AttachViewer[]; // Clients attached right now
 }
 
 /**
@@ -57,6 +89,44 @@ export interface ContinueSessionResult {
   tail?: number[]; // Raw bytes as array of numbers
 }
 
+/**
+ * Repaint of a session's screen, sent when attaching
+ */
+export interface ScreenRepaint {
+  rows: number;
+  cols: number;
+  cursor_row: number;
+  cursor_col: number;
+  cursor_visible: boolean;
+  alternate_screen: boolean;
+  title?: string;
+  scrollback?: number[][]; // Lines with SGR formatting, oldest first
+  screen: number[]; // Escape sequences that redraw the visible screen
+}
+
+/**
+ * Result from attachSession
+ */
+export interface AttachSessionResult {
+  session: Session;
+  tail?: number[]; // Raw bytes as array of numbers
+  screen?: ScreenRepaint; // Preferred over replaying tail when present
+}
+
+/**
+ * Frame on an attach stream, keyed by session
+ */
+export type StreamFrame =
+  | { type: "stream_data"; session_id: number; data: number[] }
+  | { type: "stream_resize"; session_id: number; cols: number; rows: number }
+  | {
+      type: "stream_close";
+      session_id: number;
+      exited: boolean; // The session's process exited
+      exit_code?: number;
+      message?: string; // Why the daemon closed the stream, when it's an error
+    };
+
 /**
  * SessionClient provides methods to interact with the right-now-daemon
  */
@@ -79,13 +149,20 @@ export class SessionClient {
   /**
    * Start a new session for a task
    */
-  async startSession(taskKey: string, projectPath: string, taskId?: string, shell?: string[]): Promise<Session> {
+  async startSession(
+    taskKey: string,
+    projectPath: string,
+    taskId?: string,
+    shell?: string[],
+    record?: boolean,
+  ): Promise<Session> {
     try {
       const session = await invoke<Session>("session_start", {
         taskKey,
         taskId: taskId ?? null,
         projectPath,
         shell: shell ?? null,
+        record: record ?? null,
       });
       console.log(`Session started: ${session.id} (${session.task_key})`);
       return session;
@@ -111,6 +188,22 @@ export class SessionClient {
     }
   }
 
+  /**
+   * Relaunch a stopped session with its original command, cwd and environment
+   */
+  async restartSession(sessionId: number): Promise<Session> {
+    try {
+      const session = await invoke<Session>("session_restart", {
+        sessionId,
+      });
+      console.log(`Session restarted: ${sessionId}`);
+      return session;
+    } catch (error) {
+      console.error("Failed to restart session:", error);
+      throw new Error(`Failed to restart session: ${error}`);
+    }
+  }
+
   /**
    * Continue/attach to an existing session
    */
@@ -128,6 +221,76 @@ export class SessionClient {
     }
   }
 
+  /**
+   * Type text into a running session without attaching, pressing Enter
+   * afterwards when `appendNewline` is set.
+   * Returns the number of bytes written to the PTY.
+   */
+  async sendInput(sessionId: number, data: string, appendNewline: boolean): Promise<number> {
+    try {
+      const bytes = await invoke<number>("session_send_input", {
+        sessionId,
+        data,
+        appendNewline,
+      });
+      console.log(`Sent ${bytes} bytes to session ${sessionId}`);
+      return bytes;
+    } catch (error) {
+      console.error("Failed to send input:", error);
+      throw new Error(`Failed to send input: ${error}`);
+    }
+  }
+
+  /**
+   * Attach to a running session's live output.
+   * Frames arrive on onFrame until a stream_close; several sessions can be
+   * attached at once over the app's daemon connection.
+   */
+  async attachSession(
+    sessionId: number,
+    onFrame: (frame: StreamFrame) => void,
+    tailBytes?: number,
+    mode?: AttachMode,
+  ): Promise<AttachSessionResult> {
+    const channel = new Channel<StreamFrame>();
+    channel.onmessage = onFrame;
+    try {
+      const result = await invoke<AttachSessionResult>("session_attach", {
+        sessionId,
+        tailBytes: tailBytes ?? null,
+        mode: mode ?? null,
+        onFrame: channel,
+      });
+      console.log(`Session attached: ${sessionId}`);
+      return result;
+    } catch (error) {
+      console.error("Failed to attach session:", error);
+      throw new Error(`Failed to attach session: ${error}`);
+    }
+  }
+
+  /**
+   * Type into an attached session
+   */
+  async sendStreamInput(sessionId: number, data: string): Promise<void> {
+    await invoke("session_stream_input", { sessionId, data });
+  }
+
+  /**
+   * Tell an attached session the terminal size changed
+   */
+  async resizeStream(sessionId: number, cols: number, rows: number): Promise<void> {
+    await invoke("session_stream_resize", { sessionId, cols, rows });
+  }
+
+  /**
+   * Stop receiving output from an attached session; the session keeps running
+   */
+  async detachSession(sessionId: number): Promise<void> {
+    await invoke("session_detach", { sessionId });
+    console.log(`Session detached: ${sessionId}`);
+  }
+
   /**
    * Helper: Convert tail bytes to UTF-8 string (best-effort)
    */
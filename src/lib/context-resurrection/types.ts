@@ -21,6 +21,13 @@ export type CrAttentionSummary = {
   triggered_at: string; // ISO8601
 };
 
+export type TerminalScreen = {
+  lines: string[];
+  cursor_row: number;
+  cursor_col: number;
+  title?: string;
+};
+
 export type TerminalContext = {
   session_id: number;
   status: CrSessionStatus;
@@ -28,6 +35,9 @@ export type TerminalContext = {
   last_attention?: CrAttentionSummary;
   tail_inline?: string;
   tail_path?: string;
+  screen?: TerminalScreen;
+  foreground_command?: string;
+  cwd?: string;
 };
 
 export type ContextSnapshotV1 = {